@@ -1,16 +1,78 @@
 use anyhow::{Context, Result};
 use chromiumoxide::browser::{Browser, BrowserConfig};
 use futures::StreamExt;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
-/// Launch a headless Chrome browser
-/// Automatically uses Helium if available, or falls back to CHROME_PATH environment variable
+/// Browser window size in CSS pixels unless [`LaunchOptions::window_size`] says otherwise
+pub const DEFAULT_WINDOW_SIZE: (u32, u32) = (1920, 1080);
+
+/// How to launch the browser
+#[derive(Debug, Clone)]
+pub struct LaunchOptions {
+    /// Chrome user data directory
+    pub user_data_dir: PathBuf,
+    /// Run Chrome in incognito mode (no history, cookies or cache kept)
+    pub incognito: bool,
+    /// Window (and so page viewport) size in CSS pixels
+    pub window_size: (u32, u32),
+    /// Chrome executable to launch; `None` looks for Helium, then `CHROME_PATH`, then
+    /// lets chromiumoxide find Chrome
+    pub chrome_path: Option<PathBuf>,
+}
+
+impl LaunchOptions {
+    /// Incognito, with a private throwaway profile; remove it with [`remove_profile`]
+    pub fn ephemeral() -> Self {
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.subsec_nanos())
+            .unwrap_or_default();
+        let dir =
+            std::env::temp_dir().join(format!("toast-ephemeral-{}-{}", std::process::id(), nanos));
+        Self {
+            user_data_dir: dir,
+            incognito: true,
+            window_size: DEFAULT_WINDOW_SIZE,
+            chrome_path: None,
+        }
+    }
+}
+
+impl Default for LaunchOptions {
+    fn default() -> Self {
+        // Keep Chrome's profile with toast's other files instead of a shared temp directory
+        Self {
+            user_data_dir: toast_core::paths::get().browser_profile_dir(),
+            incognito: false,
+            window_size: DEFAULT_WINDOW_SIZE,
+            chrome_path: None,
+        }
+    }
+}
+
+/// Launch a headless Chrome browser with the default options
 pub async fn launch_browser() -> Result<Browser> {
-    let mut config_builder = BrowserConfig::builder().window_size(1920, 1080);
+    launch_browser_with(&LaunchOptions::default()).await
+}
+
+/// Launch a headless Chrome browser
+/// Uses the configured executable if any, else Helium if available, or falls back to
+/// CHROME_PATH environment variable
+pub async fn launch_browser_with(options: &LaunchOptions) -> Result<Browser> {
+    let mut config_builder = BrowserConfig::builder()
+        .window_size(options.window_size.0, options.window_size.1)
+        .user_data_dir(&options.user_data_dir);
+    if options.incognito {
+        config_builder = config_builder.incognito();
+    }
 
-    // Try Helium first
+    // The one configured, or else try Helium first
     let helium_path = PathBuf::from("/Applications/Helium.app/Contents/MacOS/Helium");
-    if helium_path.exists() {
+    if let Some(chrome_path) = &options.chrome_path {
+        tracing::info!("Using configured browser: {}", chrome_path.display());
+        config_builder = config_builder.chrome_executable(chrome_path);
+    } else if helium_path.exists() {
         tracing::info!("Using Helium browser: {}", helium_path.display());
         config_builder = config_builder.chrome_executable(helium_path);
     }
@@ -30,10 +92,91 @@ pub async fn launch_browser() -> Result<Browser> {
 
     // Spawn handler to process browser events
     tokio::spawn(async move {
-        while let Some(_) = handler.next().await {
+        while handler.next().await.is_some() {
             // Process events
         }
     });
 
     Ok(browser)
 }
+
+/// Delete a browser profile directory, retrying while Chrome finishes shutting down
+pub async fn remove_profile(dir: &Path) -> Result<()> {
+    let mut attempts = 0;
+    loop {
+        match std::fs::remove_dir_all(dir) {
+            Ok(()) => return Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+            Err(e) if attempts >= 10 => {
+                return Err(e).with_context(|| format!("Failed to remove {}", dir.display()))
+            }
+            Err(_) => {
+                attempts += 1;
+                tokio::time::sleep(Duration::from_millis(100)).await;
+            }
+        }
+    }
+}
+
+/// Deletes a throwaway browser profile when dropped, so that an error or early return
+/// doesn't leave it behind
+///
+/// Dropping can't wait for Chrome to exit, so it makes a single attempt; after closing
+/// the browser, call [`remove`](Self::remove) instead, which retries until it's gone.
+#[derive(Debug)]
+pub struct ProfileGuard {
+    dir: Option<PathBuf>,
+}
+
+impl ProfileGuard {
+    /// Guard the profile in `dir`, or nothing for a profile that is kept
+    pub fn new(dir: Option<PathBuf>) -> Self {
+        Self { dir }
+    }
+
+    /// Delete the profile, retrying while Chrome finishes shutting down
+    pub async fn remove(mut self) -> Result<()> {
+        match self.dir.take() {
+            Some(dir) => remove_profile(&dir).await,
+            None => Ok(()),
+        }
+    }
+}
+
+impl Drop for ProfileGuard {
+    fn drop(&mut self) {
+        if let Some(dir) = self.dir.take() {
+            match std::fs::remove_dir_all(&dir) {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                    tracing::warn!("Failed to remove {}: {}", dir.display(), e);
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profile_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("toast-{}-{}", name, std::process::id()));
+        std::fs::create_dir_all(dir.join("Default")).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_profile_guard_removes_profile_when_dropped() {
+        let dir = profile_dir("guard-drop");
+        drop(ProfileGuard::new(Some(dir.clone())));
+        assert!(!dir.exists());
+    }
+
+    #[tokio::test]
+    async fn test_profile_guard_remove_deletes_profile() {
+        let dir = profile_dir("guard-remove");
+        ProfileGuard::new(Some(dir.clone())).remove().await.unwrap();
+        assert!(!dir.exists());
+    }
+}
@@ -0,0 +1,78 @@
+use chromiumoxide::types::MethodId;
+use chromiumoxide::{Command, Method};
+use serde::{Serialize, Serializer};
+use std::borrow::Cow;
+
+/// A CDP command toast has no typed wrapper for, e.g. `Emulation.setCPUThrottlingRate`
+///
+/// Serializes as its parameters, which is what goes on the wire next to the method name.
+#[derive(Debug, Clone)]
+pub struct RawCommand {
+    method: String,
+    params: serde_json::Value,
+}
+
+impl RawCommand {
+    /// `method` is the full `Domain.method` name; `params` must be a JSON object
+    pub fn new(method: &str, params: serde_json::Value) -> anyhow::Result<Self> {
+        let valid = method.split_once('.').is_some_and(|(domain, name)| {
+            !domain.is_empty() && !name.is_empty() && !name.contains('.')
+        });
+        anyhow::ensure!(
+            valid,
+            "'{}' is not a CDP method (expected Domain.method)",
+            method
+        );
+        anyhow::ensure!(
+            params.is_object(),
+            "CDP parameters must be a JSON object, got {}",
+            params
+        );
+        Ok(Self {
+            method: method.to_string(),
+            params,
+        })
+    }
+}
+
+impl Serialize for RawCommand {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.params.serialize(serializer)
+    }
+}
+
+impl Method for RawCommand {
+    fn identifier(&self) -> MethodId {
+        Cow::Owned(self.method.clone())
+    }
+}
+
+impl Command for RawCommand {
+    type Response = serde_json::Value;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_raw_command_wire_format() {
+        let command = RawCommand::new(
+            "Page.navigate",
+            serde_json::json!({ "url": "https://example.com" }),
+        )
+        .unwrap();
+        assert_eq!(command.identifier(), "Page.navigate");
+        assert_eq!(
+            serde_json::to_value(&command).unwrap(),
+            serde_json::json!({ "url": "https://example.com" })
+        );
+    }
+
+    #[test]
+    fn test_invalid_commands_are_rejected() {
+        assert!(RawCommand::new("reload", serde_json::json!({})).is_err());
+        assert!(RawCommand::new("Page.", serde_json::json!({})).is_err());
+        assert!(RawCommand::new("Page.reload", serde_json::json!([1])).is_err());
+    }
+}
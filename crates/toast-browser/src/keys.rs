@@ -0,0 +1,245 @@
+use chromiumoxide::cdp::browser_protocol::input::{DispatchKeyEventParams, DispatchKeyEventType};
+use crossterm::event::{KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+
+/// Shifted characters on a US keyboard and the unshifted key they're typed with
+const SHIFTED: [(char, char); 21] = [
+    ('!', '1'),
+    ('@', '2'),
+    ('#', '3'),
+    ('$', '4'),
+    ('%', '5'),
+    ('^', '6'),
+    ('&', '7'),
+    ('*', '8'),
+    ('(', '9'),
+    (')', '0'),
+    ('_', '-'),
+    ('+', '='),
+    ('{', '['),
+    ('}', ']'),
+    ('|', '\\'),
+    (':', ';'),
+    ('"', '\''),
+    ('<', ','),
+    ('>', '.'),
+    ('?', '/'),
+    ('~', '`'),
+];
+
+/// A key as the browser describes it: DOM `key` and `code`, Windows virtual key code and
+/// the text it types
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Key {
+    key: String,
+    code: String,
+    key_code: i64,
+    text: Option<String>,
+}
+
+impl Key {
+    fn named(name: &str, key_code: i64) -> Self {
+        Self {
+            key: name.to_string(),
+            code: name.to_string(),
+            key_code,
+            text: None,
+        }
+    }
+
+    fn typing(mut self, text: &str) -> Self {
+        self.text = Some(text.to_string());
+        self
+    }
+}
+
+/// The browser's description of a terminal key, if it has one
+fn key(code: KeyCode) -> Option<Key> {
+    let key = match code {
+        KeyCode::Char(c) => char_key(c),
+        KeyCode::Enter => Key::named("Enter", 13).typing("\r"),
+        KeyCode::Tab | KeyCode::BackTab => Key::named("Tab", 9),
+        KeyCode::Backspace => Key::named("Backspace", 8),
+        KeyCode::Esc => Key::named("Escape", 27),
+        KeyCode::Delete => Key::named("Delete", 46),
+        KeyCode::Insert => Key::named("Insert", 45),
+        KeyCode::Home => Key::named("Home", 36),
+        KeyCode::End => Key::named("End", 35),
+        KeyCode::PageUp => Key::named("PageUp", 33),
+        KeyCode::PageDown => Key::named("PageDown", 34),
+        KeyCode::Left => Key::named("ArrowLeft", 37),
+        KeyCode::Up => Key::named("ArrowUp", 38),
+        KeyCode::Right => Key::named("ArrowRight", 39),
+        KeyCode::Down => Key::named("ArrowDown", 40),
+        KeyCode::F(n @ 1..=24) => Key::named(&format!("F{}", n), 111 + n as i64),
+        _ => return None,
+    };
+    Some(key)
+}
+
+/// A printable character, with the key that types it on a US keyboard
+fn char_key(c: char) -> Key {
+    let base = SHIFTED
+        .iter()
+        .find(|(shifted, _)| *shifted == c)
+        .map_or(c.to_ascii_lowercase(), |(_, base)| *base);
+    let (code, key_code) = match base {
+        'a'..='z' => (
+            format!("Key{}", base.to_ascii_uppercase()),
+            base.to_ascii_uppercase() as i64,
+        ),
+        '0'..='9' => (format!("Digit{}", base), base as i64),
+        ' ' => ("Space".to_string(), 32),
+        ';' => ("Semicolon".to_string(), 186),
+        '=' => ("Equal".to_string(), 187),
+        ',' => ("Comma".to_string(), 188),
+        '-' => ("Minus".to_string(), 189),
+        '.' => ("Period".to_string(), 190),
+        '/' => ("Slash".to_string(), 191),
+        '`' => ("Backquote".to_string(), 192),
+        '[' => ("BracketLeft".to_string(), 219),
+        '\\' => ("Backslash".to_string(), 220),
+        ']' => ("BracketRight".to_string(), 221),
+        '\'' => ("Quote".to_string(), 222),
+        // Typed some other way (a non-US layout or an input method); the text is enough
+        _ => (String::new(), 0),
+    };
+    let text = c.to_string();
+    Key {
+        key: text.clone(),
+        code,
+        key_code,
+        text: Some(text),
+    }
+}
+
+/// CDP modifier bits
+const ALT: i64 = 1;
+const CTRL: i64 = 2;
+const META: i64 = 4;
+const SHIFT: i64 = 8;
+
+/// The CDP modifier bitmask for a key
+fn modifier_mask(modifiers: KeyModifiers, code: KeyCode) -> i64 {
+    let mut mask = 0;
+    if modifiers.contains(KeyModifiers::ALT) {
+        mask |= ALT;
+    }
+    if modifiers.contains(KeyModifiers::CONTROL) {
+        mask |= CTRL;
+    }
+    if modifiers.intersects(KeyModifiers::SUPER | KeyModifiers::META) {
+        mask |= META;
+    }
+    // Shift+Tab comes as its own key
+    if modifiers.contains(KeyModifiers::SHIFT) || code == KeyCode::BackTab {
+        mask |= SHIFT;
+    }
+    mask
+}
+
+/// The `Input.dispatchKeyEvent` calls for a key press: key down, the character it types
+/// (if any) and key up
+///
+/// Returns nothing for releases, which terminals rarely report and the key up already
+/// covers, and for keys the browser has no name for.
+pub(crate) fn key_events(event: &KeyEvent) -> Vec<DispatchKeyEventParams> {
+    if event.kind == KeyEventKind::Release {
+        return Vec::new();
+    }
+    let Some(key) = key(event.code) else {
+        return Vec::new();
+    };
+    let modifiers = modifier_mask(event.modifiers, event.code);
+    let params = |kind: DispatchKeyEventType| {
+        let mut params = DispatchKeyEventParams::new(kind);
+        params.modifiers = Some(modifiers);
+        params.key = Some(key.key.clone());
+        params.code = Some(key.code.clone());
+        params.windows_virtual_key_code = Some(key.key_code);
+        params.auto_repeat = Some(event.kind == KeyEventKind::Repeat);
+        params
+    };
+
+    let mut events = vec![params(DispatchKeyEventType::RawKeyDown)];
+    // With Ctrl or Alt held a key is a shortcut, not typing
+    if let Some(text) = key.text.as_ref().filter(|_| modifiers & (CTRL | ALT) == 0) {
+        let mut typed = params(DispatchKeyEventType::Char);
+        typed.text = Some(text.clone());
+        typed.unmodified_text = Some(text.clone());
+        events.push(typed);
+    }
+    let mut up = params(DispatchKeyEventType::KeyUp);
+    up.auto_repeat = None;
+    events.push(up);
+    events
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(events: &[DispatchKeyEventParams]) -> Vec<DispatchKeyEventType> {
+        events.iter().map(|event| event.r#type.clone()).collect()
+    }
+
+    #[test]
+    fn test_letter_types_text() {
+        let events = key_events(&KeyEvent::new(KeyCode::Char('A'), KeyModifiers::SHIFT));
+        assert_eq!(
+            kinds(&events),
+            vec![
+                DispatchKeyEventType::RawKeyDown,
+                DispatchKeyEventType::Char,
+                DispatchKeyEventType::KeyUp
+            ]
+        );
+        let down = &events[0];
+        assert_eq!(down.key.as_deref(), Some("A"));
+        assert_eq!(down.code.as_deref(), Some("KeyA"));
+        assert_eq!(down.windows_virtual_key_code, Some(65));
+        assert_eq!(down.modifiers, Some(SHIFT));
+        assert_eq!(events[1].text.as_deref(), Some("A"));
+    }
+
+    #[test]
+    fn test_shifted_symbol_uses_its_key() {
+        let events = key_events(&KeyEvent::new(KeyCode::Char('?'), KeyModifiers::SHIFT));
+        assert_eq!(events[0].code.as_deref(), Some("Slash"));
+        assert_eq!(events[0].windows_virtual_key_code, Some(191));
+        assert_eq!(events[1].text.as_deref(), Some("?"));
+    }
+
+    #[test]
+    fn test_shortcut_types_nothing() {
+        let events = key_events(&KeyEvent::new(KeyCode::Char('a'), KeyModifiers::CONTROL));
+        assert_eq!(
+            kinds(&events),
+            vec![
+                DispatchKeyEventType::RawKeyDown,
+                DispatchKeyEventType::KeyUp
+            ]
+        );
+        assert_eq!(events[0].modifiers, Some(CTRL));
+    }
+
+    #[test]
+    fn test_named_keys() {
+        let enter = key_events(&KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE));
+        assert_eq!(enter[0].key.as_deref(), Some("Enter"));
+        assert_eq!(enter[1].text.as_deref(), Some("\r"));
+
+        let escape = key_events(&KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE));
+        assert_eq!(kinds(&escape).len(), 2);
+        assert_eq!(escape[0].windows_virtual_key_code, Some(27));
+
+        let back_tab = key_events(&KeyEvent::new(KeyCode::BackTab, KeyModifiers::NONE));
+        assert_eq!(back_tab[0].key.as_deref(), Some("Tab"));
+        assert_eq!(back_tab[0].modifiers, Some(SHIFT));
+
+        let f5 = key_events(&KeyEvent::new(KeyCode::F(5), KeyModifiers::NONE));
+        assert_eq!(f5[0].code.as_deref(), Some("F5"));
+        assert_eq!(f5[0].windows_virtual_key_code, Some(116));
+
+        assert!(key_events(&KeyEvent::new(KeyCode::Null, KeyModifiers::NONE)).is_empty());
+    }
+}
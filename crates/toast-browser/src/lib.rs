@@ -1,5 +1,15 @@
 mod browser;
+mod cdp;
+mod keys;
+mod redirect;
 mod screenshot;
+mod tabs;
 
-pub use browser::launch_browser;
-pub use screenshot::{capture_screenshot, ScreenshotStreamer};
+pub use browser::{
+    launch_browser, launch_browser_with, remove_profile, LaunchOptions, ProfileGuard,
+    DEFAULT_WINDOW_SIZE,
+};
+pub use cdp::RawCommand;
+pub use redirect::{host, Redirect, RedirectWatcher};
+pub use screenshot::{capture_screenshot, Screencast, ScreenshotStreamer, DEFAULT_QUALITY};
+pub use tabs::{ClosedTab, TabChanges, TabInfo, TabManager};
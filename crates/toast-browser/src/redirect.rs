@@ -0,0 +1,159 @@
+/// A change of the page URL that the user did not initiate
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Redirect {
+    pub from: String,
+    pub to: String,
+    /// The redirect left the original site (different host)
+    pub cross_site: bool,
+}
+
+/// Pages remembered as having redirected; the oldest are forgotten past this
+const MAX_REDIRECTED: usize = 64;
+
+/// Tracks the page URL and reports meta-refresh / JavaScript / HTTP redirects
+///
+/// Feed it the URL observed on the page; it reports a [`Redirect`] whenever the URL
+/// changes without the app having navigated there itself (see [`RedirectWatcher::expect`])
+/// or the user having clicked or typed on the page since (see
+/// [`RedirectWatcher::user_input`]).
+#[derive(Debug, Clone)]
+pub struct RedirectWatcher {
+    current: String,
+    /// The user clicked or typed on the page since the URL last changed
+    user_input: bool,
+    /// Pages that redirected, most recent last
+    redirected: Vec<String>,
+}
+
+impl RedirectWatcher {
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            current: url.into(),
+            user_input: false,
+            redirected: Vec::new(),
+        }
+    }
+
+    /// The most recently observed URL
+    pub fn current(&self) -> &str {
+        &self.current
+    }
+
+    /// Record a navigation initiated by the user, so it is not reported as a redirect
+    pub fn expect(&mut self, url: impl Into<String>) {
+        self.current = url.into();
+        self.user_input = false;
+    }
+
+    /// Record a click or key press on the page; the next URL change may be a link the user
+    /// followed, so it is not reported as a redirect
+    pub fn user_input(&mut self) {
+        self.user_input = true;
+    }
+
+    /// Observe the page URL, returning a redirect if it changed
+    pub fn observe(&mut self, url: &str) -> Option<Redirect> {
+        if same_document(&self.current, url) {
+            self.current = url.to_string();
+            return None;
+        }
+
+        let from = std::mem::replace(&mut self.current, url.to_string());
+        if std::mem::take(&mut self.user_input) {
+            return None;
+        }
+        if self.redirected.len() == MAX_REDIRECTED {
+            self.redirected.remove(0);
+        }
+        self.redirected.push(from.clone());
+        let cross_site = host(&from) != host(url);
+        Some(Redirect {
+            from,
+            to: url.to_string(),
+            cross_site,
+        })
+    }
+
+    /// Whether the page at `url` redirected elsewhere; going back or forward skips it, as
+    /// it would only redirect again
+    pub fn redirected_from(&self, url: &str) -> bool {
+        self.redirected.iter().any(|from| same_document(from, url))
+    }
+}
+
+/// URLs that differ only by fragment (or a trailing slash) are the same document
+fn same_document(a: &str, b: &str) -> bool {
+    let strip = |u: &str| -> String {
+        let without_fragment = u.split('#').next().unwrap_or(u);
+        without_fragment.trim_end_matches('/').to_string()
+    };
+    strip(a) == strip(b)
+}
+
+/// Extract the lowercase host (without port or `www.` prefix) from a URL
+pub fn host(url: &str) -> String {
+    let after_scheme = url.split_once("://").map(|(_, rest)| rest).unwrap_or(url);
+    let authority = after_scheme
+        .split(['/', '?', '#'])
+        .next()
+        .unwrap_or(after_scheme);
+    // Drop credentials and port
+    let host_port = authority.rsplit('@').next().unwrap_or(authority);
+    let host = host_port.split(':').next().unwrap_or(host_port);
+    let host = host.to_ascii_lowercase();
+    host.strip_prefix("www.")
+        .map(str::to_string)
+        .unwrap_or(host)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_host_extraction() {
+        assert_eq!(host("https://www.Example.com/path?q=1"), "example.com");
+        assert_eq!(host("http://user:pw@example.com:8080/"), "example.com");
+        assert_eq!(host("https://news.example.com"), "news.example.com");
+    }
+
+    #[test]
+    fn test_fragment_change_is_not_a_redirect() {
+        let mut watcher = RedirectWatcher::new("https://example.com/");
+        assert_eq!(watcher.observe("https://example.com/#section"), None);
+        assert_eq!(watcher.observe("https://example.com"), None);
+    }
+
+    #[test]
+    fn test_same_site_and_cross_site_redirects() {
+        let mut watcher = RedirectWatcher::new("https://example.com/");
+
+        let redirect = watcher.observe("https://www.example.com/login").unwrap();
+        assert!(!redirect.cross_site);
+        assert_eq!(redirect.from, "https://example.com/");
+
+        let redirect = watcher.observe("https://tracker.invalid/").unwrap();
+        assert!(redirect.cross_site);
+        assert_eq!(watcher.current(), "https://tracker.invalid/");
+    }
+
+    #[test]
+    fn test_followed_link_is_not_reported() {
+        let mut watcher = RedirectWatcher::new("https://example.com/");
+        watcher.user_input();
+        assert_eq!(watcher.observe("https://example.com/#top"), None);
+        assert_eq!(watcher.observe("https://other.org/"), None);
+        assert!(!watcher.redirected_from("https://example.com/"));
+
+        // Input only covers the next change
+        assert!(watcher.observe("https://tracker.invalid/").is_some());
+        assert!(watcher.redirected_from("https://other.org"));
+    }
+
+    #[test]
+    fn test_expected_navigation_is_not_reported() {
+        let mut watcher = RedirectWatcher::new("https://example.com/");
+        watcher.expect("https://other.org/");
+        assert_eq!(watcher.observe("https://other.org/"), None);
+    }
+}
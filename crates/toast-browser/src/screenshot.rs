@@ -1,14 +1,62 @@
 use anyhow::{Context, Result};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
 use bytes::Bytes;
 use chromiumoxide::browser::Browser;
-use chromiumoxide::cdp::browser_protocol::page::{
-    CaptureScreenshotFormat, CaptureScreenshotParams,
-};
+use chromiumoxide::cdp::browser_protocol::browser::CloseParams;
+use chromiumoxide::cdp::browser_protocol::emulation::SetDeviceMetricsOverrideParams;
 use chromiumoxide::cdp::browser_protocol::input::{
-    DispatchMouseEventParams, DispatchMouseEventType, MouseButton,
+    DispatchMouseEventParams, DispatchMouseEventType, InsertTextParams, MouseButton,
+};
+use chromiumoxide::cdp::browser_protocol::page::{
+    CaptureScreenshotFormat, CaptureScreenshotParams, EventScreencastFrame,
+    GetNavigationHistoryParams, GetNavigationHistoryReturns, NavigateToHistoryEntryParams,
+    NavigationEntry, ReloadParams, ScreencastFrameAckParams, StartScreencastFormat,
+    StartScreencastParams, StopLoadingParams, StopScreencastParams, Viewport,
 };
+use chromiumoxide::listeners::EventStream;
 use chromiumoxide::page::Page;
-use toast_core::{ImageFormat, Screenshot};
+use crossterm::event::KeyEvent;
+use futures::StreamExt;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use toast_core::{Clip, Dimensions, FrameMetadata, ImageFormat, Screenshot};
+use tokio::sync::Notify;
+
+use crate::cdp::RawCommand;
+use crate::keys::key_events;
+use crate::tabs::{ClosedTab, ClosedTabs, ScrollMemory, TabChanges, TabInfo, TabManager};
+
+/// JPEG/WebP quality used unless lowered with [`ScreenshotStreamer::set_quality`]
+pub const DEFAULT_QUALITY: u8 = 85;
+
+/// DOM id of the element injected by the latency diagnostic
+const LATENCY_MARKER_ID: &str = "__toast_latency_marker";
+
+/// Mute (`true`) or unmute the page's audio and video elements, and tell whether any of
+/// them is playing sound, or would be if not muted by toast
+///
+/// Elements toast muted are marked so that unmuting leaves those the page muted itself
+/// alone. Sound made with Web Audio goes unnoticed.
+const AUDIO_SCRIPT: &str = r#"(mute => {
+    const media = Array.from(document.querySelectorAll('audio, video'));
+    for (const m of media) {
+        if (mute && !m.muted) {
+            m.muted = true;
+            m.dataset.toastMuted = '1';
+        } else if (!mute && m.dataset.toastMuted) {
+            m.muted = false;
+            delete m.dataset.toastMuted;
+        }
+    }
+    return media.some(m => !m.paused && !m.ended && m.readyState > 2 && m.volume > 0
+        && (!m.muted || m.dataset.toastMuted === '1'));
+})"#;
+
+/// Size of the latency marker in CSS pixels (width, height)
+/// Large enough to cover at least a few terminal cells at any sensible terminal size
+const LATENCY_MARKER_SIZE: (u32, u32) = (160, 80);
 
 /// Capture a screenshot from a browser page
 pub async fn capture_screenshot(browser: &Browser, url: &str) -> Result<Screenshot> {
@@ -29,7 +77,7 @@ pub async fn capture_screenshot(browser: &Browser, url: &str) -> Result<Screensh
     // Capture screenshot as JPEG (faster than PNG)
     let params = CaptureScreenshotParams::builder()
         .format(CaptureScreenshotFormat::Jpeg)
-        .quality(85) // Good quality/speed tradeoff
+        .quality(DEFAULT_QUALITY as i64) // Good quality/speed tradeoff
         .build();
 
     let screenshot_data = page
@@ -40,24 +88,238 @@ pub async fn capture_screenshot(browser: &Browser, url: &str) -> Result<Screensh
     Ok(Screenshot {
         data: Bytes::from(screenshot_data),
         format: ImageFormat::Jpeg,
+        metadata: FrameMetadata {
+            url: Some(url.to_string()),
+            ..FrameMetadata::default()
+        },
+    })
+}
+
+/// CDP capture parameters for a screenshot format
+fn capture_params(
+    format: ImageFormat,
+    quality: u8,
+    clip: Option<Clip>,
+) -> Result<CaptureScreenshotParams> {
+    let mut builder = CaptureScreenshotParams::builder();
+    if let Some(clip) = clip {
+        builder = builder.clip(Viewport {
+            x: clip.x,
+            y: clip.y,
+            width: clip.width,
+            height: clip.height,
+            scale: clip.scale,
+        });
+    }
+    let quality = quality as i64;
+    let builder = match format {
+        // Quality only applies to the lossy formats
+        ImageFormat::Jpeg => builder
+            .format(CaptureScreenshotFormat::Jpeg)
+            .quality(quality),
+        ImageFormat::Webp => builder
+            .format(CaptureScreenshotFormat::Webp)
+            .quality(quality),
+        ImageFormat::Png => builder.format(CaptureScreenshotFormat::Png),
+        ImageFormat::RawRgba { .. } => {
+            return Err(anyhow::anyhow!(
+                "Page.captureScreenshot cannot return raw pixels; use a raw capture backend"
+            ))
+        }
+    };
+    Ok(builder.build())
+}
+
+/// Scroll offset of `page` in CSS pixels
+async fn page_scroll(page: &Page) -> Result<(f64, f64)> {
+    let result = page
+        .evaluate_expression("[window.scrollX, window.scrollY]")
+        .await
+        .context("Failed to query scroll position")?;
+    let [x, y]: [f64; 2] = result
+        .into_value()
+        .context("Unexpected scroll position value")?;
+    Ok((x, y))
+}
+
+/// Mute or unmute `page` with [`AUDIO_SCRIPT`]; returns whether it is audible
+async fn page_audio(page: &Page, muted: bool) -> Result<bool> {
+    page.evaluate_expression(format!("{}({})", AUDIO_SCRIPT, muted))
+        .await
+        .context("Failed to query page audio")?
+        .into_value()
+        .context("Unexpected page audio value")
+}
+
+/// Emulate a `width`x`height` viewport of CSS pixels
+fn device_metrics(width: u32, height: u32) -> SetDeviceMetricsOverrideParams {
+    SetDeviceMetricsOverrideParams::new(width.max(1) as i64, height.max(1) as i64, 1.0, false)
+}
+
+/// Index of the history entry `step` entries away from `current`, if there is one
+fn history_step(current: i64, entries: usize, step: i64) -> Option<usize> {
+    let index = current.checked_add(step)?;
+    (0..entries as i64)
+        .contains(&index)
+        .then_some(index as usize)
+}
+
+/// Index of the entry of history `urls` that going `step` entries from `current` lands on,
+/// passing over entries `skip` matches, if there is one
+fn history_target(
+    urls: &[&str],
+    current: i64,
+    step: i64,
+    skip: impl Fn(&str) -> bool,
+) -> Option<usize> {
+    let mut index = history_step(current, urls.len(), step)?;
+    while skip(urls[index]) {
+        index = history_step(index as i64, urls.len(), step)?;
+    }
+    Some(index)
+}
+
+/// The entry of `history` the page is on
+fn current_entry(history: &GetNavigationHistoryReturns) -> Option<&NavigationEntry> {
+    usize::try_from(history.current_index)
+        .ok()
+        .and_then(|index| history.entries.get(index))
+}
+
+/// Screencast encoding for a screenshot format; screencasts only come as JPEG or PNG
+fn screencast_format(format: ImageFormat) -> Result<StartScreencastFormat> {
+    match format {
+        ImageFormat::Jpeg => Ok(StartScreencastFormat::Jpeg),
+        ImageFormat::Png => Ok(StartScreencastFormat::Png),
+        _ => Err(anyhow::anyhow!(
+            "Page.startScreencast cannot send {:?} frames, only JPEG or PNG",
+            format
+        )),
+    }
+}
+
+async fn start_screencast(page: &Page, format: StartScreencastFormat, quality: u8) -> Result<()> {
+    let params = StartScreencastParams::builder()
+        .format(format)
+        .quality(quality as i64)
+        .build();
+    page.execute(params)
+        .await
+        .context("Failed to start screencast")?;
+    Ok(())
+}
+
+/// URL, scroll offset, viewport size, title, load state and document height of a page, in
+/// one round trip
+async fn page_state(page: &Page) -> Result<FrameMetadata> {
+    let result = page
+        .evaluate_expression(
+            "[location.href, window.scrollX, window.scrollY, window.innerWidth, \
+             window.innerHeight, document.title, document.readyState, \
+             document.documentElement.scrollHeight]",
+        )
+        .await
+        .context("Failed to query page state")?;
+    let (url, x, y, width, height, title, ready_state, document_height): (
+        String,
+        f64,
+        f64,
+        u32,
+        u32,
+        String,
+        String,
+        u32,
+    ) = result.into_value().context("Unexpected page state value")?;
+
+    Ok(FrameMetadata {
+        url: Some(url),
+        scroll: (x, y),
+        viewport: Some(Dimensions::new(width, height)),
+        captured_at: None,
+        title: (!title.is_empty()).then_some(title),
+        loading: ready_state != "complete",
+        document_height: Some(document_height),
+        clip: None,
     })
 }
 
+/// An open tab: its page, and what the tab bar shows for it
+#[derive(Debug, Clone)]
+struct Tab {
+    page: Page,
+    info: TabInfo,
+    /// Where each page of the tab's history was left
+    scrolls: ScrollMemory,
+}
+
+impl Tab {
+    fn new(page: Page, url: &str) -> Self {
+        Self {
+            page,
+            info: TabInfo::new(url),
+            scrolls: ScrollMemory::default(),
+        }
+    }
+}
+
 /// Screenshot stream at a target frame rate
 pub struct ScreenshotStreamer {
     browser: Browser,
     url: String,
-    page: Option<Page>,
+    /// Open tabs, once initialized; everything acts on the shown one
+    tabs: Mutex<Option<TabManager<Tab>>>,
+    /// Wakes a running screencast to follow a switch to another tab
+    switched: Notify,
+    /// Tabs closed, to reopen
+    closed: Mutex<ClosedTabs>,
+    /// Viewport size set with [`set_viewport`](ScreenshotStreamer::set_viewport), for new tabs
+    viewport: Mutex<Option<(u32, u32)>>,
+    format: ImageFormat,
+    /// Lossy encoding quality (0-100), adjustable while streaming
+    quality: AtomicU8,
+    /// Part of the viewport to capture instead of all of it
+    clip: Mutex<Option<Clip>>,
 }
 
 impl ScreenshotStreamer {
     pub fn new(browser: Browser, url: String) -> Self {
-        Self { browser, url, page: None }
+        Self {
+            browser,
+            url,
+            tabs: Mutex::new(None),
+            switched: Notify::new(),
+            closed: Mutex::default(),
+            viewport: Mutex::new(None),
+            format: ImageFormat::Jpeg,
+            quality: AtomicU8::new(DEFAULT_QUALITY),
+            clip: Mutex::new(None),
+        }
+    }
+
+    /// Request screenshots in the given format (JPEG by default)
+    pub fn with_format(mut self, format: ImageFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Change the JPEG/WebP quality of the following captures (lower is smaller and faster)
+    pub fn set_quality(&self, quality: u8) {
+        self.quality.store(quality.min(100), Ordering::Relaxed);
+    }
+
+    /// Capture only `clip` of the viewport from now on, or all of it with `None`
+    ///
+    /// A clip scaled up shows its part of the page in more detail than a capture of the
+    /// whole viewport would. Only [`capture`](Self::capture) clips; screencast frames
+    /// always show the whole viewport.
+    pub fn set_clip(&self, clip: Option<Clip>) {
+        *self.clip.lock().unwrap_or_else(|e| e.into_inner()) = clip;
     }
 
     /// Initialize the page (call this once before capturing)
     pub async fn initialize(&mut self) -> Result<()> {
-        let page = self.browser
+        let page = self
+            .browser
             .new_page(&self.url)
             .await
             .context("Failed to create new page")?;
@@ -71,35 +333,343 @@ impl ScreenshotStreamer {
 
         tracing::info!("Page initialized and ready for interaction");
 
-        self.page = Some(page);
+        *self.tabs.get_mut().unwrap_or_else(|e| e.into_inner()) =
+            Some(TabManager::new(Tab::new(page, &self.url)));
+        Ok(())
+    }
+
+    /// The shown tab's page
+    fn page(&self) -> Option<Page> {
+        let tabs = self.tabs.lock().unwrap_or_else(|e| e.into_inner());
+        tabs.as_ref().map(|tabs| tabs.active().page.clone())
+    }
+
+    /// Run `f` on the open tabs
+    fn with_tabs<R>(&self, f: impl FnOnce(&mut TabManager<Tab>) -> Result<R>) -> Result<R> {
+        let mut tabs = self.tabs.lock().unwrap_or_else(|e| e.into_inner());
+        let tabs = tabs
+            .as_mut()
+            .context("Page not initialized. Call initialize() first.")?;
+        f(tabs)
+    }
+
+    /// Open a blank tab after the shown one, and show it
+    ///
+    /// Doesn't wait for any page to load: [`navigate`](Self::navigate) the new tab to one.
+    pub async fn open_tab(&self) -> Result<()> {
+        let page = self
+            .browser
+            .new_page("about:blank")
+            .await
+            .context("Failed to open a new tab")?;
+        let index = match self.add_tab(page.clone()).await {
+            Ok(index) => index,
+            Err(e) => {
+                // No tab refers to the page, so nothing else would ever close it
+                if let Err(close) = page.close().await {
+                    tracing::debug!("Failed to close tab: {}", close);
+                }
+                return Err(e);
+            }
+        };
+        tracing::info!("Opened tab {}", index + 1);
+        self.switched.notify_one();
+        Ok(())
+    }
+
+    /// Size a newly opened page like the others and add it after the shown tab
+    async fn add_tab(&self, page: Page) -> Result<usize> {
+        let viewport = *self.viewport.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some((width, height)) = viewport {
+            page.execute(device_metrics(width, height))
+                .await
+                .context("Failed to set viewport size")?;
+        }
+        self.with_tabs(|tabs| Ok(tabs.add(Tab::new(page, "about:blank"))))
+    }
+
+    /// Close the tab at `index`; the last tab can't be closed
+    ///
+    /// Where the tab was is kept for [`take_closed_tab`](Self::take_closed_tab).
+    pub async fn close_tab(&self, index: usize) -> Result<()> {
+        let tab = self.with_tabs(|tabs| {
+            let tab = tabs.remove(index)?;
+            tabs.active_mut().info.retitled = false;
+            Ok(tab)
+        })?;
+        self.switched.notify_one();
+        let url = tab.page.url().await.ok().flatten().unwrap_or(tab.info.url);
+        let scroll = page_scroll(&tab.page).await.unwrap_or_default();
+        self.closed
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(ClosedTab { url, scroll });
+        tab.page.close().await.context("Failed to close tab")?;
+        tracing::info!("Closed tab {}", index + 1);
+        Ok(())
+    }
+
+    /// Take the most recently closed tab, to reopen it, if any
+    pub fn take_closed_tab(&self) -> Option<ClosedTab> {
+        self.closed.lock().unwrap_or_else(|e| e.into_inner()).pop()
+    }
+
+    /// Show the tab at `index`
+    pub fn switch_tab(&self, index: usize) -> Result<()> {
+        self.with_tabs(|tabs| {
+            tabs.switch(index)?;
+            tabs.active_mut().info.retitled = false;
+            Ok(())
+        })?;
+        self.switched.notify_one();
+        Ok(())
+    }
+
+    /// The open tabs, in tab bar order, and the index of the shown one
+    pub fn tabs(&self) -> (Vec<TabInfo>, usize) {
+        let tabs = self.tabs.lock().unwrap_or_else(|e| e.into_inner());
+        tabs.as_ref().map_or((Vec::new(), 0), |tabs| {
+            let infos = tabs.list().iter().map(|tab| tab.info.clone()).collect();
+            (infos, tabs.active_index())
+        })
+    }
+
+    /// Catch up with the titles of the tabs in the background, marking those whose title
+    /// changed [`retitled`](TabInfo::retitled), and with which tabs are
+    /// [`audible`](TabInfo::audible)
+    ///
+    /// Media a muted tab started playing since the last poll is muted too. The shown tab
+    /// keeps its title up from its frames instead.
+    pub async fn poll_tabs(&self) -> Result<TabChanges> {
+        let pages: Vec<(Page, bool, bool)> = self.with_tabs(|tabs| {
+            let active = tabs.active_index();
+            Ok(tabs
+                .list()
+                .iter()
+                .enumerate()
+                .map(|(index, tab)| (tab.page.clone(), index == active, tab.info.muted))
+                .collect())
+        })?;
+        let mut polled = Vec::with_capacity(pages.len());
+        for (page, shown, muted) in pages {
+            let title = if shown {
+                None
+            } else {
+                match page.get_title().await {
+                    Ok(title) => Some(title),
+                    Err(e) => {
+                        tracing::debug!("Failed to get tab title: {}", e);
+                        None
+                    }
+                }
+            };
+            let audible = match page_audio(&page, muted).await {
+                Ok(audible) => Some(audible),
+                Err(e) => {
+                    tracing::debug!("{:#}", e);
+                    None
+                }
+            };
+            polled.push((page, title, audible));
+        }
+
+        // Tabs may have been switched, opened or closed meanwhile
+        self.with_tabs(|tabs| {
+            let active = tabs.active_index();
+            let mut changes = TabChanges::default();
+            for (page, title, audible) in polled {
+                let found = tabs
+                    .list_mut()
+                    .iter_mut()
+                    .enumerate()
+                    .find(|(_, tab)| tab.page.target_id() == page.target_id());
+                let Some((index, tab)) = found else {
+                    continue;
+                };
+                if let Some(audible) = audible {
+                    changes.audio |= tab.info.audible != audible;
+                    tab.info.audible = audible;
+                }
+                if let Some(title) = title {
+                    if index != active && tab.info.retitle(title) {
+                        changes.retitled.push((index, tab.info.label().to_string()));
+                    }
+                }
+            }
+            Ok(changes)
+        })
+    }
+
+    /// Mute the shown tab, or unmute it if it is muted; returns whether it is now muted
+    pub async fn toggle_mute(&self) -> Result<bool> {
+        let (page, muted) = self.with_tabs(|tabs| {
+            let tab = tabs.active_mut();
+            tab.info.muted = !tab.info.muted;
+            Ok((tab.page.clone(), tab.info.muted))
+        })?;
+        let audible = page_audio(&page, muted).await?;
+        self.with_tab_of(&page, |tab| tab.info.audible = audible);
+        tracing::info!("Tab {}", if muted { "muted" } else { "unmuted" });
+        Ok(muted)
+    }
+
+    /// URL and scroll offset of every open tab, in tab bar order, and the index of the
+    /// shown one
+    ///
+    /// A tab that can't be queried keeps the URL it was last seen at, scrolled to the top.
+    pub async fn tab_positions(&self) -> Result<(Vec<(String, (f64, f64))>, usize)> {
+        let (pages, active) = self.with_tabs(|tabs| {
+            let pages: Vec<(Page, String)> = tabs
+                .list()
+                .iter()
+                .map(|tab| (tab.page.clone(), tab.info.url.clone()))
+                .collect();
+            Ok((pages, tabs.active_index()))
+        })?;
+        let mut positions = Vec::with_capacity(pages.len());
+        for (page, seen) in pages {
+            let url = page.url().await.ok().flatten().unwrap_or(seen);
+            let scroll = page_scroll(&page).await.unwrap_or_default();
+            positions.push((url, scroll));
+        }
+        Ok((positions, active))
+    }
+
+    /// Update the tab showing `page` from the metadata of a frame of it
+    fn update_tab(&self, page: &Page, metadata: &FrameMetadata) {
+        self.with_tab_of(page, |tab| tab.info.update(metadata));
+    }
+
+    /// Run `f` on the tab showing `page`, if it is still open
+    fn with_tab_of<R>(&self, page: &Page, f: impl FnOnce(&mut Tab) -> R) -> Option<R> {
+        let mut tabs = self.tabs.lock().unwrap_or_else(|e| e.into_inner());
+        tabs.as_mut()
+            .and_then(|tabs| {
+                tabs.list_mut()
+                    .iter_mut()
+                    .find(|tab| tab.page.target_id() == page.target_id())
+            })
+            .map(f)
+    }
+
+    /// Remember how far `page` is scrolled on its current history entry, to go back to
+    /// after [`restore_scroll`](Self::restore_scroll)
+    async fn remember_scroll(&self, page: &Page, entry: i64) {
+        match page_scroll(page).await {
+            Ok(offset) => {
+                self.with_tab_of(page, |tab| tab.scrolls.remember(entry, offset));
+            }
+            Err(e) => tracing::debug!("Failed to get scroll position: {:#}", e),
+        }
+    }
+
+    /// Once the page gone to by [`back`](Self::back), [`forward`](Self::forward) or
+    /// [`reload`](Self::reload) has loaded, scroll it to where it was left
+    ///
+    /// Pages that were never left scrolled stay as they load.
+    pub async fn restore_scroll(&self) -> Result<()> {
+        let Some(page) = self.page() else {
+            return Err(anyhow::anyhow!(
+                "Page not initialized. Call initialize() first."
+            ));
+        };
+        page.wait_for_navigation()
+            .await
+            .context("Failed to wait for navigation")?;
+        let history = page
+            .execute(GetNavigationHistoryParams::default())
+            .await
+            .context("Failed to get navigation history")?
+            .result;
+        let Some(entry) = current_entry(&history) else {
+            return Ok(());
+        };
+        let Some((x, y)) = self
+            .with_tab_of(&page, |tab| tab.scrolls.recall(entry.id))
+            .flatten()
+        else {
+            return Ok(());
+        };
+        page.evaluate_expression(format!("window.scrollTo({}, {})", x, y))
+            .await
+            .context("Failed to restore scroll position")?;
+        tracing::info!("Restored scroll position ({}, {}) of {}", x, y, entry.url);
         Ok(())
     }
 
     /// Capture a single screenshot
     pub async fn capture(&self) -> Result<Screenshot> {
-        if let Some(page) = &self.page {
-            let params = CaptureScreenshotParams::builder()
-                .format(CaptureScreenshotFormat::Jpeg)
-                .quality(85)
-                .build();
+        if let Some(page) = &self.page() {
+            let clip = *self.clip.lock().unwrap_or_else(|e| e.into_inner());
+            // The clip is in viewport coordinates, and the browser's are the document's
+            let page_clip = match clip {
+                Some(clip) => {
+                    let (x, y) = self.scroll_position().await?;
+                    Some(Clip {
+                        x: clip.x + x,
+                        y: clip.y + y,
+                        ..clip
+                    })
+                }
+                None => None,
+            };
+            let params =
+                capture_params(self.format, self.quality.load(Ordering::Relaxed), page_clip)?;
 
-            let screenshot_data = page
-                .screenshot(params)
-                .await
-                .context("Failed to capture screenshot")?;
+            // Page state is queried alongside the capture rather than after it; it can
+            // lag the pixels slightly if the page changes in between
+            let captured_at = SystemTime::now();
+            let (screenshot_data, state) =
+                futures::join!(page.screenshot(params), page_state(page));
+            let screenshot_data = screenshot_data.context("Failed to capture screenshot")?;
+
+            let mut metadata = state.unwrap_or_else(|e| {
+                tracing::debug!("Failed to query page state: {}", e);
+                FrameMetadata::default()
+            });
+            metadata.captured_at = Some(captured_at);
+            metadata.clip = clip;
+            self.update_tab(page, &metadata);
 
             Ok(Screenshot {
                 data: Bytes::from(screenshot_data),
-                format: ImageFormat::Jpeg,
+                format: self.format,
+                metadata,
             })
         } else {
-            Err(anyhow::anyhow!("Page not initialized. Call initialize() first."))
+            Err(anyhow::anyhow!(
+                "Page not initialized. Call initialize() first."
+            ))
         }
     }
 
+    /// Have the browser push frames as its compositor produces them, instead of polling
+    /// with [`capture`](Self::capture)
+    ///
+    /// A page that doesn't change sends nothing. Only JPEG and PNG can be streamed.
+    pub async fn screencast(&self) -> Result<Screencast<'_>> {
+        let page = self
+            .page()
+            .context("Page not initialized. Call initialize() first.")?;
+        let encoding = screencast_format(self.format)?;
+        let frames = page
+            .event_listener::<EventScreencastFrame>()
+            .await
+            .context("Failed to listen for screencast frames")?;
+        let quality = self.quality.load(Ordering::Relaxed);
+        start_screencast(&page, encoding.clone(), quality).await?;
+        Ok(Screencast {
+            streamer: self,
+            page,
+            frames,
+            encoding,
+            quality,
+        })
+    }
+
     /// Send a mouse click at the specified coordinates
     pub async fn click(&self, x: f64, y: f64) -> Result<()> {
-        if let Some(page) = &self.page {
+        if let Some(page) = &self.page() {
             tracing::info!("Starting click at ({}, {})", x, y);
 
             // Use CDP mouse events (simpler and more reliable)
@@ -155,19 +725,523 @@ impl ScreenshotStreamer {
         }
     }
 
+    /// Move the mouse without pressing a button, for hover effects
+    pub async fn move_mouse(&self, x: f64, y: f64) -> Result<()> {
+        if let Some(page) = &self.page() {
+            let mouse_move = DispatchMouseEventParams::builder()
+                .r#type(DispatchMouseEventType::MouseMoved)
+                .x(x)
+                .y(y)
+                .build()
+                .map_err(|e| anyhow::anyhow!("Failed to build mouse move event: {}", e))?;
+            page.execute(mouse_move)
+                .await
+                .context("Failed to execute mouse move")?;
+            Ok(())
+        } else {
+            Err(anyhow::anyhow!(
+                "Page not initialized. Call initialize() first."
+            ))
+        }
+    }
+
+    /// Press the left mouse button, starting a click or drag
+    pub async fn press_mouse(&self, x: f64, y: f64) -> Result<()> {
+        self.left_button_event(DispatchMouseEventType::MousePressed, x, y)
+            .await
+            .context("Failed to execute mouse down")
+    }
+
+    /// Move the mouse with the left button held down
+    pub async fn drag_mouse(&self, x: f64, y: f64) -> Result<()> {
+        self.left_button_event(DispatchMouseEventType::MouseMoved, x, y)
+            .await
+            .context("Failed to execute mouse drag")
+    }
+
+    /// Release the left mouse button, ending a click or drag
+    pub async fn release_mouse(&self, x: f64, y: f64) -> Result<()> {
+        self.left_button_event(DispatchMouseEventType::MouseReleased, x, y)
+            .await
+            .context("Failed to execute mouse up")
+    }
+
+    /// Dispatch a left button press, release or drag
+    async fn left_button_event(&self, kind: DispatchMouseEventType, x: f64, y: f64) -> Result<()> {
+        let Some(page) = &self.page() else {
+            return Err(anyhow::anyhow!(
+                "Page not initialized. Call initialize() first."
+            ));
+        };
+        // `buttons` is the bitmask of buttons held after the event; 1 is the left one
+        let (buttons, click_count) = match kind {
+            DispatchMouseEventType::MousePressed => (1, 1),
+            DispatchMouseEventType::MouseReleased => (0, 1),
+            _ => (1, 0),
+        };
+        let event = DispatchMouseEventParams::builder()
+            .r#type(kind)
+            .x(x)
+            .y(y)
+            .button(MouseButton::Left)
+            .buttons(buttons)
+            .click_count(click_count)
+            .build()
+            .map_err(|e| anyhow::anyhow!("Failed to build mouse event: {}", e))?;
+        page.execute(event).await?;
+        Ok(())
+    }
+
+    /// Press a key on the page, as the terminal reported it: key down, the character it
+    /// types and key up
+    ///
+    /// Keys the browser has no name for, and key releases, are ignored.
+    pub async fn key(&self, event: &KeyEvent) -> Result<()> {
+        if let Some(page) = &self.page() {
+            for params in key_events(event) {
+                page.execute(params)
+                    .await
+                    .context("Failed to dispatch key event")?;
+            }
+            Ok(())
+        } else {
+            Err(anyhow::anyhow!(
+                "Page not initialized. Call initialize() first."
+            ))
+        }
+    }
+
+    /// Type `text` into the focused element in one go, as an input method would
+    ///
+    /// Much faster than a key press per character for pasted text, but sends no key
+    /// events, so it only reaches editable elements.
+    pub async fn insert_text(&self, text: &str) -> Result<()> {
+        if let Some(page) = &self.page() {
+            page.execute(InsertTextParams::new(text))
+                .await
+                .context("Failed to insert text")?;
+            Ok(())
+        } else {
+            Err(anyhow::anyhow!(
+                "Page not initialized. Call initialize() first."
+            ))
+        }
+    }
+
     /// Scroll the page by a given pixel amount
     pub async fn scroll(&self, delta_y: i32) -> Result<()> {
-        if let Some(page) = &self.page {
+        self.scroll_by(0, delta_y).await
+    }
+
+    /// Scroll the page horizontally and vertically by a given pixel amount
+    pub async fn scroll_by(&self, delta_x: i32, delta_y: i32) -> Result<()> {
+        if let Some(page) = &self.page() {
             // Use JavaScript to scroll - this is the most reliable method
-            let script = format!("window.scrollBy(0, {})", delta_y);
+            let script = format!("window.scrollBy({}, {})", delta_x, delta_y);
             page.evaluate_expression(script)
                 .await
                 .context("Failed to execute scroll command")?;
 
-            tracing::info!("Scrolled by {} pixels", delta_y);
+            tracing::info!("Scrolled by ({}, {}) pixels", delta_x, delta_y);
             Ok(())
         } else {
-            Err(anyhow::anyhow!("Page not initialized. Call initialize() first."))
+            Err(anyhow::anyhow!(
+                "Page not initialized. Call initialize() first."
+            ))
+        }
+    }
+
+    /// Current scroll offset of the page in CSS pixels
+    pub async fn scroll_position(&self) -> Result<(f64, f64)> {
+        if let Some(page) = &self.page() {
+            page_scroll(page).await
+        } else {
+            Err(anyhow::anyhow!(
+                "Page not initialized. Call initialize() first."
+            ))
+        }
+    }
+
+    /// Scroll the page to an absolute offset in CSS pixels
+    pub async fn scroll_to(&self, x: f64, y: f64) -> Result<()> {
+        if let Some(page) = &self.page() {
+            let script = format!("window.scrollTo({}, {})", x, y);
+            page.evaluate_expression(script)
+                .await
+                .context("Failed to execute scroll command")?;
+            Ok(())
+        } else {
+            Err(anyhow::anyhow!(
+                "Page not initialized. Call initialize() first."
+            ))
         }
     }
+
+    /// Load a new URL in the page
+    pub async fn navigate(&self, url: &str) -> Result<()> {
+        if let Some(page) = &self.page() {
+            tracing::info!("Navigating to {}", url);
+            // The tab bar shows where the tab is going before the page loads
+            self.update_tab(
+                page,
+                &FrameMetadata {
+                    url: Some(url.to_string()),
+                    ..FrameMetadata::default()
+                },
+            );
+            page.goto(url)
+                .await
+                .with_context(|| format!("Failed to navigate to {}", url))?;
+            Ok(())
+        } else {
+            Err(anyhow::anyhow!(
+                "Page not initialized. Call initialize() first."
+            ))
+        }
+    }
+
+    /// Resize the viewport of every tab, and of tabs opened later, to `width`x`height` CSS
+    /// pixels, whatever the window size
+    ///
+    /// Pages lay themselves out again for the new size; frames captured after this are
+    /// that size too.
+    pub async fn set_viewport(&self, width: u32, height: u32) -> Result<()> {
+        *self.viewport.lock().unwrap_or_else(|e| e.into_inner()) = Some((width, height));
+        let pages: Vec<Page> =
+            self.with_tabs(|tabs| Ok(tabs.list().iter().map(|tab| tab.page.clone()).collect()))?;
+        for page in pages {
+            page.execute(device_metrics(width, height))
+                .await
+                .context("Failed to set viewport size")?;
+        }
+        tracing::info!("Viewport set to {}x{}", width, height);
+        Ok(())
+    }
+
+    /// Go back to the previous page in the tab's history, passing over pages `skip`
+    /// matches
+    ///
+    /// Returns the URL gone to, or `None` if this is the first page.
+    pub async fn back(&self, skip: impl Fn(&str) -> bool) -> Result<Option<String>> {
+        self.go_history(-1, skip).await
+    }
+
+    /// Go forward to the next page in the tab's history, passing over pages `skip` matches
+    ///
+    /// Returns the URL gone to, or `None` if this is the last page.
+    pub async fn forward(&self, skip: impl Fn(&str) -> bool) -> Result<Option<String>> {
+        self.go_history(1, skip).await
+    }
+
+    async fn go_history(&self, step: i64, skip: impl Fn(&str) -> bool) -> Result<Option<String>> {
+        if let Some(page) = &self.page() {
+            let history = page
+                .execute(GetNavigationHistoryParams::default())
+                .await
+                .context("Failed to get navigation history")?
+                .result;
+            let urls: Vec<&str> = history.entries.iter().map(|e| e.url.as_str()).collect();
+            let Some(index) = history_target(&urls, history.current_index, step, skip) else {
+                return Ok(None);
+            };
+            if let Some(current) = current_entry(&history) {
+                self.remember_scroll(page, current.id).await;
+            }
+            let entry = &history.entries[index];
+            tracing::info!("Going to history entry {}", entry.url);
+            page.execute(NavigateToHistoryEntryParams::new(entry.id))
+                .await
+                .with_context(|| format!("Failed to go to {}", entry.url))?;
+            Ok(Some(entry.url.clone()))
+        } else {
+            Err(anyhow::anyhow!(
+                "Page not initialized. Call initialize() first."
+            ))
+        }
+    }
+
+    /// Load the current page again
+    pub async fn reload(&self) -> Result<()> {
+        if let Some(page) = &self.page() {
+            tracing::info!("Reloading");
+            let history = page
+                .execute(GetNavigationHistoryParams::default())
+                .await
+                .context("Failed to get navigation history")?
+                .result;
+            if let Some(current) = current_entry(&history) {
+                self.remember_scroll(page, current.id).await;
+            }
+            page.execute(ReloadParams::default())
+                .await
+                .context("Failed to reload")?;
+            Ok(())
+        } else {
+            Err(anyhow::anyhow!(
+                "Page not initialized. Call initialize() first."
+            ))
+        }
+    }
+
+    /// Stop loading the page, keeping whatever has loaded so far
+    pub async fn stop(&self) -> Result<()> {
+        if let Some(page) = &self.page() {
+            page.execute(StopLoadingParams::default())
+                .await
+                .context("Failed to stop loading")?;
+            Ok(())
+        } else {
+            Err(anyhow::anyhow!(
+                "Page not initialized. Call initialize() first."
+            ))
+        }
+    }
+
+    /// Send any CDP command to the page and return its JSON result
+    ///
+    /// An escape hatch for protocol features toast doesn't wrap, e.g.
+    /// `send_cdp("Emulation.setCPUThrottlingRate", json!({ "rate": 4 }))`.
+    pub async fn send_cdp(
+        &self,
+        method: &str,
+        params: serde_json::Value,
+    ) -> Result<serde_json::Value> {
+        if let Some(page) = &self.page() {
+            let command = RawCommand::new(method, params)?;
+            let response = page
+                .execute(command)
+                .await
+                .with_context(|| format!("CDP command {} failed", method))?;
+            Ok(response.result)
+        } else {
+            Err(anyhow::anyhow!(
+                "Page not initialized. Call initialize() first."
+            ))
+        }
+    }
+
+    /// Ask Chrome to shut down gracefully (flushing and closing its profile)
+    pub async fn close_browser(&self) -> Result<()> {
+        if let Some(page) = &self.page() {
+            page.execute(CloseParams::default())
+                .await
+                .context("Failed to close browser")?;
+            Ok(())
+        } else {
+            Err(anyhow::anyhow!(
+                "Page not initialized. Call initialize() first."
+            ))
+        }
+    }
+
+    /// The URL the streamer was created with
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Query the page's current URL (changes after redirects and navigations)
+    pub async fn current_url(&self) -> Result<Option<String>> {
+        if let Some(page) = &self.page() {
+            page.url().await.context("Failed to query page URL")
+        } else {
+            Err(anyhow::anyhow!(
+                "Page not initialized. Call initialize() first."
+            ))
+        }
+    }
+
+    /// Inject a solid magenta marker into the top-left corner of the viewport
+    /// Used by the latency diagnostic to detect when a page change reaches the terminal
+    pub async fn show_latency_marker(&self) -> Result<()> {
+        if let Some(page) = &self.page() {
+            let script = format!(
+                "(() => {{ let m = document.getElementById('{id}'); \
+                 if (!m) {{ m = document.createElement('div'); m.id = '{id}'; \
+                 m.style.cssText = 'position:fixed;top:0;left:0;width:{w}px;height:{h}px;\
+                 background:#ff00ff;z-index:2147483647;pointer-events:none'; \
+                 document.documentElement.appendChild(m); }} }})()",
+                id = LATENCY_MARKER_ID,
+                w = LATENCY_MARKER_SIZE.0,
+                h = LATENCY_MARKER_SIZE.1,
+            );
+            page.evaluate_expression(script)
+                .await
+                .context("Failed to inject latency marker")?;
+            Ok(())
+        } else {
+            Err(anyhow::anyhow!(
+                "Page not initialized. Call initialize() first."
+            ))
+        }
+    }
+
+    /// Remove the latency marker injected by `show_latency_marker`
+    pub async fn hide_latency_marker(&self) -> Result<()> {
+        if let Some(page) = &self.page() {
+            let script = format!("document.getElementById('{}')?.remove()", LATENCY_MARKER_ID);
+            page.evaluate_expression(script)
+                .await
+                .context("Failed to remove latency marker")?;
+            Ok(())
+        } else {
+            Err(anyhow::anyhow!(
+                "Page not initialized. Call initialize() first."
+            ))
+        }
+    }
+}
+
+/// Frames pushed by the browser with `Page.startScreencast`, from
+/// [`ScreenshotStreamer::screencast`]
+pub struct Screencast<'a> {
+    streamer: &'a ScreenshotStreamer,
+    /// The page streamed, the shown tab's until another is switched to
+    page: Page,
+    frames: EventStream<EventScreencastFrame>,
+    encoding: StartScreencastFormat,
+    /// Quality the screencast was started at
+    quality: u8,
+}
+
+impl Screencast<'_> {
+    /// The next frame the shown tab draws; `None` once its page is gone
+    pub async fn next(&mut self) -> Option<Result<Screenshot>> {
+        loop {
+            tokio::select! {
+                event = self.frames.next() => {
+                    let event = event?;
+                    return Some(self.receive(&event).await);
+                }
+                _ = self.streamer.switched.notified() => {
+                    if let Err(e) = self.follow_shown_tab().await {
+                        return Some(Err(e));
+                    }
+                }
+            }
+        }
+    }
+
+    /// Stream the shown tab instead, if another tab is shown now
+    async fn follow_shown_tab(&mut self) -> Result<()> {
+        let Some(page) = self.streamer.page() else {
+            return Ok(());
+        };
+        if page.target_id() == self.page.target_id() {
+            return Ok(());
+        }
+        // The old tab may be closed already
+        if let Err(e) = self.page.execute(StopScreencastParams::default()).await {
+            tracing::debug!("Failed to stop screencast: {}", e);
+        }
+        self.frames = page
+            .event_listener::<EventScreencastFrame>()
+            .await
+            .context("Failed to listen for screencast frames")?;
+        start_screencast(&page, self.encoding.clone(), self.quality).await?;
+        self.page = page;
+        Ok(())
+    }
+
+    async fn receive(&mut self, event: &EventScreencastFrame) -> Result<Screenshot> {
+        // The browser sends the next frame once this one is acknowledged
+        let ack = self
+            .page
+            .execute(ScreencastFrameAckParams::new(event.session_id));
+        let (ack, state) = futures::join!(ack, page_state(&self.page));
+        ack.context("Failed to acknowledge screencast frame")?;
+        let encoded: &str = event.data.as_ref();
+        let data = BASE64
+            .decode(encoded)
+            .context("Invalid screencast frame data")?;
+
+        // The frame's own scroll offset and time are exact; the rest of the page state
+        // can lag it slightly
+        let mut metadata = state.unwrap_or_else(|e| {
+            tracing::debug!("Failed to query page state: {}", e);
+            FrameMetadata::default()
+        });
+        let frame = &event.metadata;
+        metadata.scroll = (frame.scroll_offset_x, frame.scroll_offset_y);
+        metadata.captured_at = Some(frame.timestamp.as_ref().map_or_else(SystemTime::now, |t| {
+            UNIX_EPOCH + Duration::from_secs_f64(*t.inner())
+        }));
+        self.streamer.update_tab(&self.page, &metadata);
+
+        // Adaptive quality lowers the quality under load; the screencast restarts to apply it
+        let quality = self.streamer.quality.load(Ordering::Relaxed);
+        if quality != self.quality {
+            self.page
+                .execute(StopScreencastParams::default())
+                .await
+                .context("Failed to stop screencast")?;
+            start_screencast(&self.page, self.encoding.clone(), quality).await?;
+            self.quality = quality;
+        }
+
+        Ok(Screenshot {
+            data: Bytes::from(data),
+            format: self.streamer.format,
+            metadata,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_screencast_formats() {
+        assert_eq!(
+            screencast_format(ImageFormat::Png).unwrap(),
+            StartScreencastFormat::Png
+        );
+        assert!(screencast_format(ImageFormat::Webp).is_err());
+    }
+
+    #[test]
+    fn test_history_step() {
+        assert_eq!(history_step(1, 3, -1), Some(0));
+        assert_eq!(history_step(1, 3, 1), Some(2));
+        assert_eq!(history_step(0, 3, -1), None);
+        assert_eq!(history_step(2, 3, 1), None);
+        assert_eq!(history_step(0, 0, 1), None);
+    }
+
+    #[test]
+    fn test_history_target_skips_redirecting_pages() {
+        let urls = [
+            "https://a.example/",
+            "https://short.link/x",
+            "https://b.example/",
+        ];
+        let redirected = |url: &str| url == "https://short.link/x";
+        assert_eq!(history_target(&urls, 2, -1, redirected), Some(0));
+        assert_eq!(history_target(&urls, 0, 1, redirected), Some(2));
+        assert_eq!(history_target(&urls, 2, -1, |_| false), Some(1));
+        // Nothing but redirects before the current page
+        assert_eq!(history_target(&urls[1..], 1, -1, redirected), None);
+    }
+
+    #[test]
+    fn test_capture_params_clip() {
+        let params = capture_params(ImageFormat::Png, 80, None).unwrap();
+        assert!(params.clip.is_none());
+
+        let clip = Clip {
+            x: 100.0,
+            y: 50.0,
+            width: 400.0,
+            height: 300.0,
+            scale: 2.0,
+        };
+        let params = capture_params(ImageFormat::Jpeg, 80, Some(clip)).unwrap();
+        let viewport = params.clip.unwrap();
+        assert_eq!((viewport.x, viewport.y), (100.0, 50.0));
+        assert_eq!(
+            (viewport.width, viewport.height, viewport.scale),
+            (400.0, 300.0, 2.0)
+        );
+        assert_eq!(params.quality, Some(80));
+    }
 }
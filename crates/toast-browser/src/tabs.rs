@@ -0,0 +1,348 @@
+use anyhow::Result;
+use chromiumoxide::page::Page;
+use std::collections::{HashMap, VecDeque};
+use toast_core::FrameMetadata;
+
+/// What a tab bar shows for a tab: its page's URL and title, as last seen
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TabInfo {
+    pub url: String,
+    pub title: Option<String>,
+    /// The title changed while the tab was in the background, since it was last shown
+    pub retitled: bool,
+    /// The page is playing sound, or would be if the tab weren't muted
+    pub audible: bool,
+    /// Sound from the page is muted
+    pub muted: bool,
+}
+
+impl TabInfo {
+    /// A tab opening `url`, with no title yet
+    pub fn new(url: &str) -> Self {
+        Self {
+            url: url.to_string(),
+            title: None,
+            retitled: false,
+            audible: false,
+            muted: false,
+        }
+    }
+
+    /// The page title, or the URL until the page has one
+    pub fn label(&self) -> &str {
+        match self.title.as_deref() {
+            Some(title) if !title.trim().is_empty() => title,
+            _ => &self.url,
+        }
+    }
+
+    /// Keep up with the page, from the metadata of a frame of it
+    pub fn update(&mut self, metadata: &FrameMetadata) {
+        if let Some(url) = &metadata.url {
+            // The title belongs to the old page until the new one reports its own
+            if *url != self.url {
+                self.url = url.clone();
+                self.title = None;
+            }
+        }
+        if metadata.title.is_some() {
+            self.title = metadata.title.clone();
+        }
+    }
+
+    /// Keep up with the title of the page while the tab is in the background; a change
+    /// from a title it already had marks the tab [`retitled`](Self::retitled)
+    ///
+    /// Returns whether it did. Pages between titles while they load have none, which
+    /// isn't a change.
+    pub fn retitle(&mut self, title: Option<String>) -> bool {
+        let Some(title) = title.filter(|title| !title.trim().is_empty()) else {
+            return false;
+        };
+        let changed = self.title.as_ref().is_some_and(|old| *old != title);
+        self.title = Some(title);
+        self.retitled |= changed;
+        changed
+    }
+}
+
+/// What changed in the tabs since they were last polled
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TabChanges {
+    /// Index and new title of each tab in the background whose title changed
+    pub retitled: Vec<(usize, String)>,
+    /// Some tab started or stopped playing sound
+    pub audio: bool,
+}
+
+impl TabChanges {
+    /// Whether nothing changed
+    pub fn is_empty(&self) -> bool {
+        self.retitled.is_empty() && !self.audio
+    }
+}
+
+/// Most history entries of a tab whose scroll offset is remembered
+const REMEMBERED_SCROLLS: usize = 100;
+
+/// Where a tab was scrolled on each page of its history, by CDP history entry id
+///
+/// Only the most recently remembered entries are kept.
+#[derive(Debug, Clone, Default)]
+pub struct ScrollMemory {
+    offsets: HashMap<i64, (f64, f64)>,
+    /// Entry ids, least recently remembered first
+    order: VecDeque<i64>,
+}
+
+impl ScrollMemory {
+    /// Remember that history entry `entry` was scrolled to `offset`
+    pub fn remember(&mut self, entry: i64, offset: (f64, f64)) {
+        if self.offsets.insert(entry, offset).is_some() {
+            self.order.retain(|&e| e != entry);
+        }
+        self.order.push_back(entry);
+        if self.order.len() > REMEMBERED_SCROLLS {
+            if let Some(oldest) = self.order.pop_front() {
+                self.offsets.remove(&oldest);
+            }
+        }
+    }
+
+    /// Where history entry `entry` was last scrolled to, if it is remembered
+    pub fn recall(&self, entry: i64) -> Option<(f64, f64)> {
+        self.offsets.get(&entry).copied()
+    }
+}
+
+/// Most closed tabs kept to reopen
+const CLOSED_TABS: usize = 25;
+
+/// A tab that was closed, to reopen where it was
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClosedTab {
+    pub url: String,
+    /// Page scroll offset in CSS pixels
+    pub scroll: (f64, f64),
+}
+
+/// Recently closed tabs, most recent last; only the last few are kept
+#[derive(Debug, Default)]
+pub struct ClosedTabs {
+    tabs: VecDeque<ClosedTab>,
+}
+
+impl ClosedTabs {
+    /// Keep `tab` to reopen, forgetting the oldest one if there are too many
+    pub fn push(&mut self, tab: ClosedTab) {
+        if self.tabs.len() == CLOSED_TABS {
+            self.tabs.pop_front();
+        }
+        self.tabs.push_back(tab);
+    }
+
+    /// Take the most recently closed tab, if any
+    pub fn pop(&mut self) -> Option<ClosedTab> {
+        self.tabs.pop_back()
+    }
+}
+
+/// The browser pages open as tabs, and which one is shown
+///
+/// There is always at least one tab; the last one can't be closed.
+#[derive(Debug)]
+pub struct TabManager<T = Page> {
+    tabs: Vec<T>,
+    active: usize,
+}
+
+impl<T> TabManager<T> {
+    /// `first` as the only tab, shown
+    pub fn new(first: T) -> Self {
+        Self {
+            tabs: vec![first],
+            active: 0,
+        }
+    }
+
+    /// Number of open tabs
+    pub fn len(&self) -> usize {
+        self.tabs.len()
+    }
+
+    /// Whether no tab is open; never true, as the last tab can't be closed
+    pub fn is_empty(&self) -> bool {
+        self.tabs.is_empty()
+    }
+
+    /// The open tabs, in tab bar order
+    pub fn list(&self) -> &[T] {
+        &self.tabs
+    }
+
+    /// The open tabs, in tab bar order, to change
+    pub fn list_mut(&mut self) -> &mut [T] {
+        &mut self.tabs
+    }
+
+    /// Index of the tab shown
+    pub fn active_index(&self) -> usize {
+        self.active
+    }
+
+    /// The tab shown
+    pub fn active(&self) -> &T {
+        &self.tabs[self.active]
+    }
+
+    /// The tab shown, to change
+    pub fn active_mut(&mut self) -> &mut T {
+        &mut self.tabs[self.active]
+    }
+
+    /// Add `tab` after the one shown and show it instead; returns its index
+    pub fn add(&mut self, tab: T) -> usize {
+        self.active += 1;
+        self.tabs.insert(self.active, tab);
+        self.active
+    }
+
+    /// Show the tab at `index`
+    pub fn switch(&mut self, index: usize) -> Result<()> {
+        anyhow::ensure!(index < self.tabs.len(), "No tab {}", index + 1);
+        self.active = index;
+        Ok(())
+    }
+
+    /// Take out the tab at `index`, showing its neighbour if it was shown
+    pub fn remove(&mut self, index: usize) -> Result<T> {
+        anyhow::ensure!(index < self.tabs.len(), "No tab {}", index + 1);
+        anyhow::ensure!(self.tabs.len() > 1, "Can't close the last tab");
+        let tab = self.tabs.remove(index);
+        if self.active > index || self.active == self.tabs.len() {
+            self.active -= 1;
+        }
+        Ok(tab)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metadata(url: &str, title: Option<&str>) -> FrameMetadata {
+        FrameMetadata {
+            url: Some(url.to_string()),
+            title: title.map(str::to_string),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_label_follows_the_page() {
+        let mut tab = TabInfo::new("https://example.com/");
+        assert_eq!(tab.label(), "https://example.com/");
+
+        tab.update(&metadata("https://example.com/", Some("Example")));
+        assert_eq!(tab.label(), "Example");
+
+        // A new page shows its URL until its title arrives
+        tab.update(&metadata("https://example.org/", None));
+        assert_eq!(tab.label(), "https://example.org/");
+
+        // A blank title falls back to the URL too
+        tab.update(&metadata("https://example.org/", Some("  ")));
+        assert_eq!(tab.label(), "https://example.org/");
+    }
+
+    #[test]
+    fn test_background_title_change_marks_the_tab() {
+        let mut tab = TabInfo::new("https://mail.example.com/");
+        // The first title the page gets is no change
+        assert!(!tab.retitle(Some("Inbox".to_string())));
+        assert!(!tab.retitle(None));
+        assert!(!tab.retitle(Some("Inbox".to_string())));
+        assert!(!tab.retitled);
+
+        assert!(tab.retitle(Some("(2) Inbox".to_string())));
+        assert!(tab.retitled);
+        assert_eq!(tab.label(), "(2) Inbox");
+
+        // It stays marked until the tab is shown
+        assert!(!tab.retitle(Some("(2) Inbox".to_string())));
+        assert!(tab.retitled);
+    }
+
+    #[test]
+    fn test_new_tab_opens_after_the_active_one() {
+        let mut tabs = TabManager::new("a");
+        tabs.add("b");
+        tabs.switch(0).unwrap();
+        assert_eq!(tabs.add("c"), 1);
+        assert_eq!(tabs.list(), &["a", "c", "b"]);
+        assert_eq!(*tabs.active(), "c");
+        assert!(tabs.switch(3).is_err());
+    }
+
+    #[test]
+    fn test_remove_keeps_a_tab_shown() {
+        let mut tabs = TabManager::new("a");
+        tabs.add("b");
+        tabs.add("c");
+
+        // Closing the last tab in the bar shows the one before it
+        assert_eq!(tabs.remove(2).unwrap(), "c");
+        assert_eq!(*tabs.active(), "b");
+
+        // Closing a tab before the shown one keeps showing the same tab
+        assert_eq!(tabs.remove(0).unwrap(), "a");
+        assert_eq!((tabs.active_index(), *tabs.active()), (0, "b"));
+
+        assert!(tabs.remove(0).is_err());
+        assert_eq!(tabs.len(), 1);
+    }
+
+    #[test]
+    fn test_scroll_memory() {
+        let mut memory = ScrollMemory::default();
+        assert_eq!(memory.recall(1), None);
+
+        memory.remember(1, (0.0, 500.0));
+        memory.remember(2, (10.0, 20.0));
+        assert_eq!(memory.recall(1), Some((0.0, 500.0)));
+
+        // Leaving an entry again replaces its offset
+        memory.remember(1, (0.0, 800.0));
+        assert_eq!(memory.recall(1), Some((0.0, 800.0)));
+
+        // The least recently remembered entries are forgotten first
+        for entry in 3..REMEMBERED_SCROLLS as i64 + 2 {
+            memory.remember(entry, (0.0, 0.0));
+        }
+        assert_eq!(memory.recall(2), None);
+        assert_eq!(memory.recall(1), Some((0.0, 800.0)));
+    }
+
+    #[test]
+    fn test_closed_tabs_reopen_most_recent_first() {
+        let closed = |n: usize| ClosedTab {
+            url: format!("https://example.com/{}", n),
+            scroll: (0.0, n as f64),
+        };
+        let mut tabs = ClosedTabs::default();
+        assert_eq!(tabs.pop(), None);
+
+        for n in 0..CLOSED_TABS + 2 {
+            tabs.push(closed(n));
+        }
+        assert_eq!(tabs.pop(), Some(closed(CLOSED_TABS + 1)));
+        assert_eq!(tabs.pop(), Some(closed(CLOSED_TABS)));
+        // The two oldest were forgotten
+        let mut rest = 0;
+        while let Some(tab) = tabs.pop() {
+            assert!(tab.scroll.1 >= 2.0);
+            rest += 1;
+        }
+        assert_eq!(rest, CLOSED_TABS - 2);
+    }
+}
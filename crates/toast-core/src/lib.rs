@@ -1,3 +1,5 @@
+pub mod paths;
+pub mod text;
 pub mod types;
 
 pub use types::*;
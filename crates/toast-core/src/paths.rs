@@ -0,0 +1,152 @@
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+/// Directory name used under each platform base directory
+const APP_DIR: &str = "toast";
+
+static PATHS: OnceLock<Paths> = OnceLock::new();
+
+/// Where toast keeps its files
+///
+/// Follows the XDG base directory spec on Linux (`$XDG_CONFIG_HOME/toast`, ...) and the
+/// platform conventions elsewhere (`~/Library/Application Support/toast` on macOS,
+/// `%APPDATA%\toast` on Windows). A single override root puts everything in one place.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Paths {
+    /// User configuration (config.toml)
+    pub config_dir: PathBuf,
+    /// Persistent data (profiles, bookmarks, history)
+    pub data_dir: PathBuf,
+    /// Disposable caches (lookup tables, the Chrome profile)
+    pub cache_dir: PathBuf,
+    /// Logs and other runtime state
+    pub state_dir: PathBuf,
+}
+
+impl Paths {
+    /// Platform directories, or `None` if the home directory cannot be determined
+    pub fn platform() -> Option<Self> {
+        let config = dirs::config_dir()?;
+        let data = dirs::data_dir()?;
+        let cache = dirs::cache_dir()?;
+        // Only Linux has a state directory; elsewhere logs live with the local data
+        let state = dirs::state_dir()
+            .or_else(dirs::data_local_dir)
+            .unwrap_or_else(|| data.clone());
+
+        Some(Self {
+            config_dir: config.join(APP_DIR),
+            data_dir: data.join(APP_DIR),
+            cache_dir: cache.join(APP_DIR),
+            state_dir: state.join(APP_DIR),
+        })
+    }
+
+    /// Everything under a single root directory (the `--data-dir` override)
+    pub fn under(root: impl AsRef<Path>) -> Self {
+        let root = root.as_ref();
+        Self {
+            config_dir: root.join("config"),
+            data_dir: root.join("data"),
+            cache_dir: root.join("cache"),
+            state_dir: root.join("state"),
+        }
+    }
+
+    pub fn config_file(&self) -> PathBuf {
+        self.config_dir.join("config.toml")
+    }
+
+    pub fn log_file(&self) -> PathBuf {
+        self.state_dir.join("toast.log")
+    }
+
+    /// Autosaved sessions, one per running toast, left behind only after a crash
+    pub fn sessions_dir(&self) -> PathBuf {
+        self.state_dir.join("sessions")
+    }
+
+    /// Chrome user data directory
+    pub fn browser_profile_dir(&self) -> PathBuf {
+        self.cache_dir.join("chrome-profile")
+    }
+
+    /// Quantizer lookup tables, one file per palette and color metric
+    pub fn lut_cache_dir(&self) -> PathBuf {
+        self.cache_dir.join("lut")
+    }
+
+    /// Create all directories that do not exist yet
+    pub fn create_dirs(&self) -> io::Result<()> {
+        for dir in [
+            &self.config_dir,
+            &self.data_dir,
+            &self.cache_dir,
+            &self.state_dir,
+        ] {
+            std::fs::create_dir_all(dir)?;
+        }
+        Ok(())
+    }
+}
+
+/// Set the process-wide paths, once, at startup
+///
+/// `data_dir` overrides the platform directories. Returns the paths in effect, which are
+/// the earlier ones if paths were already initialized.
+pub fn init(data_dir: Option<&Path>) -> &'static Paths {
+    PATHS.get_or_init(|| match data_dir {
+        Some(root) => Paths::under(root),
+        None => default_paths(),
+    })
+}
+
+/// The process-wide paths (platform defaults unless [`init`] chose otherwise)
+pub fn get() -> &'static Paths {
+    PATHS.get_or_init(default_paths)
+}
+
+/// Platform directories, falling back to `./.toast` when there is no home directory
+fn default_paths() -> Paths {
+    Paths::platform().unwrap_or_else(|| Paths::under(".toast"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_override_root_layout() {
+        let paths = Paths::under("/tmp/toast-test");
+        assert_eq!(
+            paths.config_file(),
+            PathBuf::from("/tmp/toast-test/config/config.toml")
+        );
+        assert_eq!(
+            paths.log_file(),
+            PathBuf::from("/tmp/toast-test/state/toast.log")
+        );
+        assert_eq!(
+            paths.sessions_dir(),
+            PathBuf::from("/tmp/toast-test/state/sessions")
+        );
+        assert!(paths
+            .browser_profile_dir()
+            .starts_with("/tmp/toast-test/cache"));
+    }
+
+    #[test]
+    fn test_platform_dirs_are_namespaced() {
+        if let Some(paths) = Paths::platform() {
+            for dir in [
+                &paths.config_dir,
+                &paths.data_dir,
+                &paths.cache_dir,
+                &paths.state_dir,
+            ] {
+                assert!(dir.ends_with(APP_DIR));
+            }
+        }
+    }
+}
@@ -0,0 +1,80 @@
+//! Text as a terminal lays it out: grapheme clusters, one or two columns wide
+
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+/// The grapheme clusters of `text` with the columns each takes (1 or 2)
+///
+/// Clusters that take no columns on their own, like control characters or a combining
+/// mark with nothing to combine with, are left out.
+pub fn graphemes(text: &str) -> impl Iterator<Item = (&str, usize)> {
+    text.graphemes(true).filter_map(|grapheme| {
+        let width = grapheme.width().min(2);
+        let shown = width > 0 && !grapheme.chars().all(char::is_control);
+        shown.then_some((grapheme, width))
+    })
+}
+
+/// Columns `text` takes on a terminal
+pub fn text_width(text: &str) -> usize {
+    graphemes(text).map(|(_, width)| width).sum()
+}
+
+/// Cut `text` to at most `max` columns, marking the cut with an ellipsis
+pub fn truncate(text: &str, max: usize) -> String {
+    if text_width(text) <= max {
+        return text.to_string();
+    }
+    let mut cut = String::new();
+    let mut width = 0;
+    for (grapheme, columns) in graphemes(text) {
+        if width + columns > max.saturating_sub(1) {
+            break;
+        }
+        cut.push_str(grapheme);
+        width += columns;
+    }
+    if max > 0 {
+        cut.push('…');
+    }
+    cut
+}
+
+/// `text` cut or padded with spaces to exactly `columns` columns
+pub fn fit(text: &str, columns: usize) -> String {
+    let mut fitted = truncate(text, columns);
+    let width = text_width(&fitted);
+    fitted.extend(std::iter::repeat_n(' ', columns.saturating_sub(width)));
+    fitted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_graphemes() {
+        let clusters: Vec<_> = graphemes("a中e\u{301}❤\u{fe0f}\u{7}").collect();
+        assert_eq!(
+            clusters,
+            vec![("a", 1), ("中", 2), ("e\u{301}", 1), ("❤\u{fe0f}", 2)]
+        );
+        assert_eq!(text_width("日本語"), 6);
+    }
+
+    #[test]
+    fn test_truncate() {
+        assert_eq!(truncate("example", 10), "example");
+        assert_eq!(truncate("example", 4), "exa…");
+        assert_eq!(truncate("example", 0), "");
+        // A wide character that doesn't fit before the ellipsis is left out whole
+        assert_eq!(truncate("日本語", 4), "日…");
+        assert_eq!(truncate("日本語", 5), "日本…");
+    }
+
+    #[test]
+    fn test_fit() {
+        assert_eq!(fit("日本語", 4), "日… ");
+        assert_eq!(fit("ab", 4), "ab  ");
+    }
+}
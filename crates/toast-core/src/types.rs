@@ -1,7 +1,12 @@
 use bytes::Bytes;
+use std::time::SystemTime;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+use crate::text::graphemes;
 
 /// Dimensions of a viewport or terminal
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Dimensions {
     pub width: u32,
     pub height: u32,
@@ -15,6 +20,7 @@ impl Dimensions {
 
 /// RGB color with 8-bit channels
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Rgb {
     pub r: u8,
     pub g: u8,
@@ -28,7 +34,8 @@ impl Rgb {
 }
 
 /// ANSI 256 color index (0-255)
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct AnsiColor(pub u8);
 
 impl AnsiColor {
@@ -38,24 +45,141 @@ impl AnsiColor {
 }
 
 /// A single terminal cell with character and colors
-#[derive(Debug, Clone, PartialEq, Eq)]
+///
+/// A double-width character takes two cells: its own, and a [continuation] after it
+/// that it covers on screen.
+///
+/// [continuation]: TerminalCell::CONTINUATION
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TerminalCell {
     pub character: char,
     pub foreground: AnsiColor,
     pub background: AnsiColor,
+    /// The rest of a grapheme cluster starting with `character`: combining marks, a
+    /// variation selector or the parts of an emoji sequence
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub cluster: Option<Box<str>>,
+}
+
+impl TerminalCell {
+    /// Character of the cell after a double-width character
+    pub const CONTINUATION: char = '\0';
+
+    /// A cell showing `grapheme` in `fg` on `bg`
+    pub fn from_grapheme(grapheme: &str, fg: AnsiColor, bg: AnsiColor) -> Self {
+        let mut chars = grapheme.chars();
+        let character = chars.next().unwrap_or(' ');
+        let rest = chars.as_str();
+        Self {
+            character,
+            foreground: fg,
+            background: bg,
+            cluster: (!rest.is_empty()).then(|| rest.into()),
+        }
+    }
+
+    /// The cell covered by a double-width character drawn in `fg` on `bg`
+    pub fn continuation(fg: AnsiColor, bg: AnsiColor) -> Self {
+        Self {
+            character: Self::CONTINUATION,
+            foreground: fg,
+            background: bg,
+            cluster: None,
+        }
+    }
+
+    pub fn is_continuation(&self) -> bool {
+        self.character == Self::CONTINUATION
+    }
+
+    /// Columns the cell's character takes on screen: 2 for a double-width character, 0
+    /// for a continuation or a character that doesn't show on its own
+    pub fn width(&self) -> usize {
+        match &self.cluster {
+            None => self.character.width().unwrap_or(0),
+            Some(rest) => {
+                let mut grapheme = self.character.to_string();
+                grapheme.push_str(rest);
+                grapheme.width().min(2)
+            }
+        }
+    }
+}
+
+/// A rectangle of the viewport in CSS pixels, captured on its own at `scale` times its size
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Clip {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+    pub scale: f64,
+}
+
+/// Page state a screenshot was captured in, carried along to the frames rendered from it
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FrameMetadata {
+    /// Page URL
+    pub url: Option<String>,
+    /// Page scroll offset in CSS pixels
+    pub scroll: (f64, f64),
+    /// Viewport size in CSS pixels; with `scroll`, the part of the page shown
+    pub viewport: Option<Dimensions>,
+    /// When the screenshot was taken
+    pub captured_at: Option<SystemTime>,
+    /// Document title
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub title: Option<String>,
+    /// The document was still loading (its `readyState` wasn't `complete`)
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub loading: bool,
+    /// Height of the whole document in CSS pixels
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub document_height: Option<u32>,
+    /// The part of the viewport captured, when the screenshot doesn't show all of it
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub clip: Option<Clip>,
 }
 
 /// Raw screenshot data from browser
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Screenshot {
     pub data: Bytes,
     pub format: ImageFormat,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub metadata: FrameMetadata,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ImageFormat {
+    #[default]
     Jpeg,
     Png,
+    /// Lossy WebP: smaller and faster to encode than JPEG at similar quality
+    Webp,
+    /// Uncompressed RGBA8 pixels, row-major, skipping encode and decode entirely
+    RawRgba {
+        width: u32,
+        height: u32,
+    },
+}
+
+/// Pixel buffer length that doesn't match an image's dimensions
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("pixel buffer is {actual} bytes, expected {expected} for {width}x{height}")]
+pub struct ImageSizeError {
+    pub actual: usize,
+    pub expected: usize,
+    pub width: u32,
+    pub height: u32,
 }
 
 /// Decoded RGB image
@@ -67,27 +191,148 @@ pub struct RgbImage {
 }
 
 impl RgbImage {
+    /// Wrap a pixel buffer, panicking if its length doesn't match the dimensions
+    ///
+    /// For buffers from untrusted sources use [`try_new`](Self::try_new).
     pub fn new(data: Vec<u8>, width: u32, height: u32) -> Self {
-        assert_eq!(data.len(), (width * height * 3) as usize);
-        Self { data, width, height }
+        Self::try_new(data, width, height).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Wrap a pixel buffer whose length must be `width * height * 3`
+    pub fn try_new(data: Vec<u8>, width: u32, height: u32) -> Result<Self, ImageSizeError> {
+        let expected = width as usize * height as usize * 3;
+        if data.len() != expected {
+            return Err(ImageSizeError {
+                actual: data.len(),
+                expected,
+                width,
+                height,
+            });
+        }
+        Ok(Self {
+            data,
+            width,
+            height,
+        })
     }
 
+    /// Pixel at (x, y); panics outside the image, see [`get_pixel_checked`](Self::get_pixel_checked)
     pub fn get_pixel(&self, x: u32, y: u32) -> Rgb {
-        let offset = ((y * self.width + x) * 3) as usize;
-        Rgb::new(
-            self.data[offset],
-            self.data[offset + 1],
-            self.data[offset + 2],
-        )
+        self.get_pixel_checked(x, y).unwrap_or_else(|| {
+            panic!(
+                "pixel ({}, {}) outside {}x{} image",
+                x, y, self.width, self.height
+            )
+        })
+    }
+
+    /// Pixel at (x, y), `None` outside the image
+    pub fn get_pixel_checked(&self, x: u32, y: u32) -> Option<Rgb> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+        let offset = (y as usize * self.width as usize + x as usize) * 3;
+        let p = self.data.get(offset..offset + 3)?;
+        Some(Rgb::new(p[0], p[1], p[2]))
+    }
+
+    /// Bytes of row `y` (RGB triplets), `None` outside the image
+    pub fn row_slice(&self, y: u32) -> Option<&[u8]> {
+        let range = self.row_range(y)?;
+        self.data.get(range)
+    }
+
+    /// Mutable bytes of row `y` (RGB triplets), `None` outside the image
+    pub fn row_slice_mut(&mut self, y: u32) -> Option<&mut [u8]> {
+        let range = self.row_range(y)?;
+        self.data.get_mut(range)
+    }
+
+    /// Rows from top to bottom, as RGB bytes
+    pub fn rows(&self) -> impl Iterator<Item = &[u8]> {
+        let row_len = (self.width as usize * 3).max(1);
+        self.data.chunks_exact(row_len).take(self.height as usize)
+    }
+
+    /// All pixels in row-major order
+    pub fn pixels(&self) -> impl Iterator<Item = Rgb> + '_ {
+        self.data
+            .chunks_exact(3)
+            .map(|p| Rgb::new(p[0], p[1], p[2]))
+    }
+
+    /// All pixels with their coordinates, in row-major order
+    pub fn enumerate_pixels(&self) -> impl Iterator<Item = (u32, u32, Rgb)> + '_ {
+        let width = self.width.max(1);
+        self.pixels()
+            .enumerate()
+            .map(move |(i, rgb)| (i as u32 % width, i as u32 / width, rgb))
+    }
+
+    fn row_range(&self, y: u32) -> Option<std::ops::Range<usize>> {
+        if y >= self.height {
+            return None;
+        }
+        let row_len = self.width as usize * 3;
+        let start = y as usize * row_len;
+        Some(start..start + row_len)
+    }
+}
+
+/// Rectangle in terminal cell coordinates
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CellRect {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl CellRect {
+    pub fn new(x: usize, y: usize, width: usize, height: usize) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    pub fn area(&self) -> usize {
+        self.width * self.height
     }
 }
 
+/// Vertical movement of a band of rows between two frames, as when the page scrolls
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ScrollMotion {
+    /// First row of the band that moved
+    pub top: usize,
+    /// Row after the last row of the band
+    pub bottom: usize,
+    /// Rows the content moved up (positive when scrolling down the page)
+    pub rows: isize,
+}
+
 /// Terminal frame buffer
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TerminalFrame {
     pub cells: Vec<TerminalCell>,
     pub width: usize,
     pub height: usize,
+    /// Regions that changed since the previous frame, if known
+    /// `None` means anything may have changed. While this is `Some`, every `set`
+    /// call records its cell too, so overlays drawn on top are tracked automatically.
+    pub damage: Option<Vec<CellRect>>,
+    /// Set when this frame is the previous one scrolled; `damage` is then relative to the
+    /// scrolled previous frame, not the previous frame itself
+    pub scroll: Option<ScrollMotion>,
+    /// Page state of the screenshot the frame was rendered from, if known
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub metadata: FrameMetadata,
 }
 
 impl TerminalFrame {
@@ -97,10 +342,18 @@ impl TerminalFrame {
                 character: ' ',
                 foreground: AnsiColor(0),
                 background: AnsiColor(0),
+                cluster: None,
             };
             width * height
         ];
-        Self { cells, width, height }
+        Self {
+            cells,
+            width,
+            height,
+            damage: None,
+            scroll: None,
+            metadata: FrameMetadata::default(),
+        }
     }
 
     pub fn get(&self, x: usize, y: usize) -> Option<&TerminalCell> {
@@ -114,13 +367,183 @@ impl TerminalFrame {
     pub fn set(&mut self, x: usize, y: usize, cell: TerminalCell) {
         if x < self.width && y < self.height {
             self.cells[y * self.width + x] = cell;
+            if let Some(damage) = self.damage.as_mut() {
+                damage.push(CellRect::new(x, y, 1, 1));
+            }
+        }
+    }
+
+    /// Write a string of text starting at (x, y), clipped to the frame width
+    ///
+    /// Each grapheme cluster takes a cell, and a double-width one its continuation too.
+    pub fn draw_text(&mut self, x: usize, y: usize, text: &str, fg: AnsiColor, bg: AnsiColor) {
+        let mut column = x;
+        for (grapheme, width) in graphemes(text) {
+            // Half a double-width character can't be shown
+            if column + width > self.width {
+                break;
+            }
+            self.set(column, y, TerminalCell::from_grapheme(grapheme, fg, bg));
+            if width == 2 {
+                self.set(column + 1, y, TerminalCell::continuation(fg, bg));
+            }
+            column += width;
         }
     }
+
+    /// `newer` standing in for both frames, when `older` is skipped without being shown
+    ///
+    /// The newer frame's damage only covers what changed since the older one, so the two
+    /// lists are merged; a scroll in either can't be described by one list, and leaves the
+    /// damage unknown.
+    pub fn coalesce(older: TerminalFrame, mut newer: TerminalFrame) -> TerminalFrame {
+        newer.damage = match (older.damage, newer.damage.take()) {
+            _ if older.scroll.is_some() || newer.scroll.is_some() => None,
+            (Some(mut damage), Some(newer_damage)) => {
+                damage.extend(newer_damage);
+                Some(damage)
+            }
+            _ => None,
+        };
+        newer.scroll = None;
+        newer
+    }
 }
 
 /// Cursor position for overlay cursor
 #[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct CursorPosition {
     pub x: usize,
     pub y: usize,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gradient(width: u32, height: u32) -> RgbImage {
+        let data = (0..width * height)
+            .flat_map(|i| [i as u8, 0, 255 - i as u8])
+            .collect();
+        RgbImage::new(data, width, height)
+    }
+
+    #[test]
+    fn test_try_new_checks_length() {
+        assert!(RgbImage::try_new(vec![0; 12], 2, 2).is_ok());
+        assert_eq!(
+            RgbImage::try_new(vec![0; 11], 2, 2).unwrap_err(),
+            ImageSizeError {
+                actual: 11,
+                expected: 12,
+                width: 2,
+                height: 2
+            }
+        );
+        // Would overflow a u32 byte count
+        assert!(RgbImage::try_new(Vec::new(), u32::MAX, 3).is_err());
+        assert!(RgbImage::try_new(Vec::new(), 0, 0).is_ok());
+    }
+
+    #[test]
+    fn test_checked_access() {
+        let image = gradient(3, 2);
+
+        assert_eq!(image.get_pixel_checked(2, 1), Some(Rgb::new(5, 0, 250)));
+        assert_eq!(image.get_pixel_checked(3, 0), None);
+        assert_eq!(image.get_pixel_checked(0, 2), None);
+        assert_eq!(image.row_slice(1), Some(&image.data[9..18]));
+        assert_eq!(image.row_slice(2), None);
+    }
+
+    #[test]
+    fn test_iterators() {
+        let mut image = gradient(3, 2);
+
+        assert_eq!(image.rows().count(), 2);
+        assert_eq!(image.pixels().count(), 6);
+        assert_eq!(
+            image.enumerate_pixels().last(),
+            Some((2, 1, Rgb::new(5, 0, 250)))
+        );
+
+        image.row_slice_mut(0).unwrap().fill(9);
+        assert!(image.pixels().take(3).all(|p| p == Rgb::new(9, 9, 9)));
+
+        let empty = RgbImage::new(Vec::new(), 0, 0);
+        assert_eq!(empty.rows().count(), 0);
+        assert_eq!(empty.enumerate_pixels().count(), 0);
+    }
+
+    #[test]
+    fn test_draw_wide_text() {
+        let mut frame = TerminalFrame::new(4, 1);
+        frame.draw_text(0, 0, "中e\u{301}語", AnsiColor(15), AnsiColor(4));
+
+        assert_eq!(frame.cells[0].character, '中');
+        assert_eq!(frame.cells[0].width(), 2);
+        assert!(frame.cells[1].is_continuation());
+        assert_eq!(frame.cells[2].cluster.as_deref(), Some("\u{301}"));
+        assert_eq!(frame.cells[2].width(), 1);
+        // No room for the second half of the last character
+        assert_eq!(frame.cells[3].character, ' ');
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_frame_round_trip() {
+        let mut frame = TerminalFrame::new(3, 2);
+        frame.draw_text(0, 1, "h❤\u{fe0f}", AnsiColor(15), AnsiColor(4));
+        frame.damage = Some(vec![CellRect::new(0, 1, 2, 1)]);
+
+        let json = serde_json::to_string(&frame).unwrap();
+        let back: TerminalFrame = serde_json::from_str(&json).unwrap();
+        assert_eq!(back.cells, frame.cells);
+        assert_eq!((back.width, back.height), (3, 2));
+        assert_eq!(back.damage, frame.damage);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_screenshot_round_trip() {
+        let screenshot = Screenshot {
+            data: Bytes::from_static(&[1, 2, 3, 4]),
+            format: ImageFormat::RawRgba {
+                width: 1,
+                height: 1,
+            },
+            metadata: Default::default(),
+        };
+
+        let json = serde_json::to_string(&screenshot).unwrap();
+        let back: Screenshot = serde_json::from_str(&json).unwrap();
+        assert_eq!(back.data, screenshot.data);
+        assert_eq!(back.format, screenshot.format);
+    }
+
+    #[test]
+    fn test_coalesce_merges_damage() {
+        let frame = |damage| {
+            let mut frame = TerminalFrame::new(4, 4);
+            frame.damage = damage;
+            frame
+        };
+        let a = CellRect::new(0, 0, 1, 1);
+        let b = CellRect::new(2, 2, 1, 1);
+        let merged = TerminalFrame::coalesce(frame(Some(vec![a])), frame(Some(vec![b])));
+        assert_eq!(merged.damage, Some(vec![a, b]));
+
+        let merged = TerminalFrame::coalesce(frame(None), frame(Some(vec![b])));
+        assert_eq!(merged.damage, None);
+
+        let mut scrolled = frame(Some(vec![a]));
+        scrolled.scroll = Some(ScrollMotion {
+            top: 0,
+            bottom: 4,
+            rows: 1,
+        });
+        let merged = TerminalFrame::coalesce(scrolled, frame(Some(vec![b])));
+        assert_eq!((merged.damage, merged.scroll), (None, None));
+    }
+}
@@ -0,0 +1,40 @@
+//! Screenshot decoding must return an error, never panic, whatever CDP delivers
+//!
+//! Run with `cargo +nightly fuzz run decode_screenshot` from `crates/toast-render`.
+
+#![no_main]
+
+use bytes::Bytes;
+use libfuzzer_sys::fuzz_target;
+use toast_core::{ImageFormat, Screenshot};
+use toast_render::decode_screenshot;
+
+fuzz_target!(|data: &[u8]| {
+    // The first byte picks the format; raw RGBA also takes its size from the input
+    let Some((&selector, rest)) = data.split_first() else {
+        return;
+    };
+    let (format, payload) = match selector % 4 {
+        0 => (ImageFormat::Jpeg, rest),
+        1 => (ImageFormat::Png, rest),
+        2 => (ImageFormat::Webp, rest),
+        _ if rest.len() >= 4 => {
+            let width = u16::from_le_bytes([rest[0], rest[1]]) as u32;
+            let height = u16::from_le_bytes([rest[2], rest[3]]) as u32;
+            (ImageFormat::RawRgba { width, height }, &rest[4..])
+        }
+        _ => return,
+    };
+
+    let screenshot = Screenshot {
+        data: Bytes::copy_from_slice(payload),
+        format,
+        metadata: Default::default(),
+    };
+    if let Ok(image) = decode_screenshot(&screenshot) {
+        assert_eq!(
+            image.data.len(),
+            image.width as usize * image.height as usize * 3
+        );
+    }
+});
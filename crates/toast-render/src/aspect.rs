@@ -0,0 +1,134 @@
+/// Cell height over cell width assumed by half-block rendering (square half-block pixels)
+pub const NOMINAL_CELL_ASPECT: f32 = 2.0;
+
+/// Cell width in pixels assumed when the terminal doesn't report its pixel size
+pub const NOMINAL_CELL_WIDTH: f32 = 8.0;
+
+/// Viewport in CSS pixels with the shape of `cols`x`rows` cells of `cell_width` pixels and
+/// the given aspect (height / width)
+///
+/// Each cell is `cell_width` wide and two half-block pixels tall, so a page this size
+/// fills the grid without being cut or stretched.
+pub fn grid_viewport(cols: usize, rows: usize, cell_width: f32, cell_aspect: f32) -> (u32, u32) {
+    let pixel_height = cell_width * cell_aspect / 2.0;
+    let width = (cols as f32 * cell_width).round().max(1.0);
+    let height = (rows as f32 * 2.0 * pixel_height).round().max(1.0);
+    (width as u32, height as u32)
+}
+
+/// The part of a page shown on a terminal grid, keeping its proportions on screen
+///
+/// When the grid is wider than the page (physically, given the cell shape) the page
+/// keeps its full width and is cut at the bottom; when it's narrower, the whole page
+/// fills the top `rows` rows and the rest stays blank.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PageFit {
+    /// Source rectangle in page pixels (x, y, width, height)
+    pub source: (u32, u32, u32, u32),
+    /// Terminal rows the source fills
+    pub rows: usize,
+}
+
+impl PageFit {
+    /// Fit a `width`x`height` page onto `cols`x`rows` cells of the given aspect (height / width)
+    pub fn new(width: u32, height: u32, cols: usize, rows: usize, cell_aspect: f32) -> Self {
+        let whole = Self {
+            source: (0, 0, width, height),
+            rows,
+        };
+        if width == 0 || height == 0 || cols == 0 || rows == 0 || cell_aspect <= 0.0 {
+            return whole;
+        }
+
+        // Physical width over height of the grid and of the page
+        let grid = cols as f64 / (rows as f64 * cell_aspect as f64);
+        let page = width as f64 / height as f64;
+
+        if grid >= page {
+            let visible = ((width as f64 / grid).round() as u32).clamp(1, height);
+            Self {
+                source: (0, 0, width, visible),
+                rows,
+            }
+        } else {
+            let used = ((rows as f64 * grid / page).round() as usize).clamp(1, rows);
+            Self {
+                rows: used,
+                ..whole
+            }
+        }
+    }
+
+    /// Whether the whole page fills the whole grid (nothing to crop or pad)
+    pub fn is_whole(&self, width: u32, height: u32, rows: usize) -> bool {
+        self.source == (0, 0, width, height) && self.rows == rows
+    }
+
+    /// Map a position on the grid (fractions of `rows` and the columns) to the shown source
+    ///
+    /// Returns fractions of the source rectangle; rows below the page clamp to its bottom.
+    pub fn to_source(&self, x: f64, y: f64, rows: usize) -> (f64, f64) {
+        let y = y * rows as f64 / self.rows.max(1) as f64;
+        (x, y.min(1.0))
+    }
+
+    /// Map fractions of the source rectangle to page coordinates in pixels
+    pub fn to_page(&self, x: f64, y: f64) -> (f64, f64) {
+        let (sx, sy, sw, sh) = self.source;
+        (sx as f64 + x * sw as f64, sy as f64 + y * sh as f64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_grid_viewport_fills_grid() {
+        // 200x50 cells of 9x21 pixels
+        let viewport = grid_viewport(200, 50, 9.0, 21.0 / 9.0);
+        assert_eq!(viewport, (1800, 1050));
+        let fit = PageFit::new(viewport.0, viewport.1, 200, 50, 21.0 / 9.0);
+        assert!(fit.is_whole(viewport.0, viewport.1, 50));
+
+        assert_eq!(grid_viewport(0, 0, 8.0, 2.0), (1, 1));
+    }
+
+    #[test]
+    fn test_wide_grid_crops_bottom() {
+        // 200x50 cells of 1:2 are 2:1 on screen; a 16:9 page loses its bottom
+        let fit = PageFit::new(1920, 1080, 200, 50, 2.0);
+        assert_eq!(fit.source, (0, 0, 1920, 960));
+        assert_eq!(fit.rows, 50);
+    }
+
+    #[test]
+    fn test_tall_grid_leaves_rows_blank() {
+        // 100x50 cells of 1:2 are 1:1 on screen; a 16:9 page fills the top 9/16
+        let fit = PageFit::new(1920, 1080, 100, 50, 2.0);
+        assert_eq!(fit.source, (0, 0, 1920, 1080));
+        assert_eq!(fit.rows, 28);
+    }
+
+    #[test]
+    fn test_taller_cells_show_more_page() {
+        // The same grid is physically taller, so less of the page is cut off
+        let nominal = PageFit::new(1920, 1080, 200, 50, 2.0);
+        let tall = PageFit::new(1920, 1080, 200, 50, 2.2);
+        assert!(tall.source.3 > nominal.source.3);
+    }
+
+    #[test]
+    fn test_degenerate_sizes_keep_whole_page() {
+        assert!(PageFit::new(0, 1080, 200, 50, 2.0).is_whole(0, 1080, 50));
+        assert!(PageFit::new(1920, 1080, 200, 50, 0.0).is_whole(1920, 1080, 50));
+    }
+
+    #[test]
+    fn test_to_source_clamps_below_page() {
+        let fit = PageFit::new(1920, 1080, 100, 50, 2.0);
+        assert_eq!(fit.to_source(0.5, 1.0, 50).1, 1.0);
+        let (x, y) = fit.to_page(0.5, 0.5);
+        assert_eq!((x, y), (960.0, 540.0));
+    }
+}
@@ -0,0 +1,88 @@
+use toast_core::{RgbImage, TerminalFrame};
+
+use crate::halfblock::HalfBlockConverter;
+
+/// Turns a scaled page image into terminal cells
+///
+/// [`RenderPipeline`](crate::RenderPipeline) scales the page to `cell_resolution` pixels
+/// per cell, applies its color adjustments and hands the result to the backend. Other
+/// cell encodings (braille, sextants, sixel or kitty graphics) plug in by implementing
+/// this and passing it to [`RenderPipeline::with_backend`](crate::RenderPipeline::with_backend).
+pub trait RenderBackend: Send + Sync {
+    /// Short name for logs and diagnostics
+    fn name(&self) -> &str;
+
+    /// Pixels sampled per cell as (columns, rows)
+    fn cell_resolution(&self) -> (u32, u32);
+
+    /// Convert an image of `cell_resolution` pixels per cell into a frame of
+    /// `term_width`x`term_height` cells
+    fn convert(&self, image: &RgbImage, term_width: usize, term_height: usize) -> TerminalFrame;
+}
+
+impl RenderBackend for HalfBlockConverter {
+    fn name(&self) -> &str {
+        "half-block"
+    }
+
+    fn cell_resolution(&self) -> (u32, u32) {
+        (1, 2)
+    }
+
+    fn convert(&self, image: &RgbImage, term_width: usize, term_height: usize) -> TerminalFrame {
+        HalfBlockConverter::convert(self, image, term_width, term_height)
+    }
+}
+
+/// ░▒▓█ shading: one color per cell, blended from two palette entries
+#[derive(Clone)]
+pub struct ShadeBackend {
+    converter: HalfBlockConverter,
+}
+
+impl ShadeBackend {
+    /// Shade with `converter`'s quantizer
+    pub fn new(converter: HalfBlockConverter) -> Self {
+        Self { converter }
+    }
+}
+
+impl RenderBackend for ShadeBackend {
+    fn name(&self) -> &str {
+        "shade"
+    }
+
+    fn cell_resolution(&self) -> (u32, u32) {
+        (1, 2)
+    }
+
+    fn convert(&self, image: &RgbImage, term_width: usize, term_height: usize) -> TerminalFrame {
+        self.converter
+            .convert_shaded(image, term_width, term_height)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_backends_match_converter() {
+        let converter = HalfBlockConverter::new();
+        let mut data = vec![0u8; 4 * 4 * 3];
+        data[..6].copy_from_slice(&[255, 0, 0, 255, 255, 255]);
+        let image = RgbImage::new(data, 4, 4);
+
+        let backend: &dyn RenderBackend = &converter;
+        assert_eq!(
+            backend.convert(&image, 4, 2).cells,
+            converter.convert(&image, 4, 2).cells
+        );
+
+        let shade = ShadeBackend::new(converter.clone());
+        assert_eq!(
+            shade.convert(&image, 4, 2).cells,
+            converter.convert_shaded(&image, 4, 2).cells
+        );
+    }
+}
@@ -0,0 +1,95 @@
+use std::path::PathBuf;
+
+use crate::contrast::HighContrast;
+use crate::daltonize::ColorFilter;
+use crate::quantizer::ColorMetric;
+use crate::scaler::ScaleFilter;
+use crate::shade::CharacterSet;
+
+/// Rendering options for a [`RenderPipeline`](crate::RenderPipeline)
+///
+/// Start from `RenderConfig::default()` and override the fields you need:
+///
+/// ```
+/// use toast_render::{RenderConfig, RenderPipeline, ScaleFilter};
+///
+/// let pipeline = RenderPipeline::with_config(RenderConfig {
+///     filter: ScaleFilter::CatmullRom,
+///     text_enhancement: true,
+///     ..RenderConfig::default()
+/// });
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct RenderConfig {
+    /// Color distance metric used to build the quantizer LUT
+    pub metric: ColorMetric,
+    /// Resampling filter used to scale to terminal resolution
+    pub filter: ScaleFilter,
+    /// Scale to twice the cell resolution and pick each cell's colors by clustering
+    /// (sharper detail, more CPU)
+    pub supersample: bool,
+    /// Scale to twice the cell resolution and area-average each pixel's samples, so thin
+    /// horizontal lines don't alias (ignored when `supersample` is on)
+    pub subpixel: bool,
+    /// Keep 1px lines and small glyphs from vanishing in the downscale
+    pub preserve_edges: bool,
+    /// Unsharp-mask amount applied after scaling (0 disables)
+    pub sharpen: f32,
+    /// Saturation multiplier applied before quantization (1 leaves colors unchanged)
+    pub saturation: f32,
+    /// Extra saturation for muted colors, fading out for vivid ones (0 disables)
+    pub vibrance: f32,
+    /// Characters cells are drawn with
+    pub charset: CharacterSet,
+    /// Color-blindness compensation (can be changed at runtime on the pipeline)
+    pub color_filter: ColorFilter,
+    /// High-contrast accessibility mode (base16 also restricts the palette)
+    pub high_contrast: HighContrast,
+    /// Snap high-contrast text cells to crisp paper/ink colors
+    pub text_enhancement: bool,
+    /// Quantize text-like cells to two contrasting colors instead of per pixel
+    pub text_quantization: bool,
+    /// Choose each cell's two colors jointly instead of per pixel (slower, truer edges)
+    pub pair_search: bool,
+    /// Only re-render regions that changed since the previous frame
+    pub damage_tracking: bool,
+    /// Recognize page scrolls between frames and describe them in `TerminalFrame::scroll`
+    pub scroll_detection: bool,
+    /// Delta-E margin for keeping a cell's previous color (0 disables)
+    pub color_hysteresis: f32,
+    /// Show each new page image as a quick nearest-neighbour draft first, and render it
+    /// at full quality once the page holds still
+    pub progressive: bool,
+    /// Terminal cell height over width; when set, the page keeps its proportions on screen
+    /// instead of being stretched to fill the grid
+    pub cell_aspect: Option<f32>,
+    /// Where quantizer lookup tables are cached between runs (`None` builds them every time)
+    pub lut_cache_dir: Option<PathBuf>,
+}
+
+impl Default for RenderConfig {
+    fn default() -> Self {
+        Self {
+            metric: ColorMetric::default(),
+            filter: ScaleFilter::default(),
+            supersample: false,
+            subpixel: false,
+            preserve_edges: false,
+            sharpen: 0.0,
+            saturation: 1.0,
+            vibrance: 0.0,
+            charset: CharacterSet::default(),
+            color_filter: ColorFilter::default(),
+            high_contrast: HighContrast::default(),
+            text_enhancement: false,
+            text_quantization: false,
+            pair_search: false,
+            damage_tracking: false,
+            scroll_detection: false,
+            color_hysteresis: 0.0,
+            progressive: false,
+            cell_aspect: None,
+            lut_cache_dir: None,
+        }
+    }
+}
@@ -0,0 +1,83 @@
+use anyhow::Result;
+use toast_core::RgbImage;
+
+/// Slope of the linear stretch around mid-grey, before the S-curve
+const STRETCH: f32 = 1.5;
+
+/// Accessibility rendering for low-vision users and low-color terminals
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum HighContrast {
+    #[default]
+    Off,
+    /// Stretch luminance and push mid-tones towards black or white
+    Stretch,
+    /// Stretch, then use only the 16 base colors
+    Base16,
+}
+
+impl HighContrast {
+    pub fn is_enabled(self) -> bool {
+        self != HighContrast::Off
+    }
+}
+
+impl std::str::FromStr for HighContrast {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "off" | "none" => Ok(HighContrast::Off),
+            "stretch" | "on" => Ok(HighContrast::Stretch),
+            "base16" | "16" => Ok(HighContrast::Base16),
+            _ => anyhow::bail!(
+                "unknown high-contrast mode '{}' (expected off, stretch or base16)",
+                s
+            ),
+        }
+    }
+}
+
+/// Increase contrast in place: stretch each channel around mid-grey, then apply an
+/// S-curve so the remaining mid-tones are pulled apart
+///
+/// The curve is fixed rather than fitted to the frame, so damaged regions rendered on
+/// their own match the rest of the page.
+pub fn apply_high_contrast(image: &mut RgbImage) {
+    let curve: [u8; 256] = std::array::from_fn(|v| {
+        let x = ((v as f32 / 255.0 - 0.5) * STRETCH + 0.5).clamp(0.0, 1.0);
+        let s = x * x * (3.0 - 2.0 * x);
+        (s * 255.0).round() as u8
+    });
+
+    for value in image.data.iter_mut() {
+        *value = curve[*value as usize];
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mid_tones_are_pushed_apart() {
+        let mut image = RgbImage::new(vec![0, 96, 128, 160, 255, 200], 2, 1);
+        apply_high_contrast(&mut image);
+
+        assert_eq!(image.data[0], 0);
+        assert!(image.data[1] < 70);
+        assert!(image.data[2].abs_diff(128) <= 1);
+        assert!(image.data[3] > 186);
+        assert_eq!(image.data[4], 255);
+        assert!(image.data[5] > 245);
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            "base16".parse::<HighContrast>().unwrap(),
+            HighContrast::Base16
+        );
+        assert_eq!("on".parse::<HighContrast>().unwrap(), HighContrast::Stretch);
+        assert!("max".parse::<HighContrast>().is_err());
+    }
+}
@@ -0,0 +1,179 @@
+use anyhow::Result;
+use toast_core::RgbImage;
+
+type Matrix = [[f32; 3]; 3];
+
+/// RGB to LMS cone response (Viénot, Brettel and Mollon)
+const RGB_TO_LMS: Matrix = [
+    [17.8824, 43.5161, 4.11935],
+    [3.45565, 27.1554, 3.86714],
+    [0.0299566, 0.184309, 1.46709],
+];
+
+const LMS_TO_RGB: Matrix = [
+    [0.08094445, -0.1305044, 0.11672107],
+    [-0.010248534, 0.05401933, -0.11361471],
+    [-0.00036529694, -0.004121615, 0.6935114],
+];
+
+/// Moves the color information a viewer cannot see into channels they can
+const ERROR_SHIFT: Matrix = [[0.0, 0.0, 0.0], [0.7, 1.0, 0.0], [0.7, 0.0, 1.0]];
+
+/// Color-blindness compensation applied before quantization
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ColorFilter {
+    #[default]
+    None,
+    /// Red-blind
+    Protanopia,
+    /// Green-blind (the most common)
+    Deuteranopia,
+    /// Blue-blind
+    Tritanopia,
+}
+
+impl ColorFilter {
+    /// The next filter in the cycle used by the runtime key binding
+    pub fn next(self) -> Self {
+        match self {
+            ColorFilter::None => ColorFilter::Protanopia,
+            ColorFilter::Protanopia => ColorFilter::Deuteranopia,
+            ColorFilter::Deuteranopia => ColorFilter::Tritanopia,
+            ColorFilter::Tritanopia => ColorFilter::None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ColorFilter::None => "none",
+            ColorFilter::Protanopia => "protanopia",
+            ColorFilter::Deuteranopia => "deuteranopia",
+            ColorFilter::Tritanopia => "tritanopia",
+        }
+    }
+
+    /// Daltonize an image in place: colors that collapse together for this deficiency are
+    /// pushed apart along axes the viewer can still distinguish
+    pub fn apply(self, image: &mut RgbImage) {
+        let Some(simulation) = self.simulation() else {
+            return;
+        };
+
+        // corrected = rgb + shift * (rgb - simulated), folded into one matrix
+        let simulate = multiply(&LMS_TO_RGB, &multiply(&simulation, &RGB_TO_LMS));
+        let mut correction = multiply(&ERROR_SHIFT, &subtract(&IDENTITY, &simulate));
+        for (i, row) in correction.iter_mut().enumerate() {
+            row[i] += 1.0;
+        }
+
+        for pixel in image.data.chunks_exact_mut(3) {
+            let rgb = [pixel[0] as f32, pixel[1] as f32, pixel[2] as f32];
+            for (value, row) in pixel.iter_mut().zip(&correction) {
+                let v = row[0] * rgb[0] + row[1] * rgb[1] + row[2] * rgb[2];
+                *value = v.round().clamp(0.0, 255.0) as u8;
+            }
+        }
+    }
+
+    /// How the deficiency sees colors, in LMS space
+    fn simulation(self) -> Option<Matrix> {
+        match self {
+            ColorFilter::None => None,
+            ColorFilter::Protanopia => {
+                Some([[0.0, 2.02344, -2.52581], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]])
+            }
+            ColorFilter::Deuteranopia => {
+                Some([[1.0, 0.0, 0.0], [0.494207, 0.0, 1.24827], [0.0, 0.0, 1.0]])
+            }
+            ColorFilter::Tritanopia => {
+                Some([[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [-0.395913, 0.801109, 0.0]])
+            }
+        }
+    }
+}
+
+impl std::str::FromStr for ColorFilter {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "none" | "off" => Ok(ColorFilter::None),
+            "protanopia" => Ok(ColorFilter::Protanopia),
+            "deuteranopia" => Ok(ColorFilter::Deuteranopia),
+            "tritanopia" => Ok(ColorFilter::Tritanopia),
+            _ => anyhow::bail!(
+                "unknown color filter '{}' (expected none, protanopia, deuteranopia or tritanopia)",
+                s
+            ),
+        }
+    }
+}
+
+const IDENTITY: Matrix = [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]];
+
+fn multiply(a: &Matrix, b: &Matrix) -> Matrix {
+    std::array::from_fn(|i| std::array::from_fn(|j| (0..3).map(|k| a[i][k] * b[k][j]).sum()))
+}
+
+fn subtract(a: &Matrix, b: &Matrix) -> Matrix {
+    std::array::from_fn(|i| std::array::from_fn(|j| a[i][j] - b[i][j]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn single(r: u8, g: u8, b: u8) -> RgbImage {
+        RgbImage::new(vec![r, g, b], 1, 1)
+    }
+
+    #[test]
+    fn test_greys_are_unchanged() {
+        for filter in [
+            ColorFilter::Protanopia,
+            ColorFilter::Deuteranopia,
+            ColorFilter::Tritanopia,
+        ] {
+            let mut image = single(128, 128, 128);
+            filter.apply(&mut image);
+            for &c in &image.data {
+                assert!(
+                    c.abs_diff(128) <= 2,
+                    "{:?} shifted grey to {:?}",
+                    filter,
+                    image.data
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_deuteranopia_separates_red_and_green() {
+        // Red and green differ mostly in the channel a deuteranope can't use;
+        // after correction they also differ in blue
+        let mut red = single(200, 60, 60);
+        let mut green = single(60, 160, 60);
+        ColorFilter::Deuteranopia.apply(&mut red);
+        ColorFilter::Deuteranopia.apply(&mut green);
+
+        assert!(red.data[2].abs_diff(green.data[2]) > 20);
+    }
+
+    #[test]
+    fn test_none_is_noop() {
+        let mut image = single(200, 60, 60);
+        ColorFilter::None.apply(&mut image);
+        assert_eq!(image.data, vec![200, 60, 60]);
+    }
+
+    #[test]
+    fn test_cycle_and_parse() {
+        let mut filter = ColorFilter::None;
+        for _ in 0..4 {
+            filter = filter.next();
+            assert_eq!(filter.name().parse::<ColorFilter>().unwrap(), filter);
+        }
+        assert_eq!(filter, ColorFilter::None);
+        assert!("achromatopsia".parse::<ColorFilter>().is_err());
+    }
+}
@@ -0,0 +1,238 @@
+use toast_core::{CellRect, RgbImage};
+
+/// Cells around a change that are re-rendered, covering the Lanczos3 kernel support
+/// (three output pixels in each direction when downscaling)
+pub const DAMAGE_MARGIN: usize = 3;
+
+/// Find the cells whose source pixels differ between two images of the same size
+///
+/// Returns a row-major `cols * rows` mask. Each cell covers the source region that
+/// scales onto it (one column, two pixel rows at terminal resolution).
+pub fn dirty_cells(previous: &RgbImage, current: &RgbImage, cols: usize, rows: usize) -> Vec<bool> {
+    debug_assert_eq!(previous.width, current.width);
+    debug_assert_eq!(previous.height, current.height);
+
+    let mut dirty = vec![false; cols * rows];
+    if cols == 0 || rows == 0 {
+        return dirty;
+    }
+
+    let width = current.width as usize;
+    let height = current.height as usize;
+    let col_bounds: Vec<usize> = (0..=cols).map(|c| c * width / cols).collect();
+
+    for cell_y in 0..rows {
+        let y0 = cell_y * height / rows;
+        let y1 = ((cell_y + 1) * height / rows).max(y0 + 1).min(height);
+        for y in y0..y1 {
+            let row_start = y * width * 3;
+            for cell_x in 0..cols {
+                let idx = cell_y * cols + cell_x;
+                if dirty[idx] {
+                    continue;
+                }
+                let start = row_start + col_bounds[cell_x] * 3;
+                let end = row_start
+                    + col_bounds[cell_x + 1]
+                        .max(col_bounds[cell_x] + 1)
+                        .min(width)
+                        * 3;
+                if previous.data[start..end] != current.data[start..end] {
+                    dirty[idx] = true;
+                }
+            }
+        }
+    }
+
+    dirty
+}
+
+/// Merge a dirty-cell mask into rectangles, dilated by `margin` cells and clipped to the grid
+pub fn damage_rects(dirty: &[bool], cols: usize, rows: usize, margin: usize) -> Vec<CellRect> {
+    let dilated = dilate(dirty, cols, rows, margin);
+
+    // Horizontal runs per row, merged downwards while the run keeps the same span
+    let mut rects: Vec<CellRect> = Vec::new();
+    let mut open: Vec<usize> = Vec::new(); // indices into `rects` that ended on the previous row
+    for y in 0..rows {
+        let mut next_open = Vec::new();
+        let mut x = 0;
+        while x < cols {
+            if !dilated[y * cols + x] {
+                x += 1;
+                continue;
+            }
+            let start = x;
+            while x < cols && dilated[y * cols + x] {
+                x += 1;
+            }
+            let width = x - start;
+
+            let extended = open.iter().copied().find(|&i| {
+                let r = &rects[i];
+                r.x == start && r.width == width && r.y + r.height == y
+            });
+            match extended {
+                Some(i) => {
+                    rects[i].height += 1;
+                    next_open.push(i);
+                }
+                None => {
+                    rects.push(CellRect::new(start, y, width, 1));
+                    next_open.push(rects.len() - 1);
+                }
+            }
+        }
+        open = next_open;
+    }
+
+    rects
+}
+
+/// Source-pixel bounds `(x, y, width, height)` of a cell rectangle
+pub fn source_bounds(
+    rect: &CellRect,
+    source: &RgbImage,
+    cols: usize,
+    rows: usize,
+) -> (u32, u32, u32, u32) {
+    let map =
+        |cell: usize, cells: usize, size: u32| (cell as u64 * size as u64 / cells as u64) as u32;
+    let x0 = map(rect.x, cols, source.width);
+    let y0 = map(rect.y, rows, source.height);
+    let x1 = map(rect.x + rect.width, cols, source.width)
+        .max(x0 + 1)
+        .min(source.width);
+    let y1 = map(rect.y + rect.height, rows, source.height)
+        .max(y0 + 1)
+        .min(source.height);
+    (x0, y0, x1 - x0, y1 - y0)
+}
+
+/// Copy a rectangular region out of an image, clipped to the image
+pub fn crop(image: &RgbImage, x: u32, y: u32, width: u32, height: u32) -> RgbImage {
+    let (x, y) = (x.min(image.width), y.min(image.height));
+    let width = width.min(image.width - x);
+    let height = height.min(image.height - y);
+
+    let row_len = width as usize * 3;
+    let mut data = Vec::with_capacity(row_len * height as usize);
+    for row in image.rows().skip(y as usize).take(height as usize) {
+        data.extend_from_slice(&row[x as usize * 3..x as usize * 3 + row_len]);
+    }
+    RgbImage::new(data, width, height)
+}
+
+/// Grow a rectangle by `margin` cells on every side, clipped to the grid
+pub fn expand(rect: &CellRect, margin: usize, cols: usize, rows: usize) -> CellRect {
+    let x = rect.x.saturating_sub(margin);
+    let y = rect.y.saturating_sub(margin);
+    let x1 = (rect.x + rect.width + margin).min(cols);
+    let y1 = (rect.y + rect.height + margin).min(rows);
+    CellRect::new(x, y, x1 - x, y1 - y)
+}
+
+/// Separable box dilation of a cell mask
+fn dilate(mask: &[bool], cols: usize, rows: usize, margin: usize) -> Vec<bool> {
+    if margin == 0 {
+        return mask.to_vec();
+    }
+
+    let mut horizontal = vec![false; mask.len()];
+    for y in 0..rows {
+        for x in 0..cols {
+            if mask[y * cols + x] {
+                let x0 = x.saturating_sub(margin);
+                let x1 = (x + margin + 1).min(cols);
+                horizontal[y * cols + x0..y * cols + x1].fill(true);
+            }
+        }
+    }
+
+    let mut out = vec![false; mask.len()];
+    for y in 0..rows {
+        for x in 0..cols {
+            if horizontal[y * cols + x] {
+                let y0 = y.saturating_sub(margin);
+                let y1 = (y + margin + 1).min(rows);
+                for yy in y0..y1 {
+                    out[yy * cols + x] = true;
+                }
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dirty_cells_detects_single_pixel_change() {
+        // 8x8 image rendered to 4x4 cells (2x2 source pixels per cell)
+        let previous = RgbImage::new(vec![0u8; 8 * 8 * 3], 8, 8);
+        let mut current = previous.clone();
+        // Change pixel (5, 6) -> cell (2, 3)
+        let offset = (6 * 8 + 5) * 3;
+        current.data[offset] = 255;
+
+        let dirty = dirty_cells(&previous, &current, 4, 4);
+
+        assert_eq!(dirty.iter().filter(|&&d| d).count(), 1);
+        assert!(dirty[3 * 4 + 2]);
+    }
+
+    #[test]
+    fn test_damage_rects_merges_and_dilates() {
+        let (cols, rows) = (10, 6);
+        let mut dirty = vec![false; cols * rows];
+        dirty[2 * cols + 4] = true;
+
+        let rects = damage_rects(&dirty, cols, rows, 1);
+
+        // One 3x3 block around the dirty cell
+        assert_eq!(rects, vec![CellRect::new(3, 1, 3, 3)]);
+    }
+
+    #[test]
+    fn test_damage_rects_clips_to_grid() {
+        let (cols, rows) = (4, 4);
+        let mut dirty = vec![false; cols * rows];
+        dirty[0] = true;
+
+        let rects = damage_rects(&dirty, cols, rows, DAMAGE_MARGIN);
+
+        assert_eq!(rects, vec![CellRect::new(0, 0, 4, 4)]);
+    }
+
+    #[test]
+    fn test_crop_copies_region() {
+        let data: Vec<u8> = (0..4 * 3 * 3).map(|v| v as u8).collect();
+        let image = RgbImage::new(data, 4, 3);
+
+        let cropped = crop(&image, 1, 1, 2, 2);
+
+        assert_eq!((cropped.width, cropped.height), (2, 2));
+        assert_eq!(cropped.get_pixel(0, 0), image.get_pixel(1, 1));
+        assert_eq!(cropped.get_pixel(1, 1), image.get_pixel(2, 2));
+    }
+
+    #[test]
+    fn test_crop_clips_to_image() {
+        let image = RgbImage::new(vec![1u8; 4 * 3 * 3], 4, 3);
+
+        let cropped = crop(&image, 3, 2, 5, 5);
+        assert_eq!((cropped.width, cropped.height), (1, 1));
+
+        let outside = crop(&image, 9, 9, 2, 2);
+        assert_eq!((outside.width, outside.height), (0, 0));
+    }
+
+    #[test]
+    fn test_no_changes_no_damage() {
+        let image = RgbImage::new(vec![7u8; 4 * 4 * 3], 4, 4);
+        let dirty = dirty_cells(&image, &image, 2, 2);
+        assert!(damage_rects(&dirty, 2, 2, DAMAGE_MARGIN).is_empty());
+    }
+}
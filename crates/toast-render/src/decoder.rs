@@ -1,23 +1,185 @@
-use anyhow::{Context, Result};
+use std::io::Cursor;
 use toast_core::{ImageFormat, RgbImage, Screenshot};
 
+/// Largest screenshot side accepted, in pixels (Chrome's own capture limit)
+///
+/// Corrupt headers can claim enormous sizes; this bounds the allocation before decoding.
+pub const MAX_DIMENSION: u32 = 16384;
+
+/// Why a screenshot could not be decoded
+///
+/// A flaky CDP connection can deliver truncated or garbage payloads; all of them end up
+/// here rather than in a panic.
+#[derive(Debug, thiserror::Error)]
+pub enum DecodeError {
+    #[error("screenshot data is empty")]
+    Empty,
+    #[error("screenshot has zero width or height")]
+    ZeroSize,
+    #[error("screenshot exceeds the {MAX_DIMENSION}px size limit")]
+    TooLarge,
+    #[error("pixel buffer is {actual} bytes, expected {expected} for {width}x{height}")]
+    BufferSize {
+        actual: usize,
+        expected: usize,
+        width: u32,
+        height: u32,
+    },
+    #[error("malformed {format} data")]
+    Malformed {
+        format: &'static str,
+        #[source]
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
+}
+
 /// Decode a screenshot to RGB format
-pub fn decode_screenshot(screenshot: &Screenshot) -> Result<RgbImage> {
-    let img = match screenshot.format {
-        ImageFormat::Jpeg => {
-            image::load_from_memory_with_format(&screenshot.data, image::ImageFormat::Jpeg)
-        }
-        ImageFormat::Png => {
-            image::load_from_memory_with_format(&screenshot.data, image::ImageFormat::Png)
-        }
+pub fn decode_screenshot(screenshot: &Screenshot) -> Result<RgbImage, DecodeError> {
+    decode_screenshot_at_least(screenshot, u32::MAX, u32::MAX)
+}
+
+/// Decode a screenshot to RGB format, possibly smaller than captured but no smaller than
+/// `min_width` x `min_height` where the screenshot is larger
+///
+/// Only libjpeg-turbo (the `turbojpeg` feature) decodes at a reduced size, which skips
+/// most of the work: a JPEG comes out at a half or a quarter of its size when that is
+/// still large enough. Anything else is decoded at full size.
+#[cfg_attr(not(feature = "turbojpeg"), allow(unused_variables))]
+pub fn decode_screenshot_at_least(
+    screenshot: &Screenshot,
+    min_width: u32,
+    min_height: u32,
+) -> Result<RgbImage, DecodeError> {
+    if screenshot.data.is_empty() {
+        return Err(DecodeError::Empty);
     }
-    .context("Failed to decode image")?;
+
+    let (format, name) = match screenshot.format {
+        ImageFormat::RawRgba { width, height } => {
+            return rgba_to_rgb(&screenshot.data, width, height);
+        }
+        #[cfg(feature = "turbojpeg")]
+        ImageFormat::Jpeg => return decode_jpeg_turbo(&screenshot.data, min_width, min_height),
+        #[cfg(not(feature = "turbojpeg"))]
+        ImageFormat::Jpeg => (image::ImageFormat::Jpeg, "JPEG"),
+        ImageFormat::Png => (image::ImageFormat::Png, "PNG"),
+        ImageFormat::Webp => (image::ImageFormat::WebP, "WebP"),
+    };
+
+    let mut limits = image::io::Limits::default();
+    limits.max_image_width = Some(MAX_DIMENSION);
+    limits.max_image_height = Some(MAX_DIMENSION);
+    let mut reader = image::io::Reader::with_format(Cursor::new(&screenshot.data[..]), format);
+    reader.limits(limits);
+
+    let img = reader.decode().map_err(|e| match e {
+        image::ImageError::Limits(_) => DecodeError::TooLarge,
+        e => DecodeError::Malformed {
+            format: name,
+            source: Box::new(e),
+        },
+    })?;
 
     // Convert to RGB8
     let rgb = img.to_rgb8();
     let (width, height) = rgb.dimensions();
 
-    Ok(RgbImage::new(rgb.into_raw(), width, height))
+    checked_image(rgb.into_raw(), width, height)
+}
+
+/// Decode a JPEG with libjpeg-turbo straight into an RGB buffer, scaled down by the
+/// largest factor that keeps it at least `min_width` x `min_height`
+#[cfg(feature = "turbojpeg")]
+fn decode_jpeg_turbo(
+    data: &[u8],
+    min_width: u32,
+    min_height: u32,
+) -> Result<RgbImage, DecodeError> {
+    let malformed = |e: turbojpeg::Error| DecodeError::Malformed {
+        format: "JPEG",
+        source: Box::new(e),
+    };
+
+    // Check the claimed size before libjpeg-turbo allocates for it
+    let mut decompressor = turbojpeg::Decompressor::new().map_err(malformed)?;
+    let header = decompressor.read_header(data).map_err(malformed)?;
+    check_dimensions(header.width as u32, header.height as u32)?;
+
+    let factor = turbojpeg::ScalingFactor::new(
+        1,
+        jpeg_scale(header.width, header.height, min_width, min_height),
+    );
+    decompressor.set_scaling_factor(factor).map_err(malformed)?;
+    let scaled = header.scaled(factor);
+
+    let mut image = turbojpeg::Image {
+        pixels: vec![0; scaled.width * scaled.height * 3],
+        width: scaled.width,
+        pitch: scaled.width * 3,
+        height: scaled.height,
+        format: turbojpeg::PixelFormat::RGB,
+    };
+    decompressor
+        .decompress(data, image.as_deref_mut())
+        .map_err(malformed)?;
+
+    checked_image(image.pixels, image.width as u32, image.height as u32)
+}
+
+/// The largest of the JPEG scale-down factors libjpeg-turbo decodes fastest (4, 2 or 1 for
+/// full size) that leaves a `width` x `height` image at least `min_width` x `min_height`
+#[cfg(any(feature = "turbojpeg", test))]
+fn jpeg_scale(width: usize, height: usize, min_width: u32, min_height: u32) -> usize {
+    [4, 2]
+        .into_iter()
+        .find(|&denom| {
+            width.div_ceil(denom) >= min_width as usize
+                && height.div_ceil(denom) >= min_height as usize
+        })
+        .unwrap_or(1)
+}
+
+/// Drop the alpha channel of raw RGBA pixels
+fn rgba_to_rgb(data: &[u8], width: u32, height: u32) -> Result<RgbImage, DecodeError> {
+    check_dimensions(width, height)?;
+
+    let expected = width as usize * height as usize * 4;
+    if data.len() != expected {
+        return Err(DecodeError::BufferSize {
+            actual: data.len(),
+            expected,
+            width,
+            height,
+        });
+    }
+
+    let mut rgb = Vec::with_capacity(width as usize * height as usize * 3);
+    for pixel in data.chunks_exact(4) {
+        rgb.extend_from_slice(&pixel[..3]);
+    }
+
+    checked_image(rgb, width, height)
+}
+
+fn check_dimensions(width: u32, height: u32) -> Result<(), DecodeError> {
+    if width == 0 || height == 0 {
+        return Err(DecodeError::ZeroSize);
+    }
+    if width > MAX_DIMENSION || height > MAX_DIMENSION {
+        return Err(DecodeError::TooLarge);
+    }
+    Ok(())
+}
+
+/// Build the image, rejecting buffers that don't match the size instead of panicking
+fn checked_image(data: Vec<u8>, width: u32, height: u32) -> Result<RgbImage, DecodeError> {
+    check_dimensions(width, height)?;
+    RgbImage::try_new(data, width, height).map_err(|e| DecodeError::BufferSize {
+        actual: e.actual,
+        expected: e.expected,
+        width,
+        height,
+    })
 }
 
 #[cfg(test)]
@@ -41,6 +203,7 @@ mod tests {
         let screenshot = Screenshot {
             data: Bytes::from(png_data),
             format: ImageFormat::Png,
+            metadata: Default::default(),
         };
 
         let decoded = decode_screenshot(&screenshot).unwrap();
@@ -48,4 +211,168 @@ mod tests {
         assert_eq!(decoded.height, 2);
         assert_eq!(decoded.data.len(), 2 * 2 * 3);
     }
+
+    #[test]
+    fn test_decode_raw_rgba() {
+        let screenshot = Screenshot {
+            data: Bytes::from(vec![255, 0, 0, 255, 0, 0, 255, 128]),
+            format: ImageFormat::RawRgba {
+                width: 2,
+                height: 1,
+            },
+            metadata: Default::default(),
+        };
+
+        let decoded = decode_screenshot(&screenshot).unwrap();
+        assert_eq!(decoded.data, vec![255, 0, 0, 0, 0, 255]);
+    }
+
+    #[test]
+    fn test_decode_raw_rgba_rejects_wrong_size() {
+        let screenshot = Screenshot {
+            data: Bytes::from(vec![0u8; 7]),
+            format: ImageFormat::RawRgba {
+                width: 2,
+                height: 1,
+            },
+            metadata: Default::default(),
+        };
+
+        assert!(matches!(
+            decode_screenshot(&screenshot),
+            Err(DecodeError::BufferSize { .. })
+        ));
+    }
+
+    #[test]
+    fn test_decode_raw_rgba_rejects_bad_dimensions() {
+        let raw = |width, height| Screenshot {
+            data: Bytes::from(vec![0u8; 4]),
+            format: ImageFormat::RawRgba { width, height },
+            metadata: Default::default(),
+        };
+
+        assert!(matches!(
+            decode_screenshot(&raw(0, 1)),
+            Err(DecodeError::ZeroSize)
+        ));
+        // Would overflow a u32 byte count and allocate gigabytes if trusted
+        assert!(matches!(
+            decode_screenshot(&raw(u32::MAX, u32::MAX)),
+            Err(DecodeError::TooLarge)
+        ));
+    }
+
+    #[test]
+    fn test_truncated_and_corrupt_payloads_are_errors() {
+        let mut img_buffer = image::RgbImage::new(16, 16);
+        for (i, pixel) in img_buffer.pixels_mut().enumerate() {
+            *pixel = image::Rgb([i as u8, 255 - i as u8, 7]);
+        }
+
+        for (format, encoded) in [
+            (ImageFormat::Png, image::ImageFormat::Png),
+            (ImageFormat::Jpeg, image::ImageFormat::Jpeg),
+        ] {
+            let mut data = Vec::new();
+            img_buffer
+                .write_to(&mut std::io::Cursor::new(&mut data), encoded)
+                .unwrap();
+
+            // Every truncation point, then a flipped byte in the header
+            for len in 0..data.len() - 1 {
+                let screenshot = Screenshot {
+                    data: Bytes::copy_from_slice(&data[..len]),
+                    format,
+                    metadata: Default::default(),
+                };
+                let _ = decode_screenshot(&screenshot);
+            }
+            data[20] ^= 0xff;
+            let screenshot = Screenshot {
+                data: Bytes::from(data),
+                format,
+                metadata: Default::default(),
+            };
+            let _ = decode_screenshot(&screenshot);
+        }
+
+        let empty = Screenshot {
+            data: Bytes::new(),
+            format: ImageFormat::Webp,
+            metadata: Default::default(),
+        };
+        assert!(matches!(decode_screenshot(&empty), Err(DecodeError::Empty)));
+    }
+
+    #[test]
+    fn test_decode_webp() {
+        // Lossless, 4x2: every combination of two reds, two greens and two blues
+        let screenshot = Screenshot {
+            data: Bytes::from_static(include_bytes!("../tests/fixtures/pattern.webp")),
+            format: ImageFormat::Webp,
+            metadata: Default::default(),
+        };
+
+        let decoded = decode_screenshot(&screenshot).unwrap();
+        assert_eq!((decoded.width, decoded.height), (4, 2));
+        let expected: Vec<u8> = (0..8)
+            .flat_map(|i| {
+                [
+                    [30, 220][i >> 2 & 1],
+                    [0, 200][i >> 1 & 1],
+                    [60, 250][i & 1],
+                ]
+            })
+            .collect();
+        assert_eq!(decoded.data, expected);
+    }
+
+    #[test]
+    fn test_jpeg_scale_stays_at_or_above_target() {
+        assert_eq!(jpeg_scale(1280, 720, 320, 180), 4);
+        assert_eq!(jpeg_scale(1280, 720, 321, 180), 2);
+        assert_eq!(jpeg_scale(1280, 720, 640, 361), 1);
+        // Rounded up, like libjpeg-turbo's own scaled sizes
+        assert_eq!(jpeg_scale(1283, 722, 321, 181), 4);
+        assert_eq!(jpeg_scale(1280, 720, u32::MAX, u32::MAX), 1);
+    }
+
+    #[cfg(feature = "turbojpeg")]
+    #[test]
+    fn test_turbojpeg_round_trip() {
+        let mut img_buffer = image::RgbImage::new(64, 32);
+        for (x, _, pixel) in img_buffer.enumerate_pixels_mut() {
+            *pixel = image::Rgb([x as u8 * 4, 128, 255 - x as u8 * 4]);
+        }
+        let mut data = Vec::new();
+        img_buffer
+            .write_to(
+                &mut std::io::Cursor::new(&mut data),
+                image::ImageFormat::Jpeg,
+            )
+            .unwrap();
+        let screenshot = Screenshot {
+            data: Bytes::from(data),
+            format: ImageFormat::Jpeg,
+            metadata: Default::default(),
+        };
+
+        let decoded = decode_screenshot(&screenshot).unwrap();
+        assert_eq!((decoded.width, decoded.height), (64, 32));
+        // Lossy, but close to what was encoded
+        let error = decoded
+            .data
+            .iter()
+            .zip(img_buffer.as_raw())
+            .map(|(&a, &b)| a.abs_diff(b) as u64)
+            .sum::<u64>()
+            / decoded.data.len() as u64;
+        assert!(error < 8, "mean error {}", error);
+
+        // Half size is the smallest still covering 20x10
+        let scaled = decode_screenshot_at_least(&screenshot, 20, 10).unwrap();
+        assert_eq!((scaled.width, scaled.height), (32, 16));
+        assert_eq!(scaled.data.len(), 32 * 16 * 3);
+    }
 }
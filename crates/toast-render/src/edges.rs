@@ -0,0 +1,155 @@
+use rayon::prelude::*;
+use toast_core::RgbImage;
+
+use crate::text::{luminance, pixel};
+
+/// Minimum luminance spread (0-255) within a pixel's source area for it to hold a line
+const MIN_CONTRAST: u8 = 64;
+
+/// Maximum share of mid-tone pixels; lines on a flat background have few, photos many
+const MAX_MIDTONE_FRACTION: f32 = 0.3;
+
+/// Keep thin lines and small glyphs that downscaling would average away
+///
+/// A 1px border or underline covers only a sliver of the source area behind each scaled
+/// pixel, so a filter averages it into the background. For every scaled pixel whose
+/// source area is a flat background with a sparse high-contrast feature (dark on light
+/// or light on dark), the pixel takes the feature's color instead - an area-min (or
+/// area-max) on luminance, restricted to line-like content so photos are left alone.
+pub fn preserve_edges(source: &RgbImage, scaled: &mut RgbImage) {
+    let (width, height) = (scaled.width, scaled.height);
+    // Only downscaling loses lines
+    if width == 0 || height == 0 || source.width <= width && source.height <= height {
+        return;
+    }
+
+    let row_len = width as usize * 3;
+    let map_x = |x: u32| (x as u64 * source.width as u64 / width as u64) as u32;
+    let map_y = |y: u32| (y as u64 * source.height as u64 / height as u64) as u32;
+
+    scaled
+        .data
+        .par_chunks_mut(row_len)
+        .enumerate()
+        .for_each(|(y, row)| {
+            let y = y as u32;
+            let y0 = map_y(y);
+            let y1 = map_y(y + 1).max(y0 + 1).min(source.height);
+            for x in 0..width {
+                let x0 = map_x(x);
+                let x1 = map_x(x + 1).max(x0 + 1).min(source.width);
+                if let Some(rgb) = line_color(source, x0..x1, y0..y1) {
+                    let offset = x as usize * 3;
+                    row[offset..offset + 3].copy_from_slice(&rgb);
+                }
+            }
+        });
+}
+
+/// Color of a sparse line in a source area, if the area looks like one
+fn line_color(
+    source: &RgbImage,
+    xs: std::ops::Range<u32>,
+    ys: std::ops::Range<u32>,
+) -> Option<[u8; 3]> {
+    let mut min = (u8::MAX, [0u8; 3]);
+    let mut max = (u8::MIN, [0u8; 3]);
+    for y in ys.clone() {
+        for x in xs.clone() {
+            let rgb = pixel(source, x, y);
+            let l = luminance(rgb);
+            if l < min.0 {
+                min = (l, rgb);
+            }
+            if l > max.0 {
+                max = (l, rgb);
+            }
+        }
+    }
+    let spread = max.0.saturating_sub(min.0);
+    if spread < MIN_CONTRAST {
+        return None;
+    }
+
+    // Classify into dark, light and mid-tone pixels
+    let band = spread / 4;
+    let (mut dark, mut light, mut midtones) = (0u32, 0u32, 0u32);
+    for y in ys {
+        for x in xs.clone() {
+            let l = luminance(pixel(source, x, y));
+            if l <= min.0 + band {
+                dark += 1;
+            } else if l >= max.0 - band {
+                light += 1;
+            } else {
+                midtones += 1;
+            }
+        }
+    }
+    let total = dark + light + midtones;
+    if midtones as f32 / total as f32 > MAX_MIDTONE_FRACTION {
+        return None;
+    }
+
+    // The minority is the line; keep its extreme so it survives at full strength
+    Some(if dark <= light { min.1 } else { max.1 })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// White 8x8 image with a 1px black horizontal line at y=5
+    fn underline_image() -> RgbImage {
+        let mut data = vec![255u8; 8 * 8 * 3];
+        for x in 0..8 {
+            let offset = (5 * 8 + x) * 3;
+            data[offset..offset + 3].copy_from_slice(&[0, 0, 0]);
+        }
+        RgbImage::new(data, 8, 8)
+    }
+
+    #[test]
+    fn test_thin_line_survives_downscale() {
+        let source = underline_image();
+        // 4x downscale: the line is a quarter of the bottom row's area, averaged to light grey
+        let mut scaled = RgbImage::new(vec![191u8; 2 * 2 * 3], 2, 2);
+
+        preserve_edges(&source, &mut scaled);
+
+        // Bottom row holds the line, top row is flat and untouched
+        assert_eq!(scaled.get_pixel(0, 1).r, 0);
+        assert_eq!(scaled.get_pixel(1, 1).r, 0);
+        assert_eq!(scaled.get_pixel(0, 0).r, 191);
+    }
+
+    #[test]
+    fn test_light_line_on_dark() {
+        let mut source = underline_image();
+        for v in source.data.iter_mut() {
+            *v = 255 - *v;
+        }
+        let mut scaled = RgbImage::new(vec![64u8; 2 * 2 * 3], 2, 2);
+
+        preserve_edges(&source, &mut scaled);
+
+        assert_eq!(scaled.get_pixel(0, 1).r, 255);
+    }
+
+    #[test]
+    fn test_gradient_is_left_alone() {
+        let mut data = Vec::with_capacity(8 * 8 * 3);
+        for _y in 0..8 {
+            for x in 0..8u8 {
+                let v = x * 32;
+                data.extend_from_slice(&[v, v, v]);
+            }
+        }
+        let source = RgbImage::new(data, 8, 8);
+        let mut scaled = RgbImage::new(vec![112u8; 3], 1, 1);
+
+        preserve_edges(&source, &mut scaled);
+
+        assert_eq!(scaled.data, vec![112, 112, 112]);
+    }
+}
@@ -0,0 +1,133 @@
+use toast_core::{Rgb, RgbImage};
+
+use crate::text::{luminance, pixel};
+
+/// Minimum luminance range (0-255) in a cell's neighbourhood for it to be text-like
+const MIN_CONTRAST: u8 = 96;
+
+/// Maximum share of mid-tone pixels in the neighbourhood; text is ink and paper
+const MAX_MIDTONE_FRACTION: f32 = 0.4;
+
+/// Minimum number of ink/paper transitions in the neighbourhood; text is high-frequency,
+/// a single straight edge between two flat areas (at most 3 crossings) is not
+const MIN_TRANSITIONS: usize = 4;
+
+/// Ink and paper colors for a cell in a text-like region of a scaled image
+///
+/// Looks at the 3x3 cells (3 wide, 6 pixels tall) around the cell. If they are
+/// high-contrast, bimodal and busy - glyph strokes rather than photos or single edges -
+/// returns the darkest and lightest pixels, which the cell's two pixels are then snapped
+/// to so strokes keep full contrast instead of being quantized independently.
+pub(crate) fn text_colors(image: &RgbImage, cell_x: u32, cell_y: u32) -> Option<(Rgb, Rgb)> {
+    let x0 = cell_x.saturating_sub(1);
+    let x1 = (cell_x + 2).min(image.width);
+    let y0 = (cell_y * 2).saturating_sub(2);
+    let y1 = (cell_y * 2 + 4).min(image.height);
+
+    let mut darkest = (u8::MAX, [0u8; 3]);
+    let mut lightest = (u8::MIN, [0u8; 3]);
+    for y in y0..y1 {
+        for x in x0..x1 {
+            let rgb = pixel(image, x, y);
+            let l = luminance(rgb);
+            if l < darkest.0 {
+                darkest = (l, rgb);
+            }
+            if l > lightest.0 {
+                lightest = (l, rgb);
+            }
+        }
+    }
+    let range = lightest.0.saturating_sub(darkest.0);
+    if range < MIN_CONTRAST {
+        return None;
+    }
+
+    let threshold = darkest.0 + range / 2;
+    let band = range / 4;
+    let mut midtones = 0;
+    let mut transitions = 0;
+    for y in y0..y1 {
+        let mut last_dark = None;
+        for x in x0..x1 {
+            let l = luminance(pixel(image, x, y));
+            if l.abs_diff(threshold) < band {
+                midtones += 1;
+            }
+            let dark = l < threshold;
+            if last_dark.is_some_and(|d| d != dark) {
+                transitions += 1;
+            }
+            last_dark = Some(dark);
+        }
+    }
+    // Vertical transitions count too (horizontal strokes)
+    for x in x0..x1 {
+        for y in y0 + 1..y1 {
+            let above = luminance(pixel(image, x, y - 1)) < threshold;
+            let here = luminance(pixel(image, x, y)) < threshold;
+            if above != here {
+                transitions += 1;
+            }
+        }
+    }
+
+    let total = ((x1 - x0) * (y1 - y0)) as f32;
+    if midtones as f32 / total > MAX_MIDTONE_FRACTION || transitions < MIN_TRANSITIONS {
+        return None;
+    }
+
+    let rgb = |p: [u8; 3]| Rgb::new(p[0], p[1], p[2]);
+    Some((rgb(darkest.1), rgb(lightest.1)))
+}
+
+/// Snap a pixel to ink or paper, whichever is closer in luminance
+pub(crate) fn snap(pixel: Rgb, ink: Rgb, paper: Rgb) -> Rgb {
+    let l = luminance([pixel.r, pixel.g, pixel.b]);
+    let ink_l = luminance([ink.r, ink.g, ink.b]);
+    let paper_l = luminance([paper.r, paper.g, paper.b]);
+    if l.abs_diff(ink_l) <= l.abs_diff(paper_l) {
+        ink
+    } else {
+        paper
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn image_from(rows: &[&str]) -> RgbImage {
+        let mut data = Vec::new();
+        for row in rows {
+            for c in row.chars() {
+                let v = if c == '#' { 20 } else { 235 };
+                data.extend_from_slice(&[v, v, v]);
+            }
+        }
+        RgbImage::new(data, rows[0].len() as u32, rows.len() as u32)
+    }
+
+    #[test]
+    fn test_glyph_is_text() {
+        // An "H" glyph above a blank row
+        let image = image_from(&["#.#", "#.#", "###", "#.#", "#.#", "..."]);
+
+        let (ink, paper) = text_colors(&image, 1, 1).unwrap();
+        assert_eq!((ink.r, paper.r), (20, 235));
+        assert_eq!(snap(Rgb::new(90, 90, 90), ink, paper), ink);
+        assert_eq!(snap(Rgb::new(180, 180, 180), ink, paper), paper);
+    }
+
+    #[test]
+    fn test_single_edge_is_not_text() {
+        let image = image_from(&["...", "...", "...", "###", "###", "###"]);
+        assert!(text_colors(&image, 1, 1).is_none());
+    }
+
+    #[test]
+    fn test_flat_area_is_not_text() {
+        let image = image_from(&["...", "...", "...", "...", "...", "..."]);
+        assert!(text_colors(&image, 1, 1).is_none());
+    }
+}
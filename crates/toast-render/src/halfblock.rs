@@ -1,7 +1,10 @@
 use rayon::prelude::*;
-use toast_core::{RgbImage, TerminalCell, TerminalFrame};
+use std::sync::Arc;
+use toast_core::{AnsiColor, Rgb, RgbImage, TerminalCell, TerminalFrame};
 
+use crate::glyph::{snap, text_colors};
 use crate::quantizer::ColorQuantizer;
+use crate::shade::{average, shade_cell};
 
 /// Unicode upper half block character
 const UPPER_HALF_BLOCK: char = '▀';
@@ -11,20 +14,113 @@ const FULL_BLOCK: char = '█';
 
 /// Convert an RGB image to a terminal frame using half-block characters
 /// Each terminal cell represents 2 vertical pixels using the upper half block character
+///
+/// Cloning is cheap: clones share the quantizer's lookup table.
+#[derive(Clone)]
 pub struct HalfBlockConverter {
-    quantizer: ColorQuantizer,
+    quantizer: Arc<ColorQuantizer>,
+    text_quantization: bool,
+    pair_search: bool,
 }
 
 impl HalfBlockConverter {
     pub fn new() -> Self {
+        Self::with_quantizer(ColorQuantizer::new())
+    }
+
+    /// Create a converter around an existing quantizer (e.g. one using CIEDE2000)
+    pub fn with_quantizer(quantizer: ColorQuantizer) -> Self {
         Self {
-            quantizer: ColorQuantizer::new(),
+            quantizer: Arc::new(quantizer),
+            text_quantization: false,
+            pair_search: false,
         }
     }
 
+    /// Quantize text-like cells to their neighbourhood's ink and paper colors
+    ///
+    /// Independent nearest-color matching turns anti-aliased strokes into muddy mid-tones;
+    /// snapping both pixels of a glyph cell to two contrasting colors keeps them legible.
+    pub fn with_text_quantization(mut self, enabled: bool) -> Self {
+        self.text_quantization = enabled;
+        self
+    }
+
+    /// The quantizer shared by this converter and its clones
+    pub fn quantizer(&self) -> &Arc<ColorQuantizer> {
+        &self.quantizer
+    }
+
     /// Convert an RGB image to a terminal frame
     /// The image height should be 2x the terminal height
-    pub fn convert(&self, image: &RgbImage, term_width: usize, term_height: usize) -> TerminalFrame {
+    pub fn convert(
+        &self,
+        image: &RgbImage,
+        term_width: usize,
+        term_height: usize,
+    ) -> TerminalFrame {
+        self.convert_with(term_width, term_height, |x, y| {
+            self.convert_cell(image, x, y)
+        })
+    }
+
+    /// Convert an RGB image using ░▒▓█ shading instead of half blocks
+    ///
+    /// Each cell shows the average of its two pixels, blended from two palette colors.
+    pub fn convert_shaded(
+        &self,
+        image: &RgbImage,
+        term_width: usize,
+        term_height: usize,
+    ) -> TerminalFrame {
+        self.convert_with(term_width, term_height, |x, y| {
+            let (top_rgb, bottom_rgb) = cell_pixels(image, x, y);
+            shade_cell(&self.quantizer, average(top_rgb, bottom_rgb))
+        })
+    }
+
+    /// Convert an RGB image, biased towards the colors of a previous frame
+    ///
+    /// Cell (x, y) of the output is compared with cell (x + origin.0, y + origin.1) of
+    /// `previous`; see [`ColorQuantizer::quantize_stable`] for how `threshold` applies.
+    pub fn convert_stable(
+        &self,
+        image: &RgbImage,
+        term_width: usize,
+        term_height: usize,
+        previous: &TerminalFrame,
+        origin: (usize, usize),
+        threshold: f32,
+    ) -> TerminalFrame {
+        self.convert_with(term_width, term_height, |x, y| {
+            let (top_rgb, bottom_rgb) = self.cell_pixels(image, x, y);
+            match previous.get(x as usize + origin.0, y as usize + origin.1) {
+                Some(old) => {
+                    let (old_top, old_bottom) = cell_colors(old);
+                    make_cell(
+                        self.quantizer.quantize_stable(top_rgb, old_top, threshold),
+                        self.quantizer
+                            .quantize_stable(bottom_rgb, old_bottom, threshold),
+                    )
+                }
+                None => make_cell(
+                    self.quantizer.quantize(top_rgb),
+                    self.quantizer.quantize(bottom_rgb),
+                ),
+            }
+        })
+    }
+
+    /// Build a frame from a per-cell conversion function
+    fn convert_with<F>(
+        &self,
+        term_width: usize,
+        term_height: usize,
+        convert_cell: F,
+    ) -> TerminalFrame
+    where
+        F: Fn(u32, u32) -> TerminalCell + Sync,
+    {
         let mut frame = TerminalFrame::new(term_width, term_height);
 
         // Process rows in parallel using rayon
@@ -33,7 +129,7 @@ impl HalfBlockConverter {
             .map(|y| {
                 let mut row = Vec::with_capacity(term_width);
                 for x in 0..term_width {
-                    let cell = self.convert_cell(image, x as u32, y as u32);
+                    let cell = convert_cell(x as u32, y as u32);
                     row.push(cell);
                 }
                 row
@@ -50,48 +146,97 @@ impl HalfBlockConverter {
         frame
     }
 
+    /// Pick each cell's two colors jointly (see [`ColorQuantizer::quantize_pair`])
+    ///
+    /// Applies to [`convert`](Self::convert); hysteresis keeps quantizing per pixel.
+    pub fn with_pair_search(mut self, enabled: bool) -> Self {
+        self.pair_search = enabled;
+        self
+    }
+
+    /// A cell's two pixels, snapped to ink and paper in text regions if enabled
+    fn cell_pixels(&self, image: &RgbImage, cell_x: u32, cell_y: u32) -> (Rgb, Rgb) {
+        let (top, bottom) = cell_pixels(image, cell_x, cell_y);
+        if !self.text_quantization {
+            return (top, bottom);
+        }
+        match text_colors(image, cell_x, cell_y) {
+            Some((ink, paper)) => (snap(top, ink, paper), snap(bottom, ink, paper)),
+            None => (top, bottom),
+        }
+    }
+
     /// Convert a single terminal cell (2 vertical pixels)
     fn convert_cell(&self, image: &RgbImage, cell_x: u32, cell_y: u32) -> TerminalCell {
-        // Each cell represents 2 vertical pixels
-        let pixel_y_top = cell_y * 2;
-        let pixel_y_bottom = pixel_y_top + 1;
-
-        // Handle edge case where bottom pixel is out of bounds
-        let (top_rgb, bottom_rgb) = if pixel_y_bottom < image.height {
-            (
-                image.get_pixel(cell_x, pixel_y_top),
-                image.get_pixel(cell_x, pixel_y_bottom),
-            )
-        } else {
-            // Only top pixel exists
-            let top = image.get_pixel(cell_x, pixel_y_top);
-            (top, top)
-        };
+        let (top_rgb, bottom_rgb) = self.cell_pixels(image, cell_x, cell_y);
 
         // Quantize to ANSI colors
+        if self.pair_search {
+            let (top_ansi, bottom_ansi) = self.quantizer.quantize_pair(top_rgb, bottom_rgb);
+            return make_cell(top_ansi, bottom_ansi);
+        }
         let top_ansi = self.quantizer.quantize(top_rgb);
         let bottom_ansi = self.quantizer.quantize(bottom_rgb);
 
-        // Choose character and colors
-        if top_ansi == bottom_ansi {
-            // Same color - use full block or space
-            TerminalCell {
-                character: FULL_BLOCK,
-                foreground: top_ansi,
-                background: top_ansi,
-            }
-        } else {
-            // Different colors - use upper half block
-            // Foreground = top pixel, Background = bottom pixel
-            TerminalCell {
-                character: UPPER_HALF_BLOCK,
-                foreground: top_ansi,
-                background: bottom_ansi,
-            }
+        make_cell(top_ansi, bottom_ansi)
+    }
+}
+
+/// Top and bottom pixel of a cell (each terminal cell represents 2 vertical pixels)
+fn cell_pixels(image: &RgbImage, cell_x: u32, cell_y: u32) -> (Rgb, Rgb) {
+    let pixel_y_top = cell_y * 2;
+    let pixel_y_bottom = pixel_y_top + 1;
+
+    // Handle edge case where bottom pixel is out of bounds
+    if pixel_y_bottom < image.height {
+        (
+            image.get_pixel(cell_x, pixel_y_top),
+            image.get_pixel(cell_x, pixel_y_bottom),
+        )
+    } else {
+        // Only top pixel exists
+        let top = image.get_pixel(cell_x, pixel_y_top);
+        (top, top)
+    }
+}
+
+/// Choose character and colors for a pair of quantized pixels
+pub(crate) fn make_cell(top_ansi: AnsiColor, bottom_ansi: AnsiColor) -> TerminalCell {
+    if top_ansi == bottom_ansi {
+        // Same color - use full block or space
+        TerminalCell {
+            character: FULL_BLOCK,
+            foreground: top_ansi,
+            background: top_ansi,
+            cluster: None,
+        }
+    } else {
+        // Different colors - use upper half block
+        // Foreground = top pixel, Background = bottom pixel
+        TerminalCell {
+            character: UPPER_HALF_BLOCK,
+            foreground: top_ansi,
+            background: bottom_ansi,
+            cluster: None,
         }
     }
 }
 
+/// Whether a cell was produced by [`make_cell`] (as opposed to shading or overlays)
+pub(crate) fn is_block_cell(cell: &TerminalCell) -> bool {
+    cell.character == UPPER_HALF_BLOCK
+        || (cell.character == FULL_BLOCK && cell.foreground == cell.background)
+}
+
+/// Top and bottom colors of a cell produced by [`make_cell`]
+pub(crate) fn cell_colors(cell: &TerminalCell) -> (AnsiColor, AnsiColor) {
+    if cell.character == UPPER_HALF_BLOCK {
+        (cell.foreground, cell.background)
+    } else {
+        (cell.foreground, cell.foreground)
+    }
+}
+
 impl Default for HalfBlockConverter {
     fn default() -> Self {
         Self::new()
@@ -101,7 +246,6 @@ impl Default for HalfBlockConverter {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use toast_core::Rgb;
 
     #[test]
     fn test_convert_single_cell_same_color() {
@@ -135,6 +279,26 @@ mod tests {
         assert_eq!(cell.background.as_u8(), 0);  // Black
     }
 
+    #[test]
+    fn test_convert_stable_keeps_previous_colors() {
+        let converter = HalfBlockConverter::new();
+
+        // Near-white image; previous frame used the neighbouring light grey (255 = #eeeeee)
+        let data = vec![250u8; 2 * 2 * 3];
+        let image = RgbImage::new(data, 2, 2);
+        let mut previous = TerminalFrame::new(2, 1);
+        for cell in previous.cells.iter_mut() {
+            *cell = make_cell(AnsiColor(255), AnsiColor(255));
+        }
+
+        let stable = converter.convert_stable(&image, 2, 1, &previous, (0, 0), 10.0);
+        assert!(stable.cells.iter().all(|c| c.foreground.as_u8() == 255));
+
+        // Without hysteresis the nearest color is used
+        let fresh = converter.convert(&image, 2, 1);
+        assert!(fresh.cells.iter().all(|c| c.foreground.as_u8() != 255));
+    }
+
     #[test]
     fn test_convert_full_frame() {
         let converter = HalfBlockConverter::new();
@@ -0,0 +1,116 @@
+use std::fmt::Write;
+use toast_core::{AnsiColor, TerminalFrame};
+
+use crate::quantizer::ansi_to_rgb;
+
+/// Export a frame as a standalone HTML page of colored `<span>`s
+///
+/// Runs of cells with the same colors share a span. The page needs no external assets,
+/// so it can be attached to bug reports or embedded as is.
+pub fn frame_to_html(frame: &TerminalFrame, title: &str) -> String {
+    let mut html = String::with_capacity(frame.width * frame.height * 8 + 512);
+    html.push_str("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n");
+    let _ = writeln!(html, "<title>{}</title>", escape(title));
+    html.push_str(
+        "<style>\nbody { background: #000; margin: 0; }\n\
+         pre { font-family: monospace; font-size: 14px; line-height: 1; margin: 0; }\n\
+         </style>\n</head>\n<body>\n<pre>",
+    );
+
+    for row in frame.cells.chunks(frame.width.max(1)) {
+        let mut run: Option<(AnsiColor, AnsiColor)> = None;
+        for cell in row {
+            // Covered by the double-width character before it
+            if cell.is_continuation() {
+                continue;
+            }
+            let colors = (cell.foreground, cell.background);
+            if run != Some(colors) {
+                if run.is_some() {
+                    html.push_str("</span>");
+                }
+                let _ = write!(
+                    html,
+                    "<span style=\"color:{};background:{}\">",
+                    css_color(colors.0),
+                    css_color(colors.1)
+                );
+                run = Some(colors);
+            }
+            push_escaped(&mut html, cell.character);
+            for character in cell.cluster.iter().flat_map(|rest| rest.chars()) {
+                push_escaped(&mut html, character);
+            }
+        }
+        if run.is_some() {
+            html.push_str("</span>");
+        }
+        html.push('\n');
+    }
+
+    html.push_str("</pre>\n</body>\n</html>\n");
+    html
+}
+
+fn css_color(color: AnsiColor) -> String {
+    let rgb = ansi_to_rgb(color);
+    format!("#{:02x}{:02x}{:02x}", rgb.r, rgb.g, rgb.b)
+}
+
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        push_escaped(&mut out, c);
+    }
+    out
+}
+
+fn push_escaped(out: &mut String, c: char) {
+    match c {
+        '&' => out.push_str("&amp;"),
+        '<' => out.push_str("&lt;"),
+        '>' => out.push_str("&gt;"),
+        '"' => out.push_str("&quot;"),
+        _ => out.push(c),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use toast_core::TerminalCell;
+
+    #[test]
+    fn test_runs_share_a_span() {
+        let mut frame = TerminalFrame::new(3, 1);
+        for x in 0..3 {
+            frame.set(
+                x,
+                0,
+                TerminalCell {
+                    character: '▀',
+                    foreground: AnsiColor(15),
+                    background: AnsiColor(if x < 2 { 0 } else { 9 }),
+                    cluster: None,
+                },
+            );
+        }
+
+        let html = frame_to_html(&frame, "test");
+
+        assert_eq!(html.matches("<span").count(), 2);
+        assert!(html.contains("<span style=\"color:#ffffff;background:#000000\">▀▀</span>"));
+        assert!(html.contains("background:#ff0000\">▀</span>"));
+    }
+
+    #[test]
+    fn test_escapes_markup() {
+        let mut frame = TerminalFrame::new(1, 1);
+        frame.cells[0].character = '<';
+
+        let html = frame_to_html(&frame, "a & b");
+
+        assert!(html.contains("<title>a &amp; b</title>"));
+        assert!(html.contains(">&lt;</span>"));
+    }
+}
@@ -0,0 +1,124 @@
+use toast_core::{Rgb, TerminalCell, TerminalFrame};
+
+use crate::halfblock::{cell_colors, is_block_cell, make_cell};
+use crate::quantizer::{ansi_to_rgb, ColorQuantizer};
+
+/// Cross-fade between two frames of the same size, `t` from 0 (`from`) to 1 (`to`)
+///
+/// Block cells are blended per half in RGB and re-quantized. Cells drawn with other
+/// characters (shades, overlays) can't be mixed and switch over at the midpoint. Returns
+/// `None` if the frames differ in size.
+pub fn blend_frames(
+    from: &TerminalFrame,
+    to: &TerminalFrame,
+    t: f32,
+    quantizer: &ColorQuantizer,
+) -> Option<TerminalFrame> {
+    if from.width != to.width || from.height != to.height {
+        return None;
+    }
+
+    // The palette has duplicate colors, so re-quantizing an endpoint could change indices
+    if t <= 0.0 {
+        return Some(from.clone());
+    }
+    if t >= 1.0 {
+        return Some(to.clone());
+    }
+
+    let mut frame = TerminalFrame::new(to.width, to.height);
+    frame.metadata = to.metadata.clone();
+    for (out, (a, b)) in frame.cells.iter_mut().zip(from.cells.iter().zip(&to.cells)) {
+        *out = if a == b {
+            b.clone()
+        } else if is_block_cell(a) && is_block_cell(b) {
+            blend_cell(a, b, t, quantizer)
+        } else if t < 0.5 {
+            a.clone()
+        } else {
+            b.clone()
+        };
+    }
+
+    Some(frame)
+}
+
+fn blend_cell(
+    a: &TerminalCell,
+    b: &TerminalCell,
+    t: f32,
+    quantizer: &ColorQuantizer,
+) -> TerminalCell {
+    let (a_top, a_bottom) = cell_colors(a);
+    let (b_top, b_bottom) = cell_colors(b);
+    let mix = |x, y| quantizer.quantize(lerp(ansi_to_rgb(x), ansi_to_rgb(y), t));
+    make_cell(mix(a_top, b_top), mix(a_bottom, b_bottom))
+}
+
+/// Mix two colors, `t` from 0 (`a`) to 1 (`b`)
+pub(crate) fn lerp(a: Rgb, b: Rgb, t: f32) -> Rgb {
+    let channel = |x: u8, y: u8| (x as f32 + (y as f32 - x as f32) * t).round() as u8;
+    Rgb::new(channel(a.r, b.r), channel(a.g, b.g), channel(a.b, b.b))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use toast_core::AnsiColor;
+
+    fn solid(width: usize, height: usize, color: u8) -> TerminalFrame {
+        let mut frame = TerminalFrame::new(width, height);
+        for cell in frame.cells.iter_mut() {
+            *cell = make_cell(AnsiColor(color), AnsiColor(color));
+        }
+        frame
+    }
+
+    #[test]
+    fn test_endpoints() {
+        let quantizer = ColorQuantizer::new();
+        let (black, white) = (solid(2, 2, 16), solid(2, 2, 231));
+
+        assert_eq!(
+            blend_frames(&black, &white, 0.0, &quantizer).unwrap().cells,
+            black.cells
+        );
+        assert_eq!(
+            blend_frames(&black, &white, 1.0, &quantizer).unwrap().cells,
+            white.cells
+        );
+    }
+
+    #[test]
+    fn test_midpoint_is_in_between() {
+        let quantizer = ColorQuantizer::new();
+        let (black, white) = (solid(1, 1, 16), solid(1, 1, 231));
+
+        let mid = blend_frames(&black, &white, 0.5, &quantizer).unwrap();
+        let rgb = ansi_to_rgb(mid.cells[0].foreground);
+        assert!(rgb.r > 64 && rgb.r < 192, "{:?}", rgb);
+    }
+
+    #[test]
+    fn test_text_cells_switch_at_midpoint() {
+        let quantizer = ColorQuantizer::new();
+        let from = solid(1, 1, 16);
+        let mut to = solid(1, 1, 16);
+        to.cells[0].character = 'A';
+
+        assert_eq!(
+            blend_frames(&from, &to, 0.4, &quantizer).unwrap().cells,
+            from.cells
+        );
+        assert_eq!(
+            blend_frames(&from, &to, 0.6, &quantizer).unwrap().cells,
+            to.cells
+        );
+    }
+
+    #[test]
+    fn test_size_mismatch() {
+        let quantizer = ColorQuantizer::new();
+        assert!(blend_frames(&solid(1, 1, 16), &solid(2, 1, 16), 0.5, &quantizer).is_none());
+    }
+}
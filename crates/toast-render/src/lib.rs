@@ -1,26 +1,234 @@
+mod aspect;
+mod backend;
+mod config;
+mod contrast;
+mod daltonize;
+mod damage;
 mod decoder;
+mod edges;
+mod glyph;
 mod halfblock;
+mod html;
+mod interpolate;
+mod motion;
+mod ocr;
+mod overlay;
 mod quantizer;
+mod saturation;
 mod scaler;
+mod shade;
+mod sharpen;
+mod stats;
+mod supersample;
+mod text;
+mod zoom;
 
-pub use decoder::decode_screenshot;
+pub use aspect::{grid_viewport, PageFit, NOMINAL_CELL_ASPECT, NOMINAL_CELL_WIDTH};
+pub use backend::{RenderBackend, ShadeBackend};
+pub use config::RenderConfig;
+pub use contrast::{apply_high_contrast, HighContrast};
+pub use daltonize::ColorFilter;
+pub use damage::{damage_rects, dirty_cells, DAMAGE_MARGIN};
+pub use decoder::{decode_screenshot, decode_screenshot_at_least, DecodeError, MAX_DIMENSION};
+pub use edges::preserve_edges;
 pub use halfblock::HalfBlockConverter;
-pub use quantizer::ColorQuantizer;
-pub use scaler::scale_image;
+pub use html::frame_to_html;
+pub use interpolate::blend_frames;
+pub use motion::{detect_scroll, ScrollDiff};
+pub use ocr::{parse_tsv, OcrEngine, OcrResult, OcrWord};
+pub use overlay::OverlayStyle;
+pub use quantizer::{ansi_to_rgb, ColorMetric, ColorQuantizer};
+pub use saturation::adjust_saturation;
+pub use scaler::{scale_image, ScaleFilter, Scaler};
+pub use shade::CharacterSet;
+pub use sharpen::unsharp_mask;
+pub use stats::PipelineStats;
+pub use supersample::{box_average, cluster_cells, SUPERSAMPLE_FACTOR};
+pub use text::enhance_text_regions;
+pub use zoom::Zoom;
 
 use anyhow::Result;
-use toast_core::{Screenshot, TerminalFrame};
+use bytes::Bytes;
+use parking_lot::Mutex;
+use std::sync::Arc;
+use std::time::Instant;
+use toast_core::{CellRect, Clip, ImageFormat, RgbImage, Screenshot, TerminalFrame};
+
+/// Fraction of the frame above which a damaged render falls back to a full render
+const FULL_RENDER_FRACTION: f32 = 0.5;
+
+/// Previous output (and input, with damage tracking), kept between frames
+struct FrameHistory {
+    source: Option<RgbImage>,
+    frame: TerminalFrame,
+}
+
+/// The last screenshot rendered and the frame it produced
+///
+/// A static page captures to the same bytes every time; comparing against them is far
+/// cheaper than decoding and quantizing again.
+struct LastRender {
+    data: Bytes,
+    format: ImageFormat,
+    /// Part of the viewport the screenshot showed, which changes how it's cropped
+    clip: Option<Clip>,
+    size: (usize, usize),
+    frame: TerminalFrame,
+    /// The frame is a progressive draft, to be refined if the page stays the same
+    draft: bool,
+}
 
 /// Full rendering pipeline: Screenshot → Terminal Frame
 pub struct RenderPipeline {
+    config: RenderConfig,
     converter: HalfBlockConverter,
+    scaler: Mutex<Scaler>,
+    /// Turns scaled images into cells (half blocks or shades unless replaced)
+    backend: Arc<dyn RenderBackend>,
+    /// Set when `backend` was supplied by the user rather than picked from the config
+    custom_backend: bool,
+    /// Nearest-neighbour scaler and plain backend for progressive drafts
+    draft_scaler: Mutex<Scaler>,
+    draft_backend: Arc<dyn RenderBackend>,
+    history: Mutex<Option<FrameHistory>>,
+    zoom: Mutex<Zoom>,
+    color_filter: Mutex<ColorFilter>,
+    stats: Mutex<PipelineStats>,
+    last_render: Mutex<Option<LastRender>>,
 }
 
 impl RenderPipeline {
     pub fn new() -> Self {
+        Self::with_config(RenderConfig::default())
+    }
+
+    /// Create a pipeline with the given rendering options
+    pub fn with_config(config: RenderConfig) -> Self {
+        let quantizer = match (config.high_contrast, &config.lut_cache_dir) {
+            (HighContrast::Base16, Some(dir)) => ColorQuantizer::base16_cached(config.metric, dir),
+            (HighContrast::Base16, None) => ColorQuantizer::base16(config.metric),
+            (_, Some(dir)) => ColorQuantizer::with_metric_cached(config.metric, dir),
+            (_, None) => ColorQuantizer::with_metric(config.metric),
+        };
+        let converter = HalfBlockConverter::with_quantizer(quantizer)
+            .with_text_quantization(config.text_quantization)
+            .with_pair_search(config.pair_search);
         Self {
-            converter: HalfBlockConverter::new(),
+            backend: builtin_backend(&converter, config.charset),
+            custom_backend: false,
+            draft_backend: builtin_backend(&draft_converter(&converter), config.charset),
+            converter,
+            scaler: Mutex::new(Scaler::with_filter(config.filter)),
+            draft_scaler: Mutex::new(Scaler::with_filter(ScaleFilter::Nearest)),
+            history: Mutex::new(None),
+            zoom: Mutex::new(Zoom::new()),
+            color_filter: Mutex::new(config.color_filter),
+            stats: Mutex::new(PipelineStats::default()),
+            last_render: Mutex::new(None),
+            config,
+        }
+    }
+
+    /// The options this pipeline was built with
+    pub fn config(&self) -> &RenderConfig {
+        &self.config
+    }
+
+    /// The quantizer used for frames, e.g. to map other colors onto the same palette
+    pub fn quantizer(&self) -> &Arc<ColorQuantizer> {
+        self.converter.quantizer()
+    }
+
+    /// Convert cells with `backend` instead of the built-in half blocks or shades
+    ///
+    /// Color hysteresis, supersampling and subpixel sampling only apply to the built-in
+    /// half-block conversion and are skipped with a custom backend.
+    pub fn with_backend(mut self, backend: impl RenderBackend + 'static) -> Self {
+        let backend: Arc<dyn RenderBackend> = Arc::new(backend);
+        self.draft_backend = Arc::clone(&backend);
+        self.backend = backend;
+        self.custom_backend = true;
+        *self.history.lock() = None;
+        *self.last_render.lock() = None;
+        self
+    }
+
+    /// The backend frames are converted with
+    pub fn backend(&self) -> &dyn RenderBackend {
+        self.backend.as_ref()
+    }
+
+    /// The current terminal-side zoom
+    pub fn zoom(&self) -> Zoom {
+        *self.zoom.lock()
+    }
+
+    /// The part of a `width`x`height` viewport shown at the current zoom, magnified to
+    /// the size of the whole viewport; `None` when not zoomed in
+    ///
+    /// Captured on its own, the part shows in the detail of a full-viewport capture.
+    pub fn zoom_clip(
+        &self,
+        width: u32,
+        height: u32,
+        term_width: usize,
+        term_height: usize,
+    ) -> Option<Clip> {
+        let zoom = self.zoom();
+        if zoom.is_identity() {
+            return None;
         }
+        let (left, top, shown_width, shown_height) = self
+            .page_fit(width, height, term_width, term_height)
+            .map_or((0, 0, width, height), |fit| fit.source);
+        let (x, y, width, height) = zoom.source_rect(shown_width, shown_height);
+        Some(Clip {
+            x: (left + x) as f64,
+            y: (top + y) as f64,
+            width: width as f64,
+            height: height as f64,
+            scale: zoom.scale() as f64,
+        })
+    }
+
+    /// Change the terminal-side zoom; takes effect from the next rendered frame
+    pub fn set_zoom(&self, zoom: Zoom) {
+        *self.zoom.lock() = zoom;
+        *self.last_render.lock() = None;
+    }
+
+    /// Change the resampling filter, e.g. to trade quality for speed under load; takes
+    /// effect from the next rendered frame
+    pub fn set_scale_filter(&self, filter: ScaleFilter) {
+        self.scaler.lock().set_filter(filter);
+        *self.last_render.lock() = None;
+    }
+
+    /// The color-blindness filter currently applied
+    pub fn color_filter(&self) -> ColorFilter {
+        *self.color_filter.lock()
+    }
+
+    /// Change the color-blindness filter; the next frame is rendered in full
+    pub fn set_color_filter(&self, filter: ColorFilter) {
+        *self.color_filter.lock() = filter;
+        // The source is unchanged, so damage tracking would otherwise keep the old colors
+        *self.history.lock() = None;
+        *self.last_render.lock() = None;
+    }
+
+    /// Whether the last frame rendered was a progressive draft, still to be refined by
+    /// rendering the same screenshot again
+    pub fn showing_draft(&self) -> bool {
+        self.last_render
+            .lock()
+            .as_ref()
+            .is_some_and(|last| last.draft)
+    }
+
+    /// Per-stage timings of the most recent frame
+    pub fn stats(&self) -> PipelineStats {
+        *self.stats.lock()
     }
 
     /// Convert a screenshot to a terminal frame
@@ -30,18 +238,386 @@ impl RenderPipeline {
         term_width: usize,
         term_height: usize,
     ) -> Result<TerminalFrame> {
+        // Nothing changed on the page: reuse the previous frame, unless it was a draft
+        let mut refine = false;
+        if let Some(last) = self.last_render.lock().as_ref() {
+            if last.size == (term_width, term_height)
+                && last.format == screenshot.format
+                && last.clip == screenshot.metadata.clip
+                && last.data == screenshot.data
+            {
+                if last.draft {
+                    refine = true;
+                } else {
+                    let mut frame = last.frame.clone();
+                    frame.damage = Some(Vec::new());
+                    frame.scroll = None;
+                    frame.metadata = screenshot.metadata.clone();
+
+                    let mut stats = self.stats.lock();
+                    *stats = PipelineStats {
+                        frames: stats.frames + 1,
+                        unchanged: stats.unchanged + 1,
+                        ..PipelineStats::default()
+                    };
+                    return Ok(frame);
+                }
+            }
+        }
+
         // Decode screenshot to RGB
-        let rgb_image = decode_screenshot(screenshot)?;
+        let started = Instant::now();
+        // Decoded no larger than needed, unless zooming will show just a part of it
+        let rgb_image = if self.zoom().is_identity() || screenshot.metadata.clip.is_some() {
+            let (cols, rows) = self.backend.cell_resolution();
+            decode_screenshot_at_least(
+                screenshot,
+                term_width as u32 * cols,
+                term_height as u32 * rows,
+            )?
+        } else {
+            decode_screenshot(screenshot)?
+        };
+        let decode = started.elapsed();
+
+        // A changed page is shown as a quick draft first when rendering progressively
+        let draft = self.config.progressive && !refine;
+        // A clipped screenshot is already just the zoomed part of the page
+        let clipped = screenshot.metadata.clip.is_some();
+        let mut frame = self.render_pass(&rgb_image, term_width, term_height, draft, clipped)?;
+        if refine {
+            // The terminal shows the draft, not the frame damage was tracked against
+            frame.damage = None;
+            frame.scroll = None;
+        }
+        self.stats.lock().decode = decode;
+        frame.metadata = screenshot.metadata.clone();
+        *self.last_render.lock() = Some(LastRender {
+            data: screenshot.data.clone(),
+            format: screenshot.format,
+            clip: screenshot.metadata.clip,
+            size: (term_width, term_height),
+            frame: frame.clone(),
+            draft,
+        });
+        Ok(frame)
+    }
 
-        // Calculate target dimensions (height * 2 because each terminal row = 2 pixels)
-        let target_width = term_width as u32;
-        let target_height = (term_height as u32) * 2;
+    /// Convert an already-decoded image to a terminal frame
+    pub fn render_image(
+        &self,
+        rgb_image: &RgbImage,
+        term_width: usize,
+        term_height: usize,
+    ) -> Result<TerminalFrame> {
+        self.render_pass(rgb_image, term_width, term_height, false, false)
+    }
+
+    /// Render a full-quality frame, or a quick draft without the frame history
+    ///
+    /// A `clipped` image shows only the zoomed part of the page, and isn't cropped again.
+    fn render_pass(
+        &self,
+        rgb_image: &RgbImage,
+        term_width: usize,
+        term_height: usize,
+        draft: bool,
+        clipped: bool,
+    ) -> Result<TerminalFrame> {
+        // Stages add their time as they run
+        {
+            let mut stats = self.stats.lock();
+            *stats = PipelineStats {
+                frames: stats.frames,
+                unchanged: stats.unchanged,
+                ..PipelineStats::default()
+            };
+        }
+
+        // Keep the page's proportions: show only the part that fits, in the rows it fills
+        let fit = self.page_fit(rgb_image.width, rgb_image.height, term_width, term_height);
+        let fitted;
+        let page = match fit {
+            Some(fit) if !fit.is_whole(rgb_image.width, rgb_image.height, term_height) => {
+                let (x, y, width, height) = fit.source;
+                fitted = damage::crop(rgb_image, x, y, width, height);
+                &fitted
+            }
+            _ => rgb_image,
+        };
+        let rows = fit.map_or(term_height, |fit| fit.rows);
+
+        let render = |image: &RgbImage| {
+            if draft {
+                self.render_draft(image, term_width, rows)
+            } else {
+                self.render_unzoomed(image, term_width, rows)
+            }
+        };
+
+        // Magnify: render only the zoomed part of the page
+        let zoom = self.zoom();
+        let mut frame = if !zoom.is_identity() && !clipped {
+            let (x, y, width, height) = zoom.source_rect(page.width, page.height);
+            let visible = damage::crop(page, x, y, width, height);
+            render(&visible)?
+        } else {
+            render(page)?
+        };
+
+        // Rows below a page narrower than the terminal stay blank
+        if rows < term_height {
+            frame = pad_rows(frame, term_height);
+        }
+
+        self.stats.lock().frames += 1;
+        Ok(frame)
+    }
+
+    /// How a page of this size is fitted onto the grid, if cell aspect correction is on
+    pub fn page_fit(
+        &self,
+        width: u32,
+        height: u32,
+        term_width: usize,
+        term_height: usize,
+    ) -> Option<PageFit> {
+        self.config
+            .cell_aspect
+            .map(|aspect| PageFit::new(width, height, term_width, term_height, aspect))
+    }
+
+    /// Render the whole image, using the frame history for damage tracking and hysteresis
+    fn render_unzoomed(
+        &self,
+        rgb_image: &RgbImage,
+        term_width: usize,
+        term_height: usize,
+    ) -> Result<TerminalFrame> {
+        if !self.config.damage_tracking
+            && self.config.color_hysteresis <= 0.0
+            && !self.config.scroll_detection
+        {
+            return self.render_region(rgb_image, term_width, term_height, None);
+        }
+
+        let mut history = self.history.lock();
+        // Nothing carries over across a terminal resize
+        if history
+            .as_ref()
+            .is_some_and(|h| h.frame.width != term_width || h.frame.height != term_height)
+        {
+            *history = None;
+        }
 
-        // Scale to terminal dimensions
-        let scaled = scale_image(&rgb_image, target_width, target_height)?;
+        let rects = match history.as_ref().and_then(|h| h.source.as_ref()) {
+            Some(source)
+                if source.width == rgb_image.width && source.height == rgb_image.height =>
+            {
+                let dirty = dirty_cells(source, rgb_image, term_width, term_height);
+                Some(damage_rects(&dirty, term_width, term_height, DAMAGE_MARGIN))
+            }
+            _ => None,
+        };
 
-        // Convert to half-blocks
-        let frame = self.converter.convert(&scaled, term_width, term_height);
+        let damaged_area: usize = rects.iter().flatten().map(CellRect::area).sum();
+        let full_area = term_width * term_height;
+        let frame = match (rects, history.as_mut()) {
+            (Some(rects), Some(state))
+                if (damaged_area as f32) <= full_area as f32 * FULL_RENDER_FRACTION =>
+            {
+                for rect in &rects {
+                    self.render_damaged(rgb_image, rect, &mut state.frame)?;
+                }
+                if let Some(source) = state.source.as_mut() {
+                    source.data.copy_from_slice(&rgb_image.data);
+                }
+                let mut frame = state.frame.clone();
+                frame.damage = Some(rects);
+                frame
+            }
+            (_, state) => {
+                // First frame, resize, most of the page changed, or no damage tracking
+                let previous = state.map(|s| &s.frame);
+                let mut frame = self.render_region(
+                    rgb_image,
+                    term_width,
+                    term_height,
+                    previous.map(|frame| (frame, (0, 0))),
+                )?;
+                // A scroll changes most cells; describe it as one so the terminal can shift rows
+                let scroll = previous
+                    .filter(|_| self.config.scroll_detection)
+                    .and_then(|previous| detect_scroll(previous, &frame));
+                *history = Some(FrameHistory {
+                    source: self.config.damage_tracking.then(|| rgb_image.clone()),
+                    frame: frame.clone(),
+                });
+                if let Some(diff) = scroll {
+                    frame.scroll = Some(diff.motion);
+                    frame.damage = Some(diff.residual);
+                }
+                frame
+            }
+        };
+
+        Ok(frame)
+    }
+
+    /// Quick low-quality render: nearest-neighbour scaling and only the color adjustments
+    /// that must match the refined frame (saturation, color filter, high contrast)
+    fn render_draft(
+        &self,
+        rgb_image: &RgbImage,
+        term_width: usize,
+        term_height: usize,
+    ) -> Result<TerminalFrame> {
+        let started = Instant::now();
+        let (cols, rows) = self.draft_backend.cell_resolution();
+        let mut scaler = self.draft_scaler.lock();
+        let scaled = scaler.scale(
+            rgb_image,
+            term_width as u32 * cols,
+            term_height as u32 * rows,
+        )?;
+        let scaled_at = Instant::now();
+
+        adjust_saturation(scaled, self.config.saturation, self.config.vibrance);
+        self.color_filter().apply(scaled);
+        if self.config.high_contrast.is_enabled() {
+            apply_high_contrast(scaled);
+        }
+
+        let enhanced_at = Instant::now();
+        let frame = self.draft_backend.convert(scaled, term_width, term_height);
+
+        let mut stats = self.stats.lock();
+        stats.scale += scaled_at - started;
+        stats.enhance += enhanced_at - scaled_at;
+        stats.convert += enhanced_at.elapsed();
+
+        Ok(frame)
+    }
+
+    /// Re-render one damaged rectangle into `frame`
+    ///
+    /// The source is cropped with an extra margin so the scaling kernel sees the same
+    /// neighbourhood at the rectangle's edges as it would in a full render.
+    fn render_damaged(
+        &self,
+        rgb_image: &RgbImage,
+        rect: &CellRect,
+        frame: &mut TerminalFrame,
+    ) -> Result<()> {
+        let (cols, rows) = (frame.width, frame.height);
+        let context = damage::expand(rect, DAMAGE_MARGIN, cols, rows);
+        let (x, y, width, height) = damage::source_bounds(&context, rgb_image, cols, rows);
+        let region = damage::crop(rgb_image, x, y, width, height);
+
+        let rendered = self.render_region(
+            &region,
+            context.width,
+            context.height,
+            Some((frame, (context.x, context.y))),
+        )?;
+
+        let (offset_x, offset_y) = (rect.x - context.x, rect.y - context.y);
+        for row in 0..rect.height {
+            for col in 0..rect.width {
+                if let Some(cell) = rendered.get(offset_x + col, offset_y + row) {
+                    frame.set(rect.x + col, rect.y + row, cell.clone());
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Scale, enhance and quantize an image into a frame of the given size
+    ///
+    /// `previous` is the last frame and this region's origin in it, used for color hysteresis.
+    fn render_region(
+        &self,
+        rgb_image: &RgbImage,
+        term_width: usize,
+        term_height: usize,
+        previous: Option<(&TerminalFrame, (usize, usize))>,
+    ) -> Result<TerminalFrame> {
+        // Calculate target dimensions (for half blocks, height * 2 because each terminal
+        // row = 2 pixels)
+        let (cell_cols, cell_rows) = self.backend.cell_resolution();
+        let target_width = term_width as u32 * cell_cols;
+        let target_height = term_height as u32 * cell_rows;
+        // Supersampling reduces to half-block pixels
+        let half_blocks = (cell_cols, cell_rows) == (1, 2);
+
+        // Scale to terminal dimensions, reusing the scaler's buffers from the previous frame
+        let started = Instant::now();
+        let mut scaler = self.scaler.lock();
+        let mut reduced;
+        let scaled = if self.config.supersample && half_blocks {
+            // Sample at a higher resolution and reduce each cell to its dominant colors
+            let factor = SUPERSAMPLE_FACTOR;
+            let sampled = scaler.scale(rgb_image, target_width * factor, target_height * factor)?;
+            reduced = cluster_cells(sampled, term_width, term_height);
+            &mut reduced
+        } else if self.config.subpixel && half_blocks {
+            // Sample at a higher resolution and area-average each half-block pixel
+            let factor = SUPERSAMPLE_FACTOR;
+            let sampled = scaler.scale(rgb_image, target_width * factor, target_height * factor)?;
+            reduced = box_average(sampled, term_width, term_height);
+            &mut reduced
+        } else {
+            scaler.scale(rgb_image, target_width, target_height)?
+        };
+        let scaled_at = Instant::now();
+
+        // Keep thin lines the downscale averaged away
+        if self.config.preserve_edges {
+            preserve_edges(rgb_image, scaled);
+        }
+
+        // Counter the softening of the downscale
+        unsharp_mask(scaled, self.config.sharpen);
+
+        // Compensate for the palette pulling colors towards grey
+        adjust_saturation(scaled, self.config.saturation, self.config.vibrance);
+
+        // Remap hues the viewer can't tell apart
+        self.color_filter().apply(scaled);
+
+        // Accessibility: stretch contrast after all color adjustments
+        if self.config.high_contrast.is_enabled() {
+            apply_high_contrast(scaled);
+        }
+
+        // Restore text edges lost in downscaling, using the full-resolution source
+        if self.config.text_enhancement {
+            enhance_text_regions(rgb_image, scaled);
+        }
+
+        // Convert to cells; half blocks are biased towards the previous colors if requested
+        let enhanced_at = Instant::now();
+        let hysteresis = self.config.color_hysteresis;
+        let stable = hysteresis > 0.0
+            && !self.custom_backend
+            && self.config.charset == CharacterSet::HalfBlock;
+        let frame = match previous {
+            Some((frame, origin)) if stable => self.converter.convert_stable(
+                scaled,
+                term_width,
+                term_height,
+                frame,
+                origin,
+                hysteresis,
+            ),
+            _ => self.backend.convert(scaled, term_width, term_height),
+        };
+
+        let mut stats = self.stats.lock();
+        stats.scale += scaled_at - started;
+        stats.enhance += enhanced_at - scaled_at;
+        stats.convert += enhanced_at.elapsed();
 
         Ok(frame)
     }
@@ -54,7 +630,310 @@ impl Default for RenderPipeline {
 }
 
 impl Clone for RenderPipeline {
+    /// Shares the quantizer LUT; scaler buffers, frame history, zoom, color filter and stats
+    /// start fresh
     fn clone(&self) -> Self {
-        Self::new()
+        Self {
+            config: self.config.clone(),
+            converter: self.converter.clone(),
+            scaler: Mutex::new(Scaler::with_filter(self.config.filter)),
+            draft_scaler: Mutex::new(Scaler::with_filter(ScaleFilter::Nearest)),
+            backend: Arc::clone(&self.backend),
+            custom_backend: self.custom_backend,
+            draft_backend: Arc::clone(&self.draft_backend),
+            history: Mutex::new(None),
+            zoom: Mutex::new(Zoom::new()),
+            color_filter: Mutex::new(self.config.color_filter),
+            stats: Mutex::new(PipelineStats::default()),
+            last_render: Mutex::new(None),
+        }
+    }
+}
+
+/// The backend for a character set, sharing `converter`'s quantizer
+fn builtin_backend(
+    converter: &HalfBlockConverter,
+    charset: CharacterSet,
+) -> Arc<dyn RenderBackend> {
+    match charset {
+        CharacterSet::Shade => Arc::new(ShadeBackend::new(converter.clone())),
+        _ => Arc::new(converter.clone()),
+    }
+}
+
+/// A converter sharing `converter`'s quantizer with the per-cell searches turned off
+fn draft_converter(converter: &HalfBlockConverter) -> HalfBlockConverter {
+    converter
+        .clone()
+        .with_text_quantization(false)
+        .with_pair_search(false)
+}
+
+/// Extend a frame with blank rows up to `height`, keeping its damage and scroll
+fn pad_rows(frame: TerminalFrame, height: usize) -> TerminalFrame {
+    let mut padded = TerminalFrame::new(frame.width, height);
+    padded.cells[..frame.cells.len()].clone_from_slice(&frame.cells);
+    padded.damage = frame.damage;
+    padded.scroll = frame.scroll;
+    padded.metadata = frame.metadata;
+    padded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clone_shares_quantizer_lut() {
+        let pipeline = RenderPipeline::new();
+        let clone = pipeline.clone();
+
+        // A rebuilt LUT would be a different allocation
+        assert!(Arc::ptr_eq(
+            pipeline.converter.quantizer(),
+            clone.converter.quantizer()
+        ));
+    }
+
+    #[test]
+    fn test_scroll_detection() {
+        let pipeline = RenderPipeline::with_config(RenderConfig {
+            scroll_detection: true,
+            ..RenderConfig::default()
+        });
+        // A distinct color per pixel row, already at the frame's resolution
+        let page = |offset: u32| {
+            let data = (0..20u32)
+                .flat_map(|y| {
+                    let v = ((y + offset) * 37 % 256) as u8;
+                    [v, 255 - v, v / 2].repeat(8)
+                })
+                .collect();
+            RgbImage::new(data, 8, 20)
+        };
+
+        let first = pipeline.render_image(&page(0), 8, 10).unwrap();
+        assert_eq!(first.scroll, None);
+
+        // Two rows of cells further down the page
+        let scrolled = pipeline.render_image(&page(4), 8, 10).unwrap();
+        let motion = scrolled.scroll.unwrap();
+        assert_eq!((motion.top, motion.bottom, motion.rows), (0, 10, 2));
+        assert_eq!(scrolled.damage, Some(vec![CellRect::new(0, 8, 8, 2)]));
+    }
+
+    #[test]
+    fn test_frames_carry_screenshot_metadata() {
+        let metadata = toast_core::FrameMetadata {
+            url: Some("https://example.com/".to_string()),
+            scroll: (0.0, 640.0),
+            viewport: Some(toast_core::Dimensions::new(1280, 720)),
+            captured_at: Some(std::time::SystemTime::UNIX_EPOCH),
+            title: Some("Example".to_string()),
+            loading: false,
+            document_height: Some(4000),
+            clip: None,
+        };
+        let screenshot = Screenshot {
+            data: vec![200; 8 * 20 * 4].into(),
+            format: toast_core::ImageFormat::RawRgba {
+                width: 8,
+                height: 20,
+            },
+            metadata: metadata.clone(),
+        };
+
+        let frame = RenderPipeline::new().render(&screenshot, 8, 10).unwrap();
+        assert_eq!(frame.metadata, metadata);
+    }
+
+    #[test]
+    fn test_identical_screenshot_is_not_rendered_again() {
+        let pipeline = RenderPipeline::new();
+        let screenshot = |value: u8| Screenshot {
+            data: vec![value; 8 * 20 * 4].into(),
+            format: toast_core::ImageFormat::RawRgba {
+                width: 8,
+                height: 20,
+            },
+            metadata: Default::default(),
+        };
+
+        let first = pipeline.render(&screenshot(200), 8, 10).unwrap();
+        let again = pipeline.render(&screenshot(200), 8, 10).unwrap();
+        assert_eq!(again.cells, first.cells);
+        assert_eq!(again.damage, Some(Vec::new()));
+        assert_eq!(pipeline.stats().unchanged, 1);
+        assert_eq!(pipeline.stats().total(), std::time::Duration::ZERO);
+
+        // A changed page, size or filter renders again
+        pipeline.render(&screenshot(100), 8, 10).unwrap();
+        pipeline.render(&screenshot(100), 4, 10).unwrap();
+        pipeline.set_color_filter(ColorFilter::Protanopia);
+        pipeline.render(&screenshot(100), 4, 10).unwrap();
+        assert_eq!(pipeline.stats().unchanged, 1);
+        assert_eq!(pipeline.stats().frames, 5);
+    }
+
+    #[test]
+    fn test_zoom_clip() {
+        let pipeline = RenderPipeline::new();
+        assert_eq!(pipeline.zoom_clip(1600, 800, 200, 50), None);
+
+        let mut zoom = Zoom::new();
+        zoom.zoom_in();
+        zoom.zoom_in();
+        pipeline.set_zoom(zoom);
+        let (x, y, width, height) = zoom.source_rect(1600, 800);
+        assert_eq!(
+            pipeline.zoom_clip(1600, 800, 200, 50),
+            Some(Clip {
+                x: x as f64,
+                y: y as f64,
+                width: width as f64,
+                height: height as f64,
+                scale: zoom.scale() as f64,
+            })
+        );
+    }
+
+    #[test]
+    fn test_clipped_screenshot_is_not_cropped_again() {
+        // White down the left edge; zooming in on the center crops it away
+        let pixels: Vec<u8> = (0..8 * 20)
+            .flat_map(|i| if i % 8 < 2 { [255; 4] } else { [0; 4] })
+            .collect();
+        let screenshot = |clip: Option<Clip>| Screenshot {
+            data: pixels.clone().into(),
+            format: toast_core::ImageFormat::RawRgba {
+                width: 8,
+                height: 20,
+            },
+            metadata: toast_core::FrameMetadata {
+                clip,
+                ..Default::default()
+            },
+        };
+        let whole = RenderPipeline::new()
+            .render(&screenshot(None), 8, 10)
+            .unwrap();
+
+        let zoomed = RenderPipeline::new();
+        let mut zoom = Zoom::new();
+        zoom.zoom_in();
+        zoomed.set_zoom(zoom);
+        let cropped = zoomed.render(&screenshot(None), 8, 10).unwrap();
+        assert_ne!(cropped.cells, whole.cells);
+
+        let clip = zoomed.zoom_clip(8, 20, 8, 10);
+        let clipped = zoomed.render(&screenshot(clip), 8, 10).unwrap();
+        assert_eq!(clipped.cells, whole.cells);
+    }
+
+    #[test]
+    fn test_stats_cover_each_frame() {
+        let pipeline = RenderPipeline::new();
+        let image = RgbImage::new(vec![128; 40 * 20 * 3], 40, 20);
+
+        pipeline.render_image(&image, 10, 5).unwrap();
+        pipeline.render_image(&image, 10, 5).unwrap();
+
+        let stats = pipeline.stats();
+        assert_eq!(stats.frames, 2);
+        // Decoding was done by the caller
+        assert_eq!(stats.decode, std::time::Duration::ZERO);
+        assert!(stats.total() > std::time::Duration::ZERO);
+    }
+
+    #[test]
+    fn test_cell_aspect_keeps_page_proportions() {
+        let pipeline = RenderPipeline::with_config(RenderConfig {
+            cell_aspect: Some(NOMINAL_CELL_ASPECT),
+            ..RenderConfig::default()
+        });
+        // 40x20 pixels on 40x20 square-pixel cells (1:1 on screen) fill the top 10 rows
+        let image = RgbImage::new(vec![255; 40 * 20 * 3], 40, 20);
+
+        let frame = pipeline.render_image(&image, 40, 20).unwrap();
+        assert_eq!((frame.width, frame.height), (40, 20));
+        let blank = TerminalFrame::new(1, 1).cells[0].clone();
+        assert_ne!(frame.get(0, 9), Some(&blank));
+        assert_eq!(frame.get(0, 10), Some(&blank));
+        assert_eq!(frame.get(39, 19), Some(&blank));
+    }
+
+    #[test]
+    fn test_progressive_draft_is_refined_when_page_holds_still() {
+        let pipeline = RenderPipeline::with_config(RenderConfig {
+            progressive: true,
+            damage_tracking: true,
+            ..RenderConfig::default()
+        });
+        let screenshot = Screenshot {
+            data: vec![200; 8 * 20 * 4].into(),
+            format: toast_core::ImageFormat::RawRgba {
+                width: 8,
+                height: 20,
+            },
+            metadata: Default::default(),
+        };
+
+        pipeline.render(&screenshot, 8, 10).unwrap();
+        assert!(pipeline.showing_draft());
+
+        // Same page again: the full-quality pass replaces the whole draft
+        let refined = pipeline.render(&screenshot, 8, 10).unwrap();
+        assert_eq!(refined.damage, None);
+        assert!(!pipeline.showing_draft());
+        assert_eq!(pipeline.stats().unchanged, 0);
+
+        // And only then is it reused
+        pipeline.render(&screenshot, 8, 10).unwrap();
+        assert_eq!(pipeline.stats().unchanged, 1);
+        assert!(!pipeline.showing_draft());
+
+        // A changed page is drafted again, until it too is sent twice
+        let changed = Screenshot {
+            data: vec![40; 8 * 20 * 4].into(),
+            ..screenshot
+        };
+        pipeline.render(&changed, 8, 10).unwrap();
+        assert!(pipeline.showing_draft());
+        pipeline.render(&changed, 8, 10).unwrap();
+        assert!(!pipeline.showing_draft());
+    }
+
+    #[test]
+    fn test_custom_backend_gets_its_cell_resolution() {
+        /// Braille-like backend that records the size of the image it was given
+        struct Dots(Arc<Mutex<(u32, u32)>>);
+
+        impl RenderBackend for Dots {
+            fn name(&self) -> &str {
+                "dots"
+            }
+
+            fn cell_resolution(&self) -> (u32, u32) {
+                (2, 4)
+            }
+
+            fn convert(&self, image: &RgbImage, width: usize, height: usize) -> TerminalFrame {
+                *self.0.lock() = (image.width, image.height);
+                let mut frame = TerminalFrame::new(width, height);
+                for cell in frame.cells.iter_mut() {
+                    cell.character = '⣿';
+                }
+                frame
+            }
+        }
+
+        let seen = Arc::new(Mutex::new((0, 0)));
+        let pipeline = RenderPipeline::new().with_backend(Dots(Arc::clone(&seen)));
+        assert_eq!(pipeline.backend().name(), "dots");
+
+        let image = RgbImage::new(vec![128; 40 * 40 * 3], 40, 40);
+        let frame = pipeline.render_image(&image, 20, 10).unwrap();
+        assert_eq!(frame.get(0, 0).unwrap().character, '⣿');
+        assert_eq!(*seen.lock(), (40, 40));
     }
 }
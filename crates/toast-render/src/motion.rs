@@ -0,0 +1,151 @@
+use toast_core::{CellRect, ScrollMotion, TerminalCell, TerminalFrame};
+
+use crate::damage::damage_rects;
+
+/// Share of the scrolled band's cells that must line up after the shift
+const MIN_MATCH_FRACTION: f32 = 0.5;
+
+/// A frame expressed as the previous one scrolled, plus the cells that still differ
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScrollDiff {
+    pub motion: ScrollMotion,
+    /// Cells of the new frame that differ from the scrolled previous frame, including
+    /// the rows the scroll exposes
+    pub residual: Vec<CellRect>,
+}
+
+/// Detect the page scrolling between two frames of the same size
+///
+/// Unchanged rows at the top and bottom (sticky headers, footers) are left out of the
+/// band. Within it, the shift that lines up the most cells wins if it beats not
+/// shifting at all and covers at least half the band. Scrolls by a distance that isn't
+/// a whole number of rows rarely line up exactly and are usually not detected.
+pub fn detect_scroll(previous: &TerminalFrame, current: &TerminalFrame) -> Option<ScrollDiff> {
+    let (cols, rows) = (current.width, current.height);
+    if previous.width != cols || previous.height != rows || cols == 0 {
+        return None;
+    }
+
+    let top = (0..rows)
+        .find(|&y| row(previous, y) != row(current, y))
+        .unwrap_or(rows);
+    let bottom = (top..rows)
+        .rev()
+        .find(|&y| row(previous, y) != row(current, y))
+        .map_or(top, |y| y + 1);
+    let height = bottom - top;
+    if height < 2 {
+        return None;
+    }
+
+    // Cells of the band that match when the content moved up by `shift` rows
+    let matches = |shift: isize| -> usize {
+        (top..bottom)
+            .filter_map(|y| {
+                let from = y as isize + shift;
+                (from >= top as isize && from < bottom as isize).then_some((y, from as usize))
+            })
+            .map(|(y, from)| {
+                row(previous, from)
+                    .iter()
+                    .zip(row(current, y))
+                    .filter(|(a, b)| a == b)
+                    .count()
+            })
+            .sum()
+    };
+
+    // Smaller shifts first, so ties (flat areas) go to the shortest scroll
+    let mut best = (0, matches(0));
+    for distance in 1..height as isize {
+        for shift in [distance, -distance] {
+            let score = matches(shift);
+            if score > best.1 {
+                best = (shift, score);
+            }
+        }
+    }
+    let (shift, score) = best;
+    if shift == 0 || (score as f32) < (height * cols) as f32 * MIN_MATCH_FRACTION {
+        return None;
+    }
+
+    // Whatever the scrolled previous frame gets wrong, exposed rows included
+    let mut dirty = vec![false; cols * rows];
+    for y in top..bottom {
+        let from = y as isize + shift;
+        let source =
+            (from >= top as isize && from < bottom as isize).then(|| row(previous, from as usize));
+        for (x, cell) in row(current, y).iter().enumerate() {
+            dirty[y * cols + x] = source.is_none_or(|source| source[x] != *cell);
+        }
+    }
+
+    Some(ScrollDiff {
+        motion: ScrollMotion {
+            top,
+            bottom,
+            rows: shift,
+        },
+        residual: damage_rects(&dirty, cols, rows, 0),
+    })
+}
+
+fn row(frame: &TerminalFrame, y: usize) -> &[TerminalCell] {
+    &frame.cells[y * frame.width..(y + 1) * frame.width]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use toast_core::AnsiColor;
+
+    /// A frame whose rows are told apart by their text
+    fn page(lines: &[&str]) -> TerminalFrame {
+        let width = lines.iter().map(|l| l.len()).max().unwrap_or(0);
+        let mut frame = TerminalFrame::new(width, lines.len());
+        for (y, line) in lines.iter().enumerate() {
+            frame.draw_text(0, y, line, AnsiColor(15), AnsiColor(0));
+        }
+        frame.damage = None;
+        frame
+    }
+
+    #[test]
+    fn test_detects_scroll_under_sticky_header() {
+        let previous = page(&["HEAD", "aaaa", "bbbb", "cccc", "dddd", "eeee"]);
+        let current = page(&["HEAD", "cccc", "dddd", "eeee", "ffff", "gggg"]);
+
+        let diff = detect_scroll(&previous, &current).unwrap();
+        assert_eq!(
+            diff.motion,
+            ScrollMotion {
+                top: 1,
+                bottom: 6,
+                rows: 2
+            }
+        );
+        // Only the two exposed rows need drawing
+        assert_eq!(diff.residual, vec![CellRect::new(0, 4, 4, 2)]);
+    }
+
+    #[test]
+    fn test_detects_scroll_up() {
+        let previous = page(&["cccc", "dddd", "eeee", "ffff"]);
+        let current = page(&["bbbb", "cccc", "dddd", "eeee"]);
+
+        let diff = detect_scroll(&previous, &current).unwrap();
+        assert_eq!(diff.motion.rows, -1);
+        assert_eq!(diff.residual, vec![CellRect::new(0, 0, 4, 1)]);
+    }
+
+    #[test]
+    fn test_unrelated_frames_are_not_a_scroll() {
+        let previous = page(&["aaaa", "bbbb", "cccc", "dddd"]);
+        let current = page(&["wxyz", "zyxw", "xwzy", "yzwx"]);
+        assert!(detect_scroll(&previous, &current).is_none());
+
+        // Nor is an unchanged frame
+        assert!(detect_scroll(&previous, &previous).is_none());
+    }
+}
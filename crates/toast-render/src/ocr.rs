@@ -0,0 +1,210 @@
+use anyhow::Result;
+use toast_core::text::graphemes;
+use toast_core::{AnsiColor, RgbImage, TerminalFrame};
+
+use crate::quantizer::ansi_to_rgb;
+
+/// Minimum tesseract confidence (0-100) for a word to be overlaid
+const MIN_CONFIDENCE: f32 = 60.0;
+
+/// A word recognized by OCR, in source image pixel coordinates
+#[derive(Debug, Clone, PartialEq)]
+pub struct OcrWord {
+    pub text: String,
+    pub left: u32,
+    pub top: u32,
+    pub width: u32,
+    pub height: u32,
+    pub confidence: f32,
+}
+
+/// OCR output for one image, kept with the image size so it can be mapped onto any frame size
+#[derive(Debug, Clone, Default)]
+pub struct OcrResult {
+    pub words: Vec<OcrWord>,
+    pub image_width: u32,
+    pub image_height: u32,
+}
+
+impl OcrResult {
+    /// Draw the recognized words as real characters at the matching cells
+    ///
+    /// Each word starts at the cell under its left edge, on the row through its vertical
+    /// center. Characters are drawn in black or white, whichever contrasts more with the
+    /// cell's existing background.
+    pub fn overlay(&self, frame: &mut TerminalFrame) {
+        if self.image_width == 0 || self.image_height == 0 {
+            return;
+        }
+
+        for word in &self.words {
+            let x = (word.left as u64 * frame.width as u64 / self.image_width as u64) as usize;
+            let center_y = word.top + word.height / 2;
+            let y = (center_y as u64 * frame.height as u64 / self.image_height as u64) as usize;
+
+            let mut column = x;
+            for (grapheme, width) in graphemes(&word.text) {
+                let Some(cell) = frame.get(column, y) else {
+                    break;
+                };
+                let background = cell.background;
+                let foreground = contrasting_text_color(background);
+                frame.draw_text(column, y, grapheme, foreground, background);
+                column += width;
+            }
+        }
+    }
+}
+
+/// Black on light backgrounds, white on dark ones
+fn contrasting_text_color(background: AnsiColor) -> AnsiColor {
+    let rgb = ansi_to_rgb(background);
+    let luma = rgb.r as u32 * 299 + rgb.g as u32 * 587 + rgb.b as u32 * 114;
+    if luma > 128_000 {
+        AnsiColor(16)
+    } else {
+        AnsiColor(231)
+    }
+}
+
+/// Parse tesseract TSV output into words
+///
+/// Columns: level, page, block, paragraph, line, word, left, top, width, height, conf, text.
+/// Only word-level rows (level 5) above the confidence threshold are kept.
+pub fn parse_tsv(tsv: &str) -> Vec<OcrWord> {
+    tsv.lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split('\t').collect();
+            if fields.len() < 12 || fields[0] != "5" {
+                return None;
+            }
+            let text = fields[11].trim();
+            let confidence: f32 = fields[10].parse().ok()?;
+            if text.is_empty() || confidence < MIN_CONFIDENCE {
+                return None;
+            }
+            Some(OcrWord {
+                text: text.to_string(),
+                left: fields[6].parse().ok()?,
+                top: fields[7].parse().ok()?,
+                width: fields[8].parse().ok()?,
+                height: fields[9].parse().ok()?,
+                confidence,
+            })
+        })
+        .collect()
+}
+
+/// Tesseract-backed text recognizer
+///
+/// Not `Send`: create it on the thread that will run recognition.
+#[cfg(feature = "ocr")]
+pub struct OcrEngine {
+    /// Tesseract's builder-style API consumes the handle on every call
+    tess: Option<tesseract::Tesseract>,
+}
+
+#[cfg(feature = "ocr")]
+impl OcrEngine {
+    /// Initialize tesseract with the English model from the default data path
+    pub fn new() -> Result<Self> {
+        let tess = tesseract::Tesseract::new(None, Some("eng"))?;
+        Ok(Self { tess: Some(tess) })
+    }
+
+    /// Recognize words in an image
+    ///
+    /// After an error, tesseract is initialized again on the next call.
+    pub fn recognize(&mut self, image: &RgbImage) -> Result<OcrResult> {
+        use anyhow::Context;
+
+        // A failed call consumes the handle: start afresh with a new one
+        let tess = match self.tess.take() {
+            Some(tess) => tess,
+            None => tesseract::Tesseract::new(None, Some("eng"))
+                .context("Failed to restart OCR after an error")?,
+        };
+        let mut tess = tess
+            .set_frame(
+                &image.data,
+                image.width as i32,
+                image.height as i32,
+                3,
+                image.width as i32 * 3,
+            )?
+            .recognize()?;
+        let tsv = tess.get_tsv_text(0)?;
+        self.tess = Some(tess);
+
+        Ok(OcrResult {
+            words: parse_tsv(&tsv),
+            image_width: image.width,
+            image_height: image.height,
+        })
+    }
+}
+
+/// Stand-in used when toast is built without the `ocr` feature
+#[cfg(not(feature = "ocr"))]
+pub struct OcrEngine;
+
+#[cfg(not(feature = "ocr"))]
+impl OcrEngine {
+    pub fn new() -> Result<Self> {
+        anyhow::bail!("toast was built without OCR support (enable the `ocr` feature)")
+    }
+
+    pub fn recognize(&mut self, _image: &RgbImage) -> Result<OcrResult> {
+        anyhow::bail!("toast was built without OCR support (enable the `ocr` feature)")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use toast_core::TerminalCell;
+
+    const TSV: &str = "level\tpage_num\tblock_num\tpar_num\tline_num\tword_num\tleft\ttop\twidth\theight\tconf\ttext
+1\t1\t0\t0\t0\t0\t0\t0\t200\t100\t-1\t
+4\t1\t1\t1\t1\t0\t10\t20\t120\t16\t-1\t
+5\t1\t1\t1\t1\t1\t10\t20\t50\t16\t96.5\tHello
+5\t1\t1\t1\t1\t2\t70\t20\t60\t16\t12.0\tw0rld
+";
+
+    #[test]
+    fn test_parse_tsv_keeps_confident_words() {
+        let words = parse_tsv(TSV);
+        assert_eq!(words.len(), 1);
+        assert_eq!(words[0].text, "Hello");
+        assert_eq!((words[0].left, words[0].top), (10, 20));
+        assert_eq!((words[0].width, words[0].height), (50, 16));
+    }
+
+    #[test]
+    fn test_overlay_places_word_at_cells() {
+        let result = OcrResult {
+            words: parse_tsv(TSV),
+            image_width: 200,
+            image_height: 100,
+        };
+        // 20x10 cells: 10px per cell horizontally and vertically
+        let mut frame = TerminalFrame::new(20, 10);
+        for cell in frame.cells.iter_mut() {
+            *cell = TerminalCell {
+                character: '▀',
+                foreground: AnsiColor(15),
+                background: AnsiColor(15),
+                cluster: None,
+            };
+        }
+
+        result.overlay(&mut frame);
+
+        // left=10 -> column 1; vertical center 28 -> row 2
+        let text: String = (1..6).map(|x| frame.get(x, 2).unwrap().character).collect();
+        assert_eq!(text, "Hello");
+        // Dark text on the white background
+        assert_eq!(frame.get(1, 2).unwrap().foreground, AnsiColor(16));
+        assert_eq!(frame.get(0, 2).unwrap().character, '▀');
+    }
+}
@@ -0,0 +1,138 @@
+use toast_core::text::graphemes;
+use toast_core::{AnsiColor, Rgb, TerminalCell, TerminalFrame};
+
+use crate::halfblock::{cell_colors, is_block_cell};
+use crate::interpolate::lerp;
+use crate::quantizer::{ansi_to_rgb, ColorQuantizer};
+
+/// Draws overlay text (notices, badges, status bars) over a rendered page
+///
+/// With an opacity below 1 the overlay background is blended with the page colors under
+/// each cell and quantized again, so the page stays visible through it. Text is always
+/// drawn opaque to stay legible.
+#[derive(Clone, Copy)]
+pub struct OverlayStyle<'a> {
+    quantizer: &'a ColorQuantizer,
+    opacity: f32,
+}
+
+impl<'a> OverlayStyle<'a> {
+    /// `opacity` runs from 0 (background invisible) to 1 (opaque)
+    pub fn new(quantizer: &'a ColorQuantizer, opacity: f32) -> Self {
+        Self {
+            quantizer,
+            opacity: opacity.clamp(0.0, 1.0),
+        }
+    }
+
+    /// Draw `text` at (`x`, `y`) in `fg` on a background of `bg`
+    pub fn draw_text(
+        &self,
+        frame: &mut TerminalFrame,
+        x: usize,
+        y: usize,
+        text: &str,
+        fg: AnsiColor,
+        bg: AnsiColor,
+    ) {
+        if self.opacity >= 1.0 {
+            frame.draw_text(x, y, text, fg, bg);
+            return;
+        }
+
+        let overlay = ansi_to_rgb(bg);
+        let mut column = x;
+        for (grapheme, width) in graphemes(text) {
+            let Some(cell) = frame.get(column, y) else {
+                break;
+            };
+            let page = self.page_color(cell);
+            let background = self.quantizer.quantize(lerp(page, overlay, self.opacity));
+            frame.draw_text(column, y, grapheme, fg, background);
+            column += width;
+        }
+    }
+
+    /// The page color under a cell: the average of a half-block cell's two pixels
+    fn page_color(&self, cell: &TerminalCell) -> Rgb {
+        if is_block_cell(cell) {
+            let (top, bottom) = cell_colors(cell);
+            lerp(ansi_to_rgb(top), ansi_to_rgb(bottom), 0.5)
+        } else {
+            ansi_to_rgb(cell.background)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::halfblock::make_cell;
+
+    fn page(color: u8) -> TerminalFrame {
+        let mut frame = TerminalFrame::new(4, 1);
+        for x in 0..4 {
+            frame.set(x, 0, make_cell(AnsiColor(color), AnsiColor(color)));
+        }
+        frame
+    }
+
+    #[test]
+    fn test_opaque_overlay_replaces_background() {
+        let quantizer = ColorQuantizer::new();
+        let mut frame = page(21);
+        OverlayStyle::new(&quantizer, 1.0).draw_text(
+            &mut frame,
+            0,
+            0,
+            "ab",
+            AnsiColor(16),
+            AnsiColor(220),
+        );
+        assert_eq!(frame.get(0, 0).unwrap().background, AnsiColor(220));
+        assert_eq!(frame.get(1, 0).unwrap().character, 'b');
+        assert_eq!(frame.get(2, 0).unwrap().background, AnsiColor(21));
+    }
+
+    #[test]
+    fn test_translucent_overlay_shows_page() {
+        let quantizer = ColorQuantizer::new();
+        let (blue, yellow) = (AnsiColor(21), AnsiColor(226));
+
+        let mut invisible = page(blue.as_u8());
+        OverlayStyle::new(&quantizer, 0.0).draw_text(
+            &mut invisible,
+            0,
+            0,
+            "a",
+            AnsiColor(16),
+            yellow,
+        );
+        assert_eq!(
+            invisible.get(0, 0).unwrap().background,
+            quantizer.quantize(ansi_to_rgb(blue))
+        );
+        assert_eq!(invisible.get(0, 0).unwrap().character, 'a');
+
+        let mut half = page(blue.as_u8());
+        OverlayStyle::new(&quantizer, 0.5).draw_text(&mut half, 0, 0, "a", AnsiColor(16), yellow);
+        let background = half.get(0, 0).unwrap().background;
+        assert_ne!(background, blue);
+        assert_ne!(background, yellow);
+    }
+
+    #[test]
+    fn test_text_past_the_edge_is_clipped() {
+        let quantizer = ColorQuantizer::new();
+        let mut frame = page(21);
+        OverlayStyle::new(&quantizer, 0.5).draw_text(
+            &mut frame,
+            3,
+            0,
+            "abc",
+            AnsiColor(16),
+            AnsiColor(220),
+        );
+        assert_eq!(frame.get(3, 0).unwrap().character, 'a');
+    }
+}
@@ -1,45 +1,123 @@
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock};
 use toast_core::{AnsiColor, Rgb};
 
 /// ANSI 256 color palette
 const ANSI_PALETTE: [Rgb; 256] = generate_ansi_palette();
 
+/// Perceptual distance metric used when building the lookup table
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ColorMetric {
+    /// Euclidean distance in CIELAB (CIE76) - cheap, slightly off for blues and skin tones
+    #[default]
+    Cie76,
+    /// Full CIEDE2000 - noticeably better hue matching, ~10x slower LUT construction
+    Ciede2000,
+}
+
+impl std::str::FromStr for ColorMetric {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "cie76" => Ok(ColorMetric::Cie76),
+            "ciede2000" => Ok(ColorMetric::Ciede2000),
+            _ => anyhow::bail!("unknown color metric '{}' (expected cie76 or ciede2000)", s),
+        }
+    }
+}
+
+/// Nearest palette entries per pixel considered by [`ColorQuantizer::quantize_pair`]
+const PAIR_CANDIDATES: usize = 4;
+
+/// Weight of the cell's blended color against its two pixels in the pair search
+const BLEND_WEIGHT: f32 = 1.0;
+
+/// The 32KB lookup table: 32×32×32 RGB555 → ANSI 256 index
+type Lut = Box<[u8; 32768]>;
+
 /// Color quantizer using a 32KB lookup table for O(1) color matching
+///
+/// The table is built on a background thread; until it is ready, lookups fall back to a
+/// direct nearest-color search over the palette, which gives identical results.
 pub struct ColorQuantizer {
-    /// Lookup table: 32×32×32 RGB555 → ANSI 256 index
-    /// Indexed by (r>>3, g>>3, b>>3) to reduce RGB888 to RGB555
-    lut: Box<[u8; 32768]>,
+    /// Lookup table, indexed by (r>>3, g>>3, b>>3) to reduce RGB888 to RGB555
+    lut: Arc<OnceLock<Lut>>,
+    metric: ColorMetric,
+    /// Palette in LAB, for distance checks outside the LUT (temporal stability)
+    palette_lab: [Lab; 256],
+    /// Number of palette entries in use (16 for base16, otherwise 256)
+    colors: usize,
 }
 
 impl ColorQuantizer {
-    /// Build the lookup table at startup
-    /// This is a one-time cost (~50ms) that enables O(1) runtime lookups
+    /// Start building the lookup table using the default (CIE76) metric
+    /// The table takes ~50ms to build in the background and then enables O(1) lookups
     pub fn new() -> Self {
-        let mut lut = Box::new([0u8; 32768]);
-
-        // For each possible RGB555 color
-        for r5 in 0..32u8 {
-            for g5 in 0..32u8 {
-                for b5 in 0..32u8 {
-                    // Convert RGB555 back to RGB888 (expand to full range)
-                    let r8 = (r5 << 3) | (r5 >> 2);
-                    let g8 = (g5 << 3) | (g5 >> 2);
-                    let b8 = (b5 << 3) | (b5 >> 2);
-                    let rgb = Rgb::new(r8, g8, b8);
-
-                    // Find nearest ANSI color using CIELEAB distance
-                    let ansi_idx = find_nearest_ansi_color(rgb);
-
-                    // Store in LUT
-                    let lut_idx = lut_index(r5, g5, b5);
-                    lut[lut_idx] = ansi_idx;
-                }
-            }
+        Self::with_metric(ColorMetric::default())
+    }
+
+    /// Start building the lookup table using the given distance metric
+    pub fn with_metric(metric: ColorMetric) -> Self {
+        Self::with_palette_size(metric, 256)
+    }
+
+    /// Map onto the 16 base colors only, which every color terminal lets the user theme
+    pub fn base16(metric: ColorMetric) -> Self {
+        Self::with_palette_size(metric, 16)
+    }
+
+    /// Like [`with_metric`](Self::with_metric), loading the table from `cache_dir` if an
+    /// earlier run saved one, and saving it there otherwise
+    pub fn with_metric_cached(metric: ColorMetric, cache_dir: impl Into<PathBuf>) -> Self {
+        Self::build(metric, 256, Some(cache_dir.into()))
+    }
+
+    /// Like [`base16`](Self::base16), with the table cached in `cache_dir`
+    pub fn base16_cached(metric: ColorMetric, cache_dir: impl Into<PathBuf>) -> Self {
+        Self::build(metric, 16, Some(cache_dir.into()))
+    }
+
+    /// Build the lookup table over the first `colors` palette entries in the background
+    fn with_palette_size(metric: ColorMetric, colors: usize) -> Self {
+        Self::build(metric, colors, None)
+    }
+
+    fn build(metric: ColorMetric, colors: usize, cache_dir: Option<PathBuf>) -> Self {
+        // Convert the palette to LAB once rather than per comparison
+        let palette_lab = palette_lab();
+        let lut = Arc::new(OnceLock::new());
+
+        let load = move || match &cache_dir {
+            Some(dir) => load_or_build_lut(dir, &palette_lab[..colors], metric),
+            None => build_lut(&palette_lab[..colors], metric),
+        };
+        let target = Arc::clone(&lut);
+        let load_in_background = load.clone();
+        let spawned = std::thread::Builder::new()
+            .name("quantizer-lut".to_string())
+            .spawn(move || {
+                let _ = target.set(load_in_background());
+            });
+        if spawned.is_err() {
+            // No thread to spare: build it here instead
+            let _ = lut.set(load());
+        }
+
+        Self {
+            lut,
+            metric,
+            palette_lab,
+            colors,
         }
+    }
 
-        Self { lut }
+    /// Whether the lookup table has been built (lookups are slower until then)
+    pub fn is_ready(&self) -> bool {
+        self.lut.get().is_some()
     }
 
-    /// Quantize an RGB color to ANSI 256 in O(1) time
+    /// Quantize an RGB color to ANSI 256 in O(1) time once the lookup table is ready
     #[inline]
     pub fn quantize(&self, rgb: Rgb) -> AnsiColor {
         // Reduce RGB888 to RGB555 by shifting right 3 bits
@@ -47,9 +125,100 @@ impl ColorQuantizer {
         let g5 = rgb.g >> 3;
         let b5 = rgb.b >> 3;
 
-        // Lookup in table
-        let idx = lut_index(r5, g5, b5);
-        AnsiColor(self.lut[idx])
+        match self.lut.get() {
+            // Lookup in table
+            Some(lut) => AnsiColor(lut[lut_index(r5, g5, b5)]),
+            // Search the palette for the color the table will hold
+            None => AnsiColor(find_nearest_ansi_color(
+                expand_rgb555(r5, g5, b5),
+                &self.palette_lab[..self.colors],
+                self.metric,
+            )),
+        }
+    }
+
+    /// Quantize with hysteresis: keep `previous` unless the nearest color is clearly better
+    ///
+    /// The previous index is kept while its delta-E to `rgb` is within `threshold` of the
+    /// nearest palette entry's, so colors near a boundary stop flipping between frames.
+    pub fn quantize_stable(&self, rgb: Rgb, previous: AnsiColor, threshold: f32) -> AnsiColor {
+        let nearest = self.quantize(rgb);
+        if nearest == previous || threshold <= 0.0 {
+            return nearest;
+        }
+
+        let lab = rgb_to_lab(rgb);
+        let distance = |color: AnsiColor| {
+            let entry = self.palette_lab[color.as_u8() as usize];
+            match self.metric {
+                ColorMetric::Cie76 => color_distance_lab(lab, entry),
+                ColorMetric::Ciede2000 => ciede2000(lab, entry),
+            }
+        };
+
+        if distance(previous) <= distance(nearest) + threshold {
+            previous
+        } else {
+            nearest
+        }
+    }
+
+    /// Choose colors for a cell's two pixels together rather than independently
+    ///
+    /// Searches pairs among each pixel's nearest palette entries for the lowest combined
+    /// error of both pixels plus the cell's blended color, which is what the eye sees at
+    /// terminal sizes. This keeps edges between two palette colors from drifting lighter or
+    /// darker, most visibly with the small base16 palette.
+    pub fn quantize_pair(&self, top: Rgb, bottom: Rgb) -> (AnsiColor, AnsiColor) {
+        let (top_lab, bottom_lab) = (rgb_to_lab(top), rgb_to_lab(bottom));
+        let blend_lab = top_lab.midpoint(bottom_lab);
+
+        let mut candidates = self.nearest(top_lab, PAIR_CANDIDATES);
+        for idx in self.nearest(bottom_lab, PAIR_CANDIDATES) {
+            if !candidates.contains(&idx) {
+                candidates.push(idx);
+            }
+        }
+
+        let mut best = (self.quantize(top), self.quantize(bottom));
+        let mut best_error = f32::INFINITY;
+        for &fg in &candidates {
+            let fg_lab = self.palette_lab[fg as usize];
+            let top_error = self.distance(top_lab, fg_lab).powi(2);
+            for &bg in &candidates {
+                let bg_lab = self.palette_lab[bg as usize];
+                let error = top_error
+                    + self.distance(bottom_lab, bg_lab).powi(2)
+                    + BLEND_WEIGHT * self.distance(blend_lab, fg_lab.midpoint(bg_lab)).powi(2);
+                if error < best_error {
+                    best_error = error;
+                    best = (AnsiColor(fg), AnsiColor(bg));
+                }
+            }
+        }
+        best
+    }
+
+    /// Indices of the `k` palette entries nearest to a color, in no particular order
+    fn nearest(&self, lab: Lab, k: usize) -> Vec<u8> {
+        let mut scored: Vec<(f32, u8)> = self.palette_lab[..self.colors]
+            .iter()
+            .enumerate()
+            .map(|(idx, &entry)| (self.distance(lab, entry), idx as u8))
+            .collect();
+        // Only which entries are nearest matters, not their order
+        if k < scored.len() {
+            scored.select_nth_unstable_by(k, |a, b| a.0.total_cmp(&b.0));
+            scored.truncate(k);
+        }
+        scored.into_iter().map(|(_, idx)| idx).collect()
+    }
+
+    fn distance(&self, a: Lab, b: Lab) -> f32 {
+        match self.metric {
+            ColorMetric::Cie76 => color_distance_lab(a, b),
+            ColorMetric::Ciede2000 => ciede2000(a, b),
+        }
     }
 
     /// Quantize a slice of RGB colors in parallel
@@ -64,21 +233,116 @@ impl Default for ColorQuantizer {
     }
 }
 
+/// Look up the RGB value of an ANSI 256 palette entry
+pub fn ansi_to_rgb(color: AnsiColor) -> Rgb {
+    ANSI_PALETTE[color.as_u8() as usize]
+}
+
 /// Calculate LUT index from RGB555 values
 #[inline]
 const fn lut_index(r5: u8, g5: u8, b5: u8) -> usize {
     ((r5 as usize) << 10) | ((g5 as usize) << 5) | (b5 as usize)
 }
 
-/// Find the nearest ANSI 256 color using CIELEAB color distance
-fn find_nearest_ansi_color(rgb: Rgb) -> u8 {
+/// Build the lookup table over the given palette
+fn build_lut(palette_lab: &[Lab], metric: ColorMetric) -> Lut {
+    let mut lut = Box::new([0u8; 32768]);
+
+    // For each possible RGB555 color
+    for r5 in 0..32u8 {
+        for g5 in 0..32u8 {
+            for b5 in 0..32u8 {
+                // Find nearest ANSI color using the selected LAB distance
+                let rgb = expand_rgb555(r5, g5, b5);
+                lut[lut_index(r5, g5, b5)] = find_nearest_ansi_color(rgb, palette_lab, metric);
+            }
+        }
+    }
+
+    lut
+}
+
+/// Load the lookup table for this palette and metric from `dir`, or build and save it
+///
+/// The cache is best effort: unreadable or corrupt files are rebuilt and write errors
+/// are ignored.
+fn load_or_build_lut(dir: &Path, palette_lab: &[Lab], metric: ColorMetric) -> Lut {
+    let path = dir.join(format!("{:016x}.lut", lut_key(palette_lab, metric)));
+
+    if let Ok(data) = std::fs::read(&path) {
+        if let Ok(lut) = Lut::try_from(data.into_boxed_slice()) {
+            return lut;
+        }
+    }
+
+    let lut = build_lut(palette_lab, metric);
+    // Write to a temporary file first so a concurrent run never reads a partial table
+    let temp = path.with_extension(format!("tmp{}", std::process::id()));
+    let saved = std::fs::create_dir_all(dir)
+        .and_then(|_| std::fs::write(&temp, lut.as_slice()))
+        .and_then(|_| std::fs::rename(&temp, &path));
+    if saved.is_err() {
+        let _ = std::fs::remove_file(&temp);
+    }
+    lut
+}
+
+/// Identifies a lookup table: FNV-1a over the format version, metric and palette
+///
+/// Hashes the palette itself, so changing it (or the LAB conversion) never reuses a stale table.
+fn lut_key(palette_lab: &[Lab], metric: ColorMetric) -> u64 {
+    const LUT_FORMAT: u8 = 1;
+
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    let mut feed = |bytes: &[u8]| {
+        for &byte in bytes {
+            hash ^= byte as u64;
+            hash = hash.wrapping_mul(0x0100_0000_01b3);
+        }
+    };
+    feed(&[LUT_FORMAT, metric as u8]);
+    for lab in palette_lab {
+        for component in [lab.l, lab.a, lab.b] {
+            feed(&component.to_le_bytes());
+        }
+    }
+    hash
+}
+
+/// Convert RGB555 back to RGB888 (expand to full range)
+#[inline]
+const fn expand_rgb555(r5: u8, g5: u8, b5: u8) -> Rgb {
+    Rgb::new(
+        (r5 << 3) | (r5 >> 2),
+        (g5 << 3) | (g5 >> 2),
+        (b5 << 3) | (b5 >> 2),
+    )
+}
+
+/// Convert every palette entry to LAB
+fn palette_lab() -> [Lab; 256] {
+    let mut lab = [Lab {
+        l: 0.0,
+        a: 0.0,
+        b: 0.0,
+    }; 256];
+    for (entry, &rgb) in lab.iter_mut().zip(ANSI_PALETTE.iter()) {
+        *entry = rgb_to_lab(rgb);
+    }
+    lab
+}
+
+/// Find the nearest ANSI 256 color using CIELAB color distance
+fn find_nearest_ansi_color(rgb: Rgb, palette_lab: &[Lab], metric: ColorMetric) -> u8 {
     let lab = rgb_to_lab(rgb);
     let mut min_distance = f32::INFINITY;
     let mut best_idx = 0u8;
 
-    for (idx, &ansi_rgb) in ANSI_PALETTE.iter().enumerate() {
-        let ansi_lab = rgb_to_lab(ansi_rgb);
-        let distance = color_distance_lab(lab, ansi_lab);
+    for (idx, &ansi_lab) in palette_lab.iter().enumerate() {
+        let distance = match metric {
+            ColorMetric::Cie76 => color_distance_lab(lab, ansi_lab),
+            ColorMetric::Ciede2000 => ciede2000(lab, ansi_lab),
+        };
 
         if distance < min_distance {
             min_distance = distance;
@@ -97,6 +361,17 @@ struct Lab {
     b: f32,
 }
 
+impl Lab {
+    /// Average of two colors, approximating how adjacent pixels blend
+    fn midpoint(self, other: Lab) -> Lab {
+        Lab {
+            l: (self.l + other.l) / 2.0,
+            a: (self.a + other.a) / 2.0,
+            b: (self.b + other.b) / 2.0,
+        }
+    }
+}
+
 /// Convert RGB to CIELEAB color space for perceptual distance calculations
 fn rgb_to_lab(rgb: Rgb) -> Lab {
     // First convert RGB to XYZ
@@ -107,7 +382,7 @@ fn rgb_to_lab(rgb: Rgb) -> Lab {
     // RGB to XYZ matrix (D65 illuminant)
     let x = r * 0.4124564 + g * 0.3575761 + b * 0.1804375;
     let y = r * 0.2126729 + g * 0.7151522 + b * 0.0721750;
-    let z = r * 0.0193339 + g * 0.1191920 + b * 0.9503041;
+    let z = r * 0.0193339 + g * 0.119_192 + b * 0.9503041;
 
     // Normalize by D65 white point
     let x = x / 0.95047;
@@ -145,16 +420,98 @@ fn lab_f(t: f32) -> f32 {
     }
 }
 
-/// Calculate CIEDE2000 color distance (simplified)
+/// Calculate CIE76 color distance (Euclidean distance in LAB space)
 fn color_distance_lab(lab1: Lab, lab2: Lab) -> f32 {
     let dl = lab1.l - lab2.l;
     let da = lab1.a - lab2.a;
     let db = lab1.b - lab2.b;
-    // Simplified Euclidean distance in LAB space
-    // (Full CIEDE2000 is more complex but this is good enough)
     (dl * dl + da * da + db * db).sqrt()
 }
 
+/// Calculate CIEDE2000 color distance
+/// Follows Sharma, Wu & Dalal (2005) with kL = kC = kH = 1
+fn ciede2000(lab1: Lab, lab2: Lab) -> f32 {
+    const POW25_7: f32 = 6_103_515_625.0; // 25^7
+
+    // Chroma-dependent a* rescaling
+    let c1 = lab1.a.hypot(lab1.b);
+    let c2 = lab2.a.hypot(lab2.b);
+    let c_bar7 = ((c1 + c2) / 2.0).powi(7);
+    let g = 0.5 * (1.0 - (c_bar7 / (c_bar7 + POW25_7)).sqrt());
+    let a1p = (1.0 + g) * lab1.a;
+    let a2p = (1.0 + g) * lab2.a;
+
+    let c1p = a1p.hypot(lab1.b);
+    let c2p = a2p.hypot(lab2.b);
+    let h1p = hue_degrees(lab1.b, a1p);
+    let h2p = hue_degrees(lab2.b, a2p);
+
+    // Differences in lightness, chroma and hue
+    let dlp = lab2.l - lab1.l;
+    let dcp = c2p - c1p;
+    let dhp = if c1p * c2p == 0.0 {
+        0.0
+    } else {
+        let diff = h2p - h1p;
+        if diff > 180.0 {
+            diff - 360.0
+        } else if diff < -180.0 {
+            diff + 360.0
+        } else {
+            diff
+        }
+    };
+    let d_big_hp = 2.0 * (c1p * c2p).sqrt() * (dhp.to_radians() / 2.0).sin();
+
+    // Means
+    let l_bar = (lab1.l + lab2.l) / 2.0;
+    let c_bar_p = (c1p + c2p) / 2.0;
+    let h_bar_p = if c1p * c2p == 0.0 {
+        h1p + h2p
+    } else if (h1p - h2p).abs() <= 180.0 {
+        (h1p + h2p) / 2.0
+    } else if h1p + h2p < 360.0 {
+        (h1p + h2p + 360.0) / 2.0
+    } else {
+        (h1p + h2p - 360.0) / 2.0
+    };
+
+    // Weighting functions
+    let t = 1.0 - 0.17 * (h_bar_p - 30.0).to_radians().cos()
+        + 0.24 * (2.0 * h_bar_p).to_radians().cos()
+        + 0.32 * (3.0 * h_bar_p + 6.0).to_radians().cos()
+        - 0.20 * (4.0 * h_bar_p - 63.0).to_radians().cos();
+    let l_offset = (l_bar - 50.0) * (l_bar - 50.0);
+    let sl = 1.0 + 0.015 * l_offset / (20.0 + l_offset).sqrt();
+    let sc = 1.0 + 0.045 * c_bar_p;
+    let sh = 1.0 + 0.015 * c_bar_p * t;
+
+    // Rotation term for the blue region
+    let d_theta = 30.0 * (-((h_bar_p - 275.0) / 25.0).powi(2)).exp();
+    let c_bar_p7 = c_bar_p.powi(7);
+    let rc = 2.0 * (c_bar_p7 / (c_bar_p7 + POW25_7)).sqrt();
+    let rt = -(2.0 * d_theta).to_radians().sin() * rc;
+
+    let dl = dlp / sl;
+    let dc = dcp / sc;
+    let dh = d_big_hp / sh;
+    (dl * dl + dc * dc + dh * dh + rt * dc * dh).sqrt()
+}
+
+/// Hue angle in degrees, normalized to [0, 360)
+#[inline]
+fn hue_degrees(b: f32, a_prime: f32) -> f32 {
+    if b == 0.0 && a_prime == 0.0 {
+        return 0.0;
+    }
+    let h = b.atan2(a_prime).to_degrees();
+    if h < 0.0 {
+        h + 360.0
+    } else {
+        h
+    }
+}
+
 /// Generate the ANSI 256 color palette
 const fn generate_ansi_palette() -> [Rgb; 256] {
     let mut palette = [Rgb::new(0, 0, 0); 256];
@@ -229,6 +586,174 @@ mod tests {
         assert_eq!(red.as_u8(), 9);
     }
 
+    #[test]
+    fn test_fallback_matches_lut() {
+        for (metric, colors) in [(ColorMetric::Cie76, 256), (ColorMetric::Ciede2000, 16)] {
+            let palette = palette_lab();
+            let lut = build_lut(&palette[..colors], metric);
+            // A quantizer whose table hasn't been built yet
+            let pending = ColorQuantizer {
+                lut: Arc::new(OnceLock::new()),
+                metric,
+                palette_lab: palette,
+                colors,
+            };
+            assert!(!pending.is_ready());
+
+            for value in (0..=255u8).step_by(17) {
+                for rgb in [
+                    Rgb::new(value, 255 - value, value / 2),
+                    Rgb::new(value, value, 0),
+                ] {
+                    let idx = lut_index(rgb.r >> 3, rgb.g >> 3, rgb.b >> 3);
+                    assert_eq!(pending.quantize(rgb).as_u8(), lut[idx], "{:?}", rgb);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_lut_cache_round_trip() {
+        let dir = std::env::temp_dir().join(format!("toast-lut-test-{}", std::process::id()));
+        let palette = palette_lab();
+
+        let base16 = &palette[..16];
+
+        let built = load_or_build_lut(&dir, base16, ColorMetric::Cie76);
+        let key = lut_key(base16, ColorMetric::Cie76);
+        let path = dir.join(format!("{:016x}.lut", key));
+        assert!(path.exists());
+        assert_eq!(load_or_build_lut(&dir, base16, ColorMetric::Cie76), built);
+
+        // A corrupt file is rebuilt rather than trusted
+        std::fs::write(&path, [0u8; 100]).unwrap();
+        assert_eq!(load_or_build_lut(&dir, base16, ColorMetric::Cie76), built);
+        assert_eq!(std::fs::read(&path).unwrap().len(), 32768);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_lut_key_depends_on_palette_and_metric() {
+        let palette = palette_lab();
+        let key = lut_key(&palette, ColorMetric::Cie76);
+        assert_eq!(key, lut_key(&palette, ColorMetric::Cie76));
+        assert_ne!(key, lut_key(&palette, ColorMetric::Ciede2000));
+        assert_ne!(key, lut_key(&palette[..16], ColorMetric::Cie76));
+    }
+
+    #[test]
+    fn test_base16_uses_only_base_colors() {
+        let q = ColorQuantizer::base16(ColorMetric::Cie76);
+
+        for rgb in [
+            Rgb::new(0x87, 0x5f, 0xd7),
+            Rgb::new(30, 30, 30),
+            Rgb::new(250, 200, 0),
+        ] {
+            assert!(q.quantize(rgb).as_u8() < 16, "{:?}", rgb);
+        }
+        assert_eq!(q.quantize(Rgb::new(255, 0, 0)).as_u8(), 9);
+    }
+
+    #[test]
+    fn test_quantize_pair_matches_independent_on_palette_colors() {
+        let q = ColorQuantizer::new();
+        let (fg, bg) = q.quantize_pair(Rgb::new(255, 0, 0), Rgb::new(0, 0, 0));
+        assert_eq!((fg.as_u8(), bg.as_u8()), (9, 0));
+    }
+
+    #[test]
+    fn test_nearest_picks_closest_entries() {
+        let q = ColorQuantizer::base16(ColorMetric::Cie76);
+        let mut nearest = q.nearest(rgb_to_lab(Rgb::new(250, 10, 10)), 2);
+        nearest.sort_unstable();
+        // Bright and dark red
+        assert_eq!(nearest, vec![1, 9]);
+        assert_eq!(q.nearest(rgb_to_lab(Rgb::new(0, 0, 0)), 99).len(), 16);
+    }
+
+    #[test]
+    fn test_quantize_pair_is_no_worse_overall() {
+        let q = ColorQuantizer::base16(ColorMetric::Cie76);
+        let (top, bottom) = (Rgb::new(200, 120, 40), Rgb::new(40, 60, 150));
+
+        let total = |fg: AnsiColor, bg: AnsiColor| {
+            let (t, b) = (rgb_to_lab(top), rgb_to_lab(bottom));
+            let (f, g) = (
+                q.palette_lab[fg.as_u8() as usize],
+                q.palette_lab[bg.as_u8() as usize],
+            );
+            color_distance_lab(t, f).powi(2)
+                + color_distance_lab(b, g).powi(2)
+                + BLEND_WEIGHT * color_distance_lab(t.midpoint(b), f.midpoint(g)).powi(2)
+        };
+        let (fg, bg) = q.quantize_pair(top, bottom);
+        assert!(fg.as_u8() < 16 && bg.as_u8() < 16);
+        assert!(total(fg, bg) <= total(q.quantize(top), q.quantize(bottom)) + 1e-3);
+    }
+
+    #[test]
+    fn test_ciede2000_reference_pairs() {
+        // Reference values from Sharma, Wu & Dalal (2005), table 1
+        let pairs = [
+            ((50.0, 2.6772, -79.7751), (50.0, 0.0, -82.7485), 2.0425),
+            ((50.0, 0.0, 0.0), (50.0, -1.0, 2.0), 2.3669),
+            (
+                (60.2574, -34.0099, 36.2677),
+                (60.4626, -34.1751, 39.4387),
+                1.2644,
+            ),
+        ];
+
+        for ((l1, a1, b1), (l2, a2, b2), expected) in pairs {
+            let lab1 = Lab {
+                l: l1,
+                a: a1,
+                b: b1,
+            };
+            let lab2 = Lab {
+                l: l2,
+                a: a2,
+                b: b2,
+            };
+            let distance = ciede2000(lab1, lab2);
+            assert!(
+                (distance - expected).abs() < 1e-3,
+                "expected {}, got {}",
+                expected,
+                distance
+            );
+            // Symmetric in its arguments
+            assert!((ciede2000(lab2, lab1) - distance).abs() < 1e-3);
+        }
+    }
+
+    #[test]
+    fn test_quantizer_ciede2000_basic_colors() {
+        let q = ColorQuantizer::with_metric(ColorMetric::Ciede2000);
+
+        assert_eq!(q.quantize(Rgb::new(0, 0, 0)).as_u8(), 0);
+        assert_eq!(q.quantize(Rgb::new(255, 255, 255)).as_u8(), 15);
+        assert_eq!(q.quantize(Rgb::new(255, 0, 0)).as_u8(), 9);
+    }
+
+    #[test]
+    fn test_quantize_stable_keeps_close_previous_color() {
+        let quantizer = ColorQuantizer::new();
+        // Just off pure red, so the nearest entry is a bright red
+        let rgb = Rgb::new(250, 5, 5);
+        let nearest = quantizer.quantize(rgb);
+
+        // A neighbouring red within the threshold is kept
+        let neighbour = AnsiColor(160);
+        assert_eq!(quantizer.quantize_stable(rgb, neighbour, 50.0), neighbour);
+        // With a tiny threshold the nearest color wins
+        assert_eq!(quantizer.quantize_stable(rgb, neighbour, 0.5), nearest);
+        // A distant previous color is replaced
+        assert_eq!(quantizer.quantize_stable(rgb, AnsiColor(21), 5.0), nearest);
+    }
+
     #[test]
     fn test_lut_index() {
         assert_eq!(lut_index(0, 0, 0), 0);
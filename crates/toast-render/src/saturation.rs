@@ -0,0 +1,84 @@
+use toast_core::RgbImage;
+
+/// Boost (or reduce) color saturation before quantization
+///
+/// Mapping onto the 256-color palette pulls many colors towards the grey ramp, so pages
+/// look washed out. `saturation` scales every pixel's distance from its luma (1 is
+/// unchanged, 0 is greyscale); `vibrance` adds an extra boost that fades out for colors
+/// that are already saturated, so muted tones gain the most and skin tones don't clip.
+pub fn adjust_saturation(image: &mut RgbImage, saturation: f32, vibrance: f32) {
+    if saturation == 1.0 && vibrance == 0.0 {
+        return;
+    }
+
+    for pixel in image.data.chunks_exact_mut(3) {
+        let [r, g, b] = [pixel[0] as f32, pixel[1] as f32, pixel[2] as f32];
+        let luma = 0.299 * r + 0.587 * g + 0.114 * b;
+
+        let max = r.max(g).max(b);
+        let min = r.min(g).min(b);
+        let current = (max - min) / 255.0;
+        let factor = (saturation + vibrance * (1.0 - current)).max(0.0);
+
+        for value in pixel.iter_mut() {
+            let adjusted = luma + (*value as f32 - luma) * factor;
+            *value = adjusted.round().clamp(0.0, 255.0) as u8;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn single(r: u8, g: u8, b: u8) -> RgbImage {
+        RgbImage::new(vec![r, g, b], 1, 1)
+    }
+
+    #[test]
+    fn test_neutral_settings_are_noop() {
+        let mut image = single(120, 80, 40);
+        adjust_saturation(&mut image, 1.0, 0.0);
+        assert_eq!(image.data, vec![120, 80, 40]);
+    }
+
+    #[test]
+    fn test_saturation_spreads_channels() {
+        let mut image = single(120, 80, 40);
+        adjust_saturation(&mut image, 1.5, 0.0);
+
+        let [r, _, b] = [image.data[0], image.data[1], image.data[2]];
+        assert!(r > 120 && b < 40);
+    }
+
+    #[test]
+    fn test_zero_saturation_is_greyscale() {
+        let mut image = single(200, 40, 90);
+        adjust_saturation(&mut image, 0.0, 0.0);
+        assert!(image.data.iter().all(|&c| c == image.data[0]));
+    }
+
+    #[test]
+    fn test_greys_are_unchanged() {
+        let mut image = single(128, 128, 128);
+        adjust_saturation(&mut image, 2.0, 1.0);
+        assert_eq!(image.data, vec![128, 128, 128]);
+    }
+
+    #[test]
+    fn test_vibrance_favors_muted_colors() {
+        let mut muted = single(140, 120, 100);
+        let mut vivid = single(230, 40, 20);
+        let (muted_before, vivid_before) = (40.0, 210.0);
+
+        adjust_saturation(&mut muted, 1.0, 1.0);
+        adjust_saturation(&mut vivid, 1.0, 1.0);
+
+        // Relative spread gained by each color
+        let spread = |img: &RgbImage| {
+            let d = &img.data;
+            (d[0].max(d[1]).max(d[2]) - d[0].min(d[1]).min(d[2])) as f32
+        };
+        assert!(spread(&muted) / muted_before > spread(&vivid) / vivid_before);
+    }
+}
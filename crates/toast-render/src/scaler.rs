@@ -1,47 +1,151 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
 use fast_image_resize as fr;
 use std::num::NonZeroU32;
 use toast_core::RgbImage;
 
-/// Scale an RGB image to target dimensions using high-quality resampling
-pub fn scale_image(image: &RgbImage, target_width: u32, target_height: u32) -> Result<RgbImage> {
-    // If already correct size, return clone
-    if image.width == target_width && image.height == target_height {
-        return Ok(image.clone());
+/// Resampling filter used when scaling to terminal resolution
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ScaleFilter {
+    /// Nearest neighbour - fastest, aliased
+    Nearest,
+    /// Bilinear - fast, soft
+    Bilinear,
+    /// Catmull-Rom bicubic - sharper than bilinear
+    CatmullRom,
+    /// Lanczos3 (good quality/speed tradeoff)
+    #[default]
+    Lanczos3,
+}
+
+impl ScaleFilter {
+    fn resize_alg(self) -> fr::ResizeAlg {
+        match self {
+            ScaleFilter::Nearest => fr::ResizeAlg::Nearest,
+            ScaleFilter::Bilinear => fr::ResizeAlg::Convolution(fr::FilterType::Bilinear),
+            ScaleFilter::CatmullRom => fr::ResizeAlg::Convolution(fr::FilterType::CatmullRom),
+            ScaleFilter::Lanczos3 => fr::ResizeAlg::Convolution(fr::FilterType::Lanczos3),
+        }
+    }
+}
+
+impl std::str::FromStr for ScaleFilter {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "nearest" => Ok(ScaleFilter::Nearest),
+            "bilinear" => Ok(ScaleFilter::Bilinear),
+            "catmull-rom" | "bicubic" => Ok(ScaleFilter::CatmullRom),
+            "lanczos3" | "lanczos" => Ok(ScaleFilter::Lanczos3),
+            _ => anyhow::bail!(
+                "unknown scale filter '{}' (expected nearest, bilinear, catmull-rom or lanczos3)",
+                s
+            ),
+        }
     }
+}
 
-    // Create source image view
-    let src_image = fr::Image::from_vec_u8(
-        NonZeroU32::new(image.width).unwrap(),
-        NonZeroU32::new(image.height).unwrap(),
-        image.data.clone(),
-        fr::PixelType::U8x3,
-    )?;
-
-    // Create destination image
-    let mut dst_image = fr::Image::new(
-        NonZeroU32::new(target_width).unwrap(),
-        NonZeroU32::new(target_height).unwrap(),
-        fr::PixelType::U8x3,
-    );
-
-    // Create resizer with Lanczos3 algorithm (good quality/speed tradeoff)
-    let mut resizer = fr::Resizer::new(fr::ResizeAlg::Convolution(fr::FilterType::Lanczos3));
-
-    // Perform resize
-    resizer.resize(&src_image.view(), &mut dst_image.view_mut())?;
-
-    Ok(RgbImage::new(
-        dst_image.into_vec(),
-        target_width,
-        target_height,
-    ))
+/// Stateful image scaler that reuses its resizer and pixel buffers across frames
+///
+/// Consecutive frames almost always share the same source and target dimensions,
+/// so keeping the buffers around removes two full-frame allocations per frame.
+pub struct Scaler {
+    resizer: fr::Resizer,
+    /// Scratch copy of the source pixels (the resizer needs a mutable slice)
+    src_buffer: Vec<u8>,
+    /// Scaled output, overwritten on every call
+    output: RgbImage,
+}
+
+impl Scaler {
+    pub fn new() -> Self {
+        Self::with_filter(ScaleFilter::default())
+    }
+
+    /// Create a scaler using the given resampling filter
+    pub fn with_filter(filter: ScaleFilter) -> Self {
+        Self {
+            resizer: fr::Resizer::new(filter.resize_alg()),
+            src_buffer: Vec::new(),
+            output: RgbImage::new(Vec::new(), 0, 0),
+        }
+    }
+
+    /// Switch to another resampling filter, keeping the buffers
+    pub fn set_filter(&mut self, filter: ScaleFilter) {
+        self.resizer = fr::Resizer::new(filter.resize_alg());
+    }
+
+    /// Scale an image to the target dimensions
+    /// Returns the scaler's internal output buffer, which later stages may modify in place
+    pub fn scale(
+        &mut self,
+        image: &RgbImage,
+        target_width: u32,
+        target_height: u32,
+    ) -> Result<&mut RgbImage> {
+        if image.width == target_width && image.height == target_height {
+            // Already the right size - copy into the output buffer without resampling
+            self.output.data.clear();
+            self.output.data.extend_from_slice(&image.data);
+            self.output.width = target_width;
+            self.output.height = target_height;
+            return Ok(&mut self.output);
+        }
+
+        let src_width = NonZeroU32::new(image.width).context("Source image has zero width")?;
+        let src_height = NonZeroU32::new(image.height).context("Source image has zero height")?;
+        let dst_width = NonZeroU32::new(target_width).context("Target width is zero")?;
+        let dst_height = NonZeroU32::new(target_height).context("Target height is zero")?;
+
+        // Copy source pixels into the reusable scratch buffer
+        self.src_buffer.clear();
+        self.src_buffer.extend_from_slice(&image.data);
+
+        // Resize the output buffer in place (no reallocation if the size is unchanged)
+        self.output
+            .data
+            .resize((target_width * target_height * 3) as usize, 0);
+        self.output.width = target_width;
+        self.output.height = target_height;
+
+        let src_image = fr::Image::from_slice_u8(
+            src_width,
+            src_height,
+            &mut self.src_buffer,
+            fr::PixelType::U8x3,
+        )?;
+        let mut dst_image = fr::Image::from_slice_u8(
+            dst_width,
+            dst_height,
+            &mut self.output.data,
+            fr::PixelType::U8x3,
+        )?;
+
+        self.resizer
+            .resize(&src_image.view(), &mut dst_image.view_mut())?;
+
+        Ok(&mut self.output)
+    }
+}
+
+impl Default for Scaler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Scale an RGB image to target dimensions using high-quality resampling
+/// One-shot convenience wrapper around [`Scaler`]; prefer a long-lived `Scaler` per stream
+pub fn scale_image(image: &RgbImage, target_width: u32, target_height: u32) -> Result<RgbImage> {
+    let mut scaler = Scaler::new();
+    scaler.scale(image, target_width, target_height)?;
+    Ok(scaler.output)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use toast_core::Rgb;
 
     #[test]
     fn test_scale_image_upscale() {
@@ -83,4 +187,39 @@ mod tests {
         assert_eq!(scaled.width, 2);
         assert_eq!(scaled.height, 2);
     }
+
+    #[test]
+    fn test_scaler_reuses_buffers_across_sizes() {
+        let mut scaler = Scaler::new();
+        let image = RgbImage::new(vec![255u8; 8 * 8 * 3], 8, 8);
+
+        let scaled = scaler.scale(&image, 4, 4).unwrap();
+        assert_eq!(scaled.data.len(), 4 * 4 * 3);
+
+        // Shrinking the target must shrink the output, not leave stale pixels behind
+        let scaled = scaler.scale(&image, 2, 3).unwrap();
+        assert_eq!(scaled.width, 2);
+        assert_eq!(scaled.height, 3);
+        assert_eq!(scaled.data.len(), 2 * 3 * 3);
+    }
+
+    #[test]
+    fn test_scale_filter_from_str() {
+        assert_eq!(
+            "Lanczos3".parse::<ScaleFilter>().unwrap(),
+            ScaleFilter::Lanczos3
+        );
+        assert_eq!(
+            "bicubic".parse::<ScaleFilter>().unwrap(),
+            ScaleFilter::CatmullRom
+        );
+        assert!("sinc".parse::<ScaleFilter>().is_err());
+    }
+
+    #[test]
+    fn test_scaler_rejects_zero_target() {
+        let mut scaler = Scaler::new();
+        let image = RgbImage::new(vec![0u8; 2 * 2 * 3], 2, 2);
+        assert!(scaler.scale(&image, 0, 2).is_err());
+    }
 }
@@ -0,0 +1,158 @@
+use anyhow::Result;
+use toast_core::{AnsiColor, Rgb, TerminalCell};
+
+use crate::quantizer::{ansi_to_rgb, ColorQuantizer};
+
+/// Characters used to draw cells
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CharacterSet {
+    /// ▀ with separate top/bottom colors: double vertical resolution
+    #[default]
+    HalfBlock,
+    /// ░▒▓█ dithering between two colors: one color per cell, but many more
+    /// perceived colors than the 256-color palette
+    Shade,
+}
+
+impl std::str::FromStr for CharacterSet {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "half-block" | "halfblock" => Ok(CharacterSet::HalfBlock),
+            "shade" => Ok(CharacterSet::Shade),
+            _ => anyhow::bail!(
+                "unknown character set '{}' (expected half-block or shade)",
+                s
+            ),
+        }
+    }
+}
+
+/// Shade characters and the fraction of the cell drawn in the foreground color
+const SHADES: [(char, f32); 3] = [('░', 0.25), ('▒', 0.5), ('▓', 0.75)];
+
+/// Full block character (a solid cell)
+const FULL_BLOCK: char = '█';
+
+/// Pick the shade character and colors whose blend is closest to `color`
+///
+/// The background is the nearest palette color; for each shade density the foreground is
+/// the palette color that pulls the blend towards the target, so a color that falls
+/// between two palette entries is drawn as a mix of both.
+pub(crate) fn shade_cell(quantizer: &ColorQuantizer, color: Rgb) -> TerminalCell {
+    let target = to_f32(color);
+    let background = quantizer.quantize(color);
+    let bg = to_f32(ansi_to_rgb(background));
+
+    let mut best = solid(background);
+    let mut best_error = distance(target, bg);
+
+    for (character, density) in SHADES {
+        if best_error == 0.0 {
+            break;
+        }
+
+        // Foreground that would make the blend hit the target exactly
+        let wanted = std::array::from_fn::<f32, 3, _>(|i| {
+            (bg[i] + (target[i] - bg[i]) / density).clamp(0.0, 255.0)
+        });
+        let foreground = quantizer.quantize(Rgb::new(
+            wanted[0].round() as u8,
+            wanted[1].round() as u8,
+            wanted[2].round() as u8,
+        ));
+        if foreground == background {
+            continue;
+        }
+
+        let fg = to_f32(ansi_to_rgb(foreground));
+        let blend = std::array::from_fn(|i| fg[i] * density + bg[i] * (1.0 - density));
+        let error = distance(target, blend);
+        if error < best_error {
+            best_error = error;
+            best = TerminalCell {
+                character,
+                foreground,
+                background,
+                cluster: None,
+            };
+        }
+    }
+
+    best
+}
+
+/// Average of a cell's two pixels, the single color a shaded cell represents
+pub(crate) fn average(top: Rgb, bottom: Rgb) -> Rgb {
+    let mid = |a: u8, b: u8| (a as u16 + b as u16).div_ceil(2) as u8;
+    Rgb::new(
+        mid(top.r, bottom.r),
+        mid(top.g, bottom.g),
+        mid(top.b, bottom.b),
+    )
+}
+
+fn solid(color: AnsiColor) -> TerminalCell {
+    TerminalCell {
+        character: FULL_BLOCK,
+        foreground: color,
+        background: color,
+        cluster: None,
+    }
+}
+
+fn to_f32(rgb: Rgb) -> [f32; 3] {
+    [rgb.r as f32, rgb.g as f32, rgb.b as f32]
+}
+
+fn distance(a: [f32; 3], b: [f32; 3]) -> f32 {
+    (0..3).map(|i| (a[i] - b[i]).powi(2)).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_palette_color_is_solid() {
+        let quantizer = ColorQuantizer::new();
+        let cell = shade_cell(&quantizer, Rgb::new(255, 255, 255));
+
+        assert_eq!(cell.character, FULL_BLOCK);
+        assert_eq!(cell.foreground, cell.background);
+    }
+
+    #[test]
+    fn test_in_between_color_is_shaded() {
+        let quantizer = ColorQuantizer::new();
+        // Halfway between cube levels 0x5f and 0x87 on the red channel
+        let color = Rgb::new(0x73, 0x00, 0x00);
+
+        let cell = shade_cell(&quantizer, color);
+        assert_ne!(cell.character, FULL_BLOCK);
+
+        // The blend is closer than the nearest single palette color
+        let density = SHADES.iter().find(|(c, _)| *c == cell.character).unwrap().1;
+        let (fg, bg) = (
+            to_f32(ansi_to_rgb(cell.foreground)),
+            to_f32(ansi_to_rgb(cell.background)),
+        );
+        let blend = std::array::from_fn(|i| fg[i] * density + bg[i] * (1.0 - density));
+        let nearest = to_f32(ansi_to_rgb(quantizer.quantize(color)));
+        assert!(distance(to_f32(color), blend) < distance(to_f32(color), nearest));
+    }
+
+    #[test]
+    fn test_character_set_from_str() {
+        assert_eq!(
+            "shade".parse::<CharacterSet>().unwrap(),
+            CharacterSet::Shade
+        );
+        assert_eq!(
+            "Half-Block".parse::<CharacterSet>().unwrap(),
+            CharacterSet::HalfBlock
+        );
+        assert!("ascii".parse::<CharacterSet>().is_err());
+    }
+}
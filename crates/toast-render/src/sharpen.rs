@@ -0,0 +1,93 @@
+use toast_core::RgbImage;
+
+/// Unsharp mask: add back `amount` times the detail removed by a small blur
+///
+/// Lanczos downscaling to terminal resolution softens edges; at terminal sizes a 3x3
+/// Gaussian is the right radius to bring borders and UI chrome back. Typical amounts
+/// are 0.3-1.0; 0 leaves the image untouched.
+pub fn unsharp_mask(image: &mut RgbImage, amount: f32) {
+    if amount <= 0.0 || image.width < 3 || image.height < 3 {
+        return;
+    }
+
+    let blurred = gaussian_blur_3x3(image);
+    for (value, &blur) in image.data.iter_mut().zip(blurred.iter()) {
+        let original = *value as f32;
+        let sharpened = original + amount * (original - blur as f32);
+        *value = sharpened.round().clamp(0.0, 255.0) as u8;
+    }
+}
+
+/// Separable [1 2 1] / 4 blur, clamping at the edges
+fn gaussian_blur_3x3(image: &RgbImage) -> Vec<u8> {
+    let width = image.width as usize;
+    let height = image.height as usize;
+    let index = |x: usize, y: usize, c: usize| (y * width + x) * 3 + c;
+
+    // Horizontal pass (kept at 4x scale to avoid rounding twice)
+    let mut horizontal = vec![0u16; image.data.len()];
+    for y in 0..height {
+        for x in 0..width {
+            let left = x.saturating_sub(1);
+            let right = (x + 1).min(width - 1);
+            for c in 0..3 {
+                horizontal[index(x, y, c)] = image.data[index(left, y, c)] as u16
+                    + 2 * image.data[index(x, y, c)] as u16
+                    + image.data[index(right, y, c)] as u16;
+            }
+        }
+    }
+
+    // Vertical pass
+    let mut out = vec![0u8; image.data.len()];
+    for y in 0..height {
+        let up = y.saturating_sub(1);
+        let down = (y + 1).min(height - 1);
+        for x in 0..width {
+            for c in 0..3 {
+                let sum = horizontal[index(x, up, c)]
+                    + 2 * horizontal[index(x, y, c)]
+                    + horizontal[index(x, down, c)];
+                out[index(x, y, c)] = ((sum + 8) / 16) as u8;
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 6x4 image: left half dark grey, right half light grey
+    fn edge_image() -> RgbImage {
+        let mut data = Vec::new();
+        for _y in 0..4 {
+            for x in 0..6 {
+                let v = if x < 3 { 64 } else { 192 };
+                data.extend_from_slice(&[v, v, v]);
+            }
+        }
+        RgbImage::new(data, 6, 4)
+    }
+
+    #[test]
+    fn test_sharpen_increases_edge_contrast() {
+        let mut image = edge_image();
+        unsharp_mask(&mut image, 1.0);
+
+        // Either side of the edge is pushed apart; flat areas are unchanged
+        assert!(image.get_pixel(2, 1).r < 64);
+        assert!(image.get_pixel(3, 1).r > 192);
+        assert_eq!(image.get_pixel(0, 1).r, 64);
+        assert_eq!(image.get_pixel(5, 1).r, 192);
+    }
+
+    #[test]
+    fn test_zero_amount_is_noop() {
+        let mut image = edge_image();
+        let before = image.data.clone();
+        unsharp_mask(&mut image, 0.0);
+        assert_eq!(image.data, before);
+    }
+}
@@ -0,0 +1,67 @@
+use std::fmt;
+use std::time::Duration;
+
+/// Time spent in each stage of the most recent frame
+///
+/// Quantization happens cell by cell inside the half-block conversion, so the two are
+/// measured together as `convert`. With damage tracking the stages add up over every
+/// re-rendered region of the frame.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PipelineStats {
+    /// Screenshot decode (zero when the caller decoded the image itself)
+    pub decode: Duration,
+    /// Downscale to terminal resolution, including supersampling
+    pub scale: Duration,
+    /// Edge preservation, sharpening, color adjustments and text enhancement
+    pub enhance: Duration,
+    /// Palette quantization and conversion to cells
+    pub convert: Duration,
+    /// Frames rendered by the pipeline so far
+    pub frames: u64,
+    /// Of those, frames reused because the screenshot was identical to the previous one
+    pub unchanged: u64,
+}
+
+impl PipelineStats {
+    /// Time spent on the frame across all stages
+    pub fn total(&self) -> Duration {
+        self.decode + self.scale + self.enhance + self.convert
+    }
+}
+
+impl fmt::Display for PipelineStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let ms = |d: Duration| d.as_secs_f64() * 1000.0;
+        write!(
+            f,
+            "decode {:.1}ms, scale {:.1}ms, enhance {:.1}ms, convert {:.1}ms",
+            ms(self.decode),
+            ms(self.scale),
+            ms(self.enhance),
+            ms(self.convert)
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_total_and_display() {
+        let stats = PipelineStats {
+            decode: Duration::from_micros(3200),
+            scale: Duration::from_millis(4),
+            enhance: Duration::ZERO,
+            convert: Duration::from_micros(1550),
+            frames: 7,
+            unchanged: 0,
+        };
+
+        assert_eq!(stats.total(), Duration::from_micros(8750));
+        assert_eq!(
+            stats.to_string(),
+            "decode 3.2ms, scale 4.0ms, enhance 0.0ms, convert 1.6ms"
+        );
+    }
+}
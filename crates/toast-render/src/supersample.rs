@@ -0,0 +1,219 @@
+use rayon::prelude::*;
+use toast_core::RgbImage;
+
+/// Samples per output pixel along each axis
+pub const SUPERSAMPLE_FACTOR: u32 = 2;
+
+/// Squared RGB distance below which a cell is treated as smooth and simply averaged
+const SMOOTH_DISTANCE: u32 = 24 * 24 * 3;
+
+/// k-means refinement passes; two colors in eight samples converge almost immediately
+const ITERATIONS: usize = 3;
+
+/// Reduce a supersampled image to two pixels per cell by clustering each cell's samples
+///
+/// `image` is `SUPERSAMPLE_FACTOR` times the target resolution in both directions, so a
+/// cell holds 2x4 samples. They are split into two color clusters; each half of the cell
+/// takes the centroid of the cluster most of its samples belong to. Sharp features
+/// (glyph strokes, borders) keep their real color instead of a blend with the
+/// background. Smooth cells, where the clusters are close, are averaged per half.
+pub fn cluster_cells(image: &RgbImage, term_width: usize, term_height: usize) -> RgbImage {
+    let (width, height) = (term_width as u32, term_height as u32 * 2);
+    let row_len = width as usize * 3;
+    let mut out = RgbImage::new(vec![0u8; row_len * height as usize], width, height);
+    if width == 0 || height == 0 {
+        return out;
+    }
+
+    out.data
+        .par_chunks_mut(row_len * 2)
+        .enumerate()
+        .for_each(|(cell_y, rows)| {
+            for cell_x in 0..term_width {
+                let samples = cell_samples(image, cell_x as u32, cell_y as u32);
+                let [top, bottom] = resolve_cell(&samples);
+                rows[cell_x * 3..cell_x * 3 + 3].copy_from_slice(&top);
+                if rows.len() > row_len {
+                    rows[row_len + cell_x * 3..row_len + cell_x * 3 + 3].copy_from_slice(&bottom);
+                }
+            }
+        });
+
+    out
+}
+
+/// The 2x4 samples of a cell, top half first, clamped to the image
+fn cell_samples(image: &RgbImage, cell_x: u32, cell_y: u32) -> [[u8; 3]; 8] {
+    let x0 = cell_x * SUPERSAMPLE_FACTOR;
+    let y0 = cell_y * 2 * SUPERSAMPLE_FACTOR;
+    std::array::from_fn(|i| {
+        let x = (x0 + i as u32 % 2).min(image.width.saturating_sub(1));
+        let y = (y0 + i as u32 / 2).min(image.height.saturating_sub(1));
+        let p = image.get_pixel(x, y);
+        [p.r, p.g, p.b]
+    })
+}
+
+/// Reduce a supersampled image to two pixels per cell by area-averaging each pixel's
+/// `SUPERSAMPLE_FACTOR` x `SUPERSAMPLE_FACTOR` box of samples
+///
+/// Cheaper than [`cluster_cells`] and never changes colors, but a 1px horizontal line
+/// always lands in some box at proportional weight instead of being skipped or doubled
+/// depending on where the scaler's filter taps fall.
+pub fn box_average(image: &RgbImage, term_width: usize, term_height: usize) -> RgbImage {
+    let (width, height) = (term_width as u32, term_height as u32 * 2);
+    let row_len = width as usize * 3;
+    let mut out = RgbImage::new(vec![0u8; row_len * height as usize], width, height);
+    if width == 0 || height == 0 {
+        return out;
+    }
+
+    out.data
+        .par_chunks_mut(row_len)
+        .enumerate()
+        .for_each(|(y, row)| {
+            for x in 0..width {
+                let samples: [[u8; 3]; (SUPERSAMPLE_FACTOR * SUPERSAMPLE_FACTOR) as usize] =
+                    std::array::from_fn(|i| {
+                        let i = i as u32;
+                        let sx = (x * SUPERSAMPLE_FACTOR + i % SUPERSAMPLE_FACTOR)
+                            .min(image.width.saturating_sub(1));
+                        let sy = (y as u32 * SUPERSAMPLE_FACTOR + i / SUPERSAMPLE_FACTOR)
+                            .min(image.height.saturating_sub(1));
+                        let p = image.get_pixel(sx, sy);
+                        [p.r, p.g, p.b]
+                    });
+                let x = x as usize * 3;
+                row[x..x + 3].copy_from_slice(&mean(samples.iter()));
+            }
+        });
+
+    out
+}
+
+/// Top and bottom colors for a cell's samples
+fn resolve_cell(samples: &[[u8; 3]; 8]) -> [[u8; 3]; 2] {
+    let (top, bottom) = samples.split_at(4);
+
+    // Seed with the two samples furthest apart
+    let mut centroids = [samples[0], samples[0]];
+    let mut widest = 0;
+    for (i, a) in samples.iter().enumerate() {
+        for b in &samples[i + 1..] {
+            let d = distance(a, b);
+            if d > widest {
+                widest = d;
+                centroids = [*a, *b];
+            }
+        }
+    }
+    if widest < SMOOTH_DISTANCE {
+        return [mean(top.iter()), mean(bottom.iter())];
+    }
+
+    let mut membership = [0usize; 8];
+    for _ in 0..ITERATIONS {
+        for (m, sample) in membership.iter_mut().zip(samples) {
+            *m = usize::from(distance(sample, &centroids[1]) < distance(sample, &centroids[0]));
+        }
+        for (k, centroid) in centroids.iter_mut().enumerate() {
+            let members = samples.iter().zip(&membership).filter(|(_, &m)| m == k);
+            if members.clone().next().is_some() {
+                *centroid = mean(members.map(|(s, _)| s));
+            }
+        }
+    }
+
+    // Each half takes its majority cluster; ties go to the overall minority, the thin
+    // feature that averaging would lose
+    let minority = usize::from(membership.iter().sum::<usize>() * 2 < samples.len());
+    let pick = |members: &[usize]| {
+        let votes = members.iter().sum::<usize>();
+        let cluster = match (votes * 2).cmp(&members.len()) {
+            std::cmp::Ordering::Greater => 1,
+            std::cmp::Ordering::Less => 0,
+            std::cmp::Ordering::Equal => minority,
+        };
+        centroids[cluster]
+    };
+    [pick(&membership[..4]), pick(&membership[4..])]
+}
+
+fn mean<'a>(colors: impl Iterator<Item = &'a [u8; 3]>) -> [u8; 3] {
+    let mut sum = [0u32; 3];
+    let mut count = 0;
+    for c in colors {
+        for i in 0..3 {
+            sum[i] += c[i] as u32;
+        }
+        count += 1;
+    }
+    let n = count.max(1);
+    std::array::from_fn(|i| ((sum[i] + n / 2) / n) as u8)
+}
+
+fn distance(a: &[u8; 3], b: &[u8; 3]) -> u32 {
+    (0..3)
+        .map(|i| (a[i] as i32 - b[i] as i32).pow(2) as u32)
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// One cell's worth of samples (2x4) from rows of colors
+    fn cell(rows: [[u8; 3]; 4]) -> RgbImage {
+        let mut data = Vec::new();
+        for row in rows {
+            data.extend_from_slice(&row);
+            data.extend_from_slice(&row);
+        }
+        RgbImage::new(data, 2, 4)
+    }
+
+    #[test]
+    fn test_smooth_cell_is_averaged() {
+        let image = cell([[100; 3], [104; 3], [110; 3], [114; 3]]);
+        let out = cluster_cells(&image, 1, 1);
+
+        assert_eq!((out.width, out.height), (1, 2));
+        assert_eq!(out.get_pixel(0, 0).r, 102);
+        assert_eq!(out.get_pixel(0, 1).r, 112);
+    }
+
+    #[test]
+    fn test_stroke_keeps_its_color() {
+        // One dark row in the top half: averaging would give grey, clustering keeps ink
+        let image = cell([[255; 3], [0; 3], [255; 3], [255; 3]]);
+        let out = cluster_cells(&image, 1, 1);
+
+        assert_eq!(out.get_pixel(0, 0).r, 0);
+        assert_eq!(out.get_pixel(0, 1).r, 255);
+
+        let image = cell([[255; 3], [255; 3], [255; 3], [0; 3]]);
+        let out = cluster_cells(&image, 1, 1);
+        assert_eq!(out.get_pixel(0, 0).r, 255);
+        assert_eq!(out.get_pixel(0, 1).r, 0);
+    }
+
+    #[test]
+    fn test_box_average_keeps_thin_line() {
+        // A 1px dark line in the top pixel's box shows up at half weight
+        let image = cell([[255; 3], [0; 3], [255; 3], [255; 3]]);
+        let out = box_average(&image, 1, 1);
+
+        assert_eq!((out.width, out.height), (1, 2));
+        assert_eq!(out.get_pixel(0, 0).r, 128);
+        assert_eq!(out.get_pixel(0, 1).r, 255);
+    }
+
+    #[test]
+    fn test_majority_wins() {
+        let image = cell([[255, 0, 0], [255, 0, 0], [0, 0, 255], [255, 0, 0]]);
+        let out = cluster_cells(&image, 1, 1);
+
+        let top = out.get_pixel(0, 0);
+        assert_eq!((top.r, top.b), (255, 0));
+    }
+}
@@ -0,0 +1,264 @@
+use rayon::prelude::*;
+use toast_core::RgbImage;
+
+/// Minimum luminance spread (0-255) for a cell to be considered text
+const MIN_CONTRAST: u8 = 96;
+
+/// Maximum share of a text cell that may be ink (text is sparse strokes on a background)
+const MAX_INK_FRACTION: f32 = 0.5;
+
+/// Maximum share of mid-tone pixels; photos and gradients have many, anti-aliased text few
+const MAX_MIDTONE_FRACTION: f32 = 0.3;
+
+/// Ink coverage above which a half-block pixel is drawn as ink
+/// Deliberately below 0.5 so thin strokes survive instead of averaging away
+const INK_COVERAGE: f32 = 0.2;
+
+/// Sharpen text in a scaled image using the full-resolution source
+///
+/// Downscaling blurs glyph strokes into muddy intermediate colors that quantize badly.
+/// For every cell (a pair of vertically stacked pixels in `scaled`) whose source region
+/// looks like text - high contrast with a bimodal luminance distribution - both pixels
+/// are replaced with either the paper or the ink color sampled from the source.
+pub fn enhance_text_regions(source: &RgbImage, scaled: &mut RgbImage) {
+    if source.width == 0 || source.height == 0 || scaled.width == 0 || scaled.height == 0 {
+        return;
+    }
+
+    let (width, height) = (scaled.width, scaled.height);
+    let row_len = width as usize * 3;
+
+    // Each chunk is one cell row (two pixel rows, or one for an odd final row)
+    scaled
+        .data
+        .par_chunks_mut(row_len * 2)
+        .enumerate()
+        .for_each(|(cell_y, rows)| {
+            let rows_in_chunk = rows.len() / row_len;
+            for cell_x in 0..width as usize {
+                let block = CellBlock::new(source, (width, height), cell_x, cell_y);
+                if let Some(pixels) = classify_cell(source, &block) {
+                    for (half, rgb) in pixels.iter().enumerate().take(rows_in_chunk) {
+                        let offset = half * row_len + cell_x * 3;
+                        rows[offset..offset + 3].copy_from_slice(rgb);
+                    }
+                }
+            }
+        });
+}
+
+/// Source-image region covered by one terminal cell, split into its top and bottom halves
+struct CellBlock {
+    x0: u32,
+    x1: u32,
+    /// Row boundaries: top half is [ys[0], ys[1]), bottom half is [ys[1], ys[2])
+    ys: [u32; 3],
+}
+
+impl CellBlock {
+    fn new(source: &RgbImage, scaled: (u32, u32), cell_x: usize, cell_y: usize) -> Self {
+        let (scaled_w, scaled_h) = scaled;
+        let map_x = |x: u32| (x as u64 * source.width as u64 / scaled_w as u64) as u32;
+        let map_y = |y: u32| (y as u64 * source.height as u64 / scaled_h as u64) as u32;
+
+        let x = cell_x as u32;
+        let y = cell_y as u32 * 2;
+        let x0 = map_x(x);
+        let y0 = map_y(y);
+        // Always cover at least one source pixel per half, even when upscaling
+        let x1 = map_x(x + 1).max(x0 + 1).min(source.width);
+        let y1 = map_y(y + 1).max(y0 + 1).min(source.height);
+        let y2 = map_y(y + 2).max(y1 + 1).min(source.height);
+
+        Self {
+            x0,
+            x1,
+            ys: [y0, y1, y2],
+        }
+    }
+}
+
+/// Decide whether a cell is text, returning the crisp top and bottom pixel colors if so
+fn classify_cell(source: &RgbImage, block: &CellBlock) -> Option<[[u8; 3]; 2]> {
+    // First pass: luminance range
+    let mut min = u8::MAX;
+    let mut max = u8::MIN;
+    for y in block.ys[0]..block.ys[2] {
+        for x in block.x0..block.x1 {
+            let l = luminance(pixel(source, x, y));
+            min = min.min(l);
+            max = max.max(l);
+        }
+    }
+    if max.saturating_sub(min) < MIN_CONTRAST {
+        return None;
+    }
+
+    // Second pass: split into dark/light classes around the midpoint
+    let threshold = ((min as u16 + max as u16) / 2) as u8;
+    let midtone_band = (max - min) / 4;
+    let mut dark = ClassStats::default();
+    let mut light = ClassStats::default();
+    let mut midtones = 0u32;
+    let mut dark_per_half = [0u32; 2];
+    let mut total_per_half = [0u32; 2];
+
+    for half in 0..2 {
+        for y in block.ys[half]..block.ys[half + 1] {
+            for x in block.x0..block.x1 {
+                let rgb = pixel(source, x, y);
+                let l = luminance(rgb);
+                if l.abs_diff(threshold) < midtone_band {
+                    midtones += 1;
+                }
+                if l < threshold {
+                    dark.add(rgb);
+                    dark_per_half[half] += 1;
+                } else {
+                    light.add(rgb);
+                }
+                total_per_half[half] += 1;
+            }
+        }
+    }
+
+    let total = dark.count + light.count;
+    if total == 0 || midtones as f32 / total as f32 > MAX_MIDTONE_FRACTION {
+        return None;
+    }
+
+    // The minority class is the ink, the majority the paper (handles light-on-dark text too)
+    let ink_is_dark = dark.count <= light.count;
+    let (ink, paper) = if ink_is_dark {
+        (&dark, &light)
+    } else {
+        (&light, &dark)
+    };
+    if ink.count as f32 / total as f32 > MAX_INK_FRACTION {
+        return None;
+    }
+
+    let ink_rgb = ink.mean();
+    let paper_rgb = paper.mean();
+    let mut out = [paper_rgb; 2];
+    for half in 0..2 {
+        if total_per_half[half] == 0 {
+            continue;
+        }
+        let ink_count = if ink_is_dark {
+            dark_per_half[half]
+        } else {
+            total_per_half[half] - dark_per_half[half]
+        };
+        if ink_count as f32 / total_per_half[half] as f32 >= INK_COVERAGE {
+            out[half] = ink_rgb;
+        }
+    }
+
+    Some(out)
+}
+
+/// Running color sum for one luminance class
+#[derive(Default)]
+struct ClassStats {
+    sum: [u32; 3],
+    count: u32,
+}
+
+impl ClassStats {
+    #[inline]
+    fn add(&mut self, rgb: [u8; 3]) {
+        self.sum[0] += rgb[0] as u32;
+        self.sum[1] += rgb[1] as u32;
+        self.sum[2] += rgb[2] as u32;
+        self.count += 1;
+    }
+
+    fn mean(&self) -> [u8; 3] {
+        let n = self.count.max(1);
+        [
+            (self.sum[0] / n) as u8,
+            (self.sum[1] / n) as u8,
+            (self.sum[2] / n) as u8,
+        ]
+    }
+}
+
+#[inline]
+pub(crate) fn pixel(image: &RgbImage, x: u32, y: u32) -> [u8; 3] {
+    let offset = ((y * image.width + x) * 3) as usize;
+    [
+        image.data[offset],
+        image.data[offset + 1],
+        image.data[offset + 2],
+    ]
+}
+
+/// Rec. 601 luma, integer approximation
+#[inline]
+pub(crate) fn luminance(rgb: [u8; 3]) -> u8 {
+    ((rgb[0] as u32 * 77 + rgb[1] as u32 * 150 + rgb[2] as u32 * 29) >> 8) as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// White 8x8 image with a 1px black vertical line at x=2
+    fn line_image() -> RgbImage {
+        let mut data = vec![255u8; 8 * 8 * 3];
+        for y in 0..8 {
+            let offset = (y * 8 + 2) * 3;
+            data[offset..offset + 3].copy_from_slice(&[0, 0, 0]);
+        }
+        RgbImage::new(data, 8, 8)
+    }
+
+    #[test]
+    fn test_thin_line_becomes_ink() {
+        let source = line_image();
+        // Blurry gray, as a downscaler would produce
+        let mut scaled = RgbImage::new(vec![200u8; 2 * 2 * 3], 2, 2);
+
+        enhance_text_regions(&source, &mut scaled);
+
+        // Left column contains the line: both halves become pure ink
+        assert_eq!(&scaled.data[0..3], &[0, 0, 0]);
+        assert_eq!(&scaled.data[6..9], &[0, 0, 0]);
+        // Right column is flat white: left untouched
+        assert_eq!(&scaled.data[3..6], &[200, 200, 200]);
+        assert_eq!(&scaled.data[9..12], &[200, 200, 200]);
+    }
+
+    #[test]
+    fn test_gradient_is_not_text() {
+        // Horizontal gradient: high contrast but mostly mid-tones
+        let mut data = Vec::with_capacity(8 * 8 * 3);
+        for _y in 0..8 {
+            for x in 0..8u8 {
+                let v = x * 32;
+                data.extend_from_slice(&[v, v, v]);
+            }
+        }
+        let source = RgbImage::new(data, 8, 8);
+        let mut scaled = RgbImage::new(vec![128u8; 3], 1, 1);
+
+        enhance_text_regions(&source, &mut scaled);
+
+        assert_eq!(scaled.data, vec![128, 128, 128]);
+    }
+
+    #[test]
+    fn test_light_on_dark_text() {
+        // Inverted line image: white stroke on black
+        let mut source = line_image();
+        for v in source.data.iter_mut() {
+            *v = 255 - *v;
+        }
+        let mut scaled = RgbImage::new(vec![60u8; 2 * 2 * 3], 2, 2);
+
+        enhance_text_regions(&source, &mut scaled);
+
+        assert_eq!(&scaled.data[0..3], &[255, 255, 255]);
+    }
+}
@@ -0,0 +1,137 @@
+/// Largest supported magnification
+const MAX_SCALE: f32 = 8.0;
+
+/// Magnification factor applied per zoom step
+const ZOOM_STEP: f32 = 1.5;
+
+/// Terminal-side zoom: which part of the page is magnified onto the terminal
+///
+/// The page itself is untouched (no browser zoom); the pipeline crops the decoded
+/// screenshot to [`Zoom::source_rect`] before scaling. Coordinates are normalized
+/// (0.0-1.0 across the page) so the zoom survives screenshot size changes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Zoom {
+    scale: f32,
+    center_x: f32,
+    center_y: f32,
+}
+
+impl Zoom {
+    /// The whole page, unmagnified
+    pub fn new() -> Self {
+        Self {
+            scale: 1.0,
+            center_x: 0.5,
+            center_y: 0.5,
+        }
+    }
+
+    pub fn scale(&self) -> f32 {
+        self.scale
+    }
+
+    pub fn is_identity(&self) -> bool {
+        self.scale <= 1.0
+    }
+
+    pub fn zoom_in(&mut self) {
+        self.scale = (self.scale * ZOOM_STEP).min(MAX_SCALE);
+        self.clamp_center();
+    }
+
+    pub fn zoom_out(&mut self) {
+        self.scale = (self.scale / ZOOM_STEP).max(1.0);
+        self.clamp_center();
+    }
+
+    /// Move the visible region by a fraction of its own width/height
+    pub fn pan(&mut self, dx: f32, dy: f32) {
+        self.center_x += dx / self.scale;
+        self.center_y += dy / self.scale;
+        self.clamp_center();
+    }
+
+    /// Pixel rectangle `(x, y, width, height)` of an image that is visible at this zoom
+    pub fn source_rect(&self, width: u32, height: u32) -> (u32, u32, u32, u32) {
+        if width == 0 || height == 0 {
+            return (0, 0, width, height);
+        }
+        let visible_w = ((width as f32 / self.scale).round() as u32).clamp(1, width);
+        let visible_h = ((height as f32 / self.scale).round() as u32).clamp(1, height);
+        let x = (self.center_x * width as f32 - visible_w as f32 / 2.0).round() as u32;
+        let y = (self.center_y * height as f32 - visible_h as f32 / 2.0).round() as u32;
+        (
+            x.min(width - visible_w),
+            y.min(height - visible_h),
+            visible_w,
+            visible_h,
+        )
+    }
+
+    /// Map a normalized position on the terminal to a normalized position on the page
+    pub fn to_page(&self, x: f64, y: f64) -> (f64, f64) {
+        let half = 0.5 / self.scale as f64;
+        (
+            self.center_x as f64 - half + x / self.scale as f64,
+            self.center_y as f64 - half + y / self.scale as f64,
+        )
+    }
+
+    /// Keep the visible region inside the page
+    fn clamp_center(&mut self) {
+        let half = 0.5 / self.scale;
+        self.center_x = self.center_x.clamp(half, 1.0 - half);
+        self.center_y = self.center_y.clamp(half, 1.0 - half);
+    }
+}
+
+impl Default for Zoom {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_identity_covers_whole_image() {
+        let zoom = Zoom::new();
+        assert!(zoom.is_identity());
+        assert_eq!(zoom.source_rect(1920, 1080), (0, 0, 1920, 1080));
+        assert_eq!(zoom.to_page(0.25, 0.75), (0.25, 0.75));
+    }
+
+    #[test]
+    fn test_zoom_in_crops_center() {
+        let mut zoom = Zoom::new();
+        zoom.zoom_in();
+        zoom.zoom_in(); // 2.25x
+
+        let (x, y, w, h) = zoom.source_rect(900, 900);
+        assert_eq!((w, h), (400, 400));
+        assert_eq!((x, y), (250, 250));
+
+        // The terminal's center is still the page's center
+        let (px, py) = zoom.to_page(0.5, 0.5);
+        assert!((px - 0.5).abs() < 1e-6 && (py - 0.5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_pan_stays_inside_page() {
+        let mut zoom = Zoom::new();
+        zoom.zoom_in();
+        for _ in 0..20 {
+            zoom.pan(-0.25, 0.25);
+        }
+
+        let (x, y, w, h) = zoom.source_rect(300, 300);
+        assert_eq!((x, y + h), (0, 300));
+        assert_eq!(w, 200);
+
+        // Zooming all the way out restores the full page
+        zoom.zoom_out();
+        assert_eq!(zoom.source_rect(300, 300), (0, 0, 300, 300));
+    }
+}
@@ -0,0 +1,235 @@
+//! Rendering regression suite: fixture screenshots through each converter mode, compared
+//! against stored golden frames with a perceptual tolerance
+//!
+//! The first fixtures are small synthetic stand-ins for typical pages (an article, a photo,
+//! a dark UI), stored at 40x24 pixels. The others are whole pages drawn with real fonts at
+//! 512x320 and reduced to 64x40, the way a terminal sees a page: a text-heavy article, a
+//! page around a photo, and a dark-themed documentation page. Each mode renders them at the frame size whose
+//! sampling resolution is exactly the fixture's, so the goldens exercise decoding, the
+//! color stages, quantization and conversion without depending on the resampling
+//! library's filter implementation. After an intended change to the output, regenerate
+//! them with `TOAST_BLESS=1 cargo test -p toast-render --test golden` and review the diff.
+
+use bytes::Bytes;
+use std::path::{Path, PathBuf};
+use toast_core::{AnsiColor, ImageFormat, Screenshot, TerminalCell, TerminalFrame};
+use toast_render::{
+    ansi_to_rgb, decode_screenshot, CharacterSet, HighContrast, RenderConfig, RenderPipeline,
+    SUPERSAMPLE_FACTOR,
+};
+
+const FIXTURES: &[&str] = &["article", "photo", "ui", "text", "image", "dark"];
+
+/// Largest acceptable mean delta-E over all pixels
+const MAX_MEAN_DELTA_E: f32 = 3.0;
+
+/// A pixel further off than this counts as wrong
+const WRONG_DELTA_E: f32 = 12.0;
+
+/// Largest acceptable fraction of wrong pixels
+const MAX_WRONG_FRACTION: f32 = 0.03;
+
+fn modes() -> Vec<(&'static str, RenderConfig)> {
+    let default = RenderConfig::default();
+    vec![
+        ("default", default.clone()),
+        (
+            "supersample",
+            RenderConfig {
+                supersample: true,
+                ..default.clone()
+            },
+        ),
+        (
+            "subpixel",
+            RenderConfig {
+                subpixel: true,
+                ..default.clone()
+            },
+        ),
+        (
+            "shade",
+            RenderConfig {
+                charset: CharacterSet::Shade,
+                ..default.clone()
+            },
+        ),
+        (
+            "base16",
+            RenderConfig {
+                high_contrast: HighContrast::Base16,
+                pair_search: true,
+                ..default
+            },
+        ),
+    ]
+}
+
+fn fixture_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures")
+}
+
+#[test]
+fn test_golden_frames() {
+    let bless = std::env::var_os("TOAST_BLESS").is_some();
+    let mut failures = Vec::new();
+
+    for fixture in FIXTURES {
+        let dir = fixture_dir();
+        let data = std::fs::read(dir.join(format!("{}.png", fixture))).unwrap();
+        let screenshot = Screenshot {
+            data: Bytes::from(data),
+            format: ImageFormat::Png,
+            metadata: Default::default(),
+        };
+
+        let image = decode_screenshot(&screenshot).unwrap();
+
+        for (mode, config) in modes() {
+            // One pixel (or sample) per fixture pixel
+            let factor = if config.supersample || config.subpixel {
+                SUPERSAMPLE_FACTOR
+            } else {
+                1
+            };
+            let columns = (image.width / factor) as usize;
+            let rows = (image.height / factor / 2) as usize;
+            let frame = RenderPipeline::with_config(config)
+                .render(&screenshot, columns, rows)
+                .unwrap();
+            let golden = dir.join(format!("{}.{}.json", fixture, mode));
+
+            if bless {
+                std::fs::write(&golden, serde_json::to_string(&frame).unwrap()).unwrap();
+                continue;
+            }
+            let Ok(text) = std::fs::read_to_string(&golden) else {
+                failures.push(format!(
+                    "{}: missing, run with TOAST_BLESS=1",
+                    golden.display()
+                ));
+                continue;
+            };
+            let expected: TerminalFrame = serde_json::from_str(&text).unwrap();
+            if let Err(e) = compare(&expected, &frame) {
+                failures.push(format!("{} ({}): {}", fixture, mode, e));
+            }
+        }
+    }
+
+    assert!(
+        failures.is_empty(),
+        "golden mismatches:\n{}",
+        failures.join("\n")
+    );
+}
+
+/// Check two frames look alike, allowing palette-level drift
+fn compare(expected: &TerminalFrame, actual: &TerminalFrame) -> Result<(), String> {
+    if (expected.width, expected.height) != (actual.width, actual.height) {
+        return Err(format!(
+            "size {}x{} instead of {}x{}",
+            actual.width, actual.height, expected.width, expected.height
+        ));
+    }
+
+    let mut total = 0.0;
+    let mut wrong = 0;
+    let mut pixels = 0;
+    for (a, b) in expected.cells.iter().zip(&actual.cells) {
+        let distances = match (halves(a), halves(b)) {
+            (Some(x), Some(y)) => [delta_e(x.0, y.0), delta_e(x.1, y.1)],
+            // Other characters (shades, overlays) must match exactly
+            _ if a == b => [0.0; 2],
+            _ if a.character == b.character => [
+                delta_e(a.foreground, b.foreground),
+                delta_e(a.background, b.background),
+            ],
+            _ => [f32::INFINITY; 2],
+        };
+        for d in distances {
+            total += d.min(100.0);
+            wrong += usize::from(d > WRONG_DELTA_E);
+            pixels += 1;
+        }
+    }
+
+    let mean = total / pixels as f32;
+    let wrong_fraction = wrong as f32 / pixels as f32;
+    if mean > MAX_MEAN_DELTA_E || wrong_fraction > MAX_WRONG_FRACTION {
+        return Err(format!(
+            "mean delta-E {:.2}, {:.1}% of pixels wrong",
+            mean,
+            wrong_fraction * 100.0
+        ));
+    }
+    Ok(())
+}
+
+/// Top and bottom colors of a block cell, however it is drawn
+fn halves(cell: &TerminalCell) -> Option<(AnsiColor, AnsiColor)> {
+    let (fg, bg) = (cell.foreground, cell.background);
+    match cell.character {
+        '▀' => Some((fg, bg)),
+        '▄' => Some((bg, fg)),
+        '█' => Some((fg, fg)),
+        ' ' => Some((bg, bg)),
+        _ => None,
+    }
+}
+
+/// CIE76 distance between two palette colors
+fn delta_e(a: AnsiColor, b: AnsiColor) -> f32 {
+    let (a, b) = (lab(a), lab(b));
+    ((a[0] - b[0]).powi(2) + (a[1] - b[1]).powi(2) + (a[2] - b[2]).powi(2)).sqrt()
+}
+
+fn lab(color: AnsiColor) -> [f32; 3] {
+    let rgb = ansi_to_rgb(color);
+    let linear = |c: u8| {
+        let c = c as f32 / 255.0;
+        if c > 0.04045 {
+            ((c + 0.055) / 1.055).powf(2.4)
+        } else {
+            c / 12.92
+        }
+    };
+    let (r, g, b) = (linear(rgb.r), linear(rgb.g), linear(rgb.b));
+
+    // sRGB to XYZ, relative to the D65 white point
+    let x = (0.4124 * r + 0.3576 * g + 0.1805 * b) / 0.95047;
+    let y = 0.2126 * r + 0.7152 * g + 0.0722 * b;
+    let z = (0.0193 * r + 0.1192 * g + 0.9505 * b) / 1.08883;
+
+    let f = |t: f32| {
+        if t > 0.008856 {
+            t.cbrt()
+        } else {
+            7.787 * t + 16.0 / 116.0
+        }
+    };
+    let (fx, fy, fz) = (f(x), f(y), f(z));
+    [116.0 * fy - 16.0, 500.0 * (fx - fy), 200.0 * (fy - fz)]
+}
+
+#[test]
+fn test_compare_tolerates_equivalent_cells() {
+    let mut a = TerminalFrame::new(1, 1);
+    let mut b = TerminalFrame::new(1, 1);
+    a.cells[0] = TerminalCell {
+        character: '▀',
+        foreground: AnsiColor(196),
+        background: AnsiColor(21),
+        cluster: None,
+    };
+    b.cells[0] = TerminalCell {
+        character: '▄',
+        foreground: AnsiColor(21),
+        background: AnsiColor(196),
+        cluster: None,
+    };
+    assert!(compare(&a, &b).is_ok());
+
+    b.cells[0].character = '█';
+    assert!(compare(&a, &b).is_err());
+}
@@ -0,0 +1,137 @@
+use std::io::Write;
+use std::time::Duration;
+use toast_core::Rgb;
+
+/// How long to wait for the terminal to answer the OSC 11 query
+/// Terminals that don't support it never answer, so keep this short
+pub const QUERY_TIMEOUT: Duration = Duration::from_millis(150);
+
+/// Longest query reply read before giving up on it as garbage
+const MAX_RESPONSE_LEN: usize = 256;
+
+/// Ask the terminal for its default background color (OSC 11)
+///
+/// Must be called in raw mode, before anything else reads stdin. Returns `None` if the
+/// terminal doesn't answer within `timeout` or the platform isn't supported.
+pub fn query_background(timeout: Duration) -> Option<Rgb> {
+    let mut stdout = std::io::stdout();
+    stdout.write_all(b"\x1b]11;?\x07").ok()?;
+    stdout.flush().ok()?;
+
+    // The reply ends with BEL or ST
+    let response = read_response(timeout, |reply| {
+        reply.ends_with(b"\x07") || reply.ends_with(b"\x1b\\")
+    })?;
+    parse_osc11(&response)
+}
+
+/// Read a query reply from stdin, stopping once `complete` accepts what has been read
+#[cfg(unix)]
+pub(crate) fn read_response(
+    timeout: Duration,
+    complete: impl Fn(&[u8]) -> bool,
+) -> Option<Vec<u8>> {
+    use std::time::Instant;
+
+    let deadline = Instant::now() + timeout;
+    let mut response = Vec::new();
+    let mut byte = [0u8; 1];
+
+    loop {
+        let remaining = deadline.checked_duration_since(Instant::now())?;
+        let mut poll_fd = libc::pollfd {
+            fd: libc::STDIN_FILENO,
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        // SAFETY: poll_fd is a valid pollfd for the duration of the call
+        let ready = unsafe { libc::poll(&mut poll_fd, 1, remaining.as_millis() as libc::c_int) };
+        if ready <= 0 {
+            return None;
+        }
+
+        // SAFETY: reading one byte into a one-byte buffer
+        let read = unsafe { libc::read(libc::STDIN_FILENO, byte.as_mut_ptr().cast(), 1) };
+        if read != 1 {
+            return None;
+        }
+        response.push(byte[0]);
+
+        if complete(&response) {
+            return Some(response);
+        }
+        if response.len() > MAX_RESPONSE_LEN {
+            return None;
+        }
+    }
+}
+
+#[cfg(not(unix))]
+pub(crate) fn read_response(
+    _timeout: Duration,
+    _complete: impl Fn(&[u8]) -> bool,
+) -> Option<Vec<u8>> {
+    None
+}
+
+/// Parse `ESC ] 11 ; rgb:RRRR/GGGG/BBBB` (terminated by BEL or ST)
+///
+/// Channels may have 1-4 hex digits each; they are scaled to 8 bits.
+pub fn parse_osc11(response: &[u8]) -> Option<Rgb> {
+    let text = std::str::from_utf8(response).ok()?;
+    let start = text.find("]11;")? + 4;
+    let body = text[start..]
+        .trim_end_matches('\x07')
+        .trim_end_matches("\x1b\\");
+    let spec = body.strip_prefix("rgb:")?;
+
+    let mut channels = spec.split('/').map(|channel| {
+        let digits = channel.len();
+        if digits == 0 || digits > 4 {
+            return None;
+        }
+        let value = u32::from_str_radix(channel, 16).ok()?;
+        let max = (1u32 << (4 * digits)) - 1;
+        Some((value * 255 / max) as u8)
+    });
+
+    let r = channels.next()??;
+    let g = channels.next()??;
+    let b = channels.next()??;
+    if channels.next().is_some() {
+        return None;
+    }
+    Some(Rgb::new(r, g, b))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_osc11_bel_and_st() {
+        assert_eq!(
+            parse_osc11(b"\x1b]11;rgb:1e1e/1e1e/2e2e\x07"),
+            Some(Rgb::new(0x1e, 0x1e, 0x2e))
+        );
+        assert_eq!(
+            parse_osc11(b"\x1b]11;rgb:ffff/ffff/ffff\x1b\\"),
+            Some(Rgb::new(255, 255, 255))
+        );
+    }
+
+    #[test]
+    fn test_parse_osc11_short_channels() {
+        assert_eq!(
+            parse_osc11(b"\x1b]11;rgb:f/8/0\x07"),
+            Some(Rgb::new(255, 136, 0))
+        );
+    }
+
+    #[test]
+    fn test_parse_osc11_rejects_garbage() {
+        assert_eq!(parse_osc11(b"\x1b]11;?\x07"), None);
+        assert_eq!(parse_osc11(b"\x1b]10;rgb:0/0/0\x07"), None);
+        assert_eq!(parse_osc11(b"\x1b]11;rgb:00/00\x07"), None);
+    }
+}
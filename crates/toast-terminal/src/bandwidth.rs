@@ -0,0 +1,82 @@
+use std::time::{Duration, Instant};
+
+/// Output saved up while idle that may go out at once; kept short so a burst doesn't
+/// queue seconds of frames on a slow link
+const BURST: Duration = Duration::from_millis(250);
+
+/// Caps terminal output at a byte rate by holding frames back while over it
+///
+/// A token bucket: frames are let through while there is allowance left, and a large
+/// one can overdraw it, after which nothing goes out until the debt is paid off.
+#[derive(Debug)]
+pub struct BandwidthLimit {
+    /// Bytes per second
+    rate: f64,
+    /// Bytes that may still be written; negative after a frame larger than what was left
+    allowance: f64,
+    updated: Instant,
+}
+
+impl BandwidthLimit {
+    pub fn new(bytes_per_sec: u64, now: Instant) -> Self {
+        let rate = bytes_per_sec.max(1) as f64;
+        Self {
+            rate,
+            allowance: rate * BURST.as_secs_f64(),
+            updated: now,
+        }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.allowance =
+            (self.allowance + elapsed * self.rate).min(self.rate * BURST.as_secs_f64());
+        self.updated = now;
+    }
+
+    /// Whether a frame may be written at `now`
+    pub fn admit(&mut self, now: Instant) -> bool {
+        self.refill(now);
+        self.allowance > 0.0
+    }
+
+    /// Count `bytes` written
+    pub fn spend(&mut self, bytes: u64) {
+        self.allowance -= bytes as f64;
+    }
+
+    /// How long after `now` until a frame may be written again
+    pub fn wait(&mut self, now: Instant) -> Duration {
+        self.refill(now);
+        // Just past the point where the debt is paid off
+        Duration::from_secs_f64((-self.allowance).max(0.0) / self.rate) + Duration::from_millis(1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_large_frame_holds_back_the_next() {
+        let now = Instant::now();
+        // 1000 bytes/s with a 250 byte burst
+        let mut limit = BandwidthLimit::new(1000, now);
+        assert!(limit.admit(now));
+        limit.spend(750);
+        assert!(!limit.admit(now));
+        assert_eq!(limit.wait(now), Duration::from_millis(501));
+
+        assert!(!limit.admit(now + Duration::from_millis(400)));
+        assert!(limit.admit(now + Duration::from_millis(501)));
+    }
+
+    #[test]
+    fn test_idle_time_saves_only_a_burst() {
+        let now = Instant::now();
+        let mut limit = BandwidthLimit::new(1000, now);
+        assert!(limit.admit(now + Duration::from_secs(10)));
+        limit.spend(500);
+        assert!(!limit.admit(now + Duration::from_secs(10)));
+    }
+}
@@ -0,0 +1,241 @@
+use std::io::Write;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::background::read_response;
+use crate::tmux::{tmux_client_features, Passthrough};
+
+/// Kitty graphics query: a 1x1 image the terminal checks but doesn't store (`a=q`)
+const KITTY_QUERY: &[u8] = b"\x1b_Gi=31,s=1,v=1,a=q,t=d,f=24;AAAA\x1b\\";
+
+/// DECRQM for mode 2026, synchronized output
+const SYNC_QUERY: &[u8] = b"\x1b[?2026$p";
+
+/// XTGETTCAP for the `RGB` and `Tc` truecolor capabilities, names in hex
+const XTGETTCAP_QUERY: &[u8] = b"\x1bP+q524742;5463\x1b\\";
+
+/// Primary device attributes; every terminal answers, so it goes last to end the probe
+const DA1_QUERY: &[u8] = b"\x1b[c";
+
+/// What the terminal supports beyond 256-color text
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Capabilities {
+    /// 24-bit color (`38;2;r;g;b`)
+    pub truecolor: bool,
+    /// Sixel graphics
+    pub sixel: bool,
+    /// The kitty graphics protocol
+    pub kitty_graphics: bool,
+    /// Synchronized output (mode 2026): frames are shown whole, without tearing
+    pub synchronized_update: bool,
+    /// OSC 8 hyperlinks
+    pub hyperlinks: bool,
+}
+
+impl Capabilities {
+    /// What environment variables (`COLORTERM`, `TERM`, `TERM_PROGRAM` and friends) say,
+    /// looked up with `var`
+    pub fn from_env(var: impl Fn(&str) -> Option<String>) -> Self {
+        let term = var("TERM").unwrap_or_default();
+        let program = var("TERM_PROGRAM").unwrap_or_default();
+        let kitty = term == "xterm-kitty" || var("KITTY_WINDOW_ID").is_some();
+        let modern = kitty
+            || matches!(
+                program.as_str(),
+                "WezTerm" | "ghostty" | "iTerm.app" | "vscode"
+            )
+            || var("WT_SESSION").is_some();
+        // VTE added OSC 8 in 0.50
+        let vte = var("VTE_VERSION")
+            .and_then(|v| v.parse::<u32>().ok())
+            .is_some_and(|v| v >= 5000);
+
+        Self {
+            truecolor: matches!(var("COLORTERM").as_deref(), Some("truecolor" | "24bit"))
+                || term.ends_with("-direct")
+                || modern,
+            sixel: false,
+            kitty_graphics: kitty || matches!(program.as_str(), "WezTerm" | "ghostty"),
+            synchronized_update: false,
+            hyperlinks: modern || vte || term.starts_with("foot"),
+        }
+    }
+
+    /// Add what the terminal said in reply to the probe's queries
+    pub fn apply_replies(&mut self, reply: &[u8]) {
+        if let Some(attributes) = parse_da1(reply) {
+            // Attribute 4 is sixel graphics
+            self.sixel |= attributes.contains(&4);
+        }
+        self.kitty_graphics |= contains(reply, b"\x1b_Gi=31;OK");
+        // DECRPM: 1 (set) or 2 (reset) means the mode is known
+        self.synchronized_update |=
+            contains(reply, b"\x1b[?2026;1$y") || contains(reply, b"\x1b[?2026;2$y");
+        self.truecolor |= contains(reply, b"\x1bP1+r524742") || contains(reply, b"\x1bP1+r5463");
+    }
+
+    /// Add the outer terminal's features as tmux lists them (`client_termfeatures`)
+    pub fn apply_tmux_features(&mut self, features: &str) {
+        for feature in features.split(',').map(str::trim) {
+            match feature {
+                "RGB" => self.truecolor = true,
+                "sixel" => self.sixel = true,
+                "hyperlinks" => self.hyperlinks = true,
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Work out what the terminal supports from the environment, its terminfo entry and
+/// its answers to a round of queries
+///
+/// Must be called in raw mode, before anything else reads stdin. Terminals ignore the
+/// queries they don't know, and all of them answer the DA1 query sent last, so the probe
+/// takes one round trip; if nothing answers within `timeout`, only the environment and
+/// terminfo count.
+///
+/// Inside tmux, the graphics and terminfo queries are passed through to the outer
+/// terminal and tmux is asked which of its features it knows about, since tmux itself
+/// reports a plain 256-color terminal.
+pub fn detect_capabilities(timeout: Duration, passthrough: Passthrough) -> Capabilities {
+    let mut caps = Capabilities::from_env(|name| std::env::var(name).ok());
+    if let Ok(term) = std::env::var("TERM") {
+        caps.truecolor |= terminfo_has(&term, &["RGB", "Tc"]);
+    }
+    if passthrough.is_tmux() {
+        if let Some(features) = tmux_client_features() {
+            caps.apply_tmux_features(&features);
+        }
+    }
+    if let Some(reply) = query_replies(timeout, passthrough) {
+        caps.apply_replies(&reply);
+    }
+    caps
+}
+
+/// Send all the queries and read the replies, up to and including the DA1 reply
+fn query_replies(timeout: Duration, passthrough: Passthrough) -> Option<Vec<u8>> {
+    let mut stdout = std::io::stdout();
+    // Synchronized output and DA1 are for tmux, when there is one, to answer
+    stdout.write_all(&passthrough.wrap(KITTY_QUERY)).ok()?;
+    stdout.write_all(&passthrough.wrap(XTGETTCAP_QUERY)).ok()?;
+    for query in [SYNC_QUERY, DA1_QUERY] {
+        stdout.write_all(query).ok()?;
+    }
+    stdout.flush().ok()?;
+    read_response(timeout, |reply| {
+        reply.ends_with(b"c") && parse_da1(reply).is_some()
+    })
+}
+
+/// The attributes in a DA1 reply, `ESC [ ? 62 ; 4 ; 22 c`, anywhere in `reply`
+fn parse_da1(reply: &[u8]) -> Option<Vec<u32>> {
+    let text = String::from_utf8_lossy(reply);
+    let start = text.rfind("\x1b[?")? + 3;
+    let body = text[start..].strip_suffix('c')?;
+    body.split(';').map(|a| a.parse().ok()).collect()
+}
+
+fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+    haystack
+        .windows(needle.len())
+        .any(|window| window == needle)
+}
+
+/// Whether the compiled terminfo entry for `term` lists any of the extended
+/// capabilities `names`
+///
+/// Extended capability names are stored as NUL-terminated strings, so this looks for
+/// them by name rather than parsing the whole entry.
+fn terminfo_has(term: &str, names: &[&str]) -> bool {
+    let Some(first) = term.chars().next() else {
+        return false;
+    };
+    let mut dirs: Vec<PathBuf> = Vec::new();
+    dirs.extend(std::env::var_os("TERMINFO").map(PathBuf::from));
+    dirs.extend(std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".terminfo")));
+    if let Some(list) = std::env::var_os("TERMINFO_DIRS") {
+        dirs.extend(std::env::split_paths(&list));
+    }
+    dirs.extend(["/etc/terminfo", "/lib/terminfo", "/usr/share/terminfo"].map(PathBuf::from));
+
+    // Linux files entries under their first letter, macOS under its hex code
+    let entry = dirs.iter().find_map(|dir| {
+        [first.to_string(), format!("{:x}", first as u32)]
+            .iter()
+            .find_map(|sub| std::fs::read(dir.join(sub).join(term)).ok())
+    });
+    entry.is_some_and(|entry| {
+        names
+            .iter()
+            .any(|name| contains(&entry, format!("\0{}\0", name).as_bytes()))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn env(vars: &[(&str, &str)]) -> Capabilities {
+        let vars: HashMap<String, String> = vars
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        Capabilities::from_env(|name| vars.get(name).cloned())
+    }
+
+    #[test]
+    fn test_from_env() {
+        assert_eq!(env(&[("TERM", "xterm-256color")]), Capabilities::default());
+        assert!(env(&[("COLORTERM", "truecolor")]).truecolor);
+        assert!(env(&[("TERM", "xterm-direct")]).truecolor);
+
+        let kitty = env(&[("TERM", "xterm-kitty")]);
+        assert!(kitty.truecolor && kitty.kitty_graphics && kitty.hyperlinks);
+        assert!(env(&[("VTE_VERSION", "6800")]).hyperlinks);
+        assert!(!env(&[("VTE_VERSION", "4800")]).hyperlinks);
+    }
+
+    #[test]
+    fn test_apply_replies() {
+        let mut caps = Capabilities::default();
+        caps.apply_replies(
+            b"\x1b_Gi=31;OK\x1b\\\x1b[?2026;2$y\x1bP1+r524742=382F382F38\x1b\\\x1b[?62;4;22c",
+        );
+        assert_eq!(
+            caps,
+            Capabilities {
+                truecolor: true,
+                sixel: true,
+                kitty_graphics: true,
+                synchronized_update: true,
+                hyperlinks: false,
+            }
+        );
+    }
+
+    #[test]
+    fn test_unsupported_replies() {
+        let mut caps = Capabilities::default();
+        // Mode 2026 unknown (0), capability not found (0+r)
+        caps.apply_replies(b"\x1b[?2026;0$y\x1bP0+r524742\x1b\\\x1b[?1;2c");
+        assert_eq!(caps, Capabilities::default());
+    }
+
+    #[test]
+    fn test_apply_tmux_features() {
+        let mut caps = Capabilities::default();
+        caps.apply_tmux_features("256,RGB,title,hyperlinks,sync");
+        assert!(caps.truecolor && caps.hyperlinks);
+        assert!(!caps.sixel && !caps.synchronized_update);
+    }
+
+    #[test]
+    fn test_parse_da1() {
+        assert_eq!(parse_da1(b"\x1b[?62;4;22c"), Some(vec![62, 4, 22]));
+        assert_eq!(parse_da1(b"\x1b[?2026;2$y"), None);
+        assert_eq!(parse_da1(b"\x1b[?62;4"), None);
+    }
+}
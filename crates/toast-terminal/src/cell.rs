@@ -0,0 +1,70 @@
+use crossterm::terminal;
+use std::io::Write;
+use std::time::Duration;
+
+use crate::background::read_response;
+
+/// Measure the terminal's cell size in pixels, as (width, height)
+///
+/// Uses the pixel size the kernel reports for the tty (TIOCGWINSZ) and, if that's
+/// unset, asks the terminal itself (CSI 14 t). Must be called in raw mode, before
+/// anything else reads stdin. Returns `None` if neither reports a size.
+pub fn query_cell_size(cols: usize, rows: usize, timeout: Duration) -> Option<(f32, f32)> {
+    let (width, height) = match terminal::window_size() {
+        Ok(size) if size.width > 0 && size.height > 0 => (size.width as u32, size.height as u32),
+        _ => query_text_area_pixels(timeout)?,
+    };
+    cell_size(width, height, cols, rows)
+}
+
+/// Ask for the text area size in pixels: `CSI 14 t`, answered with `CSI 4 ; height ; width t`
+fn query_text_area_pixels(timeout: Duration) -> Option<(u32, u32)> {
+    let mut stdout = std::io::stdout();
+    stdout.write_all(b"\x1b[14t").ok()?;
+    stdout.flush().ok()?;
+
+    let response = read_response(timeout, |reply| reply.ends_with(b"t"))?;
+    parse_text_area_pixels(&response)
+}
+
+/// Parse `ESC [ 4 ; height ; width t` into (width, height)
+pub fn parse_text_area_pixels(response: &[u8]) -> Option<(u32, u32)> {
+    let text = std::str::from_utf8(response).ok()?;
+    let body = text.strip_prefix("\x1b[4;")?.strip_suffix('t')?;
+    let (height, width) = body.split_once(';')?;
+    let (width, height) = (width.parse().ok()?, height.parse().ok()?);
+    (width > 0 && height > 0).then_some((width, height))
+}
+
+/// Cell width and height for a text area of `width`x`height` pixels holding `cols`x`rows` cells
+fn cell_size(width: u32, height: u32, cols: usize, rows: usize) -> Option<(f32, f32)> {
+    if cols == 0 || rows == 0 {
+        return None;
+    }
+    let cell_width = width as f32 / cols as f32;
+    let cell_height = height as f32 / rows as f32;
+    (cell_width >= 1.0 && cell_height >= 1.0).then_some((cell_width, cell_height))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_text_area_pixels() {
+        assert_eq!(
+            parse_text_area_pixels(b"\x1b[4;1080;1920t"),
+            Some((1920, 1080))
+        );
+        assert_eq!(parse_text_area_pixels(b"\x1b[4;0;0t"), None);
+        assert_eq!(parse_text_area_pixels(b"\x1b[8;50;200t"), None);
+    }
+
+    #[test]
+    fn test_cell_size() {
+        // 200x50 cells of 9x21 pixels
+        assert_eq!(cell_size(1800, 1050, 200, 50), Some((9.0, 21.0)));
+        assert_eq!(cell_size(100, 100, 200, 50), None);
+        assert_eq!(cell_size(1800, 1050, 0, 50), None);
+    }
+}
@@ -0,0 +1,171 @@
+use toast_core::{AnsiColor, TerminalCell, TerminalFrame};
+
+use crate::layout::Layout;
+
+/// Where something drawn sits in the stack, bottom first
+///
+/// Overlays and the cursor are drawn in page coordinates, before the page is placed in
+/// the terminal; bars and dialogs in terminal coordinates, around and over it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Layer {
+    /// Drawn over the page: OCR text, the debug grid, notices and badges
+    Overlay,
+    /// The mouse cursor
+    Cursor,
+    /// The tab and status bars
+    Bars,
+    /// The command line and anything else that takes input
+    Dialog,
+}
+
+impl Layer {
+    fn on_page(self) -> bool {
+        self < Layer::Bars
+    }
+}
+
+/// A fixed picture of cells, drawn over a frame with its blank cells left see-through
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Sprite {
+    width: usize,
+    height: usize,
+    cells: Vec<Option<TerminalCell>>,
+}
+
+impl Sprite {
+    /// A sprite drawn as `rows` of characters in `fg` on `bg`; spaces are transparent
+    pub fn from_rows(rows: &[&str], fg: AnsiColor, bg: AnsiColor) -> Self {
+        let width = rows
+            .iter()
+            .map(|row| row.chars().count())
+            .max()
+            .unwrap_or(0);
+        let mut cells = vec![None; width * rows.len()];
+        for (y, row) in rows.iter().enumerate() {
+            for (x, character) in row.chars().enumerate() {
+                if character != ' ' {
+                    cells[y * width + x] = Some(TerminalCell {
+                        character,
+                        foreground: fg,
+                        background: bg,
+                        cluster: None,
+                    });
+                }
+            }
+        }
+        Self {
+            width,
+            height: rows.len(),
+            cells,
+        }
+    }
+
+    /// Draw with the top-left corner at (`x`, `y`), clipped to the frame
+    pub fn draw(&self, frame: &mut TerminalFrame, x: usize, y: usize) {
+        for row in 0..self.height {
+            for column in 0..self.width {
+                let Some(cell) = &self.cells[row * self.width + column] else {
+                    continue;
+                };
+                if x + column < frame.width && y + row < frame.height {
+                    frame.set(x + column, y + row, cell.clone());
+                }
+            }
+        }
+    }
+}
+
+type Draw<'a> = Box<dyn FnOnce(&mut TerminalFrame) + 'a>;
+
+/// Stacks everything drawn over and around a page frame in [`Layer`] order
+///
+/// Things on the same layer are drawn in the order they were added.
+pub struct Compositor<'a> {
+    layout: Layout,
+    layers: Vec<(Layer, Draw<'a>)>,
+}
+
+impl<'a> Compositor<'a> {
+    /// Compose pages into terminal frames laid out by `layout`
+    pub fn new(layout: Layout) -> Self {
+        Self {
+            layout,
+            layers: Vec::new(),
+        }
+    }
+
+    /// Draw on `layer` with `draw`
+    pub fn add(&mut self, layer: Layer, draw: impl FnOnce(&mut TerminalFrame) + 'a) {
+        self.layers.push((layer, Box::new(draw)));
+    }
+
+    /// Draw `sprite` on `layer` at (`x`, `y`)
+    pub fn add_sprite(&mut self, layer: Layer, sprite: &'a Sprite, x: usize, y: usize) {
+        self.add(layer, move |frame| sprite.draw(frame, x, y));
+    }
+
+    /// The terminal frame: `page` with every layer drawn on it
+    pub fn compose(mut self, mut page: TerminalFrame) -> TerminalFrame {
+        self.layers.sort_by_key(|(layer, _)| *layer);
+        let split = self.layers.partition_point(|(layer, _)| layer.on_page());
+        let screen = self.layers.split_off(split);
+
+        for (_, draw) in self.layers {
+            draw(&mut page);
+        }
+        let mut frame = self.layout.compose(page);
+        for (_, draw) in screen {
+            draw(&mut frame);
+        }
+        frame
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(frame: &TerminalFrame, y: usize) -> String {
+        (0..frame.width)
+            .map(|x| frame.get(x, y).unwrap().character)
+            .collect()
+    }
+
+    #[test]
+    fn test_sprite_is_clipped_and_see_through() {
+        let mut frame = TerminalFrame::new(3, 2);
+        frame.draw_text(0, 0, "...", AnsiColor(7), AnsiColor(0));
+        frame.draw_text(0, 1, "...", AnsiColor(7), AnsiColor(0));
+        let sprite = Sprite::from_rows(&["a b", "cd"], AnsiColor(1), AnsiColor(2));
+        sprite.draw(&mut frame, 1, 0);
+        assert_eq!(row(&frame, 0), ".a.");
+        assert_eq!(row(&frame, 1), ".cd");
+    }
+
+    #[test]
+    fn test_layers_stack_in_order() {
+        let cursor = Sprite::from_rows(&["^"], AnsiColor(0), AnsiColor(0));
+        let mut compositor = Compositor::new(Layout::default());
+        compositor.add_sprite(Layer::Cursor, &cursor, 1, 0);
+        // Added later but drawn under the cursor
+        compositor.add(Layer::Overlay, |frame| {
+            frame.draw_text(0, 0, "ab", AnsiColor(7), AnsiColor(0))
+        });
+        let frame = compositor.compose(TerminalFrame::new(3, 1));
+        assert_eq!(row(&frame, 0), "a^ ");
+    }
+
+    #[test]
+    fn test_bars_are_drawn_around_the_page() {
+        let mut compositor = Compositor::new(Layout { top: 1, bottom: 0 });
+        compositor.add(Layer::Bars, |frame| {
+            frame.draw_text(0, 0, "tab", AnsiColor(7), AnsiColor(0))
+        });
+        compositor.add(Layer::Overlay, |frame| {
+            frame.draw_text(0, 0, "pg", AnsiColor(7), AnsiColor(0))
+        });
+        let frame = compositor.compose(TerminalFrame::new(3, 1));
+        assert_eq!(row(&frame, 0), "tab");
+        assert_eq!(row(&frame, 1), "pg ");
+    }
+}
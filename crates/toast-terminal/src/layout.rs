@@ -0,0 +1,113 @@
+use toast_core::{CellRect, ScrollMotion, TerminalFrame};
+
+/// How the terminal's rows are shared between the page and the UI bars around it
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Layout {
+    /// Rows above the page (the tab bar)
+    pub top: usize,
+    /// Rows below the page (the status bar)
+    pub bottom: usize,
+}
+
+impl Layout {
+    /// Rows left for the page in a terminal `height` rows tall, at least one
+    pub fn page_height(&self, height: usize) -> usize {
+        height.saturating_sub(self.top + self.bottom).max(1)
+    }
+
+    /// The page row under terminal row `y`, or `None` if `y` is over a bar
+    pub fn page_row(&self, y: usize, page_height: usize) -> Option<usize> {
+        y.checked_sub(self.top).filter(|&row| row < page_height)
+    }
+
+    /// The page frame with blank rows around it for the bars to be drawn on
+    ///
+    /// The page's damage and scroll motion move down with it, so differential rendering
+    /// still only touches what changed in the page.
+    pub fn compose(&self, page: TerminalFrame) -> TerminalFrame {
+        if *self == Layout::default() {
+            return page;
+        }
+        let (width, page_height) = (page.width, page.height);
+        let mut frame = TerminalFrame::new(width, self.top + page_height + self.bottom);
+        let start = self.top * width;
+        frame.cells[start..start + page.cells.len()].clone_from_slice(&page.cells);
+
+        frame.damage = page.damage.map(|damage| {
+            damage
+                .into_iter()
+                .map(|rect| CellRect {
+                    y: rect.y + self.top,
+                    ..rect
+                })
+                .collect()
+        });
+        frame.scroll = page.scroll.map(|scroll| ScrollMotion {
+            top: scroll.top + self.top,
+            bottom: scroll.bottom + self.top,
+            ..scroll
+        });
+        frame.metadata = page.metadata;
+        frame
+    }
+}
+
+/// Draw a bar across row `y` with `draw`, recording the row as one damage rectangle
+/// rather than one per cell drawn
+pub(crate) fn draw_row(frame: &mut TerminalFrame, y: usize, draw: impl FnOnce(&mut TerminalFrame)) {
+    let damage = frame.damage.take();
+    draw(frame);
+    frame.damage = damage.map(|mut damage| {
+        damage.push(CellRect::new(0, y, frame.width, 1));
+        damage
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use toast_core::AnsiColor;
+
+    const LAYOUT: Layout = Layout { top: 1, bottom: 1 };
+
+    #[test]
+    fn test_page_height() {
+        assert_eq!(LAYOUT.page_height(24), 22);
+        assert_eq!(LAYOUT.page_height(2), 1);
+        assert_eq!(Layout::default().page_height(24), 24);
+    }
+
+    #[test]
+    fn test_page_row() {
+        assert_eq!(LAYOUT.page_row(0, 22), None);
+        assert_eq!(LAYOUT.page_row(1, 22), Some(0));
+        assert_eq!(LAYOUT.page_row(22, 22), Some(21));
+        assert_eq!(LAYOUT.page_row(23, 22), None);
+    }
+
+    #[test]
+    fn test_compose_moves_page_down() {
+        let mut page = TerminalFrame::new(4, 2);
+        page.draw_text(0, 0, "top", AnsiColor(1), AnsiColor(2));
+        page.damage = Some(vec![CellRect::new(1, 1, 2, 1)]);
+        page.scroll = Some(ScrollMotion {
+            top: 0,
+            bottom: 2,
+            rows: 1,
+        });
+
+        let frame = LAYOUT.compose(page);
+        assert_eq!(frame.height, 4);
+        assert_eq!(frame.get(0, 1).unwrap().character, 't');
+        assert_eq!(frame.get(0, 0).unwrap().character, ' ');
+        assert_eq!(frame.damage, Some(vec![CellRect::new(1, 2, 2, 1)]));
+        assert_eq!(
+            frame.scroll,
+            Some(ScrollMotion {
+                top: 1,
+                bottom: 3,
+                rows: 1
+            })
+        );
+    }
+}
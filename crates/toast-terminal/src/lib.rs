@@ -1,7 +1,30 @@
+mod background;
+mod bandwidth;
+mod caps;
+mod cell;
+mod compositor;
 mod dimensions;
+mod layout;
+mod output;
 mod renderer;
+mod scrollbar;
+mod status;
+mod tabs;
 mod terminal;
+mod tmux;
 
+pub use background::{parse_osc11, query_background, QUERY_TIMEOUT};
+pub use caps::{detect_capabilities, Capabilities};
+pub use cell::{parse_text_area_pixels, query_cell_size};
+pub use compositor::{Compositor, Layer, Sprite};
 pub use dimensions::get_terminal_size;
+pub use layout::Layout;
+pub use output::Output;
 pub use renderer::Renderer;
-pub use terminal::Terminal;
+pub use scrollbar::Scrollbar;
+pub use status::{StatusBar, STATUS_ROWS};
+pub use tabs::{TabBar, TabLabel, TAB_ROWS};
+#[cfg(unix)]
+pub use terminal::SUSPEND_SIGNAL;
+pub use terminal::{suspend, Terminal};
+pub use tmux::{tmux_client_features, Passthrough};
@@ -0,0 +1,72 @@
+use anyhow::{Context, Result};
+use std::fs::{File, OpenOptions};
+use std::io::{stdout, Stdout, Write};
+use std::path::Path;
+
+/// Where a [`Renderer`](crate::Renderer) writes frames
+pub enum Output {
+    /// The controlling terminal
+    Stdout(Stdout),
+    /// A file or FIFO, for another process to display
+    File(File),
+}
+
+impl Output {
+    pub fn stdout() -> Self {
+        Output::Stdout(stdout())
+    }
+
+    /// Write to the file or FIFO at `path`, truncating a file
+    ///
+    /// Opening a FIFO blocks until something opens it for reading.
+    pub fn file(path: &Path) -> Result<Self> {
+        let file = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(path)
+            .with_context(|| format!("Failed to open {} for rendering", path.display()))?;
+        Ok(Output::File(file))
+    }
+}
+
+impl Write for Output {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self {
+            Output::Stdout(out) => out.write(buf),
+            Output::File(file) => file.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self {
+            Output::Stdout(out) => out.flush(),
+            Output::File(file) => file.flush(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Renderer;
+    use toast_core::{AnsiColor, TerminalFrame};
+
+    #[test]
+    fn test_renders_to_file() {
+        let dir = std::env::temp_dir().join(format!("toast-output-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("frames");
+        std::fs::write(&path, "stale").unwrap();
+
+        let mut frame = TerminalFrame::new(2, 1);
+        frame.draw_text(0, 0, "ok", AnsiColor(7), AnsiColor(0));
+        let renderer = Renderer::with_writer(Output::file(&path).unwrap());
+        let expected = renderer.render_to_string(&frame).unwrap();
+        renderer.render(frame).unwrap();
+        drop(renderer);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), expected);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,59 +1,298 @@
 use anyhow::Result;
-use crossterm::{cursor, execute, queue};
+use crossterm::terminal::{Clear, ClearType};
+use crossterm::{cursor, queue};
 use parking_lot::Mutex;
-use std::io::{stdout, Write};
-use toast_core::TerminalFrame;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::io::{stdout, Stdout, Write};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+use toast_core::{AnsiColor, CellRect, ScrollMotion, TerminalCell, TerminalFrame};
+
+use crate::bandwidth::BandwidthLimit;
+use crate::tmux::Passthrough;
+
+/// Upper half block, as produced by the half-block converter
+const UPPER_HALF_BLOCK: char = '▀';
+
+/// Lower half block, used to move a transparent top half into the background
+const LOWER_HALF_BLOCK: char = '▄';
+
+/// Unchanged cells between two changed ones on a row that are written again rather
+/// than moved over: a cursor move costs more bytes than a few cells in the current colors
+const MAX_REWRITTEN_GAP: usize = 3;
 
 /// Double-buffered terminal renderer with differential updates
-pub struct Renderer {
+///
+/// Escape sequences go to stdout unless another writer is given with
+/// [`Renderer::with_writer`], e.g. a file, a socket or a buffer in tests.
+pub struct Renderer<W: Write = Stdout> {
     front_buffer: Mutex<Option<TerminalFrame>>,
+    /// Hash of the front buffer's size and cells
+    front_hash: Mutex<Option<u64>>,
+    out: Mutex<W>,
+    /// Color drawn with the terminal's own default background instead of a palette index
+    transparent: Option<AnsiColor>,
+    /// Bracket each frame in synchronized output (mode 2026) so it appears all at once
+    synchronized: bool,
+    /// Rows drawn on in inline mode, as (first row, row count); the whole screen otherwise
+    region: Option<(u16, u16)>,
+    /// Wraps sequences that must reach the terminal outside tmux
+    passthrough: Passthrough,
+    /// Window title last set
+    title: Mutex<Option<String>>,
+    /// Bytes of frames written so far
+    written: AtomicU64,
+    /// Output cap for slow links
+    limit: Option<Mutex<BandwidthLimit>>,
+    /// Latest frame held back by the output cap
+    pending: Mutex<Option<TerminalFrame>>,
 }
 
 impl Renderer {
+    /// Render to stdout
     pub fn new() -> Self {
+        Self::with_writer(stdout())
+    }
+}
+
+impl<W: Write> Renderer<W> {
+    /// Render to `out`
+    pub fn with_writer(out: W) -> Self {
         Self {
             front_buffer: Mutex::new(None),
+            front_hash: Mutex::new(None),
+            out: Mutex::new(out),
+            transparent: None,
+            synchronized: false,
+            region: None,
+            passthrough: Passthrough::default(),
+            title: Mutex::new(None),
+            written: AtomicU64::new(0),
+            limit: None,
+            pending: Mutex::new(None),
         }
     }
 
+    /// Stop rendering and take back the writer
+    pub fn into_writer(self) -> W {
+        self.out.into_inner()
+    }
+
+    /// Draw `color` with the terminal's default background (SGR 49)
+    ///
+    /// Pass the palette color closest to the terminal's background so page margins blend
+    /// with transparent or tinted terminal themes instead of showing a solid color.
+    pub fn with_transparent_background(mut self, color: AnsiColor) -> Self {
+        self.transparent = Some(color);
+        self
+    }
+
+    /// Have the terminal hold each frame until it is complete, so large updates don't tear
+    ///
+    /// Only for terminals that support synchronized output (see
+    /// [`Capabilities::synchronized_update`](crate::Capabilities::synchronized_update)).
+    pub fn with_synchronized_update(mut self, enabled: bool) -> Self {
+        self.synchronized = enabled;
+        self
+    }
+
+    /// Draw only on `rows` rows starting at row `top`, leaving the rest of the screen
+    /// alone, as for [`Terminal::inline`](crate::Terminal::inline)
+    pub fn with_region(mut self, top: u16, rows: u16) -> Self {
+        self.region = Some((top, rows));
+        self
+    }
+
+    /// Send the window title past tmux, so it names the outer terminal's tab or window
+    /// rather than the pane
+    pub fn with_passthrough(mut self, passthrough: Passthrough) -> Self {
+        self.passthrough = passthrough;
+        self
+    }
+
+    /// Write at most about `bytes_per_sec`, holding frames back while over it
+    ///
+    /// For slow SSH or mosh links, where a burst of full repaints queues up seconds of
+    /// output: the frame rate drops instead. Call [`render_pending`](Self::render_pending)
+    /// after [`pending_wait`](Self::pending_wait) so the last frame held back still shows.
+    pub fn with_bandwidth_limit(mut self, bytes_per_sec: u64) -> Self {
+        self.limit = Some(Mutex::new(BandwidthLimit::new(
+            bytes_per_sec,
+            Instant::now(),
+        )));
+        self
+    }
+
+    /// Set the terminal window title (OSC 2), unless it is already `title`
+    pub fn set_title(&self, title: &str) -> Result<()> {
+        let mut current = self.title.lock();
+        if current.as_deref() == Some(title) {
+            return Ok(());
+        }
+        // A control character would end the sequence early
+        let clean: String = title.chars().filter(|c| !c.is_control()).collect();
+        let sequence = format!("\x1b]2;{}\x1b\\", clean);
+        let mut out = self.out.lock();
+        out.write_all(&self.passthrough.wrap(sequence.as_bytes()))?;
+        out.flush()?;
+        *current = Some(title.to_string());
+        Ok(())
+    }
+
+    /// Total size of the frames rendered so far, escape sequences included
+    pub fn bytes_written(&self) -> u64 {
+        self.written.load(Ordering::Relaxed)
+    }
+
+    /// Ring the terminal bell
+    pub fn bell(&self) -> Result<()> {
+        let mut out = self.out.lock();
+        out.write_all(b"\x07")?;
+        out.flush()?;
+        Ok(())
+    }
+
+    /// Write `text` in bold white at cell (`x`, `y`), outside of any frame, e.g. a loading
+    /// message before the first one
+    pub fn message(&self, x: usize, y: usize, text: &str) -> Result<()> {
+        let mut out = self.out.lock();
+        write!(out, "\x1b[{};{}H\x1b[1;37m{}\x1b[0m", y + 1, x + 1, text)?;
+        out.flush()?;
+        Ok(())
+    }
+
     /// Render a frame to the terminal using differential updates
+    ///
+    /// Over the bandwidth limit, the frame is held back instead, taking the place of any
+    /// held back before it.
     pub fn render(&self, new_frame: TerminalFrame) -> Result<()> {
+        if let Some(limit) = &self.limit {
+            if !limit.lock().admit(Instant::now()) {
+                let mut pending = self.pending.lock();
+                // What changed in the frame it replaces still has to be drawn
+                *pending = Some(match pending.take() {
+                    Some(older) => TerminalFrame::coalesce(older, new_frame),
+                    None => new_frame,
+                });
+                return Ok(());
+            }
+        }
+        *self.pending.lock() = None;
+
         let mut front = self.front_buffer.lock();
-        let mut stdout = stdout();
+        let mut front_hash = self.front_hash.lock();
+        // A static page renders to the same frame over and over: skip diffing it
+        let hash = frame_hash(&new_frame);
+        if front.is_some() && *front_hash == Some(hash) {
+            *front = Some(new_frame);
+            return Ok(());
+        }
+
+        let mut out = self.out.lock();
+        let before = self.bytes_written();
+        let mut out = Counted {
+            inner: &mut *out,
+            count: &self.written,
+        };
+        self.write_frame(&mut out, front.as_ref(), &new_frame)?;
+        out.flush()?;
+        *front = Some(new_frame);
+        *front_hash = Some(hash);
+        if let Some(limit) = &self.limit {
+            limit.lock().spend(self.bytes_written() - before);
+        }
+
+        Ok(())
+    }
+
+    /// How long until the frame held back by the bandwidth limit can be rendered, if
+    /// there is one
+    pub fn pending_wait(&self) -> Option<Duration> {
+        let limit = self.limit.as_ref()?;
+        self.pending.lock().as_ref()?;
+        Some(limit.lock().wait(Instant::now()))
+    }
 
-        match front.as_ref() {
+    /// Render the frame held back by the bandwidth limit, if there is one and the limit
+    /// now allows it
+    pub fn render_pending(&self) -> Result<()> {
+        let pending = self.pending.lock().take();
+        match pending {
+            Some(frame) => self.render(frame),
+            None => Ok(()),
+        }
+    }
+
+    /// What [`render`](Self::render) writes for `frame` as the first frame, without
+    /// writing it anywhere
+    ///
+    /// For snapshot tests and debugging: the output is exact, escape sequences included.
+    pub fn render_to_string(&self, frame: &TerminalFrame) -> Result<String> {
+        let mut out = Vec::new();
+        self.write_frame(&mut out, None, frame)?;
+        Ok(String::from_utf8(out)?)
+    }
+
+    /// What [`render`](Self::render) writes to update the terminal from `old` to `new`,
+    /// without writing it anywhere
+    pub fn render_diff_to_string(
+        &self,
+        old: &TerminalFrame,
+        new: &TerminalFrame,
+    ) -> Result<String> {
+        let mut out = Vec::new();
+        self.write_frame(&mut out, Some(old), new)?;
+        Ok(String::from_utf8(out)?)
+    }
+
+    /// Write `new_frame`, in full or as the changes from `front`
+    fn write_frame(
+        &self,
+        w: &mut impl Write,
+        front: Option<&TerminalFrame>,
+        new_frame: &TerminalFrame,
+    ) -> Result<()> {
+        if self.synchronized {
+            w.write_all(b"\x1b[?2026h")?;
+        }
+
+        match front {
             None => {
                 // First frame - render everything
-                self.render_full(&mut stdout, &new_frame)?;
+                self.render_full(w, new_frame)?;
             }
             Some(old_frame) => {
                 // Differential update - only render changed cells
-                self.render_diff(&mut stdout, old_frame, &new_frame)?;
+                self.render_diff(w, old_frame, new_frame)?;
             }
         }
+        self.park(w)?;
 
-        stdout.flush()?;
-        *front = Some(new_frame);
+        if self.synchronized {
+            w.write_all(b"\x1b[?2026l")?;
+        }
+        Ok(())
+    }
 
+    /// Leave the terminal between frames with default colors and the cursor hidden in
+    /// the top-left corner, so a terminal that shows the cursor anyway, or a recorder that
+    /// picks up trailing attributes, leaves no artifacts on the page
+    fn park(&self, w: &mut impl Write) -> Result<()> {
+        write!(w, "\x1b[0m")?;
+        queue!(w, cursor::MoveTo(0, self.top()), cursor::Hide)?;
         Ok(())
     }
 
     /// Render entire frame (used for first frame)
-    fn render_full<W: Write>(&self, w: &mut W, frame: &TerminalFrame) -> Result<()> {
+    fn render_full(&self, w: &mut impl Write, frame: &TerminalFrame) -> Result<()> {
         // Move to top-left
-        queue!(w, cursor::MoveTo(0, 0))?;
+        queue!(w, cursor::MoveTo(0, self.top()))?;
 
+        let mut sgr = Sgr::default();
         for y in 0..frame.height {
             for x in 0..frame.width {
                 if let Some(cell) = frame.get(x, y) {
-                    // Write ANSI escape codes for colors and character
-                    write!(
-                        w,
-                        "\x1b[38;5;{}m\x1b[48;5;{}m{}",
-                        cell.foreground.as_u8(),
-                        cell.background.as_u8(),
-                        cell.character
-                    )?;
+                    self.write_cell(w, cell, span_at(frame, x, y), &mut sgr)?;
                 }
             }
             // Don't add newline on last row to avoid scrolling
@@ -62,72 +301,709 @@ impl Renderer {
             }
         }
 
-        // Reset colors
-        write!(w, "\x1b[0m")?;
-
         Ok(())
     }
 
     /// Render only changed cells (differential update)
-    fn render_diff<W: Write>(
+    fn render_diff(
         &self,
-        w: &mut W,
+        w: &mut impl Write,
         old_frame: &TerminalFrame,
         new_frame: &TerminalFrame,
     ) -> Result<()> {
-        // Ensure frames are same size
+        // A different size means the terminal was resized and may have reflowed the old
+        // contents; start over on a blank screen
         if old_frame.width != new_frame.width || old_frame.height != new_frame.height {
+            self.clear_to(w)?;
             return self.render_full(w, new_frame);
         }
 
-        let mut last_x = None;
-        let mut last_y = None;
+        // Let the terminal shift the rows the page scrolled, so only the rows the scroll
+        // exposes and whatever else changed are drawn
+        let shifted;
+        let (old_frame, exposed) = match new_frame
+            .scroll
+            .filter(|&motion| worth_shifting(old_frame, new_frame, motion))
+        {
+            Some(motion) => {
+                self.write_scroll(w, motion)?;
+                shifted = shift_rows(old_frame, motion);
+                (&shifted, exposed_rows(motion))
+            }
+            None => (old_frame, 0..0),
+        };
+
+        // With damage lists on both frames, only cells in either list can differ:
+        // the new frame's changes, plus overlays drawn on the old frame that must be erased
+        // A scrolled frame's damage is relative to the shifted rows, and overlays drawn on
+        // the old frame moved with them, so all cells are compared
+        let candidates = match (&old_frame.damage, &new_frame.damage) {
+            _ if new_frame.scroll.is_some() => None,
+            (Some(old_damage), Some(new_damage)) => {
+                let mut mask = vec![false; new_frame.width * new_frame.height];
+                for rect in old_damage.iter().chain(new_damage) {
+                    mark_rect(&mut mask, new_frame.width, new_frame.height, rect);
+                }
+                Some(mask)
+            }
+            _ => None,
+        };
+
+        let mut sgr = Sgr::default();
+        // Where the terminal cursor is after the last cell written
+        let mut at: Option<(usize, usize)> = None;
 
         for y in 0..new_frame.height {
+            // Only update cells that changed
+            let mut dirty: Vec<bool> = (0..new_frame.width)
+                .map(|x| {
+                    let candidate = candidates
+                        .as_ref()
+                        .is_none_or(|mask| mask[y * new_frame.width + x]);
+                    candidate
+                        && (exposed.contains(&y) || old_frame.get(x, y) != new_frame.get(x, y))
+                })
+                .collect();
+            // A double-width character and the cell it covers are written together
             for x in 0..new_frame.width {
-                let old_cell = old_frame.get(x, y);
-                let new_cell = new_frame.get(x, y);
-
-                // Only update if cell changed
-                if old_cell != new_cell {
-                    if let Some(cell) = new_cell {
-                        // Move cursor if necessary
-                        if last_x != Some(x) || last_y != Some(y) {
-                            queue!(w, cursor::MoveTo(x as u16, y as u16))?;
-                        }
+                if !dirty[x] {
+                    continue;
+                }
+                let covered = |x: usize| {
+                    [old_frame, new_frame]
+                        .iter()
+                        .any(|frame| frame.get(x, y).is_some_and(TerminalCell::is_continuation))
+                };
+                if covered(x) && x > 0 {
+                    dirty[x - 1] = true;
+                }
+                if covered(x + 1) {
+                    dirty[x + 1] = true;
+                }
+            }
 
-                        // Write cell
-                        write!(
-                            w,
-                            "\x1b[38;5;{}m\x1b[48;5;{}m{}",
-                            cell.foreground.as_u8(),
-                            cell.background.as_u8(),
-                            cell.character
-                        )?;
-
-                        last_x = Some(x + 1);
-                        last_y = Some(y);
+            for x in (0..new_frame.width).filter(|&x| dirty[x]) {
+                let Some(cell) = new_frame.get(x, y) else {
+                    continue;
+                };
+                let span = span_at(new_frame, x, y);
+                // Written with the character before it
+                if span == Span::Covered {
+                    continue;
+                }
+
+                match at {
+                    Some((ax, ay)) if ay == y && ax == x => {}
+                    // Across a short gap, writing the unchanged cells again is cheaper
+                    // than moving the cursor over them
+                    Some((ax, ay)) if ay == y && ax < x && x - ax <= MAX_REWRITTEN_GAP => {
+                        for gap_x in ax..x {
+                            if let Some(gap_cell) = new_frame.get(gap_x, y) {
+                                let gap_span = span_at(new_frame, gap_x, y);
+                                self.write_cell(w, gap_cell, gap_span, &mut sgr)?;
+                            }
+                        }
                     }
+                    _ => queue!(w, cursor::MoveTo(x as u16, self.top() + y as u16))?,
                 }
+
+                self.write_cell(w, cell, span, &mut sgr)?;
+                at = Some((x + span.columns(), y));
             }
         }
 
-        // Reset colors
-        write!(w, "\x1b[0m")?;
+        Ok(())
+    }
 
+    /// Write a cell's character, preceded by whatever color changes it needs
+    fn write_cell(
+        &self,
+        w: &mut impl Write,
+        cell: &TerminalCell,
+        span: Span,
+        sgr: &mut Sgr,
+    ) -> Result<()> {
+        if span == Span::Covered {
+            return Ok(());
+        }
+        let foreground = Some(cell.foreground.as_u8());
+        let background = Background::Palette(cell.background.as_u8());
+        let Some(transparent) = self.transparent else {
+            sgr.set(w, foreground, background)?;
+            write_symbol(w, cell, span)?;
+            return Ok(());
+        };
+
+        let fg_clear = cell.foreground == transparent;
+        let bg_clear = cell.background == transparent;
+        match (fg_clear, bg_clear) {
+            // Solid block (or blank) in the background color: let the terminal show through
+            (true, true) => {
+                sgr.set(w, None, Background::Default)?;
+                write!(w, "{:1$}", "", span.columns())?;
+            }
+            // Transparent bottom half: keep the glyph, default background
+            (false, true) => {
+                sgr.set(w, foreground, Background::Default)?;
+                write_symbol(w, cell, span)?;
+            }
+            // Transparent top half: draw the bottom half as a lower block instead
+            (true, false) if cell.character == UPPER_HALF_BLOCK => {
+                sgr.set(w, Some(cell.background.as_u8()), Background::Default)?;
+                write!(w, "{}", LOWER_HALF_BLOCK)?;
+            }
+            _ => {
+                sgr.set(w, foreground, background)?;
+                write_symbol(w, cell, span)?;
+            }
+        }
         Ok(())
     }
 
-    /// Clear the screen
+    /// Forget what the terminal shows, so the next frame is drawn in full
+    pub fn invalidate(&self) {
+        *self.front_buffer.lock() = None;
+        *self.front_hash.lock() = None;
+    }
+
+    /// Clear the screen, or in inline mode the rows drawn on
     pub fn clear(&self) -> Result<()> {
-        let mut stdout = stdout();
-        execute!(stdout, crossterm::terminal::Clear(crossterm::terminal::ClearType::All))?;
+        let mut out = self.out.lock();
+        self.clear_to(&mut *out)?;
+        out.flush()?;
+        Ok(())
+    }
+
+    fn clear_to(&self, w: &mut impl Write) -> Result<()> {
+        match self.region {
+            Some((top, rows)) => {
+                for row in top..top + rows {
+                    queue!(w, cursor::MoveTo(0, row), Clear(ClearType::CurrentLine))?;
+                }
+            }
+            None => queue!(w, Clear(ClearType::All))?,
+        }
+        Ok(())
+    }
+
+    /// Move the rows of `motion`'s band on the terminal (DECSTBM with SU or SD), leaving
+    /// blank rows where the content moved away from
+    fn write_scroll(&self, w: &mut impl Write, motion: ScrollMotion) -> Result<()> {
+        let top = self.top() as usize;
+        write!(w, "\x1b[{};{}r", top + motion.top + 1, top + motion.bottom)?;
+        if motion.rows > 0 {
+            write!(w, "\x1b[{}S", motion.rows)?;
+        } else {
+            write!(w, "\x1b[{}T", -motion.rows)?;
+        }
+        // Back to the whole screen, which also homes the cursor
+        write!(w, "\x1b[r")?;
+        Ok(())
+    }
+
+    /// First terminal row drawn on
+    fn top(&self) -> u16 {
+        self.region.map_or(0, |(top, _)| top)
+    }
+}
+
+/// Hash of what a frame shows: its size and cells, not its damage or metadata
+fn frame_hash(frame: &TerminalFrame) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    (frame.width, frame.height).hash(&mut hasher);
+    frame.cells.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// How a cell goes on screen
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Span {
+    /// A character one column wide
+    Narrow,
+    /// A double-width character, covering the next cell too
+    Wide,
+    /// The cell after a double-width character: nothing is written for it
+    Covered,
+    /// A space in place of what can't be shown: half of a double-width character whose
+    /// other half was drawn over, or a character that takes no columns
+    Blank,
+}
+
+impl Span {
+    /// Columns the terminal cursor moves when the cell is written
+    fn columns(self) -> usize {
+        match self {
+            Span::Narrow | Span::Blank => 1,
+            Span::Wide => 2,
+            Span::Covered => 0,
+        }
+    }
+}
+
+/// How the cell at (`x`, `y`) goes on screen
+fn span_at(frame: &TerminalFrame, x: usize, y: usize) -> Span {
+    let Some(cell) = frame.get(x, y) else {
+        return Span::Blank;
+    };
+    if cell.is_continuation() {
+        let wide_before = x
+            .checked_sub(1)
+            .and_then(|left| frame.get(left, y))
+            .is_some_and(|left| left.width() == 2);
+        return if wide_before {
+            Span::Covered
+        } else {
+            Span::Blank
+        };
+    }
+    match cell.width() {
+        1 => Span::Narrow,
+        2 if frame
+            .get(x + 1, y)
+            .is_some_and(TerminalCell::is_continuation) =>
+        {
+            Span::Wide
+        }
+        _ => Span::Blank,
+    }
+}
+
+/// Write a cell's grapheme cluster, or a space for a blank
+fn write_symbol(w: &mut impl Write, cell: &TerminalCell, span: Span) -> Result<()> {
+    if span == Span::Blank {
+        write!(w, " ")?;
+        return Ok(());
+    }
+    write!(w, "{}", cell.character)?;
+    if let Some(rest) = &cell.cluster {
+        w.write_all(rest.as_bytes())?;
+    }
+    Ok(())
+}
+
+/// Adds up the bytes written through it
+struct Counted<'a, W: Write> {
+    inner: &'a mut W,
+    count: &'a AtomicU64,
+}
+
+impl<W: Write> Write for Counted<'_, W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.count.fetch_add(written as u64, Ordering::Relaxed);
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Background color of a cell as written to the terminal
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Background {
+    Palette(u8),
+    /// The terminal's own default background (SGR 49)
+    Default,
+}
+
+/// The colors last set on the terminal while rendering a frame, so runs of cells in the
+/// same colors share one SGR sequence
+#[derive(Debug, Default)]
+struct Sgr {
+    foreground: Option<u8>,
+    background: Option<Background>,
+}
+
+impl Sgr {
+    /// Switch to `foreground` (`None` when the cell shows no glyph and any will do) and
+    /// `background`, writing only the parameters that change
+    fn set(
+        &mut self,
+        w: &mut impl Write,
+        foreground: Option<u8>,
+        background: Background,
+    ) -> Result<()> {
+        let mut params = Vec::new();
+        if foreground.is_some() && foreground != self.foreground {
+            params.push(format!("38;5;{}", foreground.unwrap_or_default()));
+            self.foreground = foreground;
+        }
+        if Some(background) != self.background {
+            params.push(match background {
+                Background::Palette(color) => format!("48;5;{}", color),
+                Background::Default => "49".to_string(),
+            });
+            self.background = Some(background);
+        }
+        if !params.is_empty() {
+            write!(w, "\x1b[{}m", params.join(";"))?;
+        }
         Ok(())
     }
 }
 
+/// Whether shifting by `motion` leaves more of `old` where `new` has it than not
+/// shifting; a motion that doesn't fit the frame on screen, as after frames were dropped
+/// or blended in between, is left alone
+fn worth_shifting(old: &TerminalFrame, new: &TerminalFrame, motion: ScrollMotion) -> bool {
+    let band = motion.top..motion.bottom;
+    if motion.bottom > new.height || motion.rows == 0 || motion.rows.unsigned_abs() >= band.len() {
+        return false;
+    }
+    // Cells of the band in place after moving the content up by `shift` rows
+    let matching = |shift: isize| -> usize {
+        band.clone()
+            .filter_map(|y| {
+                let from = usize::try_from(y as isize + shift).ok()?;
+                band.contains(&from).then_some((y, from))
+            })
+            .map(|(y, from)| {
+                row(old, from)
+                    .iter()
+                    .zip(row(new, y))
+                    .filter(|(a, b)| a == b)
+                    .count()
+            })
+            .sum()
+    };
+    matching(motion.rows) > matching(0)
+}
+
+fn row(frame: &TerminalFrame, y: usize) -> &[TerminalCell] {
+    &frame.cells[y * frame.width..(y + 1) * frame.width]
+}
+
+/// `frame` as the terminal shows it after [`Renderer::write_scroll`]; the exposed rows
+/// are left as they were and must be drawn
+fn shift_rows(frame: &TerminalFrame, motion: ScrollMotion) -> TerminalFrame {
+    let mut shifted = frame.clone();
+    let width = frame.width;
+    for y in motion.top..motion.bottom {
+        let Some(from) = usize::try_from(y as isize + motion.rows)
+            .ok()
+            .filter(|from| (motion.top..motion.bottom).contains(from))
+        else {
+            continue;
+        };
+        shifted.cells[y * width..(y + 1) * width].clone_from_slice(row(frame, from));
+    }
+    shifted
+}
+
+/// Rows a scroll by `motion` leaves blank
+fn exposed_rows(motion: ScrollMotion) -> std::ops::Range<usize> {
+    let distance = motion.rows.unsigned_abs();
+    if motion.rows > 0 {
+        motion.bottom - distance..motion.bottom
+    } else {
+        motion.top..motion.top + distance
+    }
+}
+
+/// Mark the cells of a rectangle in a row-major mask, clipped to the frame
+fn mark_rect(mask: &mut [bool], width: usize, height: usize, rect: &CellRect) {
+    for y in rect.y..(rect.y + rect.height).min(height) {
+        for x in rect.x..(rect.x + rect.width).min(width) {
+            mask[y * width + x] = true;
+        }
+    }
+}
+
 impl Default for Renderer {
     fn default() -> Self {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cell(character: char, fg: u8, bg: u8) -> TerminalCell {
+        TerminalCell {
+            character,
+            foreground: AnsiColor(fg),
+            background: AnsiColor(bg),
+            cluster: None,
+        }
+    }
+
+    fn output(renderer: Renderer<Vec<u8>>) -> String {
+        String::from_utf8(renderer.into_writer()).unwrap()
+    }
+
+    #[test]
+    fn test_renders_into_writer() {
+        let renderer = Renderer::with_writer(Vec::new());
+        let mut frame = TerminalFrame::new(2, 1);
+        frame.set(0, 0, cell('a', 1, 2));
+        frame.set(1, 0, cell('b', 3, 4));
+        renderer.render(frame).unwrap();
+        assert_eq!(
+            output(renderer),
+            "\x1b[1;1H\x1b[38;5;1;48;5;2ma\x1b[38;5;3;48;5;4mb\x1b[0m\x1b[1;1H\x1b[?25l"
+        );
+    }
+
+    #[test]
+    fn test_diff_writes_only_changed_cells() {
+        let renderer = Renderer::with_writer(Vec::new());
+        let frame = TerminalFrame::new(3, 2);
+        renderer.render(frame.clone()).unwrap();
+        renderer.out.lock().clear();
+
+        let mut changed = frame;
+        changed.set(2, 1, cell('x', 5, 6));
+        renderer.render(changed).unwrap();
+        assert_eq!(
+            output(renderer),
+            "\x1b[2;3H\x1b[38;5;5;48;5;6mx\x1b[0m\x1b[1;1H\x1b[?25l"
+        );
+    }
+
+    #[test]
+    fn test_wide_characters() {
+        let renderer = Renderer::with_writer(Vec::new());
+        let mut frame = TerminalFrame::new(4, 1);
+        frame.draw_text(0, 0, "中e\u{301}", AnsiColor(7), AnsiColor(0));
+        assert_eq!(
+            renderer.render_to_string(&frame).unwrap(),
+            "\x1b[1;1H\x1b[38;5;7;48;5;0m中e\u{301}\x1b[38;5;0m \x1b[0m\x1b[1;1H\x1b[?25l"
+        );
+
+        // Drawing over half of the wide character blanks the other half
+        let mut changed = frame.clone();
+        changed.set(1, 0, cell('x', 7, 0));
+        assert_eq!(
+            renderer.render_diff_to_string(&frame, &changed).unwrap(),
+            "\x1b[1;1H\x1b[38;5;7;48;5;0m x\x1b[0m\x1b[1;1H\x1b[?25l"
+        );
+    }
+
+    #[test]
+    fn test_render_to_string_matches_render() {
+        let renderer = Renderer::with_writer(Vec::new()).with_synchronized_update(true);
+        let mut frame = TerminalFrame::new(2, 2);
+        frame.draw_text(0, 0, "ab", AnsiColor(7), AnsiColor(0));
+        let mut changed = frame.clone();
+        changed.set(1, 1, cell('x', 5, 6));
+
+        let first = renderer.render_to_string(&frame).unwrap();
+        let diff = renderer.render_diff_to_string(&frame, &changed).unwrap();
+        assert_eq!(
+            diff,
+            "\x1b[?2026h\x1b[2;2H\x1b[38;5;5;48;5;6mx\x1b[0m\x1b[1;1H\x1b[?25l\x1b[?2026l"
+        );
+        // Nothing was written or remembered
+        assert_eq!(renderer.bytes_written(), 0);
+        assert!(renderer.front_buffer.lock().is_none());
+
+        renderer.render(frame).unwrap();
+        renderer.render(changed).unwrap();
+        assert_eq!(output(renderer), first + &diff);
+    }
+
+    #[test]
+    fn test_counts_bytes_written() {
+        let renderer = Renderer::with_writer(Vec::new());
+        renderer.render(TerminalFrame::new(2, 1)).unwrap();
+        renderer.set_title("toast").unwrap();
+        let written = renderer.bytes_written();
+        assert_eq!(written as usize, output(renderer).find("\x1b]2;").unwrap());
+    }
+
+    #[test]
+    fn test_identical_frame_writes_nothing() {
+        let renderer = Renderer::with_writer(Vec::new()).with_synchronized_update(true);
+        let mut frame = TerminalFrame::new(3, 2);
+        frame.draw_text(0, 0, "abc", AnsiColor(7), AnsiColor(0));
+        renderer.render(frame.clone()).unwrap();
+        let written = renderer.bytes_written();
+
+        // Even a frame that claims everything changed
+        frame.damage = None;
+        renderer.render(frame.clone()).unwrap();
+        assert_eq!(renderer.bytes_written(), written);
+
+        frame.set(0, 1, cell('x', 7, 0));
+        renderer.render(frame).unwrap();
+        assert!(renderer.bytes_written() > written);
+    }
+
+    #[test]
+    fn test_bandwidth_limit_holds_frames_back() {
+        let renderer = Renderer::with_writer(Vec::new()).with_bandwidth_limit(1);
+        let mut frame = TerminalFrame::new(3, 1);
+        renderer.render(frame.clone()).unwrap();
+        let written = renderer.bytes_written();
+        assert_eq!(renderer.pending_wait(), None);
+
+        // Far over a byte a second: held back until the debt is paid off
+        frame.draw_text(0, 0, "abc", AnsiColor(7), AnsiColor(0));
+        renderer.render(frame).unwrap();
+        assert_eq!(renderer.bytes_written(), written);
+        assert!(renderer.pending_wait().unwrap() > Duration::from_secs(10));
+        renderer.render_pending().unwrap();
+        assert_eq!(renderer.bytes_written(), written);
+        assert!(renderer.pending.lock().is_some());
+    }
+
+    #[test]
+    fn test_frames_held_back_keep_their_damage() {
+        let renderer = Renderer::with_writer(Vec::new()).with_bandwidth_limit(1);
+        let mut frame = TerminalFrame::new(3, 2);
+        renderer.render(frame.clone()).unwrap();
+
+        frame.damage = Some(Vec::new());
+        frame.set(0, 0, cell('a', 7, 0));
+        renderer.render(frame.clone()).unwrap();
+        frame.damage = Some(Vec::new());
+        frame.set(2, 1, cell('b', 7, 0));
+        renderer.render(frame).unwrap();
+
+        let pending = renderer.pending.lock().take().unwrap();
+        assert_eq!(
+            pending.damage,
+            Some(vec![CellRect::new(0, 0, 1, 1), CellRect::new(2, 1, 1, 1)])
+        );
+    }
+
+    #[test]
+    fn test_message_goes_to_the_writer() {
+        let renderer = Renderer::with_writer(Vec::new());
+        renderer.message(4, 2, "toasting...").unwrap();
+        assert_eq!(output(renderer), "\x1b[3;5H\x1b[1;37mtoasting...\x1b[0m");
+    }
+
+    #[test]
+    fn test_repeated_colors_share_one_sgr() {
+        let renderer = Renderer::with_writer(Vec::new());
+        let mut frame = TerminalFrame::new(3, 1);
+        frame.draw_text(0, 0, "abc", AnsiColor(7), AnsiColor(0));
+        frame.set(2, 0, cell('c', 7, 1));
+        renderer.render(frame).unwrap();
+        assert_eq!(
+            output(renderer),
+            "\x1b[1;1H\x1b[38;5;7;48;5;0mab\x1b[48;5;1mc\x1b[0m\x1b[1;1H\x1b[?25l"
+        );
+    }
+
+    #[test]
+    fn test_short_gaps_are_rewritten() {
+        let renderer = Renderer::with_writer(Vec::new());
+        let frame = TerminalFrame::new(10, 1);
+        renderer.render(frame.clone()).unwrap();
+        renderer.out.lock().clear();
+
+        let mut changed = frame;
+        changed.set(0, 0, cell('a', 0, 0));
+        changed.set(3, 0, cell('b', 0, 0));
+        changed.set(9, 0, cell('c', 0, 0));
+        renderer.render(changed).unwrap();
+        assert_eq!(
+            output(renderer),
+            "\x1b[1;1H\x1b[38;5;0;48;5;0ma  b\x1b[1;10Hc\x1b[0m\x1b[1;1H\x1b[?25l"
+        );
+    }
+
+    #[test]
+    fn test_transparent_blank_skips_foreground() {
+        let renderer = Renderer::with_writer(Vec::new()).with_transparent_background(AnsiColor(0));
+        let mut frame = TerminalFrame::new(2, 1);
+        frame.set(1, 0, cell('x', 3, 0));
+        renderer.render(frame).unwrap();
+        assert_eq!(
+            output(renderer),
+            "\x1b[1;1H\x1b[49m \x1b[38;5;3mx\x1b[0m\x1b[1;1H\x1b[?25l"
+        );
+    }
+
+    #[test]
+    fn test_synchronized_update_brackets_frame() {
+        let renderer = Renderer::with_writer(Vec::new()).with_synchronized_update(true);
+        renderer.render(TerminalFrame::new(1, 1)).unwrap();
+        let output = output(renderer);
+        assert!(output.starts_with("\x1b[?2026h\x1b[1;1H"));
+        assert!(output.ends_with("\x1b[0m\x1b[1;1H\x1b[?25l\x1b[?2026l"));
+    }
+
+    #[test]
+    fn test_region_offsets_rows() {
+        let renderer = Renderer::with_writer(Vec::new()).with_region(5, 2);
+        let frame = TerminalFrame::new(2, 2);
+        renderer.render(frame.clone()).unwrap();
+        renderer.out.lock().clear();
+
+        let mut changed = frame;
+        changed.set(1, 1, cell('x', 0, 0));
+        renderer.render(changed).unwrap();
+        assert_eq!(
+            output(renderer),
+            "\x1b[7;2H\x1b[38;5;0;48;5;0mx\x1b[0m\x1b[6;1H\x1b[?25l"
+        );
+    }
+
+    /// A frame whose rows are told apart by their text
+    fn page(lines: &[&str]) -> TerminalFrame {
+        let mut frame = TerminalFrame::new(lines[0].len(), lines.len());
+        for (y, line) in lines.iter().enumerate() {
+            frame.draw_text(0, y, line, AnsiColor(7), AnsiColor(0));
+        }
+        frame.damage = None;
+        frame
+    }
+
+    #[test]
+    fn test_scroll_shifts_rows() {
+        let renderer = Renderer::with_writer(Vec::new()).with_region(2, 4);
+        let old = page(&["head", "aaaa", "bbbb", "cccc"]);
+        let mut new = page(&["head", "bbbb", "cccc", "dddd"]);
+        new.scroll = Some(ScrollMotion {
+            top: 1,
+            bottom: 4,
+            rows: 1,
+        });
+        assert_eq!(
+            renderer.render_diff_to_string(&old, &new).unwrap(),
+            "\x1b[4;6r\x1b[1S\x1b[r\x1b[6;1H\x1b[38;5;7;48;5;0mdddd\x1b[0m\x1b[3;1H\x1b[?25l"
+        );
+
+        // Scrolling back up exposes the top of the band
+        let mut back = old.clone();
+        back.scroll = Some(ScrollMotion {
+            top: 1,
+            bottom: 4,
+            rows: -1,
+        });
+        assert!(renderer
+            .render_diff_to_string(&new, &back)
+            .unwrap()
+            .starts_with("\x1b[4;6r\x1b[1T\x1b[r\x1b[4;1H\x1b[38;5;7;48;5;0maaaa"));
+    }
+
+    #[test]
+    fn test_stale_scroll_is_ignored() {
+        let renderer = Renderer::with_writer(Vec::new());
+        let old = page(&["aaaa", "bbbb", "cccc"]);
+        // Claims a scroll, but the rows on screen don't line up with it
+        let mut new = page(&["aaaa", "bbbb", "xxxx"]);
+        new.scroll = Some(ScrollMotion {
+            top: 0,
+            bottom: 3,
+            rows: 1,
+        });
+        assert_eq!(
+            renderer.render_diff_to_string(&old, &new).unwrap(),
+            "\x1b[3;1H\x1b[38;5;7;48;5;0mxxxx\x1b[0m\x1b[1;1H\x1b[?25l"
+        );
+    }
+
+    #[test]
+    fn test_title_is_set_once() {
+        let renderer = Renderer::with_writer(Vec::new());
+        renderer.set_title("Example\n - toast").unwrap();
+        renderer.set_title("Example\n - toast").unwrap();
+        assert_eq!(output(renderer), "\x1b]2;Example - toast\x1b\\");
+    }
+}
@@ -0,0 +1,129 @@
+use toast_core::{AnsiColor, FrameMetadata, TerminalCell, TerminalFrame};
+
+/// Scrollbar track and thumb: thin lines in gray, over the page's own background
+const TRACK: char = '│';
+const THUMB: char = '┃';
+const TRACK_FG: AnsiColor = AnsiColor(240);
+const THUMB_FG: AnsiColor = AnsiColor(252);
+
+/// Where the viewport is in a page taller than it, drawn down the rightmost column
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Scrollbar {
+    /// Vertical scroll offset in CSS pixels
+    pub offset: f64,
+    /// Viewport height in CSS pixels
+    pub viewport: f64,
+    /// Document height in CSS pixels
+    pub document: f64,
+}
+
+impl Scrollbar {
+    /// The scrollbar for the page a frame shows, if it is known to scroll
+    pub fn from_metadata(metadata: &FrameMetadata) -> Option<Self> {
+        let viewport = metadata.viewport?.height as f64;
+        let document = metadata.document_height? as f64;
+        (document > viewport && viewport > 0.0).then_some(Self {
+            offset: metadata.scroll.1,
+            viewport,
+            document,
+        })
+    }
+
+    /// First row and number of rows of the thumb on a track `rows` tall
+    fn thumb(&self, rows: usize) -> (usize, usize) {
+        let length =
+            ((rows as f64 * self.viewport / self.document).round() as usize).clamp(1, rows);
+        let travel = (self.offset / (self.document - self.viewport)).clamp(0.0, 1.0);
+        let top = ((rows - length) as f64 * travel).round() as usize;
+        (top, length)
+    }
+
+    /// Draw down the rightmost column of a page frame
+    pub fn draw(&self, frame: &mut TerminalFrame) {
+        let Some(x) = frame.width.checked_sub(1) else {
+            return;
+        };
+        if frame.height == 0 {
+            return;
+        }
+        let (top, length) = self.thumb(frame.height);
+        for y in 0..frame.height {
+            let Some(background) = frame.get(x, y).map(|cell| cell.background) else {
+                continue;
+            };
+            let (character, foreground) = if (top..top + length).contains(&y) {
+                (THUMB, THUMB_FG)
+            } else {
+                (TRACK, TRACK_FG)
+            };
+            frame.set(
+                x,
+                y,
+                TerminalCell {
+                    character,
+                    foreground,
+                    background,
+                    cluster: None,
+                },
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use toast_core::Dimensions;
+
+    fn scrollbar(offset: f64) -> Scrollbar {
+        Scrollbar {
+            offset,
+            viewport: 500.0,
+            document: 2000.0,
+        }
+    }
+
+    fn column(frame: &TerminalFrame) -> String {
+        (0..frame.height)
+            .map(|y| frame.get(frame.width - 1, y).unwrap().character)
+            .collect()
+    }
+
+    #[test]
+    fn test_thumb_follows_offset() {
+        assert_eq!(scrollbar(0.0).thumb(8), (0, 2));
+        assert_eq!(scrollbar(750.0).thumb(8), (3, 2));
+        assert_eq!(scrollbar(1500.0).thumb(8), (6, 2));
+        // Overscroll stays on the track
+        assert_eq!(scrollbar(1800.0).thumb(8), (6, 2));
+    }
+
+    #[test]
+    fn test_draws_rightmost_column() {
+        let mut frame = TerminalFrame::new(3, 4);
+        scrollbar(1500.0).draw(&mut frame);
+        assert_eq!(column(&frame), "│││┃");
+        assert_eq!(frame.get(1, 0).unwrap().character, ' ');
+    }
+
+    #[test]
+    fn test_only_for_pages_that_scroll() {
+        let mut metadata = FrameMetadata {
+            viewport: Some(Dimensions::new(800, 600)),
+            document_height: Some(600),
+            ..Default::default()
+        };
+        assert_eq!(Scrollbar::from_metadata(&metadata), None);
+
+        metadata.document_height = Some(1800);
+        metadata.scroll = (0.0, 300.0);
+        assert_eq!(
+            Scrollbar::from_metadata(&metadata),
+            Some(Scrollbar {
+                offset: 300.0,
+                viewport: 600.0,
+                document: 1800.0,
+            })
+        );
+    }
+}
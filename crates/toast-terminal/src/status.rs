@@ -0,0 +1,117 @@
+use toast_core::text::{fit, text_width};
+use toast_core::{AnsiColor, TerminalFrame};
+
+use crate::layout::draw_row;
+
+/// Terminal rows taken by the status bar
+pub const STATUS_ROWS: usize = 1;
+
+/// Status bar colors: light gray on dark gray, with modes picked out in black on white
+const FG: AnsiColor = AnsiColor(252);
+const BG: AnsiColor = AnsiColor(238);
+const MODE_FG: AnsiColor = AnsiColor(16);
+const MODE_BG: AnsiColor = AnsiColor(252);
+
+/// The row under the page: load state, title and URL on the left, modes on the right
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StatusBar {
+    pub url: String,
+    pub title: Option<String>,
+    pub loading: bool,
+    /// Short mode indicators, e.g. "PAUSED" or "ZOOM 2.0x"
+    pub modes: Vec<String>,
+    /// Draw the whole bar in the mode colors, to catch the eye
+    pub flash: bool,
+}
+
+impl StatusBar {
+    /// Draw the bar across row `y`, usually the row [`Layout::compose`] left below the page
+    ///
+    /// [`Layout::compose`]: crate::Layout::compose
+    pub fn draw(&self, frame: &mut TerminalFrame, y: usize) {
+        let width = frame.width;
+        let modes: String = self.modes.iter().map(|m| format!(" {} ", m)).collect();
+        let modes_width = text_width(&modes);
+
+        let state = if self.loading { "⟳" } else { " " };
+        let text = match &self.title {
+            Some(title) => format!(" {} {} — {}", state, title, self.url),
+            None => format!(" {} {}", state, self.url),
+        };
+        let room = width.saturating_sub(modes_width + 1);
+        let text = fit(&text, room);
+        let (fg, bg) = if self.flash {
+            (MODE_FG, MODE_BG)
+        } else {
+            (FG, BG)
+        };
+        draw_row(frame, y, |frame| {
+            frame.draw_text(0, y, &text, fg, bg);
+            frame.draw_text(room, y, " ", fg, bg);
+            frame.draw_text(room + 1, y, &modes, MODE_FG, MODE_BG);
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Layout;
+    use toast_core::CellRect;
+
+    fn row(frame: &TerminalFrame, y: usize) -> String {
+        (0..frame.width)
+            .map(|x| frame.get(x, y).unwrap().character)
+            .collect()
+    }
+
+    #[test]
+    fn test_status_row_below_page() {
+        let mut page = TerminalFrame::new(30, 4);
+        page.draw_text(0, 3, "bottom", FG, BG);
+        let status = StatusBar {
+            url: "https://example.com/".into(),
+            title: Some("Example".into()),
+            loading: true,
+            modes: vec!["PAUSED".into()],
+            flash: false,
+        };
+        let layout = Layout {
+            top: 0,
+            bottom: STATUS_ROWS,
+        };
+        let mut frame = layout.compose(page);
+        status.draw(&mut frame, 4);
+        assert_eq!(frame.height, 5);
+        assert!(row(&frame, 3).starts_with("bottom"));
+        assert_eq!(row(&frame, 4), " ⟳ Example — https:/…  PAUSED ");
+    }
+
+    #[test]
+    fn test_damage_includes_status_row() {
+        let mut frame = TerminalFrame::new(10, 3);
+        frame.damage = Some(Vec::new());
+        StatusBar::default().draw(&mut frame, 2);
+        assert_eq!(frame.damage, Some(vec![CellRect::new(0, 2, 10, 1)]));
+    }
+
+    #[test]
+    fn test_wide_title() {
+        let mut frame = TerminalFrame::new(16, 1);
+        let status = StatusBar {
+            url: "https://example.jp/".into(),
+            title: Some("日本語のページ".into()),
+            ..Default::default()
+        };
+        status.draw(&mut frame, 0);
+        // The title is cut at a character boundary and the row stays 16 columns wide
+        let text: String = frame
+            .cells
+            .iter()
+            .filter(|cell| !cell.is_continuation())
+            .map(|cell| cell.character)
+            .collect();
+        assert_eq!(text, "   日本語のペ…  ");
+        assert!(frame.cells[12].is_continuation());
+    }
+}
@@ -0,0 +1,170 @@
+use toast_core::text::{fit, truncate};
+use toast_core::{AnsiColor, TerminalFrame};
+
+use crate::layout::draw_row;
+
+/// Terminal rows taken by the tab bar
+pub const TAB_ROWS: usize = 1;
+
+/// Tabs share the row evenly, but never get wider or narrower than this
+const MAX_TAB_WIDTH: usize = 24;
+const MIN_TAB_WIDTH: usize = 8;
+
+/// Tab bar colors: gray on dark gray, with the active tab in black on light gray
+const FG: AnsiColor = AnsiColor(250);
+const BG: AnsiColor = AnsiColor(236);
+const ACTIVE_FG: AnsiColor = AnsiColor(16);
+const ACTIVE_BG: AnsiColor = AnsiColor(252);
+
+/// The row above the page: one labelled tab per open page, the active one marked
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TabBar {
+    pub tabs: Vec<TabLabel>,
+    /// Index of the tab being shown
+    pub active: usize,
+}
+
+/// What the tab bar shows for a tab
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TabLabel {
+    pub title: String,
+    /// The title changed in the background since the tab was last shown
+    pub retitled: bool,
+    /// The page is playing sound (or would be, when muted)
+    pub audible: bool,
+    pub muted: bool,
+}
+
+impl TabLabel {
+    /// The title, after a ♪ while the page plays sound or a × while the tab is muted
+    fn text(&self) -> String {
+        match (self.muted, self.audible) {
+            (true, _) => format!("× {}", self.title),
+            (false, true) => format!("♪ {}", self.title),
+            (false, false) => self.title.clone(),
+        }
+    }
+}
+
+impl From<&str> for TabLabel {
+    fn from(title: &str) -> Self {
+        Self {
+            title: title.to_string(),
+            ..Self::default()
+        }
+    }
+}
+
+impl TabBar {
+    /// Draw the bar across row `y`, usually the row [`Layout::compose`] left above the page
+    ///
+    /// When the tabs don't all fit, the bar scrolls to keep the active one in view.
+    ///
+    /// [`Layout::compose`]: crate::Layout::compose
+    pub fn draw(&self, frame: &mut TerminalFrame, y: usize) {
+        let width = frame.width;
+        let tab_width = (width / self.tabs.len().max(1)).clamp(MIN_TAB_WIDTH, MAX_TAB_WIDTH);
+        let visible = (width / tab_width).max(1);
+        let first = (self.active + 1).saturating_sub(visible);
+
+        draw_row(frame, y, |frame| {
+            frame.draw_text(0, y, &" ".repeat(width), FG, BG);
+            let tabs = self.tabs.iter().enumerate().skip(first).take(visible);
+            for (slot, (i, tab)) in tabs.enumerate() {
+                let (marker, fg, bg) = if i == self.active {
+                    ('•', ACTIVE_FG, ACTIVE_BG)
+                } else if tab.retitled {
+                    ('*', FG, BG)
+                } else {
+                    (' ', FG, BG)
+                };
+                // Leave the last cell of each tab as a gap before the next
+                let room = tab_width - 1;
+                let label = fit(
+                    &format!(" {} {}", marker, truncate(&tab.text(), room - 3)),
+                    room,
+                );
+                frame.draw_text(slot * tab_width, y, &label, fg, bg);
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use toast_core::CellRect;
+
+    fn row(frame: &TerminalFrame, y: usize) -> String {
+        (0..frame.width)
+            .map(|x| frame.get(x, y).unwrap().character)
+            .collect()
+    }
+
+    #[test]
+    fn test_tabs_share_the_row() {
+        let mut frame = TerminalFrame::new(30, 2);
+        frame.damage = Some(Vec::new());
+        let bar = TabBar {
+            tabs: vec!["Example Domain".into(), "Docs".into()],
+            active: 1,
+        };
+        bar.draw(&mut frame, 0);
+        assert_eq!(row(&frame, 0), "   Example Do…  • Docs        ");
+        assert_eq!(frame.get(17, 0).unwrap().background, ACTIVE_BG);
+        assert_eq!(frame.get(0, 0).unwrap().background, BG);
+        assert_eq!(frame.damage, Some(vec![CellRect::new(0, 0, 30, 1)]));
+    }
+
+    #[test]
+    fn test_active_tab_stays_in_view() {
+        let mut frame = TerminalFrame::new(16, 1);
+        let bar = TabBar {
+            tabs: (1..=5).map(|i| format!("t{}", i).as_str().into()).collect(),
+            active: 3,
+        };
+        bar.draw(&mut frame, 0);
+        assert_eq!(row(&frame, 0), "   t3    • t4   ");
+    }
+
+    #[test]
+    fn test_retitled_tab_is_marked() {
+        let mut frame = TerminalFrame::new(30, 1);
+        let bar = TabBar {
+            tabs: vec![
+                "Docs".into(),
+                TabLabel {
+                    title: "(2) Inbox".into(),
+                    retitled: true,
+                    ..TabLabel::default()
+                },
+            ],
+            active: 0,
+        };
+        bar.draw(&mut frame, 0);
+        assert_eq!(row(&frame, 0), " • Docs         * (2) Inbox   ");
+    }
+
+    #[test]
+    fn test_audio_indicators() {
+        let mut frame = TerminalFrame::new(30, 1);
+        let bar = TabBar {
+            tabs: vec![
+                TabLabel {
+                    title: "Radio".into(),
+                    audible: true,
+                    ..TabLabel::default()
+                },
+                TabLabel {
+                    title: "Video".into(),
+                    audible: true,
+                    muted: true,
+                    ..TabLabel::default()
+                },
+            ],
+            active: 0,
+        };
+        bar.draw(&mut frame, 0);
+        assert_eq!(row(&frame, 0), " • ♪ Radio        × Video     ");
+    }
+}
@@ -1,23 +1,34 @@
 use anyhow::Result;
 use crossterm::{
     cursor,
+    event::{DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture},
     execute,
     terminal::{self, EnterAlternateScreen, LeaveAlternateScreen},
 };
-use std::io::stdout;
+use std::io::{stdout, Write};
+
+use crate::tmux::Passthrough;
+
+/// XTWINOPS: push the window title onto the terminal's title stack, and pop it back
+const PUSH_TITLE: &[u8] = b"\x1b[22;2t";
+const POP_TITLE: &[u8] = b"\x1b[23;2t";
 
 /// Terminal controller - manages raw mode and alternate screen
 pub struct Terminal {
+    /// Rows drawn on in inline mode, as (first row, row count)
+    region: Option<(u16, u16)>,
     _guard: TerminalGuard,
 }
 
 impl Terminal {
-    /// Initialize the terminal in raw mode with alternate screen
+    /// Initialize the terminal in raw mode with alternate screen, mouse reporting and
+    /// bracketed paste
     pub fn new() -> Result<Self> {
         let mut stdout = stdout();
 
         // Enter alternate screen
         execute!(stdout, EnterAlternateScreen)?;
+        save_title(&mut stdout)?;
 
         // Enable raw mode
         terminal::enable_raw_mode()?;
@@ -25,26 +36,142 @@ impl Terminal {
         // Hide cursor
         execute!(stdout, cursor::Hide)?;
 
+        // Report mouse clicks, drags and movement as events
+        execute!(stdout, EnableMouseCapture)?;
+
+        // Report pasted text as one event rather than as typed keys
+        execute!(stdout, EnableBracketedPaste)?;
+
         Ok(Self {
-            _guard: TerminalGuard,
+            region: None,
+            _guard: TerminalGuard { region: None },
         })
     }
 
-    /// Get current terminal dimensions
+    /// Initialize the terminal for drawing in `rows` rows of the normal screen, below
+    /// the cursor, instead of on the alternate screen
+    ///
+    /// The screen scrolls up if there isn't room below the cursor. What was drawn stays
+    /// on screen after exit, with the cursor on the line below it.
+    pub fn inline(rows: u16) -> Result<Self> {
+        let mut stdout = stdout();
+        terminal::enable_raw_mode()?;
+        let mut guard = TerminalGuard { region: None };
+        save_title(&mut stdout)?;
+        let (_, height) = terminal::size()?;
+        let rows = rows.clamp(1, height.max(1));
+
+        // Make room by printing newlines, then find where the room starts
+        execute!(stdout, cursor::MoveToColumn(0))?;
+        stdout.write_all("\r\n".repeat(rows as usize - 1).as_bytes())?;
+        stdout.flush()?;
+        let (_, bottom) = cursor::position()?;
+        let region = (bottom + 1 - rows, rows);
+
+        execute!(
+            stdout,
+            cursor::Hide,
+            EnableMouseCapture,
+            EnableBracketedPaste
+        )?;
+        guard.region = Some(region);
+        Ok(Self {
+            region: Some(region),
+            _guard: guard,
+        })
+    }
+
+    /// Get current terminal dimensions (in inline mode, the width and the rows drawn on)
     pub fn size(&self) -> Result<(usize, usize)> {
         let (cols, rows) = terminal::size()?;
+        let rows = self
+            .region
+            .map_or(rows, |(_, region_rows)| region_rows.min(rows));
         Ok((cols as usize, rows as usize))
     }
+
+    /// First and number of rows drawn on in inline mode
+    pub fn region(&self) -> Option<(u16, u16)> {
+        self.region
+    }
 }
 
 /// RAII guard to ensure terminal cleanup on drop
-struct TerminalGuard;
+struct TerminalGuard {
+    region: Option<(u16, u16)>,
+}
 
 impl Drop for TerminalGuard {
     fn drop(&mut self) {
-        let mut stdout = stdout();
-        let _ = execute!(stdout, cursor::Show);
-        let _ = terminal::disable_raw_mode();
-        let _ = execute!(stdout, LeaveAlternateScreen);
+        restore(self.region);
+    }
+}
+
+/// Hand the terminal back as it was before toast set it up
+fn restore(region: Option<(u16, u16)>) {
+    let mut stdout = stdout();
+    let _ = execute!(stdout, DisableMouseCapture, DisableBracketedPaste);
+    let _ = execute!(stdout, cursor::Show);
+    let _ = terminal::disable_raw_mode();
+    let _ = stdout.write_all(&Passthrough::detect().wrap(POP_TITLE));
+    match region {
+        // Leave the last frame in place and continue below it
+        Some((top, rows)) => {
+            let _ = execute!(stdout, cursor::MoveTo(0, top + rows - 1));
+            let _ = stdout.write_all(b"\r\n");
+            let _ = stdout.flush();
+        }
+        None => {
+            let _ = execute!(stdout, LeaveAlternateScreen);
+        }
+    }
+}
+
+/// The signal `kill -TSTP` sends; toast catches it to go through [`suspend`], so the
+/// terminal isn't left in raw mode
+#[cfg(unix)]
+pub const SUSPEND_SIGNAL: i32 = libc::SIGTSTP;
+
+/// Stop toast as Ctrl+Z would outside raw mode, handing the terminal back to the shell,
+/// and set it up again once the shell resumes toast
+///
+/// `region` is [`Terminal::region`]; in inline mode the same rows are drawn on again.
+/// The shell may have drawn over the screen and the terminal may have been resized
+/// meanwhile, so everything should be repainted after this returns.
+#[cfg(unix)]
+pub fn suspend(region: Option<(u16, u16)>) -> Result<()> {
+    restore(region);
+    // SIGTSTP is caught to come through here (see SUSPEND_SIGNAL), so stop with SIGSTOP
+    // SAFETY: raise has no memory safety requirements; SIGSTOP stops the process until
+    // SIGCONT
+    if unsafe { libc::raise(libc::SIGSTOP) } != 0 {
+        return Err(std::io::Error::last_os_error().into());
     }
+
+    let mut stdout = stdout();
+    if region.is_none() {
+        execute!(stdout, EnterAlternateScreen)?;
+    }
+    save_title(&mut stdout)?;
+    terminal::enable_raw_mode()?;
+    execute!(
+        stdout,
+        cursor::Hide,
+        EnableMouseCapture,
+        EnableBracketedPaste
+    )?;
+    Ok(())
+}
+
+#[cfg(not(unix))]
+pub fn suspend(_region: Option<(u16, u16)>) -> Result<()> {
+    anyhow::bail!("Suspending is only supported on Unix")
+}
+
+/// Save the window title so that [`Renderer::set_title`](crate::Renderer::set_title)
+/// changes are undone on exit
+fn save_title(out: &mut impl Write) -> Result<()> {
+    out.write_all(&Passthrough::detect().wrap(PUSH_TITLE))?;
+    out.flush()?;
+    Ok(())
 }
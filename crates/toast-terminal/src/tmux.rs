@@ -0,0 +1,80 @@
+use std::borrow::Cow;
+use std::process::Command;
+
+/// Sends escape sequences past tmux to the terminal it runs in
+///
+/// tmux interprets what its panes write and drops sequences it doesn't know, including
+/// graphics and most queries. Wrapped in a DCS passthrough (`ESC P tmux; … ESC \`) they
+/// reach the outer terminal unchanged, as long as tmux has `allow-passthrough` on.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Passthrough {
+    tmux: bool,
+}
+
+impl Passthrough {
+    /// Wrap sequences if toast runs inside tmux (`TMUX` is set)
+    pub fn detect() -> Self {
+        Self {
+            tmux: std::env::var_os("TMUX").is_some_and(|value| !value.is_empty()),
+        }
+    }
+
+    /// Always wrap sequences for tmux
+    pub fn tmux() -> Self {
+        Self { tmux: true }
+    }
+
+    pub fn is_tmux(&self) -> bool {
+        self.tmux
+    }
+
+    /// `sequence` as it must be written to reach the outer terminal
+    pub fn wrap<'a>(&self, sequence: &'a [u8]) -> Cow<'a, [u8]> {
+        if !self.tmux {
+            return Cow::Borrowed(sequence);
+        }
+        // Escapes inside the passthrough are doubled so tmux doesn't end it early
+        let mut wrapped = b"\x1bPtmux;".to_vec();
+        for &byte in sequence {
+            if byte == 0x1b {
+                wrapped.push(0x1b);
+            }
+            wrapped.push(byte);
+        }
+        wrapped.extend_from_slice(b"\x1b\\");
+        Cow::Owned(wrapped)
+    }
+}
+
+/// Features tmux has detected or been told the attached client's terminal has, e.g.
+/// `256,RGB,sync,hyperlinks`
+///
+/// tmux reports its own, more limited, `TERM` and device attributes to programs in its
+/// panes; this asks tmux what the terminal outside supports instead. `None` outside tmux
+/// or with a tmux older than 3.2.
+pub fn tmux_client_features() -> Option<String> {
+    let output = Command::new("tmux")
+        .args(["display-message", "-p", "#{client_termfeatures}"])
+        .output()
+        .ok()?;
+    let features = String::from_utf8(output.stdout).ok()?;
+    let features = features.trim();
+    (output.status.success() && !features.is_empty()).then(|| features.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wrap_doubles_escapes() {
+        let wrapped = Passthrough::tmux().wrap(b"\x1b]2;title\x1b\\");
+        assert_eq!(&*wrapped, b"\x1bPtmux;\x1b\x1b]2;title\x1b\x1b\\\x1b\\");
+    }
+
+    #[test]
+    fn test_no_wrapping_outside_tmux() {
+        let sequence = b"\x1b[?2026h";
+        assert_eq!(&*Passthrough::default().wrap(sequence), sequence);
+    }
+}
@@ -0,0 +1,1278 @@
+use crossterm::event::{
+    Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers, MouseButton, MouseEvent, MouseEventKind,
+};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use toast_browser::{RedirectWatcher, ScreenshotStreamer};
+use toast_core::CursorPosition;
+use toast_render::{grid_viewport, RenderPipeline};
+use toast_terminal::{Layout, Output, Renderer, TAB_ROWS};
+use tokio::sync::{mpsc, Notify};
+use tokio::task::JoinHandle;
+use tracing::{error, info};
+
+use crate::app::NOTICE_DURATION;
+use crate::command::{Command, CommandLine, LineEdit, LineHistory, LineMode};
+use crate::forward::PageInput;
+use crate::latency::LatencyProbe;
+use crate::notice::Notice;
+use crate::placeholder::PendingNavigation;
+use crate::target;
+
+/// W/S scroll the page by this many CSS pixels
+const SCROLL_STEP: i32 = 400;
+
+/// A mouse wheel notch scrolls this many CSS pixels unless configured otherwise
+pub const DEFAULT_WHEEL_STEP: i32 = 100;
+
+/// Shift+arrow pans by this fraction of the visible region
+const PAN_STEP: f32 = 0.25;
+
+/// Something the user asked for, independent of the input that triggered it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Action {
+    Quit,
+    /// Stop toast and hand the terminal back to the shell until `fg`
+    Suspend,
+    /// Scroll the page vertically by this many CSS pixels
+    Scroll(i32),
+    /// The mouse wheel turned this many notches (down and right are positive)
+    Wheel {
+        dx: i32,
+        dy: i32,
+    },
+    /// Open the configured homepage
+    Home,
+    /// Go back in the tab's history
+    Back,
+    /// Go forward in the tab's history
+    Forward,
+    /// Load the page again
+    Reload,
+    /// Stop loading the page
+    Stop,
+    /// Open a new tab after the shown one
+    NewTab,
+    /// Close the shown tab
+    CloseTab,
+    /// Open the most recently closed tab again, scrolled where it was
+    ReopenTab,
+    /// Mute the shown tab, or unmute it
+    ToggleMute,
+    /// Show the tab this many tabs after the shown one (before it when negative)
+    SwitchTab(isize),
+    /// Start a latency measurement (only with `--latency-test`)
+    LatencyTest,
+    CycleColorFilter,
+    ZoomIn,
+    ZoomOut,
+    /// Pan the zoomed region by a fraction of its size
+    Pan {
+        dx: f32,
+        dy: f32,
+    },
+    /// Move the terminal cursor by this many cells
+    MoveCursor {
+        dx: i32,
+        dy: i32,
+    },
+    /// Click the page under the terminal cursor
+    Click,
+    /// The terminal mouse moved to a cell
+    PointAt {
+        x: usize,
+        y: usize,
+    },
+    /// The left mouse button went down over a cell
+    MouseDown {
+        x: usize,
+        y: usize,
+    },
+    /// The mouse moved to a cell with the left button down
+    MouseDrag {
+        x: usize,
+        y: usize,
+    },
+    /// The left mouse button came up over a cell
+    MouseUp {
+        x: usize,
+        y: usize,
+    },
+    /// Freeze or resume capture and display
+    TogglePause,
+    /// Send key presses to the page until [`Action::StopTyping`]
+    StartTyping,
+    /// Go back to key presses acting on toast
+    StopTyping,
+    /// Open the `:` command line
+    OpenCommandLine,
+    /// Open the URL line, empty or starting from the current page's URL
+    OpenUrl {
+        edit_current: bool,
+    },
+    /// Show or hide the cell grid debug overlay
+    ToggleGrid,
+    /// Show or hide the frame rate and latency stats overlay
+    ToggleStats,
+    /// The terminal now has this many cells
+    Resize {
+        width: usize,
+        height: usize,
+    },
+}
+
+/// Sets of key bindings, chosen with `keys` in the config file
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Keymap {
+    #[default]
+    Default,
+    /// Like browser extensions for vim users: j/k scroll, H/L go back and forward, J/K
+    /// switch tabs, x closes a tab and X reopens it; other keys act as by default
+    Vim,
+}
+
+impl std::str::FromStr for Keymap {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "default" => Ok(Keymap::Default),
+            "vim" => Ok(Keymap::Vim),
+            _ => anyhow::bail!("unknown key bindings '{}' (expected default or vim)", s),
+        }
+    }
+}
+
+impl Keymap {
+    /// Map a key event to the action it triggers with these bindings, if any
+    pub fn action_for_key(self, key: &KeyEvent) -> Option<Action> {
+        let plain = !key
+            .modifiers
+            .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT);
+        if self == Keymap::Vim && plain && key.kind == KeyEventKind::Press {
+            let action = match key.code {
+                KeyCode::Char('j') => Some(Action::Scroll(SCROLL_STEP)),
+                KeyCode::Char('k') => Some(Action::Scroll(-SCROLL_STEP)),
+                KeyCode::Char('H') => Some(Action::Back),
+                KeyCode::Char('L') => Some(Action::Forward),
+                KeyCode::Char('J') => Some(Action::SwitchTab(-1)),
+                KeyCode::Char('K') => Some(Action::SwitchTab(1)),
+                KeyCode::Char('x') => Some(Action::CloseTab),
+                KeyCode::Char('X') => Some(Action::ReopenTab),
+                _ => None,
+            };
+            if action.is_some() {
+                return action;
+            }
+        }
+        action_for_key(key)
+    }
+}
+
+/// Map a terminal event to the action it triggers with `keymap`, if any
+pub fn action_for_event(event: &Event, keymap: Keymap) -> Option<Action> {
+    match event {
+        Event::Key(key) => keymap.action_for_key(key),
+        Event::Mouse(mouse) => action_for_mouse(mouse),
+        Event::Resize(width, height) => Some(Action::Resize {
+            width: *width as usize,
+            height: *height as usize,
+        }),
+        _ => None,
+    }
+}
+
+/// Put pasted text in the input line if it's open, or else hand it back for the page while
+/// typing into it; with neither, the paste starts a URL to open
+fn paste(
+    line: &mut Option<CommandLine>,
+    typing: bool,
+    history: &LineHistory,
+    text: &str,
+) -> Option<PageInput> {
+    if line.is_none() && typing {
+        return Some(PageInput::Text(text.to_string()));
+    }
+    line.get_or_insert_with(|| CommandLine::new(LineMode::Url, history))
+        .paste(text);
+    None
+}
+
+/// Index of the tab `step` tabs after `active` of `count` (before it when negative),
+/// wrapping around
+fn neighbour(active: usize, count: usize, step: isize) -> usize {
+    (active as isize + step).rem_euclid(count.max(1) as isize) as usize
+}
+
+/// Map a key event to the action it triggers while typing into the page, if any; every
+/// other key goes to the page
+pub fn typing_action(key: &KeyEvent) -> Option<Action> {
+    if key.kind != KeyEventKind::Press || !key.modifiers.contains(KeyModifiers::CONTROL) {
+        return None;
+    }
+    match key.code {
+        KeyCode::Char('c') => Some(Action::Quit),
+        KeyCode::Char('z') => Some(Action::Suspend),
+        KeyCode::Char('g') => Some(Action::StopTyping),
+        _ => None,
+    }
+}
+
+/// Map a key event to the action it triggers, if any
+pub fn action_for_key(key: &KeyEvent) -> Option<Action> {
+    // Only key presses act, not releases or repeats
+    if key.kind != KeyEventKind::Press {
+        return None;
+    }
+
+    let shift = key.modifiers.contains(KeyModifiers::SHIFT);
+    let alt = key.modifiers.contains(KeyModifiers::ALT);
+    let action = match key.code {
+        KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => Action::Quit,
+        KeyCode::Char('z') if key.modifiers.contains(KeyModifiers::CONTROL) => Action::Suspend,
+        KeyCode::Char('T') if key.modifiers.contains(KeyModifiers::CONTROL) => Action::ReopenTab,
+        KeyCode::Char('t') if key.modifiers.contains(KeyModifiers::CONTROL) => Action::NewTab,
+        KeyCode::Char('w') if key.modifiers.contains(KeyModifiers::CONTROL) => Action::CloseTab,
+        KeyCode::PageDown if key.modifiers.contains(KeyModifiers::CONTROL) => Action::SwitchTab(1),
+        KeyCode::PageUp if key.modifiers.contains(KeyModifiers::CONTROL) => Action::SwitchTab(-1),
+        KeyCode::Char('w') | KeyCode::Char('W') => Action::Scroll(-SCROLL_STEP),
+        KeyCode::Char('s') | KeyCode::Char('S') => Action::Scroll(SCROLL_STEP),
+        KeyCode::Char('l') | KeyCode::Char('L') => Action::LatencyTest,
+        KeyCode::Char('h') | KeyCode::Char('H') => Action::Home,
+        KeyCode::Left if alt => Action::Back,
+        KeyCode::Backspace => Action::Back,
+        KeyCode::Right if alt => Action::Forward,
+        KeyCode::Char('r') | KeyCode::Char('R') | KeyCode::F(5) => Action::Reload,
+        KeyCode::Esc => Action::Stop,
+        KeyCode::Char('f') | KeyCode::Char('F') => Action::CycleColorFilter,
+        KeyCode::Char('+') | KeyCode::Char('=') => Action::ZoomIn,
+        KeyCode::Char('-') => Action::ZoomOut,
+        KeyCode::Char('p') | KeyCode::Char('P') => Action::TogglePause,
+        KeyCode::Char('i') | KeyCode::Char('I') => Action::StartTyping,
+        KeyCode::Char(':') => Action::OpenCommandLine,
+        KeyCode::Char('o') => Action::OpenUrl {
+            edit_current: false,
+        },
+        KeyCode::Char('O') => Action::OpenUrl { edit_current: true },
+        KeyCode::Char('g') | KeyCode::Char('G') => Action::ToggleGrid,
+        KeyCode::Char('m') | KeyCode::Char('M') => Action::ToggleMute,
+        KeyCode::F(12) => Action::ToggleStats,
+        KeyCode::Up if shift => Action::Pan {
+            dx: 0.0,
+            dy: -PAN_STEP,
+        },
+        KeyCode::Down if shift => Action::Pan {
+            dx: 0.0,
+            dy: PAN_STEP,
+        },
+        KeyCode::Left if shift => Action::Pan {
+            dx: -PAN_STEP,
+            dy: 0.0,
+        },
+        KeyCode::Right if shift => Action::Pan {
+            dx: PAN_STEP,
+            dy: 0.0,
+        },
+        KeyCode::Up => Action::MoveCursor { dx: 0, dy: -1 },
+        KeyCode::Down => Action::MoveCursor { dx: 0, dy: 1 },
+        KeyCode::Left => Action::MoveCursor { dx: -1, dy: 0 },
+        KeyCode::Right => Action::MoveCursor { dx: 1, dy: 0 },
+        KeyCode::Enter => Action::Click,
+        _ => return None,
+    };
+    Some(action)
+}
+
+/// Map a terminal mouse event to the action it triggers, if any
+fn action_for_mouse(mouse: &MouseEvent) -> Option<Action> {
+    let (x, y) = (mouse.column as usize, mouse.row as usize);
+    let action = match mouse.kind {
+        MouseEventKind::Moved => Action::PointAt { x, y },
+        MouseEventKind::Down(MouseButton::Left) => Action::MouseDown { x, y },
+        MouseEventKind::Drag(MouseButton::Left) => Action::MouseDrag { x, y },
+        MouseEventKind::Up(MouseButton::Left) => Action::MouseUp { x, y },
+        MouseEventKind::ScrollDown => Action::Wheel { dx: 0, dy: 1 },
+        MouseEventKind::ScrollUp => Action::Wheel { dx: 0, dy: -1 },
+        MouseEventKind::ScrollRight => Action::Wheel { dx: 1, dy: 0 },
+        MouseEventKind::ScrollLeft => Action::Wheel { dx: -1, dy: 0 },
+        _ => return None,
+    };
+    Some(action)
+}
+
+/// Move `pos` by (`dx`, `dy`) cells, staying within a `width`x`height` grid
+pub fn move_cursor(pos: &mut CursorPosition, dx: i32, dy: i32, width: usize, height: usize) {
+    let step = |v: usize, d: i32, len: usize| {
+        (v as i64 + d as i64).clamp(0, len.saturating_sub(1) as i64) as usize
+    };
+    pos.x = step(pos.x, dx, width);
+    pos.y = step(pos.y, dy, height);
+}
+
+/// Read a shared terminal size
+pub fn current_size(size: &Mutex<(usize, usize)>) -> (usize, usize) {
+    *size.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// The browser viewport size in CSS pixels
+pub fn current_viewport(viewport: &Mutex<(u32, u32)>) -> (u32, u32) {
+    *viewport.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// How mouse wheel notches turn into page scrolling
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WheelScroll {
+    /// CSS pixels per notch
+    pub step: i32,
+    /// Content follows the wheel, like a touchscreen: wheel down scrolls up
+    pub natural: bool,
+}
+
+impl Default for WheelScroll {
+    fn default() -> Self {
+        Self {
+            step: DEFAULT_WHEEL_STEP,
+            natural: false,
+        }
+    }
+}
+
+impl WheelScroll {
+    /// Scroll offset in CSS pixels for (`dx`, `dy`) notches
+    pub fn delta(self, dx: i32, dy: i32) -> (i32, i32) {
+        let step = if self.natural { -self.step } else { self.step };
+        (dx.saturating_mul(step), dy.saturating_mul(step))
+    }
+}
+
+/// Whether input handling should keep going after an action
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Flow {
+    Continue,
+    Quit,
+}
+
+/// Applies actions to the shared application state
+///
+/// Every input source (keyboard and mouse) funnels through `dispatch`, so new features
+/// add an `Action` variant and a handler here rather than another branch in the input loop.
+pub struct Dispatcher {
+    pub streamer: Arc<ScreenshotStreamer>,
+    pub pipeline: Arc<RenderPipeline>,
+    pub cursor_pos: Arc<Mutex<CursorPosition>>,
+    pub latency: Arc<Mutex<LatencyProbe>>,
+    pub watcher: Arc<Mutex<RedirectWatcher>>,
+    pub notice: Arc<Mutex<Option<Notice>>>,
+    pub shutdown_tx: mpsc::Sender<()>,
+    /// Input for the page, sent in order by the [`Forwarder`](crate::forward::Forwarder)
+    pub input_tx: mpsc::Sender<PageInput>,
+    /// Set while capture and display are frozen
+    pub paused: Arc<AtomicBool>,
+    /// Set while key presses go to the page instead of acting on toast
+    pub typing: Arc<AtomicBool>,
+    /// Set while the cell grid debug overlay is shown
+    pub grid: Arc<AtomicBool>,
+    /// Set while the stats overlay is shown
+    pub stats: Arc<AtomicBool>,
+    /// Asks the display task to redraw the last frame, for changes made while paused
+    pub redraw: Arc<Notify>,
+    /// Navigation whose first frame is still pending (the display shows a placeholder)
+    pub navigation: Arc<Mutex<Option<PendingNavigation>>>,
+    /// The load of a page toast navigated to, waited on in its own task so that keys still
+    /// work meanwhile; Esc or the next navigation cancels the wait
+    pub loading: Mutex<Option<JoinHandle<()>>>,
+    /// The command or URL line while it is open; it takes key presses before actions do
+    pub command_line: Arc<Mutex<Option<CommandLine>>>,
+    /// Lines entered on the command and URL lines, for Up and Down to recall
+    pub history: Mutex<LineHistory>,
+    /// Size of the page area in cells, shared with the render, display and forwarding tasks
+    pub size: Arc<Mutex<(usize, usize)>>,
+    /// Terminal rows taken by the tab and status bars around the page, shared with the
+    /// display task; the tab bar comes and goes with the second tab
+    pub layout: Arc<Mutex<Layout>>,
+    /// Show the tab bar even with a single tab open
+    pub tab_bar: bool,
+    /// Rows drawn on in inline mode, as (first row, row count)
+    pub region: Option<(usize, usize)>,
+    /// Browser viewport size in CSS pixels, shared with the display and forwarding tasks
+    pub viewport: Arc<Mutex<(u32, u32)>>,
+    /// Cell size in pixels, as (width, height), that the viewport follows as the terminal is
+    /// resized; `None` keeps it fixed
+    pub viewport_cell: Option<(f32, f32)>,
+    /// Asks the render task to render the last screenshot again, after a resize
+    pub rerender: Arc<Notify>,
+    /// Shared with the display task, to repaint everything after a suspend
+    pub renderer: Arc<Renderer<Output>>,
+    pub homepage: Option<String>,
+    pub latency_test: bool,
+    pub wheel: WheelScroll,
+    pub keymap: Keymap,
+    /// Search URL template for URL line input that isn't an address (see
+    /// [`target::resolve_or_search`])
+    pub search_engine: Option<String>,
+}
+
+impl Dispatcher {
+    /// Handle a terminal event: edit the command line if it's open, or dispatch its action
+    pub async fn handle_event(&self, event: &Event) -> Flow {
+        if let Event::Key(key) = event {
+            let edit = self.command_line.lock().ok().and_then(|mut line| {
+                line.as_mut()
+                    .map(|line| (line.mode(), line.handle_key(key)))
+            });
+            match edit {
+                None | Some((_, LineEdit::Ignored)) => {}
+                Some((mode, edit)) => {
+                    if edit != LineEdit::Editing {
+                        if let Ok(mut line) = self.command_line.lock() {
+                            *line = None;
+                        }
+                    }
+                    self.redraw.notify_one();
+                    if let LineEdit::Submit(text) = edit {
+                        if let Ok(mut history) = self.history.lock() {
+                            history.push(mode, &text);
+                        }
+                        match mode {
+                            LineMode::Command => self.run_command(&text).await,
+                            LineMode::Url => self.open(&text).await,
+                        }
+                    }
+                    return Flow::Continue;
+                }
+            }
+        }
+
+        if let Event::Key(key) = event {
+            if self.typing.load(Ordering::Relaxed) {
+                match typing_action(key) {
+                    Some(action) => return self.dispatch(action).await,
+                    None => {
+                        let _ = self.input_tx.send(PageInput::Key(*key)).await;
+                        return Flow::Continue;
+                    }
+                }
+            }
+        }
+
+        if let Event::Paste(text) = event {
+            let typing = self.typing.load(Ordering::Relaxed);
+            let for_page = {
+                let history = self.history.lock().unwrap_or_else(|e| e.into_inner());
+                self.command_line
+                    .lock()
+                    .ok()
+                    .and_then(|mut line| paste(&mut line, typing, &history, text))
+            };
+            match for_page {
+                Some(input) => {
+                    let _ = self.input_tx.send(input).await;
+                }
+                None => self.redraw.notify_one(),
+            }
+            return Flow::Continue;
+        }
+
+        match action_for_event(event, self.keymap) {
+            Some(action) => self.dispatch(action).await,
+            None => Flow::Continue,
+        }
+    }
+
+    pub async fn dispatch(&self, action: Action) -> Flow {
+        match action {
+            Action::Quit => {
+                info!("Ctrl+C detected from keyboard");
+                let _ = self.shutdown_tx.send(()).await;
+                return Flow::Quit;
+            }
+            Action::Suspend => {
+                // Nothing is captured or drawn while stopped, so no backlog is left for fg
+                let was_paused = self.paused.swap(true, Ordering::Relaxed);
+                let region = self.region.map(|(top, rows)| (top as u16, rows as u16));
+                if let Err(e) = toast_terminal::suspend(region) {
+                    error!("Failed to suspend: {:#}", e);
+                    self.paused.store(was_paused, Ordering::Relaxed);
+                    return Flow::Continue;
+                }
+                info!("Resumed after suspend");
+                // The shell drew over the screen meanwhile, and the terminal may have been
+                // resized
+                self.renderer.invalidate();
+                match crossterm::terminal::size() {
+                    Ok((width, height)) => self.resize(width as usize, height as usize).await,
+                    Err(e) => error!("Failed to get terminal size: {}", e),
+                }
+                self.paused.store(was_paused, Ordering::Relaxed);
+                self.redraw.notify_one();
+            }
+            Action::Scroll(delta) => {
+                let _ = self
+                    .input_tx
+                    .send(PageInput::Scroll { dx: 0, dy: delta })
+                    .await;
+            }
+            Action::Wheel { dx, dy } => {
+                let (dx, dy) = self.wheel.delta(dx, dy);
+                let _ = self.input_tx.send(PageInput::Scroll { dx, dy }).await;
+            }
+            Action::LatencyTest => {
+                if !self.latency_test {
+                    return Flow::Continue;
+                }
+                let started = self
+                    .latency
+                    .lock()
+                    .map(|mut probe| probe.start(Instant::now()))
+                    .unwrap_or(false);
+                if started {
+                    info!("Latency test started");
+                    if let Err(e) = self.streamer.show_latency_marker().await {
+                        error!("Failed to inject latency marker: {}", e);
+                        if let Ok(mut probe) = self.latency.lock() {
+                            probe.abandon();
+                        }
+                    }
+                }
+            }
+            Action::Home => {
+                if let Some(home) = &self.homepage {
+                    self.navigate(home);
+                }
+            }
+            Action::Back => self.go_history(true).await,
+            Action::Forward => self.go_history(false).await,
+            Action::Reload => match self.streamer.reload().await {
+                Ok(()) => self.restore_scroll(),
+                Err(e) => error!("Failed to reload: {:#}", e),
+            },
+            Action::Stop => {
+                if let Some(loading) = self.loading.lock().ok().and_then(|mut l| l.take()) {
+                    loading.abort();
+                }
+                if let Err(e) = self.streamer.stop().await {
+                    error!("Failed to stop loading: {:#}", e);
+                }
+                // Show the page as far as it got
+                if let Ok(mut navigation) = self.navigation.lock() {
+                    if let Some(pending) = navigation.as_mut() {
+                        pending.finish_loading();
+                    }
+                }
+            }
+            Action::NewTab => self.new_tab().await,
+            Action::CloseTab => self.close_tab().await,
+            Action::ReopenTab => self.reopen_tab().await,
+            Action::ToggleMute => match self.streamer.toggle_mute().await {
+                Ok(muted) => {
+                    self.show_notice(if muted { "Tab muted" } else { "Tab unmuted" }.to_string());
+                    self.redraw.notify_one();
+                }
+                Err(e) => {
+                    error!("Failed to mute tab: {:#}", e);
+                    self.show_notice(format!("{:#}", e));
+                }
+            },
+            Action::SwitchTab(step) => self.switch_tab(step).await,
+            Action::CycleColorFilter => {
+                let filter = self.pipeline.color_filter().next();
+                self.pipeline.set_color_filter(filter);
+                self.show_notice(format!("Color filter: {}", filter.name()));
+            }
+            Action::ZoomIn => {
+                let mut zoom = self.pipeline.zoom();
+                zoom.zoom_in();
+                self.pipeline.set_zoom(zoom);
+            }
+            Action::ZoomOut => {
+                let mut zoom = self.pipeline.zoom();
+                zoom.zoom_out();
+                self.pipeline.set_zoom(zoom);
+            }
+            Action::Pan { dx, dy } => {
+                let mut zoom = self.pipeline.zoom();
+                zoom.pan(dx, dy);
+                self.pipeline.set_zoom(zoom);
+            }
+            Action::MoveCursor { dx, dy } => {
+                let (width, height) = current_size(&self.size);
+                let moved = self.cursor_pos.lock().ok().map(|mut pos| {
+                    move_cursor(&mut pos, dx, dy, width, height);
+                    (pos.x, pos.y)
+                });
+                if self.paused.load(Ordering::Relaxed) {
+                    self.redraw.notify_one();
+                }
+                // Hover follows the cursor
+                if let Some((x, y)) = moved {
+                    let _ = self.input_tx.send(PageInput::MouseMove { x, y }).await;
+                }
+            }
+            Action::Click => {
+                // Copy values out of the mutex before await
+                let coords = self.cursor_pos.lock().map(|pos| (pos.x, pos.y)).ok();
+                if let Some((x, y)) = coords {
+                    info!("Enter pressed - sending click at terminal ({}, {})", x, y);
+                    let _ = self.input_tx.send(PageInput::Click { x, y }).await;
+                }
+            }
+            Action::PointAt { x, y } => {
+                self.point(x, y, |x, y| PageInput::MouseMove { x, y }).await
+            }
+            Action::MouseDown { x, y } => self.point(x, y, |x, y| PageInput::Press { x, y }).await,
+            Action::MouseDrag { x, y } => self.point(x, y, |x, y| PageInput::Drag { x, y }).await,
+            Action::MouseUp { x, y } => self.point(x, y, |x, y| PageInput::Release { x, y }).await,
+            Action::TogglePause => {
+                let paused = !self.paused.fetch_xor(true, Ordering::Relaxed);
+                info!("Rendering {}", if paused { "paused" } else { "resumed" });
+                self.redraw.notify_one();
+            }
+            Action::StartTyping => {
+                self.typing.store(true, Ordering::Relaxed);
+                self.show_notice("Typing into the page, Ctrl+G to stop".to_string());
+                self.redraw.notify_one();
+            }
+            Action::StopTyping => {
+                self.typing.store(false, Ordering::Relaxed);
+                self.redraw.notify_one();
+            }
+            Action::ToggleGrid => {
+                self.grid.fetch_xor(true, Ordering::Relaxed);
+                self.redraw.notify_one();
+            }
+            Action::ToggleStats => {
+                self.stats.fetch_xor(true, Ordering::Relaxed);
+                self.redraw.notify_one();
+            }
+            Action::OpenCommandLine => self.open_line(LineMode::Command, ""),
+            Action::OpenUrl { edit_current } => {
+                let url = if edit_current {
+                    self.streamer.current_url().await.ok().flatten()
+                } else {
+                    None
+                };
+                self.open_line(LineMode::Url, url.as_deref().unwrap_or_default());
+            }
+            Action::Resize { width, height } => {
+                info!("Terminal resized to {}x{}", width, height);
+                self.resize(width, height).await;
+            }
+        }
+        Flow::Continue
+    }
+
+    /// Fit the page to a terminal of `width` by `height` cells
+    async fn resize(&self, width: usize, height: usize) {
+        // Inline mode keeps its rows
+        let height = self.region.map_or(height, |(_, rows)| rows.min(height));
+        let height = self.layout().page_height(height);
+        if let Ok(mut size) = self.size.lock() {
+            *size = (width, height);
+        }
+        if let Some((cell_width, cell_height)) = self.viewport_cell {
+            let (page_width, page_height) =
+                grid_viewport(width, height, cell_width, cell_height / cell_width);
+            match self.streamer.set_viewport(page_width, page_height).await {
+                Ok(()) => {
+                    if let Ok(mut viewport) = self.viewport.lock() {
+                        *viewport = (page_width, page_height);
+                    }
+                }
+                Err(e) => error!("Failed to resize viewport: {}", e),
+            }
+        }
+        // Keep the cursor on screen
+        if let Ok(mut pos) = self.cursor_pos.lock() {
+            move_cursor(&mut pos, 0, 0, width, height);
+        }
+        // Redraw now rather than at the next capture, which may be a while when paused
+        self.rerender.notify_one();
+    }
+
+    /// Start loading `url` without waiting for it, showing a placeholder until a frame of
+    /// the new page is captured; a failure is shown as a notice
+    fn navigate(&self, url: &str) {
+        self.load(url, None);
+    }
+
+    /// [`navigate`](Self::navigate) to `url`, then scroll the page to `scroll` once it
+    /// has loaded
+    fn load(&self, url: &str, scroll: Option<(f64, f64)>) {
+        // A navigation the user asked for is not a redirect
+        if let Ok(mut w) = self.watcher.lock() {
+            w.expect(url.to_string());
+        }
+        if let Ok(mut navigation) = self.navigation.lock() {
+            *navigation = Some(PendingNavigation::new(url));
+        }
+
+        let streamer = Arc::clone(&self.streamer);
+        let navigation = Arc::clone(&self.navigation);
+        let notice = Arc::clone(&self.notice);
+        let url = url.to_string();
+        let task = tokio::spawn(async move {
+            let result = streamer.navigate(&url).await;
+            if let (Ok(()), Some((x, y))) = (&result, scroll) {
+                if let Err(e) = streamer.scroll_to(x, y).await {
+                    error!("Failed to restore scroll position: {:#}", e);
+                }
+            }
+            if let Ok(mut navigation) = navigation.lock() {
+                match (&result, navigation.as_mut()) {
+                    (Ok(()), Some(pending)) => pending.finish_loading(),
+                    // Nothing new is coming; go back to the page as it is
+                    _ => *navigation = None,
+                }
+            }
+            if let Err(e) = result {
+                error!("Failed to open {}: {:#}", url, e);
+                if let Ok(mut notice) = notice.lock() {
+                    *notice = Some(Notice::new(format!("{:#}", e), NOTICE_DURATION));
+                }
+            }
+        });
+        if let Ok(mut loading) = self.loading.lock() {
+            if let Some(previous) = loading.replace(task) {
+                previous.abort();
+            }
+        }
+    }
+
+    /// Scroll the page gone to through history, or reloaded, back to where it was left
+    /// once it has loaded, without waiting for it
+    fn restore_scroll(&self) {
+        let streamer = Arc::clone(&self.streamer);
+        let task = tokio::spawn(async move {
+            if let Err(e) = streamer.restore_scroll().await {
+                error!("Failed to restore scroll position: {:#}", e);
+            }
+        });
+        if let Ok(mut loading) = self.loading.lock() {
+            if let Some(previous) = loading.replace(task) {
+                previous.abort();
+            }
+        }
+    }
+
+    /// Rows taken by the bars around the page
+    fn layout(&self) -> Layout {
+        *self.layout.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Open a tab on the homepage (or a blank page), with the URL line to go elsewhere
+    async fn new_tab(&self) {
+        if let Err(e) = self.streamer.open_tab().await {
+            error!("Failed to open tab: {:#}", e);
+            self.show_notice(format!("{:#}", e));
+            return;
+        }
+        self.tab_switched().await;
+        if let Some(home) = &self.homepage {
+            self.navigate(home);
+        }
+        self.open_line(LineMode::Url, "");
+    }
+
+    /// Close the shown tab and show its neighbour
+    async fn close_tab(&self) {
+        let (_, index) = self.streamer.tabs();
+        if let Err(e) = self.streamer.close_tab(index).await {
+            self.show_notice(format!("{:#}", e));
+            return;
+        }
+        self.tab_switched().await;
+    }
+
+    /// Open the most recently closed tab again after the shown one
+    async fn reopen_tab(&self) {
+        let Some(closed) = self.streamer.take_closed_tab() else {
+            self.show_notice("No closed tab to reopen".to_string());
+            return;
+        };
+        if let Err(e) = self.streamer.open_tab().await {
+            error!("Failed to reopen tab: {:#}", e);
+            self.show_notice(format!("{:#}", e));
+            return;
+        }
+        self.tab_switched().await;
+        self.load(&closed.url, Some(closed.scroll));
+    }
+
+    /// Show the tab `step` tabs after the shown one
+    async fn switch_tab(&self, step: isize) {
+        let (tabs, active) = self.streamer.tabs();
+        let index = neighbour(active, tabs.len(), step);
+        if let Err(e) = self.streamer.switch_tab(index) {
+            error!("Failed to switch tab: {:#}", e);
+            return;
+        }
+        self.tab_switched().await;
+    }
+
+    /// Catch up with another tab being shown, or the number of tabs changing
+    async fn tab_switched(&self) {
+        let (tabs, active) = self.streamer.tabs();
+        let count = tabs.len();
+        let Some(tab) = tabs.into_iter().nth(active) else {
+            return;
+        };
+        // Showing another tab is not a redirect
+        if let Ok(mut w) = self.watcher.lock() {
+            w.expect(tab.url);
+        }
+
+        // The tab bar takes a row from the page once there is more than one tab
+        let top = if self.tab_bar || count > 1 {
+            TAB_ROWS
+        } else {
+            0
+        };
+        let moved = self.layout.lock().is_ok_and(|mut layout| {
+            let moved = layout.top != top;
+            layout.top = top;
+            moved
+        });
+        if moved {
+            match crossterm::terminal::size() {
+                Ok((width, height)) => self.resize(width as usize, height as usize).await,
+                Err(e) => error!("Failed to get terminal size: {}", e),
+            }
+        }
+        self.redraw.notify_one();
+    }
+
+    /// Go back or forward a page in the tab's history
+    async fn go_history(&self, back: bool) {
+        // Pages that redirected would only redirect again
+        let watcher = self.watcher.lock().ok().map(|w| w.clone());
+        let redirected = |url: &str| watcher.as_ref().is_some_and(|w| w.redirected_from(url));
+        let result = if back {
+            self.streamer.back(redirected).await
+        } else {
+            self.streamer.forward(redirected).await
+        };
+        match result {
+            // Going through history is not a redirect
+            Ok(Some(url)) => {
+                if let Ok(mut w) = self.watcher.lock() {
+                    w.expect(url);
+                }
+                self.restore_scroll();
+            }
+            Ok(None) if back => self.show_notice("No earlier page".to_string()),
+            Ok(None) => self.show_notice("No later page".to_string()),
+            Err(e) => error!("Failed to go through history: {:#}", e),
+        }
+    }
+
+    /// Open the input line in `mode`, starting out with `text`
+    pub fn open_line(&self, mode: LineMode, text: &str) {
+        let history = self.history.lock().unwrap_or_else(|e| e.into_inner());
+        if let Ok(mut line) = self.command_line.lock() {
+            *line = Some(CommandLine::new(mode, &history).with_text(text));
+        }
+        self.redraw.notify_one();
+    }
+
+    /// Open a URL, host name or local file typed by the user
+    async fn open(&self, target: &str) {
+        match target::resolve_or_search(target, self.search_engine.as_deref()) {
+            Ok(url) => self.navigate(&url),
+            Err(e) => {
+                error!("Failed to open {}: {:#}", target, e);
+                self.show_notice(format!("{:#}", e));
+            }
+        }
+    }
+
+    /// Run a line typed on the command line, showing its result or error as a notice
+    async fn run_command(&self, line: &str) {
+        let command = match Command::parse(line) {
+            Ok(command) => command,
+            Err(e) => {
+                self.show_notice(format!("{:#}", e));
+                return;
+            }
+        };
+        match command {
+            Command::Open { target } => self.open(&target).await,
+            Command::Cdp { method, params } => {
+                match self.streamer.send_cdp(&method, params).await {
+                    Ok(result) => {
+                        info!("{} returned {}", method, result);
+                        self.show_notice(format!("{}: {}", method, result));
+                    }
+                    Err(e) => {
+                        error!("{:#}", e);
+                        self.show_notice(format!("{:#}", e));
+                    }
+                }
+            }
+        }
+    }
+
+    /// Put the cursor where the mouse is, at terminal cell (`x`, `y`), and send the page
+    /// the input `input` makes for that page cell
+    async fn point(&self, x: usize, y: usize, input: fn(usize, usize) -> PageInput) {
+        // The mouse is outside the rows drawn on, or over a bar rather than the page
+        let top = self.region.map_or(0, |(top, _)| top);
+        let page_height = current_size(&self.size).1;
+        let Some(y) = y
+            .checked_sub(top)
+            .and_then(|y| self.layout().page_row(y, page_height))
+        else {
+            return;
+        };
+        if let Ok(mut pos) = self.cursor_pos.lock() {
+            *pos = CursorPosition { x, y };
+        }
+        if self.paused.load(Ordering::Relaxed) {
+            self.redraw.notify_one();
+        }
+        let _ = self.input_tx.send(input(x, y)).await;
+    }
+
+    /// Replace the notice drawn over the top row
+    pub fn show_notice(&self, text: String) {
+        if let Ok(mut current) = self.notice.lock() {
+            *current = Some(Notice::new(text, NOTICE_DURATION));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn press(code: KeyCode, modifiers: KeyModifiers) -> KeyEvent {
+        KeyEvent::new(code, modifiers)
+    }
+
+    #[test]
+    fn test_key_mapping() {
+        assert_eq!(
+            action_for_key(&press(KeyCode::Char('c'), KeyModifiers::CONTROL)),
+            Some(Action::Quit)
+        );
+        assert_eq!(
+            action_for_key(&press(KeyCode::Char('c'), KeyModifiers::NONE)),
+            None
+        );
+        assert_eq!(
+            action_for_key(&press(KeyCode::Char('z'), KeyModifiers::CONTROL)),
+            Some(Action::Suspend)
+        );
+        assert_eq!(
+            action_for_key(&press(KeyCode::Char('W'), KeyModifiers::SHIFT)),
+            Some(Action::Scroll(-SCROLL_STEP))
+        );
+        assert_eq!(
+            action_for_key(&press(KeyCode::Up, KeyModifiers::SHIFT)),
+            Some(Action::Pan {
+                dx: 0.0,
+                dy: -PAN_STEP
+            })
+        );
+        assert_eq!(
+            action_for_key(&press(KeyCode::Up, KeyModifiers::NONE)),
+            Some(Action::MoveCursor { dx: 0, dy: -1 })
+        );
+        assert_eq!(
+            action_for_key(&press(KeyCode::Enter, KeyModifiers::NONE)),
+            Some(Action::Click)
+        );
+        assert_eq!(
+            action_for_key(&press(KeyCode::Char('p'), KeyModifiers::NONE)),
+            Some(Action::TogglePause)
+        );
+        assert_eq!(
+            action_for_key(&press(KeyCode::Char(':'), KeyModifiers::SHIFT)),
+            Some(Action::OpenCommandLine)
+        );
+        assert_eq!(
+            action_for_key(&press(KeyCode::Char('g'), KeyModifiers::NONE)),
+            Some(Action::ToggleGrid)
+        );
+        assert_eq!(
+            action_for_key(&press(KeyCode::F(12), KeyModifiers::NONE)),
+            Some(Action::ToggleStats)
+        );
+        assert_eq!(
+            action_for_key(&press(KeyCode::Char('O'), KeyModifiers::SHIFT)),
+            Some(Action::OpenUrl { edit_current: true })
+        );
+    }
+
+    #[test]
+    fn test_tab_keys() {
+        assert_eq!(
+            action_for_key(&press(KeyCode::Char('t'), KeyModifiers::CONTROL)),
+            Some(Action::NewTab)
+        );
+        assert_eq!(
+            action_for_key(&press(KeyCode::Char('w'), KeyModifiers::CONTROL)),
+            Some(Action::CloseTab)
+        );
+        assert_eq!(
+            action_for_key(&press(KeyCode::Char('m'), KeyModifiers::NONE)),
+            Some(Action::ToggleMute)
+        );
+        assert_eq!(
+            action_for_key(&press(
+                KeyCode::Char('T'),
+                KeyModifiers::CONTROL | KeyModifiers::SHIFT
+            )),
+            Some(Action::ReopenTab)
+        );
+        assert_eq!(
+            action_for_key(&press(KeyCode::PageDown, KeyModifiers::CONTROL)),
+            Some(Action::SwitchTab(1))
+        );
+        assert_eq!(
+            action_for_key(&press(KeyCode::PageUp, KeyModifiers::CONTROL)),
+            Some(Action::SwitchTab(-1))
+        );
+        // Without Ctrl, W still scrolls
+        assert_eq!(
+            action_for_key(&press(KeyCode::Char('w'), KeyModifiers::NONE)),
+            Some(Action::Scroll(-SCROLL_STEP))
+        );
+    }
+
+    #[test]
+    fn test_tab_neighbours_wrap_around() {
+        assert_eq!(neighbour(1, 3, 1), 2);
+        assert_eq!(neighbour(1, 3, 2), 0);
+        assert_eq!(neighbour(1, 3, -2), 2);
+        assert_eq!(neighbour(0, 1, 1), 0);
+    }
+
+    #[test]
+    fn test_typing_keys() {
+        assert_eq!(
+            action_for_key(&press(KeyCode::Char('i'), KeyModifiers::NONE)),
+            Some(Action::StartTyping)
+        );
+        assert_eq!(
+            typing_action(&press(KeyCode::Char('g'), KeyModifiers::CONTROL)),
+            Some(Action::StopTyping)
+        );
+        assert_eq!(
+            typing_action(&press(KeyCode::Char('c'), KeyModifiers::CONTROL)),
+            Some(Action::Quit)
+        );
+        // Everything else, shortcuts included, is for the page
+        assert_eq!(
+            typing_action(&press(KeyCode::Char('q'), KeyModifiers::NONE)),
+            None
+        );
+        assert_eq!(
+            typing_action(&press(KeyCode::Esc, KeyModifiers::NONE)),
+            None
+        );
+        assert_eq!(
+            typing_action(&press(KeyCode::Char('a'), KeyModifiers::CONTROL)),
+            None
+        );
+    }
+
+    #[test]
+    fn test_paste_while_typing_goes_to_the_page() {
+        let history = LineHistory::default();
+        let mut line = None;
+        assert_eq!(
+            paste(&mut line, true, &history, "hello"),
+            Some(PageInput::Text("hello".to_string()))
+        );
+        assert!(line.is_none());
+
+        // An open line still takes the paste
+        let mut line = Some(CommandLine::new(LineMode::Command, &history));
+        assert_eq!(paste(&mut line, true, &history, "open"), None);
+        let enter = press(KeyCode::Enter, KeyModifiers::NONE);
+        assert_eq!(
+            line.unwrap().handle_key(&enter),
+            LineEdit::Submit("open".to_string())
+        );
+    }
+
+    #[test]
+    fn test_paste_opens_the_url_line() {
+        let mut line = None;
+        assert_eq!(
+            paste(&mut line, false, &LineHistory::default(), "example.com"),
+            None
+        );
+        let mut line = line.unwrap();
+        assert_eq!(line.mode(), LineMode::Url);
+        assert_eq!(
+            line.handle_key(&press(KeyCode::Enter, KeyModifiers::NONE)),
+            LineEdit::Submit("example.com".to_string())
+        );
+    }
+
+    #[test]
+    fn test_navigation_keys() {
+        assert_eq!(
+            action_for_key(&press(KeyCode::Left, KeyModifiers::ALT)),
+            Some(Action::Back)
+        );
+        assert_eq!(
+            action_for_key(&press(KeyCode::Backspace, KeyModifiers::NONE)),
+            Some(Action::Back)
+        );
+        assert_eq!(
+            action_for_key(&press(KeyCode::Right, KeyModifiers::ALT)),
+            Some(Action::Forward)
+        );
+        assert_eq!(
+            action_for_key(&press(KeyCode::F(5), KeyModifiers::NONE)),
+            Some(Action::Reload)
+        );
+        assert_eq!(
+            action_for_key(&press(KeyCode::Char('r'), KeyModifiers::NONE)),
+            Some(Action::Reload)
+        );
+        assert_eq!(
+            action_for_key(&press(KeyCode::Esc, KeyModifiers::NONE)),
+            Some(Action::Stop)
+        );
+        // Plain arrows still move the cursor
+        assert_eq!(
+            action_for_key(&press(KeyCode::Left, KeyModifiers::NONE)),
+            Some(Action::MoveCursor { dx: -1, dy: 0 })
+        );
+    }
+
+    #[test]
+    fn test_vim_keys() {
+        let vim = |code, modifiers| Keymap::Vim.action_for_key(&press(code, modifiers));
+        assert_eq!(
+            vim(KeyCode::Char('j'), KeyModifiers::NONE),
+            Some(Action::Scroll(SCROLL_STEP))
+        );
+        assert_eq!(
+            vim(KeyCode::Char('H'), KeyModifiers::SHIFT),
+            Some(Action::Back)
+        );
+        assert_eq!(
+            vim(KeyCode::Char('K'), KeyModifiers::SHIFT),
+            Some(Action::SwitchTab(1))
+        );
+        assert_eq!(
+            vim(KeyCode::Char('X'), KeyModifiers::SHIFT),
+            Some(Action::ReopenTab)
+        );
+        // Everything else acts as by default
+        assert_eq!(
+            vim(KeyCode::Char('c'), KeyModifiers::CONTROL),
+            Some(Action::Quit)
+        );
+        assert_eq!(
+            vim(KeyCode::Char('h'), KeyModifiers::NONE),
+            Some(Action::Home)
+        );
+        // The default bindings leave j and k alone
+        assert_eq!(
+            Keymap::Default.action_for_key(&press(KeyCode::Char('j'), KeyModifiers::NONE)),
+            None
+        );
+        assert_eq!("Vim".parse::<Keymap>().unwrap(), Keymap::Vim);
+        assert!("emacs".parse::<Keymap>().is_err());
+    }
+
+    #[test]
+    fn test_resize_event() {
+        assert_eq!(
+            action_for_event(&Event::Resize(100, 30), Keymap::Default),
+            Some(Action::Resize {
+                width: 100,
+                height: 30
+            })
+        );
+        assert_eq!(action_for_event(&Event::FocusGained, Keymap::Default), None);
+    }
+
+    #[test]
+    fn test_mouse_mapping() {
+        let mouse = |kind| {
+            Event::Mouse(MouseEvent {
+                kind,
+                column: 12,
+                row: 3,
+                modifiers: KeyModifiers::NONE,
+            })
+        };
+        assert_eq!(
+            action_for_event(&mouse(MouseEventKind::Moved), Keymap::Default),
+            Some(Action::PointAt { x: 12, y: 3 })
+        );
+        assert_eq!(
+            action_for_event(
+                &mouse(MouseEventKind::Down(MouseButton::Left)),
+                Keymap::Default
+            ),
+            Some(Action::MouseDown { x: 12, y: 3 })
+        );
+        assert_eq!(
+            action_for_event(
+                &mouse(MouseEventKind::Drag(MouseButton::Left)),
+                Keymap::Default
+            ),
+            Some(Action::MouseDrag { x: 12, y: 3 })
+        );
+        assert_eq!(
+            action_for_event(
+                &mouse(MouseEventKind::Up(MouseButton::Left)),
+                Keymap::Default
+            ),
+            Some(Action::MouseUp { x: 12, y: 3 })
+        );
+        assert_eq!(
+            action_for_event(&mouse(MouseEventKind::ScrollUp), Keymap::Default),
+            Some(Action::Wheel { dx: 0, dy: -1 })
+        );
+        assert_eq!(
+            action_for_event(&mouse(MouseEventKind::ScrollRight), Keymap::Default),
+            Some(Action::Wheel { dx: 1, dy: 0 })
+        );
+        assert_eq!(
+            action_for_event(
+                &mouse(MouseEventKind::Down(MouseButton::Right)),
+                Keymap::Default
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn test_wheel_direction() {
+        let wheel = WheelScroll {
+            step: 100,
+            natural: false,
+        };
+        assert_eq!(wheel.delta(0, -2), (0, -200));
+        let natural = WheelScroll {
+            natural: true,
+            ..wheel
+        };
+        assert_eq!(natural.delta(1, 1), (-100, -100));
+    }
+
+    #[test]
+    fn test_releases_are_ignored() {
+        let mut key = press(KeyCode::Enter, KeyModifiers::NONE);
+        key.kind = KeyEventKind::Release;
+        assert_eq!(action_for_key(&key), None);
+    }
+
+    #[test]
+    fn test_move_cursor_clamps() {
+        let mut pos = CursorPosition { x: 0, y: 9 };
+        move_cursor(&mut pos, -1, 1, 20, 10);
+        assert_eq!((pos.x, pos.y), (0, 9));
+        move_cursor(&mut pos, 3, -2, 20, 10);
+        assert_eq!((pos.x, pos.y), (3, 7));
+    }
+}
@@ -0,0 +1,173 @@
+use std::time::Duration;
+use toast_render::ScaleFilter;
+
+/// Consecutive over-budget frames before stepping quality down
+const DEGRADE_AFTER: u32 = 5;
+
+/// Consecutive fast frames before stepping back up (~3s at the target rate)
+const RECOVER_AFTER: u32 = 45;
+
+/// Share of the budget a frame must stay under to count towards recovery; well below 1
+/// so the higher level, which is slower, doesn't immediately fall behind again
+const RECOVER_FRACTION: f64 = 0.5;
+
+/// JPEG/WebP quality at the lowest level
+const MINIMAL_QUALITY: u8 = 50;
+
+/// How much quality is traded for speed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum QualityLevel {
+    /// The configured scaling filter and capture quality
+    Full,
+    /// Nearest-neighbour scaling
+    Reduced,
+    /// Nearest-neighbour scaling and lower capture quality
+    Minimal,
+}
+
+impl QualityLevel {
+    pub fn name(self) -> &'static str {
+        match self {
+            QualityLevel::Full => "full",
+            QualityLevel::Reduced => "reduced",
+            QualityLevel::Minimal => "minimal",
+        }
+    }
+
+    /// Resampling filter at this level, given the configured one
+    pub fn scale_filter(self, configured: ScaleFilter) -> ScaleFilter {
+        match self {
+            QualityLevel::Full => configured,
+            QualityLevel::Reduced | QualityLevel::Minimal => ScaleFilter::Nearest,
+        }
+    }
+
+    /// JPEG/WebP capture quality at this level, given the configured one
+    pub fn capture_quality(self, configured: u8) -> u8 {
+        match self {
+            QualityLevel::Full | QualityLevel::Reduced => configured,
+            QualityLevel::Minimal => MINIMAL_QUALITY.min(configured),
+        }
+    }
+
+    fn lower(self) -> Self {
+        match self {
+            QualityLevel::Full => QualityLevel::Reduced,
+            _ => QualityLevel::Minimal,
+        }
+    }
+
+    fn higher(self) -> Self {
+        match self {
+            QualityLevel::Minimal => QualityLevel::Reduced,
+            _ => QualityLevel::Full,
+        }
+    }
+}
+
+/// Steps quality down when rendering can't keep up with the frame rate, and back up
+/// once it comfortably can
+#[derive(Debug)]
+pub struct QualityGovernor {
+    budget: Duration,
+    level: QualityLevel,
+    slow_frames: u32,
+    fast_frames: u32,
+}
+
+impl QualityGovernor {
+    /// `budget` is the time available per frame
+    pub fn new(budget: Duration) -> Self {
+        Self {
+            budget,
+            level: QualityLevel::Full,
+            slow_frames: 0,
+            fast_frames: 0,
+        }
+    }
+
+    /// Record how long a frame took to render; returns the new level when it changes
+    pub fn observe(&mut self, render_time: Duration) -> Option<QualityLevel> {
+        if render_time > self.budget {
+            self.slow_frames += 1;
+            self.fast_frames = 0;
+        } else if render_time.as_secs_f64() < self.budget.as_secs_f64() * RECOVER_FRACTION {
+            self.fast_frames += 1;
+            self.slow_frames = 0;
+        } else {
+            // Keeping up, but without room to spare
+            self.slow_frames = 0;
+            self.fast_frames = 0;
+        }
+
+        let next = if self.slow_frames >= DEGRADE_AFTER {
+            self.level.lower()
+        } else if self.fast_frames >= RECOVER_AFTER {
+            self.level.higher()
+        } else {
+            return None;
+        };
+        self.slow_frames = 0;
+        self.fast_frames = 0;
+
+        (next != self.level).then(|| {
+            self.level = next;
+            next
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BUDGET: Duration = Duration::from_millis(66);
+
+    fn feed(governor: &mut QualityGovernor, ms: u64, frames: u32) -> Vec<QualityLevel> {
+        (0..frames)
+            .filter_map(|_| governor.observe(Duration::from_millis(ms)))
+            .collect()
+    }
+
+    #[test]
+    fn test_degrades_under_sustained_load() {
+        let mut governor = QualityGovernor::new(BUDGET);
+
+        // An occasional slow frame is tolerated
+        assert!(feed(&mut governor, 100, DEGRADE_AFTER - 1).is_empty());
+        assert!(feed(&mut governor, 30, 1).is_empty());
+
+        assert_eq!(
+            feed(&mut governor, 100, DEGRADE_AFTER * 3),
+            vec![QualityLevel::Reduced, QualityLevel::Minimal]
+        );
+    }
+
+    #[test]
+    fn test_recovers_when_load_drops() {
+        let mut governor = QualityGovernor::new(BUDGET);
+        feed(&mut governor, 100, DEGRADE_AFTER * 2);
+
+        // Just under budget is not enough to step back up
+        assert!(feed(&mut governor, 60, RECOVER_AFTER * 2).is_empty());
+
+        assert_eq!(
+            feed(&mut governor, 10, RECOVER_AFTER * 3),
+            vec![QualityLevel::Reduced, QualityLevel::Full]
+        );
+    }
+
+    #[test]
+    fn test_levels() {
+        assert_eq!(
+            QualityLevel::Full.scale_filter(ScaleFilter::Lanczos3),
+            ScaleFilter::Lanczos3
+        );
+        assert_eq!(
+            QualityLevel::Reduced.scale_filter(ScaleFilter::Lanczos3),
+            ScaleFilter::Nearest
+        );
+        assert!(QualityLevel::Minimal.capture_quality(85) < QualityLevel::Full.capture_quality(85));
+        assert_eq!(QualityLevel::Minimal.capture_quality(40), 40);
+    }
+}
@@ -1,100 +1,566 @@
 use anyhow::Result;
-use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
-use std::io::Write;
+use crossterm::event;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
-use std::time::Duration;
-use tokio::sync::mpsc;
-use toast_browser::{launch_browser, ScreenshotStreamer};
-use toast_core::{AnsiColor, CursorPosition, TerminalCell};
-use toast_render::RenderPipeline;
-use toast_terminal::{Renderer, Terminal};
+use std::time::{Duration, Instant, SystemTime};
+use toast_browser::{
+    launch_browser_with, LaunchOptions, ProfileGuard, RedirectWatcher, ScreenshotStreamer,
+    DEFAULT_WINDOW_SIZE,
+};
+use toast_core::{AnsiColor, CursorPosition, ImageFormat, Screenshot, TerminalFrame};
+use toast_render::{
+    blend_frames, decode_screenshot, grid_viewport, ColorFilter, OcrEngine, OcrResult,
+    OverlayStyle, RenderConfig, RenderPipeline, NOMINAL_CELL_ASPECT, NOMINAL_CELL_WIDTH,
+};
+use toast_terminal::{
+    detect_capabilities, query_background, query_cell_size, Compositor, Layer, Layout, Output,
+    Passthrough, Renderer, Scrollbar, Sprite, StatusBar, TabBar, TabLabel, Terminal, QUERY_TIMEOUT,
+    STATUS_ROWS, TAB_ROWS,
+};
+use tokio::sync::{mpsc, Notify};
 use tracing::{error, info};
 
-const TARGET_FPS: u32 = 15;
-const FRAME_INTERVAL_MS: u64 = 1000 / TARGET_FPS as u64; // ~66ms
+use crate::action::{
+    current_size, current_viewport, Action, Dispatcher, Flow, Keymap, WheelScroll,
+};
+use crate::adaptive::QualityGovernor;
+use crate::bell::{LoadBell, LoadWatcher};
+use crate::command::{CommandLine, LineHistory, LineMode};
+use crate::forward::{CellMapping, Forwarder};
+use crate::grid::draw_grid;
+use crate::latency::{LatencyProbe, ProbeOutcome};
+use crate::notice::Notice;
+use crate::pace::FramePacer;
+use crate::placeholder::PendingNavigation;
+use crate::session::{Session, SessionFile, SessionTab};
+use crate::stats::{CaptureCounters, FrameStats};
+use crate::title::format_title;
+
+/// Capture frame rate unless a profile sets one
+pub(crate) const DEFAULT_FPS: u32 = 15;
+
+/// Log per-stage render timings about this often
+const STATS_LOG_PERIOD_SECS: u64 = 10;
+
+/// How often the page URL is polled to detect meta refresh / JS redirects
+const REDIRECT_POLL_MS: u64 = 500;
+
+/// How often the tabs are checked for background title changes and for sound
+const TAB_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How often the session (each tab's URL and scroll offset) is autosaved for crash recovery
+const AUTOSAVE_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Display refresh cadence for cross-fading between frames (~30Hz)
+const INTERPOLATION_CADENCE: Duration = Duration::from_millis(33);
+
+/// Most in-between frames shown per new frame, bounding the latency a fade adds
+const MAX_INTERPOLATION_STEPS: u32 = 3;
+
+/// How long notices stay on screen
+pub(crate) const NOTICE_DURATION: Duration = Duration::from_secs(4);
+
+/// How often the loading placeholder is redrawn to animate its spinner
+const PLACEHOLDER_REFRESH: Duration = Duration::from_millis(125);
+
+/// How long the page must stay unchanged under screencast before a progressive draft of
+/// it is rendered again in full quality; the browser sends no frames for a still page
+const DRAFT_REFINE_DELAY: Duration = Duration::from_millis(200);
+
+/// Page input waiting to be sent; once full, input handling waits for the browser
+const INPUT_QUEUE: usize = 64;
+
+/// OCR is far too slow for every frame; recognize the latest screenshot at this interval
+const OCR_INTERVAL: Duration = Duration::from_millis(1000);
+
+/// Most times the OCR interval is doubled while recognition keeps failing (about 30s)
+const OCR_MAX_BACKOFF: u32 = 5;
+
+/// User-selectable application options (from the command line)
+#[derive(Debug, Clone, Default)]
+pub struct AppOptions {
+    /// Enables the L key latency diagnostic
+    pub latency_test: bool,
+    /// Start with the cell grid debug overlay shown
+    pub debug_grid: bool,
+    /// Start with the frame rate and latency stats overlay shown
+    pub stats: bool,
+    /// Reserve the bottom row for a status bar (URL, title, load state and modes)
+    pub status_bar: bool,
+    /// Draw a scrollbar down the rightmost column of pages taller than the viewport
+    pub scrollbar: bool,
+    /// Show the tab bar along the top row even with a single tab open
+    pub tab_bar: bool,
+    /// Show a notice when the title of a tab in the background changes
+    pub tab_notices: bool,
+    /// Start with the URL line open, for when no page was given
+    pub url_prompt: bool,
+    /// Pages to open in further tabs, after the first page's
+    pub tab_urls: Vec<String>,
+    /// Draw in this many rows of the normal screen instead of on the alternate screen
+    pub inline_rows: Option<u16>,
+    /// Write frames to this file or FIFO instead of the terminal
+    pub render_to: Option<PathBuf>,
+    /// Terminal title format (see [`format_title`]); `None` leaves the title alone
+    pub title_format: Option<String>,
+    /// How a page finishing loading is announced
+    pub load_bell: LoadBell,
+    /// Overlay OCR-recognized text on the page
+    pub ocr: bool,
+    /// Render page areas matching the terminal background with the default background
+    pub match_background: bool,
+    /// Incognito browser with a throwaway profile, deleted on exit
+    pub ephemeral: bool,
+    /// Cross-fade between frames when they arrive slower than the display cadence
+    pub interpolate: bool,
+    /// Redraw the page at most this many times a second
+    pub max_redraw_fps: Option<u32>,
+    /// Opacity of notice and badge backgrounds, from 0 (see-through) to 1 (solid)
+    pub overlay_opacity: f32,
+    /// How mouse wheel notches scroll the page
+    pub wheel: WheelScroll,
+    /// Trade scaling and capture quality for speed while rendering falls behind
+    pub adaptive_quality: bool,
+    /// This instance's session file, autosaved to; `None` disables autosave
+    pub session_file: Option<Arc<SessionFile>>,
+    /// Scroll offsets to restore once the pages have loaded, the first page's then each
+    /// tab's in order (from a recovered session)
+    pub restore_scroll: Vec<(f64, f64)>,
+    /// Index of the tab to show once all are open
+    pub active_tab: usize,
+    /// Page opened by the H key
+    pub homepage: Option<String>,
+    /// Key bindings
+    pub keymap: Keymap,
+    /// Search URL template for URL line input that isn't an address
+    pub search_engine: Option<String>,
+    /// Chrome executable from the config file; `None` finds one
+    pub chrome_path: Option<PathBuf>,
+    /// Screenshot format requested from the browser
+    pub capture_format: ImageFormat,
+    /// Capture frame rate when polling, for formats the browser can't stream (WebP)
+    pub fps: u32,
+    /// JPEG/WebP capture quality (0-100)
+    pub capture_quality: u8,
+    /// Cap terminal output at this many KiB per second
+    pub max_bandwidth: Option<u32>,
+    /// Browser viewport size in CSS pixels; `None` sizes it to the terminal and follows resizes
+    pub viewport: Option<(u32, u32)>,
+    /// Render pipeline options
+    pub render: RenderConfig,
+}
 
 /// Main application orchestrator
 pub struct App {
     url: String,
+    options: AppOptions,
 }
 
 impl App {
-    pub fn new(url: String) -> Self {
-        Self { url }
+    pub fn new(url: String, options: AppOptions) -> Self {
+        Self { url, options }
     }
 
     /// Run the application
     pub async fn run(self) -> Result<()> {
         info!("Launching browser...");
-        let browser = launch_browser().await?;
+        let launch = LaunchOptions {
+            window_size: self.options.viewport.unwrap_or(DEFAULT_WINDOW_SIZE),
+            chrome_path: self.options.chrome_path.clone(),
+            ..if self.options.ephemeral {
+                LaunchOptions::ephemeral()
+            } else {
+                LaunchOptions::default()
+            }
+        };
+        // Declared before the browser, so on an early return Chrome is killed first
+        let profile =
+            ProfileGuard::new(self.options.ephemeral.then(|| launch.user_data_dir.clone()));
+        let browser = launch_browser_with(&launch).await?;
 
         info!("Initializing terminal...");
-        let terminal = Terminal::new()?;
+        let terminal = match self.options.inline_rows {
+            Some(rows) => Terminal::inline(rows)?,
+            None => Terminal::new()?,
+        };
         let (width, height) = terminal.size()?;
         info!("Terminal size: {}x{}", width, height);
 
-        let renderer = Renderer::new();
-        let pipeline = RenderPipeline::new();
-        let mut streamer = ScreenshotStreamer::new(browser, self.url.clone());
+        let mut render = self.options.render.clone();
+        // Cells are rarely exactly 1:2; keep the nominal shape if the terminal won't say
+        let cell_size = match query_cell_size(width, height, QUERY_TIMEOUT) {
+            Some((cell_width, cell_height)) => {
+                info!(
+                    "Terminal cell size: {:.1}x{:.1} pixels",
+                    cell_width, cell_height
+                );
+                if render.cell_aspect.is_some() {
+                    render.cell_aspect = Some(cell_height / cell_width);
+                }
+                (cell_width, cell_height)
+            }
+            None => {
+                info!("Terminal did not report its pixel size");
+                (NOMINAL_CELL_WIDTH, NOMINAL_CELL_WIDTH * NOMINAL_CELL_ASPECT)
+            }
+        };
+        let pipeline = Arc::new(RenderPipeline::with_config(render));
+        let passthrough = Passthrough::detect();
+        if passthrough.is_tmux() {
+            info!("Running inside tmux; graphics and queries need `allow-passthrough on`");
+        }
+        let caps = detect_capabilities(QUERY_TIMEOUT, passthrough);
+        info!("Terminal capabilities: {:?}", caps);
+        let output = match &self.options.render_to {
+            Some(path) => {
+                info!(
+                    "Rendering to {} (a FIFO waits for a reader)",
+                    path.display()
+                );
+                Output::file(path)?
+            }
+            None => Output::stdout(),
+        };
+        let mut renderer = Renderer::with_writer(output)
+            .with_synchronized_update(caps.synchronized_update)
+            .with_passthrough(passthrough);
+        let top = terminal.region().map_or(0, |(top, _)| top);
+        if let Some((top, rows)) = terminal.region() {
+            renderer = renderer.with_region(top, rows);
+        }
+        if let Some(max_bandwidth) = self.options.max_bandwidth {
+            renderer = renderer.with_bandwidth_limit(max_bandwidth as u64 * 1024);
+        }
+        if self.options.match_background {
+            // Draw page areas in the terminal's background color with its default background
+            match query_background(QUERY_TIMEOUT) {
+                Some(rgb) => {
+                    let color = pipeline.quantizer().quantize(rgb);
+                    info!("Terminal background {:?} -> ANSI {}", rgb, color.as_u8());
+                    renderer = renderer.with_transparent_background(color);
+                }
+                None => info!("Terminal did not report its background color"),
+            }
+        }
+        let mut streamer = ScreenshotStreamer::new(browser, self.url.clone())
+            .with_format(self.options.capture_format);
+        streamer.set_quality(self.options.capture_quality);
+        let fps = self.options.fps.max(1);
+        let frame_interval = Duration::from_millis(1000 / fps as u64);
+
+        // Shared with the input task, which repaints everything after a suspend
+        let renderer = Arc::new(renderer);
 
         // Show loading message
         renderer.clear()?;
-        print!("\x1b[{};{}H\x1b[1;37mtoasting...\x1b[0m", height / 2, (width / 2).saturating_sub(5));
-        std::io::stdout().flush()?;
+        renderer.message(
+            (width / 2).saturating_sub(5),
+            (top as usize + height / 2).saturating_sub(1),
+            "toasting...",
+        )?;
 
         // Initialize the page
         streamer.initialize().await?;
+        let restore_scroll = |index: usize| {
+            let scroll = self.options.restore_scroll.get(index).copied();
+            let streamer = &streamer;
+            async move {
+                if let Some((x, y)) = scroll {
+                    if let Err(e) = streamer.scroll_to(x, y).await {
+                        error!("Failed to restore scroll position: {}", e);
+                    }
+                }
+            }
+        };
+        restore_scroll(0).await;
+
+        // Further pages open in tabs after the first, which stays shown
+        for (index, url) in self.options.tab_urls.iter().enumerate() {
+            let opened = match streamer.open_tab().await {
+                Ok(()) => streamer.navigate(url).await,
+                Err(e) => Err(e),
+            };
+            match opened {
+                Ok(()) => restore_scroll(index + 1).await,
+                Err(e) => error!("Failed to open {} in a tab: {:#}", url, e),
+            }
+        }
+        if !self.options.tab_urls.is_empty() {
+            // Tabs that failed to open shift the rest down
+            let last = streamer.tabs().0.len().saturating_sub(1);
+            streamer.switch_tab(self.options.active_tab.min(last))?;
+        }
 
         // Wrap streamer in Arc for sharing between tasks
         let streamer = Arc::new(streamer);
 
+        // The tab bar is shown once there is more than one tab
+        let tab_rows = if self.options.tab_bar || streamer.tabs().0.len() > 1 {
+            TAB_ROWS
+        } else {
+            0
+        };
+        let status_rows = if self.options.status_bar {
+            STATUS_ROWS
+        } else {
+            0
+        };
+        let layout = Layout {
+            top: tab_rows,
+            bottom: status_rows,
+        };
+
+        // Size of the page area in cells, updated by the input task when the terminal is resized
+        let size = Arc::new(Mutex::new((width, layout.page_height(height))));
+        // Updated by the input task as the tab bar comes and goes
+        let layout = Arc::new(Mutex::new(layout));
+        // Unless the profile fixes it, the viewport takes the page area's shape in pixels, so
+        // the page is neither cut nor stretched and clicks land where they're aimed
+        let viewport_cell = self.options.viewport.is_none().then_some(cell_size);
+        let mut viewport = launch.window_size;
+        if let Some((cell_width, cell_height)) = viewport_cell {
+            let (cols, rows) = current_size(&size);
+            let (page_width, page_height) =
+                grid_viewport(cols, rows, cell_width, cell_height / cell_width);
+            match streamer.set_viewport(page_width, page_height).await {
+                Ok(()) => viewport = (page_width, page_height),
+                Err(e) => error!("Failed to size viewport to the terminal: {}", e),
+            }
+        }
+        let viewport = Arc::new(Mutex::new(viewport));
+        // Wakes the render task to render the last screenshot again at the new size
+        let rerender = Arc::new(Notify::new());
+
         // Cursor position (shared between keyboard and display tasks)
         let cursor_pos = Arc::new(Mutex::new(CursorPosition {
             x: width / 2,
             y: height / 2,
         }));
 
+        // Latency diagnostic state (shared between capture, render, display and keyboard tasks)
+        let latency = Arc::new(Mutex::new(LatencyProbe::default()));
+
+        // Transient notice drawn over the top row (shared with the display task)
+        let notice: Arc<Mutex<Option<Notice>>> = Arc::new(Mutex::new(None));
+
+        // Latest screenshot handed to the OCR thread, and its most recent result
+        let ocr_input: Arc<Mutex<Option<Screenshot>>> = Arc::new(Mutex::new(None));
+        let ocr_output: Arc<Mutex<Option<OcrResult>>> = Arc::new(Mutex::new(None));
+        if self.options.ocr {
+            spawn_ocr_thread(
+                Arc::clone(&ocr_input),
+                Arc::clone(&ocr_output),
+                Arc::clone(&notice),
+            );
+        }
+
+        // Set by the P key: no captures are taken and the display keeps its last frame
+        let paused = Arc::new(AtomicBool::new(false));
+        // Set by the I key: key presses go to the page until Ctrl+G
+        let typing = Arc::new(AtomicBool::new(false));
+        // Wakes the display task to redraw its last frame (pause badge, cursor while paused)
+        let redraw = Arc::new(Notify::new());
+        // Toggled by the G key: draw the cell grid debug overlay
+        let grid = Arc::new(AtomicBool::new(self.options.debug_grid));
+        // Toggled by F12: draw the stats overlay, fed by the capture counters
+        let stats = Arc::new(AtomicBool::new(self.options.stats));
+        let counters = Arc::new(CaptureCounters::default());
+
+        // Set while a navigation's first frame is pending, to draw a placeholder instead
+        let navigation: Arc<Mutex<Option<PendingNavigation>>> = Arc::new(Mutex::new(None));
+
+        // The command or URL line while it is open (edited by the input task, drawn by the display)
+        let command_line = self
+            .options
+            .url_prompt
+            .then(|| CommandLine::new(LineMode::Url, &LineHistory::default()));
+        let command_line = Arc::new(Mutex::new(command_line));
+
         // Channels for async pipeline
         let (screenshot_tx, mut screenshot_rx) = mpsc::channel(2);
         let (frame_tx, mut frame_rx) = mpsc::channel(1);
 
-        // Screenshot capture task - runs at 15fps interval
+        // Screenshot capture task - the browser pushes frames as the page changes, or for
+        // formats it can't stream, screenshots are polled at the profile's frame rate (15fps
+        // by default)
         let screenshot_task = {
             let streamer = Arc::clone(&streamer);
+            let latency = Arc::clone(&latency);
+            let ocr_input = self.options.ocr.then(|| Arc::clone(&ocr_input));
+            let paused = Arc::clone(&paused);
+            let counters = Arc::clone(&counters);
+            let pipeline = Arc::clone(&pipeline);
+            let size = Arc::clone(&size);
+            let viewport = Arc::clone(&viewport);
             tokio::spawn(async move {
-                let mut interval = tokio::time::interval(Duration::from_millis(FRAME_INTERVAL_MS));
+                // Hand a screenshot on to OCR and the render task
+                let deliver = |screenshot: Screenshot| {
+                    counters.captured.fetch_add(1, Ordering::Relaxed);
+
+                    // Cheap: screenshot data is reference counted
+                    if let Some(ocr_input) = &ocr_input {
+                        if let Ok(mut latest) = ocr_input.lock() {
+                            *latest = Some(screenshot.clone());
+                        }
+                    }
+
+                    // Use try_send for backpressure - drop frame if channel full
+                    if screenshot_tx.try_send(screenshot).is_err() {
+                        info!("Dropped screenshot frame (channel full)");
+                        counters.dropped.fetch_add(1, Ordering::Relaxed);
+                    }
+                };
+
+                let mut interval = tokio::time::interval(frame_interval);
                 interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
 
-                loop {
-                    interval.tick().await;
+                let mut screencast = match streamer.screencast().await {
+                    Ok(screencast) => screencast,
+                    Err(e) => {
+                        info!("Polling for screenshots: {:#}", e);
+                        loop {
+                            interval.tick().await;
+                            if paused.load(Ordering::Relaxed) {
+                                continue;
+                            }
+
+                            // Zoomed in, only the part shown is captured, in more detail
+                            let (width, height) = current_viewport(&viewport);
+                            let (cols, rows) = current_size(&size);
+                            streamer.set_clip(pipeline.zoom_clip(width, height, cols, rows));
 
-                    match streamer.capture().await {
-                        Ok(screenshot) => {
-                            // Use try_send for backpressure - drop frame if channel full
-                            if screenshot_tx.try_send(screenshot).is_err() {
-                                info!("Dropped screenshot frame (channel full)");
+                            let started = Instant::now();
+                            match streamer.capture().await {
+                                Ok(screenshot) => {
+                                    if let Ok(mut probe) = latency.lock() {
+                                        probe.capture = started.elapsed();
+                                    }
+                                    deliver(screenshot);
+                                }
+                                Err(e) => {
+                                    error!("Failed to capture screenshot: {}", e);
+                                }
                             }
                         }
-                        Err(e) => {
-                            error!("Failed to capture screenshot: {}", e);
+                    }
+                };
+
+                // A frame drawn while paused, shown once unpaused: the page may not draw
+                // again for a while
+                let mut held: Option<Screenshot> = None;
+                // The last frame delivered, sent again to refine it if it was drawn as a draft
+                let mut last: Option<Screenshot> = None;
+                loop {
+                    tokio::select! {
+                        frame = screencast.next() => match frame {
+                            Some(Ok(screenshot)) => {
+                                if let Ok(mut probe) = latency.lock() {
+                                    probe.capture = screenshot
+                                        .metadata
+                                        .captured_at
+                                        .and_then(|at| at.elapsed().ok())
+                                        .unwrap_or_default();
+                                }
+                                if paused.load(Ordering::Relaxed) {
+                                    held = Some(screenshot);
+                                } else {
+                                    last = Some(screenshot.clone());
+                                    deliver(screenshot);
+                                }
+                            }
+                            Some(Err(e)) => error!("Failed to receive screencast frame: {}", e),
+                            None => break,
+                        },
+                        _ = tokio::time::sleep(DRAFT_REFINE_DELAY), if last.is_some() => {
+                            if let Some(screenshot) = last.take() {
+                                if pipeline.showing_draft() && !paused.load(Ordering::Relaxed) {
+                                    deliver(screenshot);
+                                }
+                            }
+                        }
+                        _ = interval.tick(), if held.is_some() => {
+                            if !paused.load(Ordering::Relaxed) {
+                                if let Some(screenshot) = held.take() {
+                                    last = Some(screenshot.clone());
+                                    deliver(screenshot);
+                                }
+                            }
                         }
                     }
                 }
+                info!("Screencast ended");
             })
         };
 
         // Render task - CPU-intensive processing
         let render_task = {
+            let latency = Arc::clone(&latency);
+            let pipeline = Arc::clone(&pipeline);
+            let streamer = Arc::clone(&streamer);
+            let notice = Arc::clone(&notice);
+            let configured_filter = self.options.render.filter;
+            let configured_quality = self.options.capture_quality;
+            let stats_log_frames = fps as u64 * STATS_LOG_PERIOD_SECS;
+            let size = Arc::clone(&size);
+            let rerender = Arc::clone(&rerender);
+            let mut governor = self
+                .options
+                .adaptive_quality
+                .then(|| QualityGovernor::new(frame_interval));
             tokio::spawn(async move {
-                while let Some(screenshot) = screenshot_rx.recv().await {
-                    // Render in blocking thread pool
-                    let pipeline_clone = pipeline.clone();
+                // Last screenshot rendered, for redrawing it after a resize
+                let mut last: Option<Screenshot> = None;
+                loop {
+                    let screenshot = tokio::select! {
+                        screenshot = screenshot_rx.recv() => match screenshot {
+                            Some(screenshot) => screenshot,
+                            None => break,
+                        },
+                        _ = rerender.notified() => match &last {
+                            Some(screenshot) => screenshot.clone(),
+                            None => continue,
+                        },
+                    };
+                    last = Some(screenshot.clone());
+                    let (width, height) = current_size(&size);
+
+                    // Render in blocking thread pool (shared pipeline keeps its LUT and scaler buffers)
+                    let pipeline_clone = Arc::clone(&pipeline);
+                    let latency = Arc::clone(&latency);
                     match tokio::task::spawn_blocking(move || {
-                        pipeline_clone.render(&screenshot, width, height)
+                        let frame = pipeline_clone.render(&screenshot, width, height);
+                        let stats = pipeline_clone.stats();
+                        if let Ok(mut probe) = latency.lock() {
+                            probe.stages = stats;
+                        }
+                        if frame.is_ok() && stats.frames.is_multiple_of(stats_log_frames) {
+                            info!(
+                                "Frame {} ({} unchanged so far) timings: {}",
+                                stats.frames, stats.unchanged, stats
+                            );
+                        }
+                        frame
                     })
                     .await
                     {
                         Ok(Ok(frame)) => {
+                            // Feed render times back into the pipeline and capture settings
+                            let level = governor
+                                .as_mut()
+                                .and_then(|g| g.observe(pipeline.stats().total()));
+                            if let Some(level) = level {
+                                info!("Render quality now {}", level.name());
+                                pipeline.set_scale_filter(level.scale_filter(configured_filter));
+                                streamer.set_quality(level.capture_quality(configured_quality));
+                                if let Ok(mut current) = notice.lock() {
+                                    *current = Some(Notice::new(
+                                        format!("Render quality: {}", level.name()),
+                                        NOTICE_DURATION,
+                                    ));
+                                }
+                            }
+
                             // Send to display task
                             if frame_tx.send(frame).await.is_err() {
                                 error!("Display task disconnected");
@@ -115,159 +581,506 @@ impl App {
         // Display task - write to terminal with cursor overlay
         let display_task = {
             let cursor_pos: Arc<Mutex<CursorPosition>> = Arc::clone(&cursor_pos);
+            let latency = Arc::clone(&latency);
+            let streamer = Arc::clone(&streamer);
+            let notice = Arc::clone(&notice);
+            let ocr_output = Arc::clone(&ocr_output);
+            let pipeline = Arc::clone(&pipeline);
+            let interpolate = self.options.interpolate;
+            let max_redraw_fps = self.options.max_redraw_fps;
+            let overlay_opacity = self.options.overlay_opacity;
+            let renderer = Arc::clone(&renderer);
+            let paused = Arc::clone(&paused);
+            let typing = Arc::clone(&typing);
+            let redraw = Arc::clone(&redraw);
+            let navigation = Arc::clone(&navigation);
+            let size = Arc::clone(&size);
+            let command_line = Arc::clone(&command_line);
+            let grid = Arc::clone(&grid);
+            let stats = Arc::clone(&stats);
+            let counters = Arc::clone(&counters);
+            let viewport = Arc::clone(&viewport);
+            let layout = Arc::clone(&layout);
+            let title_format = self.options.title_format.clone();
+            let load_bell = self.options.load_bell;
+            let scrollbar = self.options.scrollbar;
             tokio::spawn(async move {
-                while let Some(mut frame) = frame_rx.recv().await {
-                    // Overlay cursor on the frame - draw classic arrow pointer using half blocks
+                // Everything drawn over and around the page: OCR text, the debug grid, notices,
+                // the pause badge, the cursor, the tab and status bars and the command line
+                let quantizer = Arc::clone(pipeline.quantizer());
+                let frame_stats = Mutex::new(FrameStats::new(Instant::now()));
+                let arrow = arrow_cursor();
+                let load_watcher = Mutex::new(LoadWatcher::new(load_bell));
+                let decorate = |page: TerminalFrame| -> TerminalFrame {
+                    let layout = *layout.lock().unwrap_or_else(|e| e.into_inner());
+                    let overlay = OverlayStyle::new(&quantizer, overlay_opacity);
+                    let mut compositor = Compositor::new(layout);
+                    let flashing = load_watcher
+                        .lock()
+                        .is_ok_and(|watcher| watcher.flashing(Instant::now()));
+
+                    // Overlay OCR-recognized text
+                    compositor.add(Layer::Overlay, |frame| {
+                        if let Ok(result) = ocr_output.lock() {
+                            if let Some(result) = result.as_ref() {
+                                result.overlay(frame);
+                            }
+                        }
+                    });
+
+                    if scrollbar {
+                        if let Some(bar) = Scrollbar::from_metadata(&page.metadata) {
+                            compositor.add(Layer::Overlay, move |frame| bar.draw(frame));
+                        }
+                    }
+
+                    if grid.load(Ordering::Relaxed) {
+                        compositor.add(Layer::Overlay, |frame| {
+                            let mapping = CellMapping::new(
+                                &pipeline,
+                                current_viewport(&viewport),
+                                (frame.width, frame.height),
+                            );
+                            let cursor = cursor_pos
+                                .lock()
+                                .map(|pos| (pos.x, pos.y))
+                                .unwrap_or_default();
+                            draw_grid(frame, &overlay, &mapping, cursor);
+                        });
+                    }
+
+                    if stats.load(Ordering::Relaxed) {
+                        compositor.add(Layer::Overlay, |frame| {
+                            if let Ok(mut frame_stats) = frame_stats.lock() {
+                                frame_stats.update(
+                                    Instant::now(),
+                                    &counters,
+                                    renderer.bytes_written(),
+                                );
+                                frame_stats.draw(frame, &overlay);
+                            }
+                        });
+                    }
+
+                    // Draw the current notice, dropping it once it has expired
+                    compositor.add(Layer::Overlay, |frame| {
+                        if let Ok(mut current) = notice.lock() {
+                            if current.as_ref().is_some_and(Notice::is_expired) {
+                                *current = None;
+                            }
+                            if let Some(n) = current.as_ref() {
+                                n.draw(frame, &overlay);
+                            }
+                        }
+                    });
+
+                    // The status bar says so when it's shown
+                    if paused.load(Ordering::Relaxed) && layout.bottom == 0 {
+                        compositor.add(Layer::Overlay, |frame| {
+                            draw_badge(frame, &overlay, " PAUSED ")
+                        });
+                    }
+                    if flashing && layout.bottom == 0 {
+                        compositor.add(Layer::Overlay, |frame| {
+                            draw_badge(frame, &overlay, " LOADED ")
+                        });
+                    }
+
                     if let Ok(pos) = cursor_pos.lock() {
-                        // Classic arrow cursor using half blocks (2 pixel rows per char row):
-                        // █▄           (row 0: pixels 0-1)
-                        // ███▄         (row 1: pixels 2-3)
-                        // █████▄       (row 2: pixels 4-5)
-                        // ▀ ██         (row 3: pixels 6-7)
-                        //    ▀         (row 4: pixel 8)
-
-                        let black = AnsiColor(16);
-
-                        // Helper to set cursor cell with specific character (all black)
-                        let mut set_cell = |x: usize, y: usize, ch: char| {
-                            if x < frame.width && y < frame.height {
-                                frame.set(x, y, TerminalCell {
-                                    character: ch,
-                                    foreground: black,
-                                    background: black,
-                                });
+                        compositor.add_sprite(Layer::Cursor, &arrow, pos.x, pos.y);
+                    }
+
+                    if layout.top > 0 {
+                        compositor.add(Layer::Bars, |frame| {
+                            let (tabs, active) = streamer.tabs();
+                            let tabs = tabs
+                                .iter()
+                                .map(|tab| TabLabel {
+                                    title: tab.label().to_string(),
+                                    retitled: tab.retitled,
+                                    audible: tab.audible,
+                                    muted: tab.muted,
+                                })
+                                .collect();
+                            TabBar { tabs, active }.draw(frame, 0);
+                        });
+                    }
+                    if layout.bottom > 0 {
+                        compositor.add(Layer::Bars, |frame| {
+                            let status = StatusBar {
+                                url: frame.metadata.url.clone().unwrap_or_default(),
+                                title: frame.metadata.title.clone(),
+                                loading: frame.metadata.loading,
+                                modes: status_modes(&pipeline, &paused, &typing, &grid),
+                                flash: flashing,
+                            };
+                            let y = frame.height - 1;
+                            status.draw(frame, y);
+                        });
+                    }
+
+                    // Over the status bar, or the bottom row of the page without one
+                    compositor.add(Layer::Dialog, |frame| {
+                        if let Ok(line) = command_line.lock() {
+                            if let Some(line) = line.as_ref() {
+                                line.draw(frame, &overlay);
                             }
-                        };
+                        }
+                    });
 
-                        // Row 0: █▄ (pixels 0,1 at x=0; pixel 1 at x=1)
-                        set_cell(pos.x, pos.y, '█');
-                        set_cell(pos.x + 1, pos.y, '▄');
+                    // Written straight away, but only when the title changes
+                    if let Some(format) = &title_format {
+                        if page.metadata.url.is_some() {
+                            let title = format_title(format, &page.metadata);
+                            if let Err(e) = renderer.set_title(&title) {
+                                error!("Failed to set terminal title: {}", e);
+                            }
+                        }
+                    }
 
-                        // Row 1: ███▄ (pixels 2,3 full at x=0,1,2; pixel 3 at x=3)
-                        set_cell(pos.x, pos.y + 1, '█');
-                        set_cell(pos.x + 1, pos.y + 1, '█');
-                        set_cell(pos.x + 2, pos.y + 1, '█');
-                        set_cell(pos.x + 3, pos.y + 1, '▄');
+                    compositor.compose(page)
+                };
 
-                        // Row 2: █████▄ (pixels 4,5 full at x=0-4; pixel 5 at x=5)
-                        set_cell(pos.x, pos.y + 2, '█');
-                        set_cell(pos.x + 1, pos.y + 2, '█');
-                        set_cell(pos.x + 2, pos.y + 2, '█');
-                        set_cell(pos.x + 3, pos.y + 2, '█');
-                        set_cell(pos.x + 4, pos.y + 2, '█');
-                        set_cell(pos.x + 5, pos.y + 2, '▄');
+                // Last page frame and when it arrived, the start of the next cross-fade
+                let mut previous: Option<(TerminalFrame, Instant)> = None;
+                // Last page frame shown, undecorated, for redraws
+                let mut shown: Option<TerminalFrame> = None;
+                let mut pacer = FramePacer::new(max_redraw_fps);
+                // Set when frames were dropped while paused, whose damage the next one lacks
+                let mut dropped = false;
 
-                        // Row 3: ▀ ██ (pixel 6 at x=0; pixels 6,7 at x=3,4)
-                        set_cell(pos.x, pos.y + 3, '▀');
-                        set_cell(pos.x + 3, pos.y + 3, '█');
-                        set_cell(pos.x + 4, pos.y + 3, '█');
+                loop {
+                    let loading = navigation.lock().map(|n| n.is_some()).unwrap_or(false);
+                    let held_back = renderer.pending_wait();
+                    let mut frame = tokio::select! {
+                        frame = frame_rx.recv() => match frame {
+                            Some(frame) => frame,
+                            None => break,
+                        },
+                        // Keep the spinner turning even if no frames arrive
+                        _ = tokio::time::sleep(PLACEHOLDER_REFRESH), if loading => {
+                            let (width, height) = current_size(&size);
+                            let placeholder = navigation
+                                .lock()
+                                .ok()
+                                .and_then(|n| n.as_ref().map(|n| n.draw(width, height)));
+                            if let Some(placeholder) = placeholder {
+                                if let Err(e) = renderer.render(decorate(placeholder)) {
+                                    error!("Failed to render to terminal: {}", e);
+                                }
+                            }
+                            continue;
+                        }
+                        // Show the last frame held back by the bandwidth limit once it allows
+                        _ = tokio::time::sleep(held_back.unwrap_or_default()),
+                            if held_back.is_some() =>
+                        {
+                            if let Err(e) = renderer.render_pending() {
+                                error!("Failed to render to terminal: {}", e);
+                            }
+                            continue;
+                        }
+                        _ = redraw.notified() => {
+                            if let Some(frame) = shown.clone() {
+                                if let Err(e) = renderer.render(decorate(frame)) {
+                                    error!("Failed to render to terminal: {}", e);
+                                }
+                            }
+                            continue;
+                        }
+                    };
+                    // Hold to the redraw rate, then skip to the newest of the frames that
+                    // piled up meanwhile or during a stall
+                    let wait = pacer.delay(Instant::now());
+                    if !wait.is_zero() {
+                        tokio::time::sleep(wait).await;
+                    }
+                    while let Ok(newer) = frame_rx.try_recv() {
+                        frame = TerminalFrame::coalesce(frame, newer);
+                    }
+                    pacer.drawn(Instant::now());
+                    // Frames still in flight when pausing are dropped to keep the display frozen,
+                    // but one redrawn for a new terminal size replaces the frozen frame
+                    let resized = shown
+                        .as_ref()
+                        .is_some_and(|s| (s.width, s.height) != (frame.width, frame.height));
+                    if paused.load(Ordering::Relaxed) && !resized {
+                        dropped = true;
+                        continue;
+                    }
+                    if std::mem::take(&mut dropped) {
+                        frame.damage = None;
+                        frame.scroll = None;
+                    }
+                    let received = Instant::now();
 
-                        // Row 4:    ▀ (pixel 8 at x=4)
-                        set_cell(pos.x + 4, pos.y + 4, '▀');
+                    // Check for the latency marker before anything is drawn over it
+                    let outcome = latency
+                        .lock()
+                        .ok()
+                        .and_then(|mut probe| probe.check_frame(&frame, received));
+                    if let Some(outcome) = outcome {
+                        if let ProbeOutcome::Report(report) = outcome {
+                            info!("Latency: {}", report);
+                        }
+                        let streamer = Arc::clone(&streamer);
+                        let latency = Arc::clone(&latency);
+                        tokio::spawn(async move {
+                            if let Err(e) = streamer.hide_latency_marker().await {
+                                error!("Failed to remove latency marker: {}", e);
+                                if let Ok(mut probe) = latency.lock() {
+                                    probe.abandon();
+                                }
+                            }
+                        });
+                    }
+
+                    // Until the new page has been captured, frames show the old one
+                    let placeholder = navigation.lock().ok().and_then(|mut n| {
+                        if n.as_ref().is_some_and(|n| n.is_shown_by(&frame.metadata)) {
+                            *n = None;
+                        }
+                        n.as_ref().map(|n| n.draw(frame.width, frame.height))
+                    });
+                    if let Some(placeholder) = placeholder {
+                        if let Ok(mut watcher) = load_watcher.lock() {
+                            watcher.observe(true, received);
+                        }
+                        // Don't fade from the old page into the new one
+                        previous = None;
+                        if let Err(e) = renderer.render(decorate(placeholder)) {
+                            error!("Failed to render to terminal: {}", e);
+                        }
+                        continue;
+                    }
+
+                    let loaded = load_watcher
+                        .lock()
+                        .is_ok_and(|mut watcher| watcher.observe(frame.metadata.loading, received));
+                    if loaded {
+                        if let Err(e) = renderer.bell() {
+                            error!("Failed to ring the terminal bell: {}", e);
+                        }
+                    }
+
+                    // Frames arrive slower than the display cadence: fade in the new one
+                    if interpolate {
+                        if let Some((from, arrived)) = &previous {
+                            let steps = interpolation_steps(received.duration_since(*arrived));
+                            for step in 1..=steps {
+                                let t = step as f32 / (steps + 1) as f32;
+                                let Some(blended) =
+                                    blend_frames(from, &frame, t, pipeline.quantizer())
+                                else {
+                                    break;
+                                };
+                                if let Err(e) = renderer.render(decorate(blended)) {
+                                    error!("Failed to render to terminal: {}", e);
+                                }
+                                tokio::time::sleep(INTERPOLATION_CADENCE).await;
+                                // Never hold back a newer frame
+                                if !frame_rx.is_empty() {
+                                    break;
+                                }
+                            }
+                        }
+                        previous = Some((frame.clone(), received));
                     }
 
-                    if let Err(e) = renderer.render(frame) {
+                    shown = Some(frame.clone());
+                    if let Ok(mut frame_stats) = frame_stats.lock() {
+                        frame_stats.frame_shown(&frame.metadata, SystemTime::now());
+                    }
+                    if let Err(e) = renderer.render(decorate(frame)) {
                         error!("Failed to render to terminal: {}", e);
                     }
                 }
             })
         };
 
-        info!("Rendering started. Use arrow keys to move cursor, W/S to scroll, Enter to click, Ctrl+C to exit.");
-
-        // Channel for shutdown signal and click events
-        let (shutdown_tx, mut shutdown_rx) = mpsc::channel(1);
-        let (click_tx, mut click_rx) = mpsc::channel(10);
+        // Shared so user navigations (home) and links the user follows aren't reported as
+        // redirects
+        let watcher = Arc::new(Mutex::new(RedirectWatcher::new(self.url.clone())));
 
-        // Keyboard input task - handle arrow keys, W/S scroll, Enter, and Ctrl+C
-        let keyboard_task = {
-            let shutdown_tx = shutdown_tx.clone();
-            let cursor_pos: Arc<Mutex<CursorPosition>> = Arc::clone(&cursor_pos);
+        // Redirect watcher task - polls the page URL to catch meta refresh / JS redirects
+        let redirect_task = {
             let streamer = Arc::clone(&streamer);
+            let notice = Arc::clone(&notice);
+            let watcher = Arc::clone(&watcher);
             tokio::spawn(async move {
+                let mut interval = tokio::time::interval(Duration::from_millis(REDIRECT_POLL_MS));
+                interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+
                 loop {
-                    // Poll for events with timeout
-                    if let Ok(true) = event::poll(Duration::from_millis(16)) {
-                        if let Ok(Event::Key(key_event)) = event::read() {
-                        // Only handle key press events, not release or repeat
-                        if key_event.kind == KeyEventKind::Press {
-                            match key_event.code {
-                                KeyCode::Char('c') if key_event.modifiers.contains(KeyModifiers::CONTROL) => {
-                                    info!("Ctrl+C detected from keyboard");
-                                    let _ = shutdown_tx.send(()).await;
-                                    break;
-                                }
-                                KeyCode::Char('w') | KeyCode::Char('W') => {
-                                    let _ = streamer.scroll(-400).await;
-                                }
-                                KeyCode::Char('s') | KeyCode::Char('S') => {
-                                    let _ = streamer.scroll(400).await;
-                                }
-                                KeyCode::Up => {
-                                    if let Ok(mut pos) = cursor_pos.lock() {
-                                        if pos.y > 0 {
-                                            pos.y -= 1;
-                                        }
-                                    }
-                                }
-                                KeyCode::Down => {
-                                    if let Ok(mut pos) = cursor_pos.lock() {
-                                        if pos.y < height - 1 {
-                                            pos.y += 1;
-                                        }
-                                    }
-                                }
-                                KeyCode::Left => {
-                                    if let Ok(mut pos) = cursor_pos.lock() {
-                                        if pos.x > 0 {
-                                            pos.x -= 1;
-                                        }
-                                    }
-                                }
-                                KeyCode::Right => {
-                                    if let Ok(mut pos) = cursor_pos.lock() {
-                                        if pos.x < width - 1 {
-                                            pos.x += 1;
-                                        }
-                                    }
-                                }
-                                KeyCode::Enter => {
-                                    // Copy values out of the mutex before await
-                                    let coords = cursor_pos.lock().map(|pos| (pos.x, pos.y)).ok();
-                                    if let Some((x, y)) = coords {
-                                        info!("Enter pressed - sending click at terminal ({}, {})", x, y);
-                                        let _ = click_tx.send((x, y)).await;
-                                    }
-                                }
-                                _ => {}
+                    interval.tick().await;
+
+                    let url = match streamer.current_url().await {
+                        Ok(Some(url)) => url,
+                        Ok(None) => continue,
+                        Err(e) => {
+                            error!("Failed to query page URL: {}", e);
+                            continue;
+                        }
+                    };
+
+                    let redirect = watcher.lock().ok().and_then(|mut w| w.observe(&url));
+                    if let Some(redirect) = redirect {
+                        info!("Page redirected: {} -> {}", redirect.from, redirect.to);
+                        // Only interrupt the user when the redirect left the original site
+                        if redirect.cross_site {
+                            if let Ok(mut current) = notice.lock() {
+                                *current = Some(Notice::new(
+                                    format!("Redirected to {}", redirect.to),
+                                    NOTICE_DURATION,
+                                ));
                             }
                         }
+                    }
+                }
+            })
+        };
+
+        // Tab task - marks tabs whose title changes out of sight, e.g. "(2) Inbox", and tabs
+        // playing sound
+        let tab_task = {
+            let streamer = Arc::clone(&streamer);
+            let notice = Arc::clone(&notice);
+            let redraw = Arc::clone(&redraw);
+            let notices = self.options.tab_notices;
+            tokio::spawn(async move {
+                let mut interval = tokio::time::interval(TAB_POLL_INTERVAL);
+                interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+
+                loop {
+                    interval.tick().await;
+
+                    let changes = match streamer.poll_tabs().await {
+                        Ok(changes) => changes,
+                        Err(e) => {
+                            error!("Failed to query tabs: {:#}", e);
+                            continue;
+                        }
+                    };
+                    if changes.is_empty() {
+                        continue;
+                    }
+                    redraw.notify_one();
+                    if let (true, Some((index, title))) = (notices, changes.retitled.last()) {
+                        if let Ok(mut current) = notice.lock() {
+                            *current = Some(Notice::new(
+                                format!("Tab {}: {}", index + 1, title),
+                                NOTICE_DURATION,
+                            ));
                         }
                     }
                 }
             })
         };
 
-        // Click handler task - sends clicks to the browser
-        let click_task = {
+        // Session autosave task - records where the user is, for recovery after a crash
+        let autosave_task = self.options.session_file.clone().map(|file| {
             let streamer = Arc::clone(&streamer);
             tokio::spawn(async move {
-                while let Some((x, y)) = click_rx.recv().await {
-                    // Convert terminal coordinates to browser viewport coordinates
-                    // Terminal char represents 2 vertical pixels (half-block)
-                    // Browser viewport is 1920x1080
-                    let browser_x = (x as f64 / width as f64) * 1920.0;
-                    let browser_y = (y as f64 / height as f64) * 1080.0;
+                let mut interval = tokio::time::interval(AUTOSAVE_INTERVAL);
+                interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+
+                loop {
+                    interval.tick().await;
+
+                    let (tabs, active) = match streamer.tab_positions().await {
+                        Ok(positions) => positions,
+                        Err(e) => {
+                            error!("Failed to query open tabs: {:#}", e);
+                            continue;
+                        }
+                    };
+                    let session = Session {
+                        tabs: tabs
+                            .into_iter()
+                            .map(|(url, scroll)| SessionTab { url, scroll })
+                            .collect(),
+                        active,
+                    };
+                    if let Err(e) = file.save(&session) {
+                        error!("Failed to autosave session: {:#}", e);
+                    }
+                }
+            })
+        });
+
+        info!("Rendering started. Use the mouse or arrow keys to point, W/S to scroll, Enter to click, O to open a URL, P to pause, Ctrl+Z to suspend, Ctrl+C to exit.");
 
-                    info!("Clicking at terminal ({}, {}) -> browser ({:.0}, {:.0})", x, y, browser_x, browser_y);
+        // Channel for shutdown signal and input for the page
+        let (shutdown_tx, mut shutdown_rx) = mpsc::channel(1);
+        let (input_tx, input_rx) = mpsc::channel(INPUT_QUEUE);
 
-                    if let Err(e) = streamer.click(browser_x, browser_y).await {
-                        error!("Failed to send click: {}", e);
+        // Input task - maps keys, mouse and resize events to actions and hands them to the dispatcher
+        let keyboard_task = {
+            let dispatcher = Dispatcher {
+                streamer: Arc::clone(&streamer),
+                pipeline: Arc::clone(&pipeline),
+                cursor_pos: Arc::clone(&cursor_pos),
+                latency: Arc::clone(&latency),
+                watcher: Arc::clone(&watcher),
+                notice: Arc::clone(&notice),
+                shutdown_tx,
+                input_tx,
+                paused: Arc::clone(&paused),
+                typing,
+                grid: Arc::clone(&grid),
+                stats: Arc::clone(&stats),
+                redraw: Arc::clone(&redraw),
+                navigation: Arc::clone(&navigation),
+                loading: Mutex::default(),
+                command_line: Arc::clone(&command_line),
+                history: Mutex::default(),
+                size: Arc::clone(&size),
+                layout: Arc::clone(&layout),
+                tab_bar: self.options.tab_bar,
+                region: terminal
+                    .region()
+                    .map(|(top, rows)| (top as usize, rows as usize)),
+                viewport: Arc::clone(&viewport),
+                viewport_cell,
+                rerender: Arc::clone(&rerender),
+                renderer: Arc::clone(&renderer),
+                homepage: self.options.homepage.clone(),
+                latency_test: self.options.latency_test,
+                wheel: self.options.wheel,
+                keymap: self.options.keymap,
+                search_engine: self.options.search_engine.clone(),
+            };
+            let mut suspend_rx = watch_suspend_signal();
+            tokio::spawn(async move {
+                loop {
+                    // Suspended from outside, with kill -TSTP
+                    if suspend_rx.try_recv().is_ok() {
+                        dispatcher.dispatch(Action::Suspend).await;
+                    }
+                    // Poll for events with timeout
+                    if let Ok(true) = event::poll(Duration::from_millis(16)) {
+                        if let Ok(event) = event::read() {
+                            if dispatcher.handle_event(&event).await == Flow::Quit {
+                                break;
+                            }
+                        }
                     }
                 }
             })
         };
 
+        // Input forwarding task - sends clicks, scrolls and mouse moves to the browser in order
+        let forward_task = tokio::spawn(
+            Forwarder {
+                streamer: Arc::clone(&streamer),
+                pipeline: Arc::clone(&pipeline),
+                viewport: Arc::clone(&viewport),
+                size: Arc::clone(&size),
+                watcher: Arc::clone(&watcher),
+            }
+            .run(input_rx),
+        );
+
         // Wait for shutdown signal (from keyboard or Ctrl+C signal)
         tokio::select! {
             _ = shutdown_rx.recv() => {
@@ -288,9 +1101,168 @@ impl App {
         drop(render_task);
         drop(display_task);
         drop(keyboard_task);
-        drop(click_task);
+        drop(forward_task);
+        drop(redirect_task);
+        drop(tab_task);
+
+        // A clean exit leaves no session behind; stop the autosave first so it can't rewrite it
+        if let Some(task) = autosave_task {
+            task.abort();
+            let _ = task.await;
+        }
+        if let Some(file) = &self.options.session_file {
+            if let Err(e) = file.clear() {
+                error!("Failed to clear session: {:#}", e);
+            }
+        }
+
+        // Leave nothing behind: close Chrome, then delete its throwaway profile
+        if self.options.ephemeral {
+            if let Err(e) = streamer.close_browser().await {
+                error!("Failed to close browser: {}", e);
+            }
+        }
+        profile.remove().await?;
 
         Ok(())
     }
 }
 
+/// Number of in-between frames for a cross-fade after a gap of `gap` between frames
+fn interpolation_steps(gap: Duration) -> u32 {
+    let steps = (gap.as_millis() / INTERPOLATION_CADENCE.as_millis()).saturating_sub(1);
+    steps.min(MAX_INTERPOLATION_STEPS as u128) as u32
+}
+
+/// Requests to suspend sent as signals, for the input task to handle like Ctrl+Z
+#[cfg(unix)]
+fn watch_suspend_signal() -> mpsc::Receiver<()> {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let (tx, rx) = mpsc::channel(1);
+    match signal(SignalKind::from_raw(toast_terminal::SUSPEND_SIGNAL)) {
+        Ok(mut signals) => {
+            tokio::spawn(async move {
+                while signals.recv().await.is_some() {
+                    let _ = tx.try_send(());
+                }
+            });
+        }
+        Err(e) => error!("Failed to catch SIGTSTP: {}", e),
+    }
+    rx
+}
+
+#[cfg(not(unix))]
+fn watch_suspend_signal() -> mpsc::Receiver<()> {
+    mpsc::channel(1).1
+}
+
+/// Mode indicators for the status bar
+fn status_modes(
+    pipeline: &RenderPipeline,
+    paused: &AtomicBool,
+    typing: &AtomicBool,
+    grid: &AtomicBool,
+) -> Vec<String> {
+    let mut modes = Vec::new();
+    let zoom = pipeline.zoom();
+    if !zoom.is_identity() {
+        modes.push(format!("ZOOM {:.1}x", zoom.scale()));
+    }
+    let filter = pipeline.color_filter();
+    if filter != ColorFilter::None {
+        modes.push(filter.name().to_ascii_uppercase());
+    }
+    if grid.load(Ordering::Relaxed) {
+        modes.push("GRID".to_string());
+    }
+    if typing.load(Ordering::Relaxed) {
+        modes.push("TYPING".to_string());
+    }
+    if paused.load(Ordering::Relaxed) {
+        modes.push("PAUSED".to_string());
+    }
+    modes
+}
+
+/// Draw a badge like " PAUSED " in the top-right corner (black on yellow, like notices)
+fn draw_badge(frame: &mut TerminalFrame, overlay: &OverlayStyle, badge: &str) {
+    let x = frame.width.saturating_sub(badge.len());
+    overlay.draw_text(frame, x, 0, badge, AnsiColor(16), AnsiColor(220));
+}
+
+/// The classic arrow pointer, drawn in half blocks (2 pixel rows per cell row) with
+/// its tip at the cursor position
+fn arrow_cursor() -> Sprite {
+    let black = AnsiColor(16);
+    Sprite::from_rows(
+        &[
+            "█▄",     // pixels 0-1
+            "███▄",   // pixels 2-3
+            "█████▄", // pixels 4-5
+            "▀  ██",  // pixels 6-7
+            "    ▀",  // pixel 8
+        ],
+        black,
+        black,
+    )
+}
+
+/// Run OCR on a dedicated thread (the engine is not `Send`), reading the latest screenshot
+fn spawn_ocr_thread(
+    input: Arc<Mutex<Option<Screenshot>>>,
+    output: Arc<Mutex<Option<OcrResult>>>,
+    notice: Arc<Mutex<Option<Notice>>>,
+) {
+    std::thread::spawn(move || {
+        let mut engine = match OcrEngine::new() {
+            Ok(engine) => engine,
+            Err(e) => {
+                error!("Failed to start OCR: {}", e);
+                if let Ok(mut current) = notice.lock() {
+                    *current = Some(Notice::new(
+                        format!("OCR unavailable: {}", e),
+                        NOTICE_DURATION,
+                    ));
+                }
+                return;
+            }
+        };
+
+        // Failures in a row, backed off from rather than retried and logged every interval
+        let mut failures = 0;
+        loop {
+            std::thread::sleep(OCR_INTERVAL * (1 << failures.min(OCR_MAX_BACKOFF)));
+
+            let Some(screenshot) = input.lock().ok().and_then(|mut latest| latest.take()) else {
+                continue;
+            };
+
+            match decode_screenshot(&screenshot)
+                .map_err(anyhow::Error::from)
+                .and_then(|image| engine.recognize(&image))
+            {
+                Ok(result) => {
+                    if failures > 0 {
+                        info!("OCR recovered");
+                        failures = 0;
+                    }
+                    if let Ok(mut current) = output.lock() {
+                        *current = Some(result);
+                    }
+                }
+                Err(e) => {
+                    if failures == 0 {
+                        error!("OCR failed, retrying less often until it recovers: {:#}", e);
+                    }
+                    failures += 1;
+                    // Words from an older page would be drawn over the current one
+                    if let Ok(mut current) = output.lock() {
+                        *current = None;
+                    }
+                }
+            }
+        }
+    });
+}
@@ -0,0 +1,116 @@
+use std::time::{Duration, Instant};
+
+/// How long the status bar flashes when a page finishes loading
+const FLASH_DURATION: Duration = Duration::from_millis(400);
+
+/// How a page finishing loading is announced, for users who switched away meanwhile
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LoadBell {
+    #[default]
+    Off,
+    /// Ring the terminal bell
+    Bell,
+    /// Flash the status bar
+    Flash,
+    Both,
+}
+
+impl LoadBell {
+    fn rings(self) -> bool {
+        matches!(self, LoadBell::Bell | LoadBell::Both)
+    }
+
+    fn flashes(self) -> bool {
+        matches!(self, LoadBell::Flash | LoadBell::Both)
+    }
+}
+
+impl std::str::FromStr for LoadBell {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "off" | "none" => Ok(LoadBell::Off),
+            "bell" => Ok(LoadBell::Bell),
+            "flash" => Ok(LoadBell::Flash),
+            "both" => Ok(LoadBell::Both),
+            _ => anyhow::bail!(
+                "unknown load bell '{}' (expected off, bell, flash or both)",
+                s
+            ),
+        }
+    }
+}
+
+/// Follows the load state of the frames shown to announce when a page has loaded
+#[derive(Debug)]
+pub struct LoadWatcher {
+    bell: LoadBell,
+    loading: bool,
+    flash_until: Option<Instant>,
+}
+
+impl LoadWatcher {
+    pub fn new(bell: LoadBell) -> Self {
+        Self {
+            bell,
+            loading: false,
+            flash_until: None,
+        }
+    }
+
+    /// Note whether the page in the latest frame is still `loading`; true when it has
+    /// just finished and the bell should ring
+    pub fn observe(&mut self, loading: bool, now: Instant) -> bool {
+        let finished = self.loading && !loading;
+        self.loading = loading;
+        if !finished {
+            return false;
+        }
+        if self.bell.flashes() {
+            self.flash_until = Some(now + FLASH_DURATION);
+        }
+        self.bell.rings()
+    }
+
+    /// Whether the status bar should be flashing at `now`
+    pub fn flashing(&self, now: Instant) -> bool {
+        self.flash_until.is_some_and(|until| now < until)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!("Flash".parse::<LoadBell>().unwrap(), LoadBell::Flash);
+        assert_eq!("none".parse::<LoadBell>().unwrap(), LoadBell::Off);
+        assert!("loud".parse::<LoadBell>().is_err());
+    }
+
+    #[test]
+    fn test_announces_finished_loads() {
+        let now = Instant::now();
+        let mut watcher = LoadWatcher::new(LoadBell::Both);
+        // Already loaded: nothing to announce
+        assert!(!watcher.observe(false, now));
+        assert!(!watcher.observe(true, now));
+        assert!(!watcher.flashing(now));
+
+        assert!(watcher.observe(false, now));
+        assert!(watcher.flashing(now + Duration::from_millis(100)));
+        assert!(!watcher.flashing(now + FLASH_DURATION));
+        assert!(!watcher.observe(false, now));
+    }
+
+    #[test]
+    fn test_off_stays_quiet() {
+        let now = Instant::now();
+        let mut watcher = LoadWatcher::new(LoadBell::Off);
+        watcher.observe(true, now);
+        assert!(!watcher.observe(false, now));
+        assert!(!watcher.flashing(now));
+    }
+}
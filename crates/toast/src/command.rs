@@ -0,0 +1,406 @@
+use anyhow::{Context, Result};
+use crossterm::event::{KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use toast_core::{AnsiColor, TerminalFrame};
+use toast_render::OverlayStyle;
+
+/// A command typed on the `:` line
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    /// `:cdp <method> [json-params]`: send a raw CDP command to the page
+    Cdp {
+        method: String,
+        params: serde_json::Value,
+    },
+    /// `:open <target>` (or `:o`): open a URL, host name or local file
+    Open { target: String },
+}
+
+impl Command {
+    /// Parse a command line, without its leading `:`
+    pub fn parse(line: &str) -> Result<Self> {
+        let line = line.trim();
+        let (name, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        match name {
+            "cdp" => {
+                let rest = rest.trim();
+                let (method, params) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+                anyhow::ensure!(!method.is_empty(), "Usage: cdp <method> [json-params]");
+                let params = match params.trim() {
+                    "" => serde_json::json!({}),
+                    params => serde_json::from_str(params).context("Invalid JSON parameters")?,
+                };
+                Ok(Command::Cdp {
+                    method: method.to_string(),
+                    params,
+                })
+            }
+            "open" | "o" => {
+                let target = rest.trim();
+                anyhow::ensure!(!target.is_empty(), "Usage: open <url>");
+                Ok(Command::Open {
+                    target: target.to_string(),
+                })
+            }
+            "" => anyhow::bail!("Empty command"),
+            _ => anyhow::bail!("Unknown command: {}", name),
+        }
+    }
+}
+
+/// What a key did to the command line
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LineEdit {
+    /// Still editing
+    Editing,
+    /// Closed without running anything
+    Cancel,
+    /// Enter was pressed on this text
+    Submit(String),
+    /// Not an editing key; handle it as usual
+    Ignored,
+}
+
+/// Most lines kept in each history
+const HISTORY_LIMIT: usize = 100;
+
+/// What the line is for, which decides its prompt and what Enter does
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LineMode {
+    /// A `:` command
+    #[default]
+    Command,
+    /// A URL, host name or local file to open (the `o` key)
+    Url,
+}
+
+impl LineMode {
+    fn prompt(self) -> &'static str {
+        match self {
+            LineMode::Command => ":",
+            LineMode::Url => "open: ",
+        }
+    }
+}
+
+/// Lines entered earlier, oldest first, kept separately for commands and URLs
+#[derive(Debug, Clone, Default)]
+pub struct LineHistory {
+    commands: Vec<String>,
+    urls: Vec<String>,
+}
+
+impl LineHistory {
+    /// Remember `line`, unless it is blank or repeats the last one
+    pub fn push(&mut self, mode: LineMode, line: &str) {
+        let entries = match mode {
+            LineMode::Command => &mut self.commands,
+            LineMode::Url => &mut self.urls,
+        };
+        let line = line.trim();
+        if line.is_empty() || entries.last().is_some_and(|last| last == line) {
+            return;
+        }
+        if entries.len() == HISTORY_LIMIT {
+            entries.remove(0);
+        }
+        entries.push(line.to_string());
+    }
+
+    fn entries(&self, mode: LineMode) -> &[String] {
+        match mode {
+            LineMode::Command => &self.commands,
+            LineMode::Url => &self.urls,
+        }
+    }
+}
+
+/// The input line drawn on the bottom row (over the status bar) while open
+///
+/// Left/Right, Home/End, Backspace/Delete and Ctrl+U edit as in a shell, and Up/Down
+/// step through earlier lines of the same mode.
+#[derive(Debug, Clone, Default)]
+pub struct CommandLine {
+    mode: LineMode,
+    text: Vec<char>,
+    /// Index into `text` that typing inserts at
+    cursor: usize,
+    history: Vec<String>,
+    /// Entry of `history` shown, or `None` while on the line being typed
+    recalled: Option<usize>,
+    /// The line being typed, put back when stepping down past the newest entry
+    draft: Vec<char>,
+}
+
+impl CommandLine {
+    /// An empty line, with a copy of the earlier lines of `mode` from `history`
+    pub fn new(mode: LineMode, history: &LineHistory) -> Self {
+        Self {
+            mode,
+            history: history.entries(mode).to_vec(),
+            ..Default::default()
+        }
+    }
+
+    /// Start out with `text`, the cursor after it
+    pub fn with_text(mut self, text: &str) -> Self {
+        self.set_text(text.chars().collect());
+        self
+    }
+
+    pub fn mode(&self) -> LineMode {
+        self.mode
+    }
+
+    /// Apply a key press
+    pub fn handle_key(&mut self, key: &KeyEvent) -> LineEdit {
+        if key.kind != KeyEventKind::Press {
+            return LineEdit::Editing;
+        }
+        if key.modifiers.contains(KeyModifiers::CONTROL) {
+            return match key.code {
+                KeyCode::Char('u') => {
+                    self.text.drain(..self.cursor);
+                    self.cursor = 0;
+                    LineEdit::Editing
+                }
+                // Ctrl+C still quits and Ctrl+Z suspends
+                _ => LineEdit::Ignored,
+            };
+        }
+        match key.code {
+            KeyCode::Esc => return LineEdit::Cancel,
+            KeyCode::Enter => {
+                let line = std::mem::take(&mut self.text).into_iter().collect();
+                self.cursor = 0;
+                return LineEdit::Submit(line);
+            }
+            // Backspace on an empty line closes it, as in vi
+            KeyCode::Backspace if self.text.is_empty() => return LineEdit::Cancel,
+            KeyCode::Backspace if self.cursor > 0 => {
+                self.cursor -= 1;
+                self.text.remove(self.cursor);
+            }
+            KeyCode::Delete if self.cursor < self.text.len() => {
+                self.text.remove(self.cursor);
+            }
+            KeyCode::Left => self.cursor = self.cursor.saturating_sub(1),
+            KeyCode::Right => self.cursor = (self.cursor + 1).min(self.text.len()),
+            KeyCode::Home => self.cursor = 0,
+            KeyCode::End => self.cursor = self.text.len(),
+            KeyCode::Up => self.recall_older(),
+            KeyCode::Down => self.recall_newer(),
+            KeyCode::Char(c) => {
+                self.text.insert(self.cursor, c);
+                self.cursor += 1;
+            }
+            _ => {}
+        }
+        LineEdit::Editing
+    }
+
+    /// Insert pasted text at the cursor; line breaks and other control characters are
+    /// dropped, since the line can't hold them
+    pub fn paste(&mut self, text: &str) {
+        let pasted: Vec<char> = text.chars().filter(|c| !c.is_control()).collect();
+        let count = pasted.len();
+        self.text.splice(self.cursor..self.cursor, pasted);
+        self.cursor += count;
+    }
+
+    fn recall_older(&mut self) {
+        let index = match self.recalled {
+            Some(0) => return,
+            Some(index) => index - 1,
+            None if self.history.is_empty() => return,
+            None => {
+                self.draft = self.text.clone();
+                self.history.len() - 1
+            }
+        };
+        self.recalled = Some(index);
+        self.set_text(self.history[index].chars().collect());
+    }
+
+    fn recall_newer(&mut self) {
+        let Some(index) = self.recalled else {
+            return;
+        };
+        if index + 1 < self.history.len() {
+            self.recalled = Some(index + 1);
+            self.set_text(self.history[index + 1].chars().collect());
+        } else {
+            self.recalled = None;
+            let draft = std::mem::take(&mut self.draft);
+            self.set_text(draft);
+        }
+    }
+
+    fn set_text(&mut self, text: Vec<char>) {
+        self.cursor = text.len();
+        self.text = text;
+    }
+
+    /// Draw the line across the bottom row (white on dark gray), with the cursor cell in
+    /// inverse colors
+    pub fn draw(&self, frame: &mut TerminalFrame, style: &OverlayStyle) {
+        let Some(y) = frame.height.checked_sub(1) else {
+            return;
+        };
+        let (fg, bg) = (AnsiColor(231), AnsiColor(236));
+        let prompt = self.mode.prompt();
+        let cursor = prompt.chars().count() + self.cursor;
+
+        // Scroll a long line sideways to keep the cursor in view
+        let skip = (cursor + 1).saturating_sub(frame.width);
+        let shown: String = prompt
+            .chars()
+            .chain(self.text.iter().copied())
+            .skip(skip)
+            .collect();
+        let line = format!("{:<width$}", shown, width = frame.width);
+        style.draw_text(frame, 0, y, &line, fg, bg);
+
+        let under = self.text.get(self.cursor).copied().unwrap_or(' ');
+        style.draw_text(frame, cursor - skip, y, &under.to_string(), bg, fg);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use toast_render::ColorQuantizer;
+
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    #[test]
+    fn test_parse_cdp() {
+        assert_eq!(
+            Command::parse("cdp Page.reload").unwrap(),
+            Command::Cdp {
+                method: "Page.reload".into(),
+                params: serde_json::json!({})
+            }
+        );
+        assert_eq!(
+            Command::parse(" cdp  Emulation.setCPUThrottlingRate {\"rate\": 4} ").unwrap(),
+            Command::Cdp {
+                method: "Emulation.setCPUThrottlingRate".into(),
+                params: serde_json::json!({ "rate": 4 })
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_open() {
+        assert_eq!(
+            Command::parse("o example.com").unwrap(),
+            Command::Open {
+                target: "example.com".into()
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(Command::parse("").is_err());
+        assert!(Command::parse("open").is_err());
+        assert!(Command::parse("cdp").is_err());
+        assert!(Command::parse("cdp Page.navigate {url}").is_err());
+        assert!(Command::parse("frobnicate").is_err());
+    }
+
+    #[test]
+    fn test_editing() {
+        let mut line = CommandLine::default();
+        for c in "cdx".chars() {
+            assert_eq!(line.handle_key(&key(KeyCode::Char(c))), LineEdit::Editing);
+        }
+        line.handle_key(&key(KeyCode::Backspace));
+        line.handle_key(&key(KeyCode::Char('p')));
+        assert_eq!(
+            line.handle_key(&key(KeyCode::Enter)),
+            LineEdit::Submit("cdp".into())
+        );
+        assert_eq!(line.handle_key(&key(KeyCode::Backspace)), LineEdit::Cancel);
+        assert_eq!(
+            line.handle_key(&KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL)),
+            LineEdit::Ignored
+        );
+    }
+
+    fn typed(text: &str) -> CommandLine {
+        CommandLine::default().with_text(text)
+    }
+
+    fn submit(line: &mut CommandLine) -> LineEdit {
+        line.handle_key(&key(KeyCode::Enter))
+    }
+
+    #[test]
+    fn test_cursor_movement() {
+        let mut line = typed("exmple.com");
+        line.handle_key(&key(KeyCode::Home));
+        line.handle_key(&key(KeyCode::Right));
+        line.handle_key(&key(KeyCode::Right));
+        line.handle_key(&key(KeyCode::Char('a')));
+        line.handle_key(&key(KeyCode::End));
+        line.handle_key(&key(KeyCode::Left));
+        line.handle_key(&key(KeyCode::Delete));
+        line.handle_key(&key(KeyCode::Backspace));
+        assert_eq!(submit(&mut line), LineEdit::Submit("example.c".into()));
+
+        let mut line = typed("old/new");
+        line.handle_key(&key(KeyCode::Left));
+        line.handle_key(&key(KeyCode::Left));
+        line.handle_key(&key(KeyCode::Left));
+        line.handle_key(&KeyEvent::new(KeyCode::Char('u'), KeyModifiers::CONTROL));
+        assert_eq!(submit(&mut line), LineEdit::Submit("new".into()));
+    }
+
+    #[test]
+    fn test_history() {
+        let mut history = LineHistory::default();
+        history.push(LineMode::Url, "first.com");
+        history.push(LineMode::Url, "second.com");
+        history.push(LineMode::Url, "second.com");
+        history.push(LineMode::Url, " ");
+        history.push(LineMode::Command, "cdp Page.reload");
+
+        let mut line = CommandLine::new(LineMode::Url, &history).with_text("draft");
+        line.handle_key(&key(KeyCode::Up));
+        line.handle_key(&key(KeyCode::Up));
+        line.handle_key(&key(KeyCode::Up));
+        assert_eq!(line.text.iter().collect::<String>(), "first.com");
+        line.handle_key(&key(KeyCode::Down));
+        assert_eq!(line.text.iter().collect::<String>(), "second.com");
+        line.handle_key(&key(KeyCode::Down));
+        assert_eq!(submit(&mut line), LineEdit::Submit("draft".into()));
+    }
+
+    #[test]
+    fn test_paste_inserts_at_cursor() {
+        let mut line = typed("https://.com");
+        for _ in 0..4 {
+            line.handle_key(&key(KeyCode::Left));
+        }
+        line.paste("example\n");
+        assert_eq!(
+            submit(&mut line),
+            LineEdit::Submit("https://example.com".into())
+        );
+    }
+
+    #[test]
+    fn test_long_line_scrolls_to_cursor() {
+        let quantizer = ColorQuantizer::new();
+        let mut frame = TerminalFrame::new(10, 1);
+        typed("example.com/page").draw(&mut frame, &OverlayStyle::new(&quantizer, 1.0));
+        let row: String = (0..10)
+            .map(|x| frame.get(x, 0).unwrap().character)
+            .collect();
+        assert_eq!(row, ".com/page ");
+        assert_eq!(frame.get(9, 0).unwrap().background, AnsiColor(231));
+    }
+}
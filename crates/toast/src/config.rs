@@ -0,0 +1,236 @@
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Highest frame rate a profile may ask for
+const MAX_FPS: u32 = 60;
+
+/// Placeholder for the search terms in `search_engine`
+pub const SEARCH_QUERY: &str = "{query}";
+
+/// User configuration, read from `config.toml` in the config directory
+///
+/// Every key is optional; a missing file is the same as an empty one.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Page opened when toast starts without a URL and by the H key
+    /// Anything accepted on the command line works, including `toast:start`.
+    pub homepage: Option<String>,
+    /// Color-blindness compensation: none, protanopia, deuteranopia or tritanopia
+    pub color_filter: Option<String>,
+    /// Terminal title, with `{title}`, `{url}` and `{host}` filled in; empty leaves the
+    /// title alone
+    pub title_format: Option<String>,
+    /// Announce pages finishing loading: off, bell, flash or both
+    pub load_bell: Option<String>,
+    /// Cell characters unless a profile or flag says otherwise: half-block or shade
+    pub charset: Option<String>,
+    /// Key bindings: default, or vim for j/k to scroll, H/L through history and J/K
+    /// between tabs
+    pub keys: Option<String>,
+    /// Search URL for what's typed on the URL line that isn't an address, with `{query}`
+    /// where the search terms go
+    pub search_engine: Option<String>,
+    /// Chrome (or Chromium) executable, instead of the one found automatically
+    pub chrome_path: Option<PathBuf>,
+    /// Named presets selected with `--profile-name`, e.g. `[profiles.ssh-slow]`
+    pub profiles: BTreeMap<String, Profile>,
+}
+
+/// Settings for one kind of terminal or connection; unset keys keep their defaults
+///
+/// Command-line flags still win over the profile.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Profile {
+    /// Cell characters: half-block or shade
+    pub charset: Option<String>,
+    /// Capture frame rate when polling (WebP); JPEG and PNG frames come as the page draws
+    pub fps: Option<u32>,
+    /// JPEG/WebP capture quality, 0-100
+    pub quality: Option<u8>,
+    /// Fixed browser viewport as [width, height] in CSS pixels, instead of one sized to the
+    /// terminal
+    pub viewport: Option<(u32, u32)>,
+    /// Terminal output cap in KiB per second, for slow links
+    pub max_bandwidth: Option<u32>,
+}
+
+impl Config {
+    /// Load the config file, or the defaults if it doesn't exist
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = match std::fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(e).with_context(|| format!("Failed to read {}", path.display())),
+        };
+        Self::parse(&text).with_context(|| format!("Invalid config file {}", path.display()))
+    }
+
+    /// The profile called `name`
+    pub fn profile(&self, name: &str) -> Result<&Profile> {
+        self.profiles.get(name).with_context(|| {
+            let names: Vec<&str> = self.profiles.keys().map(String::as_str).collect();
+            if names.is_empty() {
+                format!("No profile named '{}' (the config file defines none)", name)
+            } else {
+                format!(
+                    "No profile named '{}' (expected one of: {})",
+                    name,
+                    names.join(", ")
+                )
+            }
+        })
+    }
+
+    fn parse(text: &str) -> Result<Self> {
+        let config: Self = toml::from_str(text)?;
+        if let Some(search) = &config.search_engine {
+            anyhow::ensure!(
+                search.contains(SEARCH_QUERY),
+                "search_engine must contain {} where the search terms go",
+                SEARCH_QUERY
+            );
+        }
+        for (name, profile) in &config.profiles {
+            profile
+                .validate()
+                .with_context(|| format!("Invalid profile '{}'", name))?;
+        }
+        Ok(config)
+    }
+}
+
+impl Profile {
+    fn validate(&self) -> Result<()> {
+        if let Some(fps) = self.fps {
+            anyhow::ensure!(
+                (1..=MAX_FPS).contains(&fps),
+                "fps must be from 1 to {}, got {}",
+                MAX_FPS,
+                fps
+            );
+        }
+        if let Some(quality) = self.quality {
+            anyhow::ensure!(
+                quality <= 100,
+                "quality must be from 0 to 100, got {}",
+                quality
+            );
+        }
+        if let Some((width, height)) = self.viewport {
+            anyhow::ensure!(
+                width > 0 && height > 0,
+                "viewport must be at least 1x1, got {}x{}",
+                width,
+                height
+            );
+        }
+        if let Some(max_bandwidth) = self.max_bandwidth {
+            anyhow::ensure!(max_bandwidth > 0, "max_bandwidth must be at least 1 KiB/s");
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_empty_config_is_default() {
+        let config = Config::parse("").unwrap();
+        assert_eq!(config.homepage, None);
+    }
+
+    #[test]
+    fn test_homepage() {
+        let config = Config::parse("homepage = \"example.com\"").unwrap();
+        assert_eq!(config.homepage.as_deref(), Some("example.com"));
+    }
+
+    #[test]
+    fn test_color_filter() {
+        let config = Config::parse("color_filter = \"deuteranopia\"").unwrap();
+        assert_eq!(config.color_filter.as_deref(), Some("deuteranopia"));
+    }
+
+    #[test]
+    fn test_load_bell() {
+        let config = Config::parse("load_bell = \"flash\"").unwrap();
+        assert_eq!(config.load_bell.as_deref(), Some("flash"));
+    }
+
+    #[test]
+    fn test_title_format() {
+        let config = Config::parse("title_format = \"{host} | toast\"").unwrap();
+        assert_eq!(config.title_format.as_deref(), Some("{host} | toast"));
+    }
+
+    #[test]
+    fn test_setup_keys() {
+        let config = Config::parse(
+            "charset = \"shade\"\nkeys = \"vim\"\n\
+             search_engine = \"https://duckduckgo.com/?q={query}\"\n\
+             chrome_path = \"/usr/bin/chromium\"\n",
+        )
+        .unwrap();
+        assert_eq!(config.charset.as_deref(), Some("shade"));
+        assert_eq!(config.keys.as_deref(), Some("vim"));
+        assert_eq!(
+            config.search_engine.as_deref(),
+            Some("https://duckduckgo.com/?q={query}")
+        );
+        assert_eq!(config.chrome_path, Some(PathBuf::from("/usr/bin/chromium")));
+
+        // Searches need somewhere to put the terms
+        assert!(Config::parse("search_engine = \"https://duckduckgo.com/\"").is_err());
+    }
+
+    #[test]
+    fn test_profiles() {
+        let config = Config::parse(
+            "[profiles.ssh-slow]\nfps = 5\nquality = 40\nmax_bandwidth = 64\n\n\
+             [profiles.4k-kitty]\ncharset = \"shade\"\nviewport = [2560, 1440]\n",
+        )
+        .unwrap();
+        let slow = config.profile("ssh-slow").unwrap();
+        assert_eq!(
+            (slow.fps, slow.quality, slow.viewport),
+            (Some(5), Some(40), None)
+        );
+        assert_eq!(slow.max_bandwidth, Some(64));
+        let kitty = config.profile("4k-kitty").unwrap();
+        assert_eq!(kitty.charset.as_deref(), Some("shade"));
+        assert_eq!(kitty.viewport, Some((2560, 1440)));
+    }
+
+    #[test]
+    fn test_unknown_profile_lists_names() {
+        let config = Config::parse("[profiles.laptop]\nfps = 10").unwrap();
+        let error = config.profile("desktop").unwrap_err().to_string();
+        assert!(error.contains("laptop"), "{}", error);
+    }
+
+    #[test]
+    fn test_invalid_profiles_are_rejected() {
+        assert!(Config::parse("[profiles.laptop]\nfps = 0").is_err());
+        assert!(Config::parse("[profiles.laptop]\nquality = 101").is_err());
+        assert!(Config::parse("[profiles.laptop]\nviewport = [0, 1080]").is_err());
+        assert!(Config::parse("[profiles.laptop]\nmax_bandwidth = 0").is_err());
+        assert!(Config::parse("[profiles.laptop]\nframerate = 10").is_err());
+    }
+
+    #[test]
+    fn test_unknown_keys_are_rejected() {
+        assert!(Config::parse("hompage = \"typo.example\"").is_err());
+    }
+
+    #[test]
+    fn test_missing_file_is_default() {
+        let config = Config::load(Path::new("/nonexistent/toast/config.toml")).unwrap();
+        assert_eq!(config.homepage, None);
+    }
+}
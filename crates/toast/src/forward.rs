@@ -0,0 +1,304 @@
+use crossterm::event::KeyEvent;
+use std::sync::{Arc, Mutex};
+use toast_browser::{RedirectWatcher, ScreenshotStreamer};
+use toast_render::{PageFit, RenderPipeline, Zoom};
+use tokio::sync::mpsc;
+use tracing::{error, info};
+
+use crate::action::{current_size, current_viewport};
+
+/// Input for the page, in terminal cells where it has a position
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PageInput {
+    /// Hover over a cell
+    MouseMove { x: usize, y: usize },
+    /// Click a cell
+    Click { x: usize, y: usize },
+    /// Press the mouse button over a cell
+    Press { x: usize, y: usize },
+    /// Move to a cell with the mouse button held
+    Drag { x: usize, y: usize },
+    /// Release the mouse button over a cell
+    Release { x: usize, y: usize },
+    /// Scroll by this many CSS pixels
+    Scroll { dx: i32, dy: i32 },
+    /// Press a key, in the focused element
+    Key(KeyEvent),
+    /// Type text into the focused element at once
+    Text(String),
+}
+
+impl PageInput {
+    /// Whether the page may follow a link or submit a form on this input
+    fn may_navigate(&self) -> bool {
+        matches!(
+            self,
+            PageInput::Click { .. }
+                | PageInput::Release { .. }
+                | PageInput::Key(_)
+                | PageInput::Text(_)
+        )
+    }
+}
+
+/// Drop input that a later event makes redundant, keeping the order of the rest
+///
+/// A mouse move is redundant when the next event moves the mouse again (clicks and
+/// presses move it themselves), and so is a drag followed by another; runs of scrolls
+/// are merged into one.
+pub fn coalesce(batch: Vec<PageInput>) -> Vec<PageInput> {
+    let mut merged: Vec<PageInput> = Vec::with_capacity(batch.len());
+    for input in batch {
+        match (merged.last_mut(), &input) {
+            (
+                Some(last @ PageInput::MouseMove { .. }),
+                PageInput::MouseMove { .. } | PageInput::Click { .. } | PageInput::Press { .. },
+            )
+            | (Some(last @ PageInput::Drag { .. }), PageInput::Drag { .. }) => *last = input,
+            (Some(PageInput::Scroll { dx: x, dy: y }), PageInput::Scroll { dx, dy }) => {
+                *x = x.saturating_add(*dx);
+                *y = y.saturating_add(*dy);
+            }
+            _ => merged.push(input),
+        }
+    }
+    merged
+}
+
+/// Sends page input to the browser one event at a time, in order
+///
+/// Everything that reaches the page goes through this one queue, so a slow CDP
+/// connection delays input but never reorders it (a scroll can't overtake a click).
+pub struct Forwarder {
+    pub streamer: Arc<ScreenshotStreamer>,
+    pub pipeline: Arc<RenderPipeline>,
+    /// Browser viewport size in CSS pixels, updated on resize
+    pub viewport: Arc<Mutex<(u32, u32)>>,
+    /// Terminal size in cells, updated on resize
+    pub size: Arc<Mutex<(usize, usize)>>,
+    /// Told about clicks and keys, after which a change of page is the user's doing
+    pub watcher: Arc<Mutex<RedirectWatcher>>,
+}
+
+impl Forwarder {
+    /// Forward input until every sender is gone
+    pub async fn run(self, mut input_rx: mpsc::Receiver<PageInput>) {
+        while let Some(first) = input_rx.recv().await {
+            // Whatever queued up behind the last event is sent as one batch
+            let mut batch = vec![first];
+            while let Ok(input) = input_rx.try_recv() {
+                batch.push(input);
+            }
+
+            for input in coalesce(batch) {
+                self.send(input).await;
+            }
+        }
+    }
+
+    async fn send(&self, input: PageInput) {
+        if input.may_navigate() {
+            if let Ok(mut watcher) = self.watcher.lock() {
+                watcher.user_input();
+            }
+        }
+        match input {
+            PageInput::MouseMove { x, y } => {
+                let (browser_x, browser_y) = self.to_browser(x, y);
+                if let Err(e) = self.streamer.move_mouse(browser_x, browser_y).await {
+                    error!("Failed to move mouse: {}", e);
+                }
+            }
+            PageInput::Click { x, y } => {
+                let (browser_x, browser_y) = self.to_browser(x, y);
+                info!(
+                    "Clicking at terminal ({}, {}) -> browser ({:.0}, {:.0})",
+                    x, y, browser_x, browser_y
+                );
+                if let Err(e) = self.streamer.click(browser_x, browser_y).await {
+                    error!("Failed to send click: {}", e);
+                }
+            }
+            PageInput::Press { x, y } => {
+                let (browser_x, browser_y) = self.to_browser(x, y);
+                if let Err(e) = self.streamer.press_mouse(browser_x, browser_y).await {
+                    error!("Failed to press mouse: {}", e);
+                }
+            }
+            PageInput::Drag { x, y } => {
+                let (browser_x, browser_y) = self.to_browser(x, y);
+                if let Err(e) = self.streamer.drag_mouse(browser_x, browser_y).await {
+                    error!("Failed to drag mouse: {}", e);
+                }
+            }
+            PageInput::Release { x, y } => {
+                let (browser_x, browser_y) = self.to_browser(x, y);
+                if let Err(e) = self.streamer.release_mouse(browser_x, browser_y).await {
+                    error!("Failed to release mouse: {}", e);
+                }
+            }
+            PageInput::Scroll { dx, dy } => {
+                if let Err(e) = self.streamer.scroll_by(dx, dy).await {
+                    error!("Failed to scroll: {}", e);
+                }
+            }
+            PageInput::Key(key) => {
+                if let Err(e) = self.streamer.key(&key).await {
+                    error!("Failed to send key: {}", e);
+                }
+            }
+            PageInput::Text(text) => {
+                if let Err(e) = self.streamer.insert_text(&text).await {
+                    error!("Failed to insert text: {}", e);
+                }
+            }
+        }
+    }
+
+    /// Convert terminal coordinates to browser viewport coordinates
+    fn to_browser(&self, x: usize, y: usize) -> (f64, f64) {
+        let viewport = current_viewport(&self.viewport);
+        CellMapping::new(&self.pipeline, viewport, current_size(&self.size)).to_page(x, y)
+    }
+}
+
+/// How terminal cells map onto the page, as used for input
+#[derive(Debug, Clone)]
+pub struct CellMapping {
+    /// Browser viewport size in CSS pixels
+    pub viewport: (u32, u32),
+    /// Terminal size in cells
+    pub size: (usize, usize),
+    /// The part of the page shown and the rows it fills; `None` when stretched
+    pub fit: Option<PageFit>,
+    pub zoom: Zoom,
+}
+
+impl CellMapping {
+    /// The mapping the pipeline currently renders with
+    pub fn new(pipeline: &RenderPipeline, viewport: (u32, u32), size: (usize, usize)) -> Self {
+        Self {
+            viewport,
+            size,
+            fit: pipeline.page_fit(viewport.0, viewport.1, size.0, size.1),
+            zoom: pipeline.zoom(),
+        }
+    }
+
+    /// Convert terminal coordinates to browser viewport coordinates
+    ///
+    /// Undoes the aspect fit and any terminal-side zoom.
+    pub fn to_page(&self, x: usize, y: usize) -> (f64, f64) {
+        let (page_width, page_height) = self.viewport;
+        let (width, height) = self.size;
+        let (mut fx, mut fy) = (x as f64 / width as f64, y as f64 / height as f64);
+        if let Some(fit) = &self.fit {
+            (fx, fy) = fit.to_source(fx, fy, height);
+        }
+        let (zx, zy) = self.zoom.to_page(fx, fy);
+        match &self.fit {
+            Some(fit) => fit.to_page(zx, zy),
+            None => (zx * page_width as f64, zy * page_height as f64),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossterm::event::{KeyCode, KeyModifiers};
+
+    #[test]
+    fn test_text_keeps_its_place_between_keys() {
+        let key = |c| PageInput::Key(KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE));
+        let batch = vec![
+            key('a'),
+            PageInput::Text("pasted".to_string()),
+            key('b'),
+            PageInput::Text("again".to_string()),
+        ];
+        assert_eq!(coalesce(batch.clone()), batch);
+    }
+
+    #[test]
+    fn test_coalesce_drops_superseded_moves() {
+        let batch = vec![
+            PageInput::MouseMove { x: 1, y: 1 },
+            PageInput::MouseMove { x: 2, y: 1 },
+            PageInput::MouseMove { x: 3, y: 1 },
+            PageInput::Click { x: 3, y: 1 },
+            PageInput::MouseMove { x: 4, y: 1 },
+        ];
+        assert_eq!(
+            coalesce(batch),
+            vec![
+                PageInput::Click { x: 3, y: 1 },
+                PageInput::MouseMove { x: 4, y: 1 }
+            ]
+        );
+    }
+
+    #[test]
+    fn test_coalesce_merges_drags_but_keeps_press_and_release() {
+        let batch = vec![
+            PageInput::MouseMove { x: 1, y: 1 },
+            PageInput::Press { x: 1, y: 1 },
+            PageInput::Drag { x: 2, y: 1 },
+            PageInput::Drag { x: 3, y: 1 },
+            PageInput::Release { x: 3, y: 1 },
+        ];
+        assert_eq!(
+            coalesce(batch),
+            vec![
+                PageInput::Press { x: 1, y: 1 },
+                PageInput::Drag { x: 3, y: 1 },
+                PageInput::Release { x: 3, y: 1 },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_mapping_undoes_the_aspect_fit() {
+        // 100x50 cells of 1:2 show a 16:9 page in the top 28 rows
+        let mapping = CellMapping {
+            viewport: (1920, 1080),
+            size: (100, 50),
+            fit: Some(PageFit::new(1920, 1080, 100, 50, 2.0)),
+            zoom: Zoom::new(),
+        };
+        let (x, y) = mapping.to_page(50, 14);
+        assert_eq!(x, 960.0);
+        assert!((y - 540.0).abs() < 1e-6);
+        // Rows below the page land on its bottom edge
+        assert_eq!(mapping.to_page(0, 40).1, 1080.0);
+
+        let stretched = CellMapping {
+            fit: None,
+            ..mapping
+        };
+        assert_eq!(stretched.to_page(50, 25), (960.0, 540.0));
+    }
+
+    #[test]
+    fn test_coalesce_keeps_order_across_kinds() {
+        let batch = vec![
+            PageInput::Scroll { dx: 0, dy: 400 },
+            PageInput::Scroll { dx: 100, dy: 0 },
+            PageInput::Scroll { dx: 0, dy: 400 },
+            PageInput::Click { x: 0, y: 0 },
+            PageInput::Scroll { dx: 0, dy: -400 },
+            PageInput::MouseMove { x: 5, y: 5 },
+            PageInput::Scroll { dx: 0, dy: 400 },
+        ];
+        assert_eq!(
+            coalesce(batch),
+            vec![
+                PageInput::Scroll { dx: 100, dy: 800 },
+                PageInput::Click { x: 0, y: 0 },
+                PageInput::Scroll { dx: 0, dy: -400 },
+                PageInput::MouseMove { x: 5, y: 5 },
+                PageInput::Scroll { dx: 0, dy: 400 },
+            ]
+        );
+    }
+}
@@ -0,0 +1,120 @@
+use toast_core::{AnsiColor, TerminalFrame};
+use toast_render::OverlayStyle;
+
+use crate::forward::CellMapping;
+
+/// Cells between grid markers (columns, rows)
+const GRID_STEP: (usize, usize) = (10, 5);
+
+/// Ruler and marker colors: black on cyan
+const FG: AnsiColor = AnsiColor(16);
+const BG: AnsiColor = AnsiColor(51);
+
+/// Draw the cell grid debug overlay, for diagnosing clicks that land in the wrong place
+///
+/// Rulers along the top and left edges label every few columns and rows, a `+` marks
+/// each grid intersection, and a box in the corner describes how cells map onto the
+/// page, ending with the page position under the cursor.
+pub fn draw_grid(
+    frame: &mut TerminalFrame,
+    overlay: &OverlayStyle,
+    mapping: &CellMapping,
+    cursor: (usize, usize),
+) {
+    let (step_x, step_y) = GRID_STEP;
+    for y in (step_y..frame.height).step_by(step_y) {
+        for x in (step_x..frame.width).step_by(step_x) {
+            overlay.draw_text(frame, x, y, "+", FG, BG);
+        }
+    }
+    for x in (0..frame.width).step_by(step_x) {
+        overlay.draw_text(frame, x, 0, &x.to_string(), FG, BG);
+    }
+    for y in (step_y..frame.height).step_by(step_y) {
+        overlay.draw_text(frame, 0, y, &y.to_string(), FG, BG);
+    }
+
+    let lines = describe(mapping, cursor);
+    let width = lines.iter().map(|l| l.chars().count()).max().unwrap_or(0);
+    for (i, line) in lines.iter().enumerate() {
+        let line = format!(" {:<width$} ", line, width = width);
+        overlay.draw_text(frame, 3, 1 + i, &line, AnsiColor(231), AnsiColor(236));
+    }
+}
+
+/// The mapping in words: sizes, the part of the page shown, blank or cropped edges,
+/// zoom and where the cursor points
+fn describe(mapping: &CellMapping, (x, y): (usize, usize)) -> Vec<String> {
+    let (cols, rows) = mapping.size;
+    let (page_width, page_height) = mapping.viewport;
+    let mut lines = vec![format!(
+        "cells {}x{}  viewport {}x{}",
+        cols, rows, page_width, page_height
+    )];
+
+    match &mapping.fit {
+        Some(fit) => {
+            let (sx, sy, sw, sh) = fit.source;
+            lines.push(format!(
+                "shows page ({},{}) {}x{} on rows 0-{}",
+                sx,
+                sy,
+                sw,
+                sh,
+                fit.rows.saturating_sub(1)
+            ));
+            if fit.rows < rows {
+                lines.push(format!("letterbox: rows {}-{} blank", fit.rows, rows - 1));
+            }
+            if sy + sh < page_height {
+                lines.push(format!("cropped: page below y={} not shown", sy + sh));
+            }
+        }
+        None => lines.push("stretched to fill (no aspect fit)".to_string()),
+    }
+
+    if !mapping.zoom.is_identity() {
+        lines.push(format!("zoom {:.1}x", mapping.zoom.scale()));
+    }
+
+    let (page_x, page_y) = mapping.to_page(x, y);
+    lines.push(format!(
+        "cursor ({},{}) -> page ({:.0},{:.0})",
+        x, y, page_x, page_y
+    ));
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use toast_render::{PageFit, Zoom};
+
+    #[test]
+    fn test_describe_letterbox() {
+        let mapping = CellMapping {
+            viewport: (1920, 1080),
+            size: (100, 50),
+            fit: Some(PageFit::new(1920, 1080, 100, 50, 2.0)),
+            zoom: Zoom::new(),
+        };
+        let lines = describe(&mapping, (0, 0));
+        assert_eq!(lines[0], "cells 100x50  viewport 1920x1080");
+        assert_eq!(lines[1], "shows page (0,0) 1920x1080 on rows 0-27");
+        assert_eq!(lines[2], "letterbox: rows 28-49 blank");
+        assert_eq!(lines[3], "cursor (0,0) -> page (0,0)");
+    }
+
+    #[test]
+    fn test_describe_crop() {
+        let mapping = CellMapping {
+            viewport: (1920, 1080),
+            size: (200, 50),
+            fit: Some(PageFit::new(1920, 1080, 200, 50, 2.0)),
+            zoom: Zoom::new(),
+        };
+        let lines = describe(&mapping, (100, 25));
+        assert_eq!(lines[2], "cropped: page below y=960 not shown");
+        assert_eq!(lines[3], "cursor (100,25) -> page (960,480)");
+    }
+}
@@ -0,0 +1,211 @@
+use std::fmt;
+use std::time::{Duration, Instant};
+use toast_core::TerminalFrame;
+use toast_render::{ansi_to_rgb, PipelineStats};
+use tracing::warn;
+
+/// Give up on a marker that hasn't shown up after this long, so a lost injection
+/// doesn't block later runs
+const MARKER_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Input-to-frame latency diagnostic
+///
+/// On keypress the app injects a magenta marker into the page and starts a timer.
+/// The display task checks every frame for the marker and reports how long it took
+/// to show up, along with the most recent per-stage timings.
+#[derive(Debug, Default)]
+pub struct LatencyProbe {
+    state: ProbeState,
+    /// Duration of the most recent screenshot capture
+    pub capture: Duration,
+    /// Per-stage timings of the most recent frame
+    pub stages: PipelineStats,
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+enum ProbeState {
+    #[default]
+    Idle,
+    /// Marker injected, waiting for it to appear in a frame
+    Waiting(Instant),
+    /// Measurement done, waiting for the marker to disappear before the next run
+    Clearing,
+}
+
+impl LatencyProbe {
+    /// Start a measurement at `now`. Returns false if one is already in flight.
+    pub fn start(&mut self, now: Instant) -> bool {
+        match self.state {
+            ProbeState::Idle => {
+                self.state = ProbeState::Waiting(now);
+                true
+            }
+            ProbeState::Waiting(started)
+                if now.saturating_duration_since(started) > MARKER_TIMEOUT =>
+            {
+                self.state = ProbeState::Waiting(now);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Inspect a frame rendered at `now`. Either outcome means the marker should be
+    /// removed from the page.
+    pub fn check_frame(&mut self, frame: &TerminalFrame, now: Instant) -> Option<ProbeOutcome> {
+        let visible = frame_shows_marker(frame);
+        match self.state {
+            ProbeState::Waiting(started) if visible => {
+                self.state = ProbeState::Clearing;
+                Some(ProbeOutcome::Report(LatencyReport {
+                    end_to_end: now.saturating_duration_since(started),
+                    capture: self.capture,
+                    stages: self.stages,
+                }))
+            }
+            ProbeState::Waiting(started)
+                if now.saturating_duration_since(started) > MARKER_TIMEOUT =>
+            {
+                warn!("Latency marker never appeared; giving up");
+                self.state = ProbeState::Idle;
+                Some(ProbeOutcome::TimedOut)
+            }
+            ProbeState::Clearing if !visible => {
+                self.state = ProbeState::Idle;
+                None
+            }
+            _ => None,
+        }
+    }
+
+    /// Give up on the current run, when the marker couldn't be injected or removed
+    pub fn abandon(&mut self) {
+        self.state = ProbeState::Idle;
+    }
+}
+
+/// Result of checking a frame for the marker
+#[derive(Debug, Clone, Copy)]
+pub enum ProbeOutcome {
+    /// The marker showed up
+    Report(LatencyReport),
+    /// The marker didn't show up in time
+    TimedOut,
+}
+
+/// Result of a single latency measurement
+#[derive(Debug, Clone, Copy)]
+pub struct LatencyReport {
+    pub end_to_end: Duration,
+    pub capture: Duration,
+    pub stages: PipelineStats,
+}
+
+impl fmt::Display for LatencyReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}ms end-to-end (capture {}ms, {})",
+            self.end_to_end.as_millis(),
+            self.capture.as_millis(),
+            self.stages
+        )
+    }
+}
+
+/// The marker sits in the top-left corner, so the first cell is fully covered by it
+fn frame_shows_marker(frame: &TerminalFrame) -> bool {
+    frame.get(0, 0).is_some_and(|cell| {
+        let fg = ansi_to_rgb(cell.foreground);
+        let bg = ansi_to_rgb(cell.background);
+        is_magenta(fg.r, fg.g, fg.b) && is_magenta(bg.r, bg.g, bg.b)
+    })
+}
+
+#[inline]
+fn is_magenta(r: u8, g: u8, b: u8) -> bool {
+    r > 200 && b > 200 && g < 80
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use toast_core::AnsiColor;
+
+    fn frame_with(foreground: u8, background: u8) -> TerminalFrame {
+        let mut frame = TerminalFrame::new(4, 2);
+        frame.cells[0].foreground = AnsiColor(foreground);
+        frame.cells[0].background = AnsiColor(background);
+        frame
+    }
+
+    fn frame(marked: bool) -> TerminalFrame {
+        if marked {
+            frame_with(201, 201)
+        } else {
+            TerminalFrame::new(4, 2)
+        }
+    }
+
+    #[test]
+    fn test_marker_detection() {
+        assert!(frame_shows_marker(&frame(true)));
+        assert!(!frame_shows_marker(&frame(false)));
+
+        // Magenta over another color isn't the marker
+        assert!(!frame_shows_marker(&frame_with(201, 15)));
+    }
+
+    #[test]
+    fn test_reports_once_then_returns_to_idle() {
+        let now = Instant::now();
+        let mut probe = LatencyProbe::default();
+        assert!(probe.start(now));
+        assert!(!probe.start(now));
+
+        assert!(probe.check_frame(&frame(false), now).is_none());
+        let outcome = probe.check_frame(&frame(true), now + Duration::from_millis(120));
+        let Some(ProbeOutcome::Report(report)) = outcome else {
+            panic!("expected a report, got {:?}", outcome);
+        };
+        assert_eq!(report.end_to_end, Duration::from_millis(120));
+
+        // Still showing the marker: no second report, and no new run until it's gone
+        assert!(probe.check_frame(&frame(true), now).is_none());
+        assert!(!probe.start(now));
+        assert!(probe.check_frame(&frame(false), now).is_none());
+        assert!(probe.start(now));
+    }
+
+    #[test]
+    fn test_missing_marker_times_out() {
+        let now = Instant::now();
+        let mut probe = LatencyProbe::default();
+        assert!(probe.start(now));
+
+        let later = now + MARKER_TIMEOUT + Duration::from_millis(1);
+        assert!(matches!(
+            probe.check_frame(&frame(false), later),
+            Some(ProbeOutcome::TimedOut)
+        ));
+        assert!(probe.start(later));
+
+        // A new run can also replace one that timed out before any frame came
+        let much_later = later + MARKER_TIMEOUT + Duration::from_millis(1);
+        assert!(probe.start(much_later));
+        assert!(!probe.start(much_later));
+    }
+
+    #[test]
+    fn test_abandon_returns_to_idle() {
+        let now = Instant::now();
+        let mut probe = LatencyProbe::default();
+        assert!(probe.start(now));
+        probe.check_frame(&frame(true), now);
+
+        // The marker couldn't be removed, so it will never disappear
+        assert!(probe.check_frame(&frame(true), now).is_none());
+        probe.abandon();
+        assert!(probe.start(now));
+    }
+}
@@ -1,26 +1,577 @@
+mod action;
+mod adaptive;
 mod app;
+mod bell;
+mod command;
+mod config;
+mod forward;
+mod grid;
+mod latency;
+mod notice;
+mod pace;
+mod placeholder;
+mod session;
+mod setup;
+mod shot;
+mod stats;
+mod target;
+mod title;
 
-use anyhow::Result;
-use clap::Parser;
+use anyhow::{Context, Result};
+use clap::{CommandFactory, Parser, Subcommand};
+use clap_complete::Shell;
 use std::fs::OpenOptions;
-use std::io::{self, Write};
-use tracing_subscriber;
+use std::io::{self, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use toast_browser::{DEFAULT_QUALITY, DEFAULT_WINDOW_SIZE};
+use toast_core::{paths, ImageFormat};
+use toast_render::{
+    CharacterSet, ColorFilter, ColorMetric, HighContrast, RenderConfig, ScaleFilter,
+    NOMINAL_CELL_ASPECT,
+};
 
 #[derive(Parser, Debug)]
 #[command(name = "toast")]
 #[command(about = "The browser - Render web pages in your terminal", long_about = None)]
 struct Args {
-    /// URL to render
-    url: Option<String>,
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// URLs, host names or local files to open, each in its own tab; `-` reads them from
+    /// stdin, one per line
+    #[arg(value_name = "URL")]
+    urls: Vec<String>,
+
+    /// Enable the latency diagnostic: press L to measure keypress-to-frame latency
+    #[arg(long)]
+    latency_test: bool,
+
+    /// Detect text regions and render them with crisp foreground/background colors
+    #[arg(long)]
+    crisp_text: bool,
+
+    /// Quantize glyph cells to two contrasting ink/paper colors (sharper text in
+    /// half-block mode; works on the scaled image, so it is cheaper than --crisp-text)
+    #[arg(long)]
+    text_quantization: bool,
+
+    /// Choose each cell's two colors together to minimize the error of the whole cell,
+    /// including its blended color (truer edges, especially with --high-contrast base16)
+    #[arg(long)]
+    pair_search: bool,
+
+    /// Overlay OCR-recognized text on the page (requires a build with the `ocr` feature)
+    #[arg(long)]
+    ocr: bool,
+
+    /// Only re-render the parts of the page that changed (lower CPU on mostly static pages)
+    #[arg(long)]
+    damage_tracking: bool,
+
+    /// Show a quick low-quality frame as soon as the page changes, then the full-quality
+    /// one once it holds still (snappier scrolling on slow machines)
+    #[arg(long)]
+    progressive: bool,
+
+    /// Reduce color shimmer: keep a cell's previous color unless the new one is closer
+    /// by more than this delta-E (0 disables; around 3 is barely noticeable)
+    #[arg(long, value_name = "DELTA_E", default_value_t = 0.0)]
+    color_hysteresis: f32,
+
+    /// Resampling filter: nearest, bilinear, catmull-rom or lanczos3
+    #[arg(long, value_name = "FILTER", default_value = "lanczos3")]
+    filter: ScaleFilter,
+
+    /// Sample at twice the cell resolution and choose each cell's colors by clustering:
+    /// crisper detail on busy pages, at roughly four times the scaling cost
+    #[arg(long)]
+    supersample: bool,
+
+    /// Sample at twice the cell resolution and average each half-block pixel's area:
+    /// steadier thin horizontal lines (rules, underlines) than scaling alone
+    #[arg(long, conflicts_with = "supersample")]
+    subpixel: bool,
+
+    /// Stretch a fixed-size page (a profile's viewport) to fill the terminal instead of
+    /// keeping its proportions by cutting it to fit
+    #[arg(long)]
+    stretch: bool,
+
+    /// Keep thin borders, underlines and small glyphs that scaling would average away
+    #[arg(long)]
+    preserve_edges: bool,
+
+    /// Sharpen after scaling to counter the softening of the downscale (0 disables; try 0.5)
+    #[arg(long, value_name = "AMOUNT", default_value_t = 0.0)]
+    sharpen: f32,
+
+    /// Saturation multiplier applied before quantization, which tends to wash colors
+    /// out (1 leaves colors unchanged; try 1.2)
+    #[arg(long, value_name = "FACTOR", default_value_t = 1.0)]
+    saturation: f32,
+
+    /// Boost muted colors more than already-vivid ones (0 disables; try 0.3)
+    #[arg(long, value_name = "AMOUNT", default_value_t = 0.0)]
+    vibrance: f32,
+
+    /// Cell characters: half-block (double vertical resolution, the default) or shade
+    /// (░▒▓█ blends for more perceived colors)
+    #[arg(long, value_name = "CHARSET")]
+    charset: Option<CharacterSet>,
+
+    /// Color-blindness compensation: none, protanopia, deuteranopia or tritanopia
+    /// (overrides the config file; press F to cycle at runtime)
+    #[arg(long, value_name = "FILTER")]
+    color_filter: Option<ColorFilter>,
+
+    /// High-contrast mode for low vision: stretch (the default when given without a value)
+    /// or base16, which also limits output to the 16 themeable base colors
+    #[arg(
+        long,
+        value_name = "MODE",
+        num_args = 0..=1,
+        default_value = "off",
+        default_missing_value = "stretch"
+    )]
+    high_contrast: HighContrast,
+
+    /// Color distance metric for quantization: cie76 or ciede2000 (slower startup)
+    #[arg(long, value_name = "METRIC", default_value = "cie76")]
+    color_metric: ColorMetric,
+
+    /// Opacity of notice and badge backgrounds, from 0 (page shows through) to 1 (solid)
+    #[arg(long, value_name = "OPACITY", default_value_t = 1.0, value_parser = parse_opacity)]
+    overlay_opacity: f32,
+
+    /// Hide the status bar (URL, title, load state and modes) and give its row to the page
+    #[arg(long)]
+    no_status_bar: bool,
+
+    /// Hide the scrollbar drawn down the rightmost column of long pages
+    #[arg(long)]
+    no_scrollbar: bool,
+
+    /// Show the tab bar along the top row even with a single tab open
+    #[arg(long)]
+    tab_bar: bool,
+
+    /// Show a notice when the title of a tab in the background changes, besides marking
+    /// it on the tab bar
+    #[arg(long)]
+    tab_notices: bool,
+
+    /// Terminal title: {title}, {url} and {host} are filled in from the page, and an
+    /// empty format leaves the title alone [default: "{title} - toast"]
+    #[arg(long, value_name = "FORMAT")]
+    title_format: Option<String>,
+
+    /// Announce when a page finishes loading: bell (the default when given without a
+    /// value), flash (the status bar) or both (overrides the config file)
+    #[arg(long, value_name = "HOW", num_args = 0..=1, default_missing_value = "bell")]
+    load_bell: Option<bell::LoadBell>,
+
+    /// Draw in this many rows below the prompt instead of taking over the screen; the
+    /// last frame stays behind on exit
+    #[arg(long, value_name = "ROWS", num_args = 0..=1, default_missing_value = "20")]
+    inline: Option<u16>,
+
+    /// Write frames to this file or FIFO instead of the terminal, for another process
+    /// to show (e.g. `cat` in a terminal of the same size); keys and mouse still work here
+    #[arg(long, value_name = "PATH")]
+    render_to: Option<PathBuf>,
+
+    /// Start with the cell grid debug overlay shown (G toggles it): rulers, grid markers
+    /// and how cells map onto the page, for diagnosing misplaced clicks
+    #[arg(long)]
+    debug_grid: bool,
+
+    /// Start with the stats overlay shown (F12 toggles it): capture and display frame
+    /// rates, dropped frames, latency and terminal output
+    #[arg(long)]
+    stats: bool,
+
+    /// CSS pixels scrolled per mouse wheel notch
+    #[arg(long, value_name = "PIXELS", default_value_t = action::DEFAULT_WHEEL_STEP)]
+    wheel_step: i32,
+
+    /// Reverse the mouse wheel so the page follows it, like dragging on a touchscreen
+    #[arg(long)]
+    natural_scroll: bool,
+
+    /// Cross-fade between frames when captures arrive slower than ~30fps, so scrolling
+    /// looks less like a slideshow on slow links (adds up to ~100ms of latency)
+    #[arg(long)]
+    interpolate: bool,
+
+    /// Redraw the page at most this many times a second, whatever the capture rate;
+    /// frames arriving in between are merged into the next redraw
+    #[arg(long, value_name = "FPS", value_parser = clap::value_parser!(u32).range(1..))]
+    max_redraw_fps: Option<u32>,
+
+    /// Cap terminal output at this many KiB per second, dropping the frame rate instead
+    /// of queueing repaints on slow SSH or mosh links (overrides the profile)
+    #[arg(long, value_name = "KIB", value_parser = clap::value_parser!(u32).range(1..))]
+    max_bandwidth: Option<u32>,
+
+    /// When rendering can't keep up with the frame rate, switch to nearest-neighbour
+    /// scaling and then lower capture quality, recovering once load drops
+    #[arg(long)]
+    adaptive_quality: bool,
+
+    /// Blend with the terminal theme: areas in the terminal's background color use the
+    /// default background (for transparent or tinted terminals)
+    #[arg(long)]
+    match_background: bool,
+
+    /// Private session: incognito browser with a throwaway profile, no log file, nothing
+    /// left on disk after exit
+    #[arg(long)]
+    ephemeral: bool,
+
+    /// Use a named profile from the config file (render mode, frame rate, capture quality
+    /// and viewport for a kind of terminal or connection); other flags override it
+    #[arg(long, value_name = "NAME")]
+    profile_name: Option<String>,
+
+    /// Keep config, data, cache and logs under this directory instead of the platform ones
+    #[arg(long, value_name = "DIR")]
+    data_dir: Option<PathBuf>,
+
+    /// Screenshot format requested from Chrome: jpeg, webp or png (Chrome streams jpeg and
+    /// png frames as the page draws; webp is polled at the profile's frame rate)
+    #[arg(long, value_name = "FORMAT", default_value = "jpeg", value_parser = parse_capture_format)]
+    capture_format: ImageFormat,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Print a shell completion script, e.g. `toast completions zsh > ~/.zfunc/_toast`
+    Completions {
+        #[arg(value_enum)]
+        shell: Shell,
+    },
+    /// Render one page to a file instead of the terminal, e.g. for bug reports
+    Shot {
+        /// URL, host name or local file to render
+        url: String,
+        /// Output format
+        #[arg(long, value_enum, default_value = "html")]
+        format: shot::ShotFormat,
+        /// Write here instead of stdout
+        #[arg(short, long, value_name = "FILE")]
+        output: Option<PathBuf>,
+        /// Width in terminal cells
+        #[arg(long, default_value_t = 120)]
+        width: usize,
+        /// Height in terminal cells
+        #[arg(long, default_value_t = 40)]
+        height: usize,
+        /// Print a JSON report for scripts: frame size, timings and the document (or the
+        /// output file), or the error
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+fn parse_capture_format(value: &str) -> Result<ImageFormat, String> {
+    match value.to_ascii_lowercase().as_str() {
+        "jpeg" | "jpg" => Ok(ImageFormat::Jpeg),
+        "webp" => Ok(ImageFormat::Webp),
+        "png" => Ok(ImageFormat::Png),
+        _ => Err(format!(
+            "unknown format '{}' (expected jpeg, webp or png)",
+            value
+        )),
+    }
+}
+
+fn parse_opacity(value: &str) -> Result<f32, String> {
+    match value.parse::<f32>() {
+        Ok(opacity) if (0.0..=1.0).contains(&opacity) => Ok(opacity),
+        _ => Err(format!(
+            "invalid opacity '{}' (expected a number from 0 to 1)",
+            value
+        )),
+    }
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    // Create log file
+    let args = Args::parse();
+
+    if let Some(Command::Completions { shell }) = args.command {
+        clap_complete::generate(shell, &mut Args::command(), "toast", &mut io::stdout());
+        return Ok(());
+    }
+
+    // Resolve toast's directories before anything writes to them
+    let paths = paths::init(args.data_dir.as_deref());
+    if !args.ephemeral {
+        paths
+            .create_dirs()
+            .context("Failed to create toast's config/data/cache directories")?;
+        init_logging(&paths.log_file())?;
+    }
+
+    // The first interactive run without a config file sets one up
+    let config_file = paths.config_file();
+    let interactive = io::stdin().is_terminal() && io::stdout().is_terminal();
+    if !args.ephemeral && args.command.is_none() && interactive && !config_file.exists() {
+        if let Err(e) = setup::run(&config_file) {
+            tracing::warn!("First-run setup failed: {:#}", e);
+            eprintln!("Setup failed ({:#}); starting with the defaults", e);
+        }
+    }
+
+    let config = config::Config::load(&config_file)?;
+    let homepage = config
+        .homepage
+        .as_deref()
+        .map(target::resolve)
+        .transpose()?;
+    let color_filter = match args.color_filter {
+        Some(filter) => filter,
+        None => config
+            .color_filter
+            .as_deref()
+            .map(str::parse)
+            .transpose()
+            .context("Invalid color_filter in config file")?
+            .unwrap_or_default(),
+    };
+    let load_bell = match args.load_bell {
+        Some(bell) => bell,
+        None => config
+            .load_bell
+            .as_deref()
+            .map(str::parse)
+            .transpose()
+            .context("Invalid load_bell in config file")?
+            .unwrap_or_default(),
+    };
+    let title_format = args
+        .title_format
+        .clone()
+        .or(config.title_format.clone())
+        .unwrap_or_else(|| title::DEFAULT_TITLE_FORMAT.to_string());
+    let profile = match &args.profile_name {
+        Some(name) => config.profile(name)?.clone(),
+        None => config::Profile::default(),
+    };
+    let charset = match (args.charset, &profile.charset) {
+        (Some(charset), _) => charset,
+        (None, Some(charset)) => charset.parse().context("Invalid charset in profile")?,
+        (None, None) => config
+            .charset
+            .as_deref()
+            .map(str::parse)
+            .transpose()
+            .context("Invalid charset in config file")?
+            .unwrap_or_default(),
+    };
+    let keymap = config
+        .keys
+        .as_deref()
+        .map(str::parse)
+        .transpose()
+        .context("Invalid keys in config file")?
+        .unwrap_or_default();
+    let viewport = profile.viewport;
+
+    if let Some(Command::Shot {
+        url,
+        format,
+        output,
+        width,
+        height,
+        json,
+    }) = &args.command
+    {
+        let options = shot::ShotOptions {
+            format: *format,
+            size: (*width, *height),
+            output: output.clone(),
+            viewport: viewport.unwrap_or(DEFAULT_WINDOW_SIZE),
+            ephemeral: args.ephemeral,
+            json: *json,
+            chrome_path: config.chrome_path.clone(),
+        };
+        let render = render_config(&args, color_filter, charset);
+        let result = match target::resolve(url) {
+            Ok(url) => shot::run(&url, &options, render).await,
+            Err(e) => Err(e),
+        };
+        if let (Err(e), true) = (&result, options.json) {
+            // Scripts read the error from stdout; the exit status still says it failed
+            println!("{}", shot::error_json(url, e));
+            std::process::exit(1);
+        }
+        return result;
+    }
+
+    // Get URLs from args (or stdin), fall back to the homepage, or the start page
+    let mut urls = target::collect(&args.urls, io::stdin().lock())?;
+
+    // A session left behind means a toast crashed; offer to pick up where it was
+    let sessions_dir = (!args.ephemeral).then(|| paths.sessions_dir());
+    let mut restore_scroll = Vec::new();
+    let mut active_tab = 0;
+    if let (true, Some(dir)) = (urls.is_empty(), &sessions_dir) {
+        let orphan = match session::orphans(dir) {
+            Ok(orphans) => orphans.into_iter().next(),
+            Err(e) => {
+                tracing::warn!("Ignoring saved sessions: {:#}", e);
+                None
+            }
+        };
+        if let Some(path) = orphan {
+            let recovered = match session::Session::load(&path) {
+                Ok(session) => session,
+                Err(e) => {
+                    tracing::warn!("Ignoring unreadable session: {:#}", e);
+                    None
+                }
+            };
+            if let Some(session) = recovered {
+                if let Some(shown) = session.active_tab() {
+                    let question = match session.tabs.len() {
+                        1 => format!("toast did not exit cleanly. Restore {}?", shown.url),
+                        tabs => format!(
+                            "toast did not exit cleanly. Restore {} tabs, showing {}?",
+                            tabs, shown.url
+                        ),
+                    };
+                    if confirm(&question)? {
+                        active_tab = if session.active < session.tabs.len() {
+                            session.active
+                        } else {
+                            0
+                        };
+                        for tab in session.tabs {
+                            urls.push(tab.url);
+                            restore_scroll.push(tab.scroll);
+                        }
+                    }
+                }
+            }
+            // Offered once, whatever the answer
+            if let Err(e) = session::discard(&path) {
+                tracing::warn!("Failed to remove recovered session: {:#}", e);
+            }
+        }
+    }
+    let session_file = sessions_dir.and_then(|dir| match session::SessionFile::create(&dir) {
+        Ok(file) => Some(Arc::new(file)),
+        Err(e) => {
+            tracing::warn!("Session autosave disabled: {:#}", e);
+            None
+        }
+    });
+    if urls.is_empty() {
+        urls.extend(homepage.clone());
+    }
+    // Nothing to open: start on the start page with the URL line open
+    let url_prompt = urls.is_empty();
+    if url_prompt {
+        urls.push(target::resolve(target::START_PAGE)?);
+    }
+
+    // The first page is shown; the rest open in tabs after it
+    let url = urls.remove(0);
+
+    let options = app::AppOptions {
+        latency_test: args.latency_test,
+        debug_grid: args.debug_grid,
+        stats: args.stats,
+        status_bar: !args.no_status_bar,
+        scrollbar: !args.no_scrollbar,
+        tab_bar: args.tab_bar,
+        tab_notices: args.tab_notices,
+        inline_rows: args.inline,
+        render_to: args.render_to.clone(),
+        title_format: (!title_format.is_empty()).then_some(title_format),
+        load_bell,
+        url_prompt,
+        tab_urls: urls,
+        ocr: args.ocr,
+        match_background: args.match_background,
+        ephemeral: args.ephemeral,
+        interpolate: args.interpolate,
+        max_redraw_fps: args.max_redraw_fps,
+        overlay_opacity: args.overlay_opacity,
+        wheel: action::WheelScroll {
+            step: args.wheel_step,
+            natural: args.natural_scroll,
+        },
+        adaptive_quality: args.adaptive_quality,
+        session_file,
+        restore_scroll,
+        active_tab,
+        homepage,
+        capture_format: args.capture_format,
+        fps: profile.fps.unwrap_or(app::DEFAULT_FPS),
+        capture_quality: profile.quality.unwrap_or(DEFAULT_QUALITY),
+        max_bandwidth: args.max_bandwidth.or(profile.max_bandwidth),
+        viewport,
+        render: render_config(&args, color_filter, charset),
+        keymap,
+        search_engine: config.search_engine.clone(),
+        chrome_path: config.chrome_path.clone(),
+    };
+    let app = app::App::new(url, options);
+    app.run().await
+}
+
+/// Rendering options from the command line and profile
+fn render_config(args: &Args, color_filter: ColorFilter, charset: CharacterSet) -> RenderConfig {
+    RenderConfig {
+        metric: args.color_metric,
+        filter: args.filter,
+        supersample: args.supersample,
+        subpixel: args.subpixel,
+        preserve_edges: args.preserve_edges,
+        sharpen: args.sharpen,
+        saturation: args.saturation,
+        vibrance: args.vibrance,
+        charset,
+        color_filter,
+        high_contrast: args.high_contrast,
+        text_enhancement: args.crisp_text,
+        text_quantization: args.text_quantization,
+        pair_search: args.pair_search,
+        damage_tracking: args.damage_tracking,
+        progressive: args.progressive,
+        // Scrolls are drawn by shifting the terminal's rows instead of repainting them
+        scroll_detection: true,
+        color_hysteresis: args.color_hysteresis,
+        // The app measures the real cell shape once the terminal is up
+        cell_aspect: (!args.stretch).then_some(NOMINAL_CELL_ASPECT),
+        // Private sessions leave nothing on disk, so the lookup tables are built every run
+        lut_cache_dir: (!args.ephemeral).then(|| paths::get().lut_cache_dir()),
+    }
+}
+
+/// Ask a yes/no question on the terminal, defaulting to yes
+fn confirm(question: &str) -> Result<bool> {
+    print!("{} [Y/n] ", question);
+    io::stdout().flush()?;
+
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
+    Ok(!matches!(
+        answer.trim().to_ascii_lowercase().as_str(),
+        "n" | "no"
+    ))
+}
+
+/// Log to a file, since the terminal is taken over by rendering
+fn init_logging(path: &Path) -> Result<()> {
     let log_file = OpenOptions::new()
         .create(true)
         .append(true)
-        .open("toastylog.log")?;
+        .open(path)
+        .with_context(|| format!("Failed to open log file {}", path.display()))?;
 
     // Initialize tracing with file output
     tracing_subscriber::fmt()
@@ -32,27 +583,17 @@ async fn main() -> Result<()> {
         .with_ansi(false) // Disable ANSI colors in log file
         .init();
 
-    let args = Args::parse();
-
-    // Get URL from args or prompt user
-    let url_input = if let Some(url) = args.url {
-        url
-    } else {
-        print!("\"Toast\" - the browser. Enter a URL: ");
-        io::stdout().flush()?;
-
-        let mut input = String::new();
-        io::stdin().read_line(&mut input)?;
-        input.trim().to_string()
-    };
+    Ok(())
+}
 
-    // Validate URL
-    let url = if url_input.starts_with("http://") || url_input.starts_with("https://") {
-        url_input
-    } else {
-        format!("https://{}", url_input)
-    };
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    let app = app::App::new(url);
-    app.run().await
+    #[test]
+    fn test_ephemeral_render_config_has_no_cache_dir() {
+        let args = Args::parse_from(["toast", "--ephemeral", "example.com"]);
+        let config = render_config(&args, ColorFilter::None, CharacterSet::default());
+        assert_eq!(config.lut_cache_dir, None);
+    }
 }
@@ -0,0 +1,31 @@
+use std::time::{Duration, Instant};
+use toast_core::{AnsiColor, TerminalFrame};
+use toast_render::OverlayStyle;
+
+/// A short message drawn across the top row of the page for a few seconds
+#[derive(Debug, Clone)]
+pub struct Notice {
+    text: String,
+    expires: Instant,
+}
+
+impl Notice {
+    pub fn new(text: impl Into<String>, duration: Duration) -> Self {
+        Self {
+            text: text.into(),
+            expires: Instant::now() + duration,
+        }
+    }
+
+    pub fn is_expired(&self) -> bool {
+        Instant::now() >= self.expires
+    }
+
+    /// Draw the notice as a full-width bar on the top row (black on yellow)
+    pub fn draw(&self, frame: &mut TerminalFrame, style: &OverlayStyle) {
+        let fg = AnsiColor(16);
+        let bg = AnsiColor(220);
+        let line = format!(" {:<width$}", self.text, width = frame.width);
+        style.draw_text(frame, 0, 0, &line, fg, bg);
+    }
+}
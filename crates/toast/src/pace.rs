@@ -0,0 +1,55 @@
+use std::time::{Duration, Instant};
+
+/// Holds page redraws to a maximum rate, independent of the capture rate
+#[derive(Debug)]
+pub struct FramePacer {
+    /// Shortest time between redraws; zero for no limit
+    interval: Duration,
+    last: Option<Instant>,
+}
+
+impl FramePacer {
+    /// Redraw at most `max_fps` times a second, or as often as frames come with `None`
+    pub fn new(max_fps: Option<u32>) -> Self {
+        Self {
+            interval: max_fps
+                .filter(|&fps| fps > 0)
+                .map_or(Duration::ZERO, |fps| Duration::from_secs(1) / fps),
+            last: None,
+        }
+    }
+
+    /// How long to wait at `now` before the next redraw
+    pub fn delay(&self, now: Instant) -> Duration {
+        self.last.map_or(Duration::ZERO, |last| {
+            (last + self.interval).saturating_duration_since(now)
+        })
+    }
+
+    /// Note a redraw at `now`
+    pub fn drawn(&mut self, now: Instant) {
+        self.last = Some(now);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pacer_spaces_redraws() {
+        let now = Instant::now();
+        let mut pacer = FramePacer::new(Some(10));
+        assert_eq!(pacer.delay(now), Duration::ZERO);
+        pacer.drawn(now);
+        assert_eq!(
+            pacer.delay(now + Duration::from_millis(40)),
+            Duration::from_millis(60)
+        );
+        assert_eq!(pacer.delay(now + Duration::from_secs(1)), Duration::ZERO);
+
+        let mut unlimited = FramePacer::new(None);
+        unlimited.drawn(now);
+        assert_eq!(unlimited.delay(now), Duration::ZERO);
+    }
+}
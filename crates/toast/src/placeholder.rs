@@ -0,0 +1,128 @@
+use std::time::{Instant, SystemTime};
+use toast_core::{AnsiColor, FrameMetadata, TerminalFrame};
+
+/// Spinner frames, advanced by elapsed time
+const SPINNER: [char; 4] = ['|', '/', '-', '\\'];
+
+/// Spinner frame duration in milliseconds
+const SPINNER_STEP_MS: u128 = 125;
+
+/// A navigation whose first frame hasn't been captured yet
+///
+/// Until then frames still show the previous page, so a placeholder is drawn instead of
+/// them rather than inviting clicks on stale content.
+#[derive(Debug, Clone)]
+pub struct PendingNavigation {
+    url: String,
+    started: Instant,
+    /// When the page finished loading; captures started after this show the new page
+    loaded: Option<SystemTime>,
+}
+
+impl PendingNavigation {
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            started: Instant::now(),
+            loaded: None,
+        }
+    }
+
+    /// The page finished loading; the next capture shows it
+    pub fn finish_loading(&mut self) {
+        self.loaded = Some(SystemTime::now());
+    }
+
+    /// Whether a frame with this metadata was captured after the page loaded
+    pub fn is_shown_by(&self, metadata: &FrameMetadata) -> bool {
+        match (self.loaded, metadata.captured_at) {
+            (Some(loaded), Some(captured)) => captured >= loaded,
+            // Without a capture time, any frame after loading has to do
+            (Some(_), None) => true,
+            (None, _) => false,
+        }
+    }
+
+    /// A frame showing the URL, a spinner and the time spent waiting, centered
+    pub fn draw(&self, width: usize, height: usize) -> TerminalFrame {
+        let mut frame = TerminalFrame::new(width, height);
+        frame.metadata = FrameMetadata {
+            url: Some(self.url.clone()),
+            loading: true,
+            ..FrameMetadata::default()
+        };
+        let elapsed = self.started.elapsed();
+        let spinner = SPINNER[(elapsed.as_millis() / SPINNER_STEP_MS) as usize % SPINNER.len()];
+
+        let lines = [
+            format!("{} Loading", spinner),
+            truncate(&self.url, width.saturating_sub(4)),
+            format!("{:.1}s", elapsed.as_secs_f32()),
+        ];
+        let top = (height / 2).saturating_sub(lines.len() / 2);
+        for (row, line) in lines.iter().enumerate() {
+            let x = width.saturating_sub(line.chars().count()) / 2;
+            let fg = if row == 1 {
+                AnsiColor(15)
+            } else {
+                AnsiColor(245)
+            };
+            frame.draw_text(x, top + row, line, fg, AnsiColor(0));
+        }
+        frame
+    }
+}
+
+/// Shorten `text` to at most `max` characters, marking the cut with an ellipsis
+fn truncate(text: &str, max: usize) -> String {
+    if text.chars().count() <= max {
+        return text.to_string();
+    }
+    let mut short: String = text.chars().take(max.saturating_sub(1)).collect();
+    short.push('…');
+    short
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn row_text(frame: &TerminalFrame, y: usize) -> String {
+        (0..frame.width)
+            .filter_map(|x| frame.get(x, y).map(|cell| cell.character))
+            .collect()
+    }
+
+    #[test]
+    fn test_draw_shows_url() {
+        let frame = PendingNavigation::new("https://example.com/").draw(40, 10);
+        let text: Vec<String> = (0..10).map(|y| row_text(&frame, y)).collect();
+        assert!(text.iter().any(|row| row.trim() == "https://example.com/"));
+        assert!(text.iter().any(|row| row.contains("Loading")));
+    }
+
+    #[test]
+    fn test_long_urls_are_truncated() {
+        assert_eq!(truncate("https://example.com/", 10), "https://e…");
+        assert_eq!(truncate("short", 10), "short");
+    }
+
+    #[test]
+    fn test_only_frames_captured_after_loading_count() {
+        let mut nav = PendingNavigation::new("https://example.com/");
+        let before = FrameMetadata {
+            captured_at: Some(SystemTime::now() - Duration::from_secs(1)),
+            ..Default::default()
+        };
+        assert!(!nav.is_shown_by(&before));
+
+        nav.finish_loading();
+        assert!(!nav.is_shown_by(&before));
+        let after = FrameMetadata {
+            captured_at: Some(SystemTime::now() + Duration::from_secs(1)),
+            ..Default::default()
+        };
+        assert!(nav.is_shown_by(&after));
+    }
+}
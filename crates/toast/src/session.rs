@@ -0,0 +1,254 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::path::{Path, PathBuf};
+
+/// Where the user was, autosaved while toast runs and removed on a clean exit
+///
+/// A session file left behind at startup means toast or Chrome crashed.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Session {
+    /// The open tabs, in tab bar order
+    pub tabs: Vec<SessionTab>,
+    /// Index of the tab shown
+    #[serde(default)]
+    pub active: usize,
+}
+
+/// A tab of a saved session
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SessionTab {
+    pub url: String,
+    /// Page scroll offset in CSS pixels
+    #[serde(default)]
+    pub scroll: (f64, f64),
+}
+
+impl Session {
+    /// Load a saved session, `None` if there is none
+    pub fn load(path: &Path) -> Result<Option<Self>> {
+        let text = match std::fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e).with_context(|| format!("Failed to read {}", path.display())),
+        };
+        let session = toml::from_str(&text)
+            .with_context(|| format!("Invalid session file {}", path.display()))?;
+        Ok(Some(session))
+    }
+
+    /// The tab that was shown, or the first if the index is out of range
+    pub fn active_tab(&self) -> Option<&SessionTab> {
+        self.tabs.get(self.active).or(self.tabs.first())
+    }
+}
+
+/// This instance's session file, one of several if more than one toast is running
+///
+/// Each instance holds a lock on a `.lock` file next to its session for as long as it
+/// runs, so a session whose lock can be taken was left behind by a crash, not by another
+/// toast that is still running.
+#[derive(Debug)]
+pub struct SessionFile {
+    path: PathBuf,
+    _lock: File,
+}
+
+impl SessionFile {
+    /// Claim a new session file in `dir` for this instance
+    ///
+    /// Sessions list where the user has been, so the directory and files are only
+    /// readable by them.
+    pub fn create(dir: &Path) -> Result<Self> {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create {}", dir.display()))?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(dir, std::fs::Permissions::from_mode(0o700))
+                .with_context(|| format!("Failed to restrict access to {}", dir.display()))?;
+        }
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.subsec_nanos())
+            .unwrap_or_default();
+        let path = dir.join(format!("{}-{}.toml", std::process::id(), nanos));
+        let lock_path = lock_path(&path);
+        let lock = File::create(&lock_path)
+            .with_context(|| format!("Failed to create {}", lock_path.display()))?;
+        lock.try_lock()
+            .with_context(|| format!("Failed to lock {}", lock_path.display()))?;
+        Ok(Self { path, _lock: lock })
+    }
+
+    /// Write the session, replacing the previous one atomically
+    pub fn save(&self, session: &Session) -> Result<()> {
+        let text = toml::to_string(session).context("Failed to serialize session")?;
+        let temp = self.path.with_extension("toml.tmp");
+        write_private(&temp, text.as_bytes())
+            .with_context(|| format!("Failed to write {}", temp.display()))?;
+        std::fs::rename(&temp, &self.path)
+            .with_context(|| format!("Failed to replace {}", self.path.display()))
+    }
+
+    /// Forget the saved session (after a clean exit)
+    pub fn clear(&self) -> Result<()> {
+        discard(&self.path)
+    }
+}
+
+/// Sessions in `dir` left behind by toasts that are no longer running, newest first
+pub fn orphans(dir: &Path) -> Result<Vec<PathBuf>> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e).with_context(|| format!("Failed to read {}", dir.display())),
+    };
+    let mut found = Vec::new();
+    for entry in entries {
+        let path = entry?.path();
+        if path.extension().is_none_or(|ext| ext != "toml") {
+            continue;
+        }
+        // A lock that can't be taken is held by a running toast
+        let orphaned = match File::open(lock_path(&path)) {
+            Ok(lock) => lock.try_lock().is_ok(),
+            Err(_) => true,
+        };
+        if orphaned {
+            let modified = std::fs::metadata(&path).and_then(|m| m.modified()).ok();
+            found.push((modified, path));
+        }
+    }
+    found.sort_by_key(|(modified, _)| std::cmp::Reverse(*modified));
+    Ok(found.into_iter().map(|(_, path)| path).collect())
+}
+
+/// Remove a session file and its lock
+pub fn discard(path: &Path) -> Result<()> {
+    for path in [path.to_path_buf(), lock_path(path)] {
+        match std::fs::remove_file(&path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                return Err(e).with_context(|| format!("Failed to remove {}", path.display()))
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Write `contents` to a new file at `path` that only the user can read
+fn write_private(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    std::io::Write::write_all(&mut options.open(path)?, contents)
+}
+
+fn lock_path(session: &Path) -> PathBuf {
+    session.with_extension("lock")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("toast-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn test_save_load_clear() {
+        let dir = test_dir("session-test");
+        let file = SessionFile::create(&dir).unwrap();
+
+        let session = Session {
+            tabs: vec![
+                SessionTab {
+                    url: "https://example.com/a?b=c".to_string(),
+                    scroll: (0.0, 1234.5),
+                },
+                SessionTab {
+                    url: "https://example.org".to_string(),
+                    scroll: (0.0, 0.0),
+                },
+            ],
+            active: 1,
+        };
+        file.save(&session).unwrap();
+        assert_eq!(Session::load(&file.path).unwrap(), Some(session));
+
+        file.clear().unwrap();
+        assert_eq!(Session::load(&file.path).unwrap(), None);
+        // Clearing twice is fine
+        file.clear().unwrap();
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_sessions_are_private() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = test_dir("session-private");
+        let file = SessionFile::create(&dir).unwrap();
+        file.save(&Session {
+            tabs: Vec::new(),
+            active: 0,
+        })
+        .unwrap();
+        let mode = |path: &Path| std::fs::metadata(path).unwrap().permissions().mode() & 0o777;
+        assert_eq!(mode(&dir), 0o700);
+        assert_eq!(mode(&file.path), 0o600);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_running_instance_session_is_not_an_orphan() {
+        let dir = test_dir("session-orphans");
+        let session = Session {
+            tabs: vec![SessionTab {
+                url: "https://example.com".to_string(),
+                scroll: (0.0, 0.0),
+            }],
+            active: 0,
+        };
+
+        let running = SessionFile::create(&dir).unwrap();
+        running.save(&session).unwrap();
+        assert!(orphans(&dir).unwrap().is_empty());
+
+        // Once its toast is gone, the lock is released and the session can be recovered
+        let path = running.path.clone();
+        drop(running);
+        assert_eq!(orphans(&dir).unwrap(), vec![path.clone()]);
+        discard(&path).unwrap();
+        assert!(orphans(&dir).unwrap().is_empty());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_missing_fields_default() {
+        let session: Session = toml::from_str("[[tabs]]\nurl = \"https://example.com\"").unwrap();
+        assert_eq!(session.active, 0);
+        assert_eq!(session.tabs[0].scroll, (0.0, 0.0));
+
+        let out_of_range = Session {
+            active: 3,
+            ..session
+        };
+        assert_eq!(
+            out_of_range.active_tab().unwrap().url,
+            "https://example.com"
+        );
+    }
+}
@@ -0,0 +1,458 @@
+use anyhow::{Context, Result};
+use std::io::{BufRead, Write};
+use std::path::{Path, PathBuf};
+use toast_render::CharacterSet;
+use toast_terminal::{detect_capabilities, Capabilities, Passthrough, QUERY_TIMEOUT};
+
+use crate::action::Keymap;
+use crate::config::SEARCH_QUERY;
+
+/// Search engines offered by name; any URL with `{query}` works too
+const SEARCH_ENGINES: [(&str, &str); 4] = [
+    ("duckduckgo", "https://duckduckgo.com/?q={query}"),
+    ("google", "https://www.google.com/search?q={query}"),
+    ("bing", "https://www.bing.com/search?q={query}"),
+    ("brave", "https://search.brave.com/search?q={query}"),
+];
+
+/// Where Chrome and friends are installed on macOS, looked at before `PATH`
+const APP_BUNDLES: [&str; 3] = [
+    "/Applications/Helium.app/Contents/MacOS/Helium",
+    "/Applications/Google Chrome.app/Contents/MacOS/Google Chrome",
+    "/Applications/Chromium.app/Contents/MacOS/Chromium",
+];
+
+/// Chrome executable names looked for on `PATH`
+const CHROME_NAMES: [&str; 5] = [
+    "google-chrome",
+    "google-chrome-stable",
+    "chromium",
+    "chromium-browser",
+    "chrome",
+];
+
+/// Answers to the first-run questions, which become the config file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Setup {
+    pub charset: CharacterSet,
+    pub keys: Keymap,
+    /// Search URL with `{query}`, or `None` to treat words as host names
+    pub search_engine: Option<String>,
+    /// `None` finds Chrome at each launch
+    pub chrome_path: Option<PathBuf>,
+}
+
+/// What the terminal and the system suggest, offered as each question's default
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Suggestions {
+    pub capabilities: Capabilities,
+    pub charset: CharacterSet,
+    pub chrome_path: Option<PathBuf>,
+}
+
+impl Suggestions {
+    /// Probe the terminal and look for Chrome
+    ///
+    /// The terminal is switched to raw mode for the probe and back.
+    pub fn detect() -> Self {
+        let capabilities = match crossterm::terminal::enable_raw_mode() {
+            Ok(()) => {
+                let caps = detect_capabilities(QUERY_TIMEOUT, Passthrough::detect());
+                let _ = crossterm::terminal::disable_raw_mode();
+                caps
+            }
+            Err(_) => Capabilities::from_env(|name| std::env::var(name).ok()),
+        };
+        let term = std::env::var("TERM").unwrap_or_default();
+        Self {
+            capabilities,
+            charset: suggested_charset(&term),
+            chrome_path: find_chrome(
+                std::env::var_os("CHROME_PATH").map(PathBuf::from),
+                std::env::var_os("PATH"),
+            ),
+        }
+    }
+}
+
+impl Setup {
+    /// The config file recording these answers
+    pub fn config_text(&self) -> String {
+        let quote = |s: &str| toml::Value::String(s.to_string()).to_string();
+        let mut text = String::from("# Written by toast's first-run setup; edit freely\n");
+        let charset = match self.charset {
+            CharacterSet::HalfBlock => "half-block",
+            CharacterSet::Shade => "shade",
+        };
+        text += &format!("charset = {}\n", quote(charset));
+        let keys = match self.keys {
+            Keymap::Default => "default",
+            Keymap::Vim => "vim",
+        };
+        text += &format!("keys = {}\n", quote(keys));
+        if let Some(search) = &self.search_engine {
+            text += &format!("search_engine = {}\n", quote(search));
+        }
+        if let Some(chrome) = &self.chrome_path {
+            text += &format!("chrome_path = {}\n", quote(&chrome.to_string_lossy()));
+        }
+        text
+    }
+}
+
+/// Ask the first-run questions on the terminal and write the config file at `path`
+///
+/// Declining to set up writes a config file all the same, so the questions aren't asked
+/// again; it has no settings, like no file at all.
+pub fn run(path: &Path) -> Result<()> {
+    let suggestions = Suggestions::detect();
+    let stdin = std::io::stdin();
+    let mut stdout = std::io::stdout();
+    let text = match ask(&mut stdin.lock(), &mut stdout, &suggestions)? {
+        Some(setup) => setup.config_text(),
+        None => "# toast's config file; see the README for the keys it takes\n".to_string(),
+    };
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create {}", dir.display()))?;
+    }
+    std::fs::write(path, text).with_context(|| format!("Failed to write {}", path.display()))?;
+    writeln!(stdout, "Wrote {}\n", path.display())?;
+    Ok(())
+}
+
+/// Ask the questions on `output`, reading answers from `input`; `None` if the user would
+/// rather not
+///
+/// An empty answer, or the end of `input`, takes the suggestion; an answer that doesn't
+/// make sense asks again.
+pub fn ask(
+    input: &mut impl BufRead,
+    output: &mut impl Write,
+    suggestions: &Suggestions,
+) -> Result<Option<Setup>> {
+    writeln!(output, "Welcome to toast! There is no config file yet.")?;
+    let start = question(
+        input,
+        output,
+        "Set up toast now? [Y/n]",
+        |answer| match answer.to_ascii_lowercase().as_str() {
+            "" | "y" | "yes" => Some(true),
+            "n" | "no" => Some(false),
+            _ => None,
+        },
+    )?;
+    if !start {
+        return Ok(None);
+    }
+    writeln!(
+        output,
+        "Press Enter to take the suggestion in [brackets].\n"
+    )?;
+
+    writeln!(
+        output,
+        "Your terminal supports {}.",
+        describe(&suggestions.capabilities)
+    )?;
+    writeln!(
+        output,
+        "Pages are drawn with half-block characters (▀, twice the vertical detail) or \
+         shade characters (░▒▓, more colors)."
+    )?;
+    let charset_name = match suggestions.charset {
+        CharacterSet::HalfBlock => "half-block",
+        CharacterSet::Shade => "shade",
+    };
+    let charset = question(
+        input,
+        output,
+        &format!("Draw pages with half-block or shade? [{}]", charset_name),
+        |answer| match answer {
+            "" => Some(suggestions.charset),
+            answer => answer.parse().ok(),
+        },
+    )?;
+
+    let keys = question(
+        input,
+        output,
+        "Key bindings: default, or vim (j/k scroll, H/L back/forward, J/K tabs)? [default]",
+        |answer| match answer {
+            "" => Some(Keymap::Default),
+            answer => answer.parse().ok(),
+        },
+    )?;
+
+    let names: Vec<&str> = SEARCH_ENGINES.iter().map(|(name, _)| *name).collect();
+    let search_engine = question(
+        input,
+        output,
+        &format!(
+            "Search engine for words typed on the URL line: {}, none, or a URL with {} [{}]",
+            names.join(", "),
+            SEARCH_QUERY,
+            names[0]
+        ),
+        |answer| search_engine(answer).ok(),
+    )?;
+
+    let chrome_path = match &suggestions.chrome_path {
+        Some(found) => question(
+            input,
+            output,
+            &format!("Chrome executable [{}]", found.display()),
+            |answer| match answer {
+                "" => Some(Some(found.clone())),
+                answer => chrome_answer(answer),
+            },
+        )?,
+        None => question(
+            input,
+            output,
+            "Chrome was not found; path to its executable (Enter to look for it at each \
+             launch)",
+            |answer| match answer {
+                "" => Some(None),
+                answer => chrome_answer(answer),
+            },
+        )?,
+    };
+
+    Ok(Some(Setup {
+        charset,
+        keys,
+        search_engine,
+        chrome_path,
+    }))
+}
+
+/// Ask `prompt` until `parse` makes sense of the trimmed answer; the end of `input`
+/// counts as an empty answer
+fn question<T>(
+    input: &mut impl BufRead,
+    output: &mut impl Write,
+    prompt: &str,
+    parse: impl Fn(&str) -> Option<T>,
+) -> Result<T> {
+    loop {
+        write!(output, "{} ", prompt)?;
+        output.flush()?;
+        let mut answer = String::new();
+        let read = input.read_line(&mut answer)?;
+        match parse(answer.trim()) {
+            Some(value) => return Ok(value),
+            // Nothing more to read: take the suggestion rather than asking forever
+            None if read == 0 => {
+                return parse("").context("No answer and nothing to suggest");
+            }
+            None => writeln!(
+                output,
+                "Sorry, '{}' isn't one of the choices.",
+                answer.trim()
+            )?,
+        }
+    }
+}
+
+/// The search URL an answer names: a known engine, `none`, or a URL with `{query}`
+fn search_engine(answer: &str) -> Result<Option<String>> {
+    let answer = if answer.is_empty() {
+        SEARCH_ENGINES[0].0
+    } else {
+        answer
+    };
+    if answer.eq_ignore_ascii_case("none") {
+        return Ok(None);
+    }
+    if let Some((_, url)) = SEARCH_ENGINES
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(answer))
+    {
+        return Ok(Some(url.to_string()));
+    }
+    anyhow::ensure!(
+        answer.contains("://") && answer.contains(SEARCH_QUERY),
+        "not a search engine or a URL with {}",
+        SEARCH_QUERY
+    );
+    Ok(Some(answer.to_string()))
+}
+
+/// An executable the user typed, if it exists
+fn chrome_answer(answer: &str) -> Option<Option<PathBuf>> {
+    let path = PathBuf::from(answer);
+    path.is_file().then_some(Some(path))
+}
+
+/// Half-block draws sharper pages; the Linux console's 16 colors need the shades
+fn suggested_charset(term: &str) -> CharacterSet {
+    if term == "linux" {
+        CharacterSet::Shade
+    } else {
+        CharacterSet::HalfBlock
+    }
+}
+
+/// Chrome as toast would launch it: `chrome_env` (`CHROME_PATH`) if set, else an app
+/// bundle, else the first Chrome on `path` (`PATH`)
+fn find_chrome(chrome_env: Option<PathBuf>, path: Option<std::ffi::OsString>) -> Option<PathBuf> {
+    if let Some(chrome) = chrome_env.filter(|chrome| chrome.is_file()) {
+        return Some(chrome);
+    }
+    if let Some(bundle) = APP_BUNDLES.iter().map(PathBuf::from).find(|p| p.is_file()) {
+        return Some(bundle);
+    }
+    let dirs: Vec<PathBuf> = path
+        .map(|path| std::env::split_paths(&path).collect())
+        .unwrap_or_default();
+    CHROME_NAMES.iter().find_map(|name| {
+        dirs.iter()
+            .map(|dir| dir.join(name))
+            .find(|candidate| candidate.is_file())
+    })
+}
+
+/// The capabilities worth mentioning, as a list for a sentence
+fn describe(caps: &Capabilities) -> String {
+    let mut features = vec!["256 colors"];
+    let known = [
+        (caps.truecolor, "24-bit color"),
+        (caps.sixel, "sixel graphics"),
+        (caps.kitty_graphics, "kitty graphics"),
+        (caps.synchronized_update, "synchronized output"),
+        (caps.hyperlinks, "hyperlinks"),
+    ];
+    features.extend(known.iter().filter(|(has, _)| *has).map(|(_, name)| *name));
+    match features.split_last() {
+        Some((last, [])) => last.to_string(),
+        Some((last, rest)) => format!("{} and {}", rest.join(", "), last),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+
+    fn suggestions() -> Suggestions {
+        Suggestions {
+            capabilities: Capabilities {
+                truecolor: true,
+                ..Capabilities::default()
+            },
+            charset: CharacterSet::HalfBlock,
+            chrome_path: Some(PathBuf::from("/usr/bin/chromium")),
+        }
+    }
+
+    fn answers(text: &str) -> Option<Setup> {
+        let mut output = Vec::new();
+        ask(&mut text.as_bytes(), &mut output, &suggestions()).unwrap()
+    }
+
+    #[test]
+    fn test_enter_takes_the_suggestions() {
+        let setup = answers("\n\n\n\n\n").unwrap();
+        assert_eq!(
+            setup,
+            Setup {
+                charset: CharacterSet::HalfBlock,
+                keys: Keymap::Default,
+                search_engine: Some(SEARCH_ENGINES[0].1.to_string()),
+                chrome_path: Some(PathBuf::from("/usr/bin/chromium")),
+            }
+        );
+        // So does running out of input
+        assert_eq!(answers(""), Some(setup));
+    }
+
+    #[test]
+    fn test_answers_are_asked_again_until_they_make_sense() {
+        let setup = answers("yes\nascii\nshade\nvim\nnone\n\n").unwrap();
+        assert_eq!(setup.charset, CharacterSet::Shade);
+        assert_eq!(setup.keys, Keymap::Vim);
+        assert_eq!(setup.search_engine, None);
+    }
+
+    #[test]
+    fn test_declining() {
+        assert_eq!(answers("n\n"), None);
+    }
+
+    #[test]
+    fn test_search_engine_answers() {
+        assert_eq!(
+            search_engine("Google").unwrap().as_deref(),
+            Some("https://www.google.com/search?q={query}")
+        );
+        assert_eq!(
+            search_engine("https://search.example/?q={query}")
+                .unwrap()
+                .as_deref(),
+            Some("https://search.example/?q={query}")
+        );
+        assert!(search_engine("https://search.example/").is_err());
+        assert!(search_engine("altavista").is_err());
+    }
+
+    #[test]
+    fn test_config_text_is_a_valid_config() {
+        let setup = Setup {
+            charset: CharacterSet::Shade,
+            keys: Keymap::Vim,
+            search_engine: Some("https://duckduckgo.com/?q={query}".to_string()),
+            chrome_path: Some(PathBuf::from("/opt/Google Chrome/chrome")),
+        };
+        let config: Config = toml::from_str(&setup.config_text()).unwrap();
+        assert_eq!(config.charset.as_deref(), Some("shade"));
+        assert_eq!(config.keys.as_deref(), Some("vim"));
+        assert_eq!(
+            config.search_engine.as_deref(),
+            Some("https://duckduckgo.com/?q={query}")
+        );
+        assert_eq!(
+            config.chrome_path,
+            Some(PathBuf::from("/opt/Google Chrome/chrome"))
+        );
+    }
+
+    #[test]
+    fn test_find_chrome_on_path() {
+        let dir = std::env::temp_dir().join(format!("toast-setup-chrome-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let chromium = dir.join("chromium");
+        std::fs::write(&chromium, "").unwrap();
+
+        let path = std::env::join_paths(["/nonexistent", dir.to_str().unwrap()]).unwrap();
+        // Unless there's an app bundle on this machine, PATH is where it's found
+        if APP_BUNDLES
+            .iter()
+            .all(|bundle| !Path::new(bundle).is_file())
+        {
+            assert_eq!(
+                find_chrome(None, Some(path.clone())),
+                Some(chromium.clone())
+            );
+        }
+        // CHROME_PATH comes first, when it exists
+        assert_eq!(
+            find_chrome(Some(chromium.clone()), None),
+            Some(chromium.clone())
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_describe_capabilities() {
+        assert_eq!(describe(&Capabilities::default()), "256 colors");
+        let caps = Capabilities {
+            truecolor: true,
+            hyperlinks: true,
+            ..Capabilities::default()
+        };
+        assert_eq!(describe(&caps), "256 colors, 24-bit color and hyperlinks");
+    }
+}
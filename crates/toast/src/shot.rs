@@ -0,0 +1,181 @@
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use serde::Serialize;
+use std::io::Write;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use toast_browser::{capture_screenshot, launch_browser_with, LaunchOptions, ProfileGuard};
+use toast_render::{frame_to_html, PipelineStats, RenderConfig, RenderPipeline};
+
+/// Output formats for `toast shot`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ShotFormat {
+    /// Standalone HTML page with colored spans
+    Html,
+}
+
+impl ShotFormat {
+    fn name(self) -> &'static str {
+        match self {
+            ShotFormat::Html => "html",
+        }
+    }
+}
+
+/// How `toast shot` renders and where the result goes
+#[derive(Debug, Clone)]
+pub struct ShotOptions {
+    pub format: ShotFormat,
+    /// Size in terminal cells
+    pub size: (usize, usize),
+    /// Write here instead of stdout
+    pub output: Option<PathBuf>,
+    /// Browser viewport size in CSS pixels
+    pub viewport: (u32, u32),
+    pub ephemeral: bool,
+    /// Print a JSON report (with the document inline unless written to `output`)
+    pub json: bool,
+    /// Chrome executable from the config file
+    pub chrome_path: Option<PathBuf>,
+}
+
+/// What `toast shot --json` prints on success
+#[derive(Debug, Serialize)]
+pub struct ShotReport {
+    pub url: String,
+    pub format: &'static str,
+    /// Frame size in cells
+    pub width: usize,
+    pub height: usize,
+    /// File the document was written to
+    pub output: Option<PathBuf>,
+    /// The document itself when it wasn't written to a file
+    pub content: Option<String>,
+    pub timings_ms: ShotTimings,
+}
+
+/// Milliseconds spent in each step of a shot
+#[derive(Debug, Default, Serialize)]
+pub struct ShotTimings {
+    pub launch: f64,
+    pub capture: f64,
+    pub decode: f64,
+    pub scale: f64,
+    pub enhance: f64,
+    pub convert: f64,
+    pub total: f64,
+}
+
+impl ShotTimings {
+    fn new(launch: Duration, capture: Duration, render: PipelineStats) -> Self {
+        // Whole microseconds keep float noise out of the JSON
+        let ms = |d: Duration| d.as_micros() as f64 / 1000.0;
+        Self {
+            launch: ms(launch),
+            capture: ms(capture),
+            decode: ms(render.decode),
+            scale: ms(render.scale),
+            enhance: ms(render.enhance),
+            convert: ms(render.convert),
+            total: ms(launch + capture + render.total()),
+        }
+    }
+}
+
+/// What `toast shot --json` prints when it fails
+pub fn error_json(url: &str, error: &anyhow::Error) -> String {
+    serde_json::json!({ "url": url, "error": format!("{:#}", error) }).to_string()
+}
+
+/// Render a single page to a file (or stdout) without taking over the terminal
+pub async fn run(url: &str, options: &ShotOptions, render: RenderConfig) -> Result<()> {
+    let (width, height) = options.size;
+    let launch = LaunchOptions {
+        window_size: options.viewport,
+        chrome_path: options.chrome_path.clone(),
+        ..if options.ephemeral {
+            LaunchOptions::ephemeral()
+        } else {
+            LaunchOptions::default()
+        }
+    };
+    let started = Instant::now();
+    // Declared before the browser, so on an early return Chrome is killed first
+    let profile = ProfileGuard::new(options.ephemeral.then(|| launch.user_data_dir.clone()));
+    let browser = launch_browser_with(&launch).await?;
+    let launched = Instant::now();
+    let screenshot = capture_screenshot(&browser, url).await?;
+    let captured = Instant::now();
+    let pipeline = RenderPipeline::with_config(render);
+    let frame = pipeline.render(&screenshot, width, height)?;
+
+    let text = match options.format {
+        ShotFormat::Html => frame_to_html(&frame, url),
+    };
+    let content = match &options.output {
+        Some(path) => {
+            std::fs::write(path, &text)
+                .with_context(|| format!("Failed to write {}", path.display()))?;
+            None
+        }
+        None => Some(text),
+    };
+
+    if options.json {
+        let report = ShotReport {
+            url: url.to_string(),
+            format: options.format.name(),
+            width: frame.width,
+            height: frame.height,
+            output: options.output.clone(),
+            content,
+            timings_ms: ShotTimings::new(launched - started, captured - launched, pipeline.stats()),
+        };
+        println!("{}", serde_json::to_string(&report)?);
+    } else if let Some(text) = content {
+        std::io::stdout().write_all(text.as_bytes())?;
+    }
+
+    // Dropping the browser kills Chrome, releasing the profile
+    drop(browser);
+    profile.remove().await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report_json() {
+        let report = ShotReport {
+            url: "https://example.com/".into(),
+            format: ShotFormat::Html.name(),
+            width: 120,
+            height: 40,
+            output: None,
+            content: Some("<html></html>".into()),
+            timings_ms: ShotTimings::new(
+                Duration::from_millis(300),
+                Duration::from_millis(700),
+                PipelineStats {
+                    scale: Duration::from_millis(5),
+                    ..PipelineStats::default()
+                },
+            ),
+        };
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["width"], 120);
+        assert_eq!(json["format"], "html");
+        assert_eq!(json["output"], serde_json::Value::Null);
+        assert_eq!(json["timings_ms"]["total"], 1005.0);
+    }
+
+    #[test]
+    fn test_error_json() {
+        let error = anyhow::anyhow!("refused").context("Failed to launch browser");
+        let json: serde_json::Value =
+            serde_json::from_str(&error_json("https://example.com/", &error)).unwrap();
+        assert_eq!(json["error"], "Failed to launch browser: refused");
+    }
+}
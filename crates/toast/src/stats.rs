@@ -0,0 +1,164 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant, SystemTime};
+use toast_core::{AnsiColor, FrameMetadata, TerminalFrame};
+use toast_render::OverlayStyle;
+
+/// Rates are recomputed over windows of this length
+const RATE_WINDOW: Duration = Duration::from_secs(1);
+
+/// Weight of the newest frame in the smoothed latency
+const LATENCY_SMOOTHING: f64 = 0.2;
+
+/// Counters kept by the capture task for the stats overlay
+#[derive(Debug, Default)]
+pub struct CaptureCounters {
+    pub captured: AtomicU64,
+    /// Screenshots dropped because rendering hadn't caught up
+    pub dropped: AtomicU64,
+}
+
+/// Running totals at the start of a rate window
+#[derive(Debug, Clone, Copy)]
+struct Sample {
+    at: Instant,
+    captured: u64,
+    displayed: u64,
+    bytes: u64,
+}
+
+/// Frame rates, drops, latency and output bandwidth for the F12 stats overlay
+#[derive(Debug)]
+pub struct FrameStats {
+    window: Sample,
+    displayed: u64,
+    capture_fps: f64,
+    display_fps: f64,
+    bytes_per_sec: f64,
+    dropped: u64,
+    /// Capture to display, smoothed
+    latency: Option<Duration>,
+}
+
+impl FrameStats {
+    pub fn new(now: Instant) -> Self {
+        Self {
+            window: Sample {
+                at: now,
+                captured: 0,
+                displayed: 0,
+                bytes: 0,
+            },
+            displayed: 0,
+            capture_fps: 0.0,
+            display_fps: 0.0,
+            bytes_per_sec: 0.0,
+            dropped: 0,
+            latency: None,
+        }
+    }
+
+    /// Count a page frame shown at `now`, captured as `metadata` says
+    pub fn frame_shown(&mut self, metadata: &FrameMetadata, now: SystemTime) {
+        self.displayed += 1;
+        let Some(latency) = metadata
+            .captured_at
+            .and_then(|captured| now.duration_since(captured).ok())
+        else {
+            return;
+        };
+        self.latency = Some(match self.latency {
+            Some(smoothed) => {
+                smoothed.mul_f64(1.0 - LATENCY_SMOOTHING) + latency.mul_f64(LATENCY_SMOOTHING)
+            }
+            None => latency,
+        });
+    }
+
+    /// Recompute the rates once a window has passed; `bytes` is the total written to the
+    /// terminal so far
+    pub fn update(&mut self, now: Instant, counters: &CaptureCounters, bytes: u64) {
+        let elapsed = now.duration_since(self.window.at);
+        let captured = counters.captured.load(Ordering::Relaxed);
+        self.dropped = counters.dropped.load(Ordering::Relaxed);
+        if elapsed < RATE_WINDOW {
+            return;
+        }
+        let secs = elapsed.as_secs_f64();
+        self.capture_fps = captured.saturating_sub(self.window.captured) as f64 / secs;
+        self.display_fps = self.displayed.saturating_sub(self.window.displayed) as f64 / secs;
+        self.bytes_per_sec = bytes.saturating_sub(self.window.bytes) as f64 / secs;
+        self.window = Sample {
+            at: now,
+            captured,
+            displayed: self.displayed,
+            bytes,
+        };
+    }
+
+    fn lines(&self) -> Vec<String> {
+        let latency = match self.latency {
+            Some(latency) => format!("{} ms", latency.as_millis()),
+            None => "-".to_string(),
+        };
+        vec![
+            format!("capture {:5.1} fps", self.capture_fps),
+            format!("display {:5.1} fps", self.display_fps),
+            format!("dropped {:>5}", self.dropped),
+            format!("latency {:>8}", latency),
+            format!("output  {:>5} KiB/s", (self.bytes_per_sec / 1024.0).round()),
+        ]
+    }
+
+    /// Draw the stats in a box at the top right, below the notice row
+    pub fn draw(&self, frame: &mut TerminalFrame, overlay: &OverlayStyle) {
+        let lines = self.lines();
+        let width = lines.iter().map(|l| l.chars().count()).max().unwrap_or(0) + 2;
+        let x = frame.width.saturating_sub(width);
+        for (i, line) in lines.iter().enumerate() {
+            let line = format!(" {:<width$}", line, width = width - 1);
+            overlay.draw_text(frame, x, 1 + i, &line, AnsiColor(231), AnsiColor(236));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rates_over_window() {
+        let start = Instant::now();
+        let mut stats = FrameStats::new(start);
+        let counters = CaptureCounters::default();
+        counters.captured.store(30, Ordering::Relaxed);
+        counters.dropped.store(2, Ordering::Relaxed);
+        for _ in 0..15 {
+            stats.frame_shown(&FrameMetadata::default(), SystemTime::now());
+        }
+
+        // Nothing changes until a full window has passed
+        stats.update(start + Duration::from_millis(500), &counters, 4096);
+        assert_eq!(stats.capture_fps, 0.0);
+
+        stats.update(start + Duration::from_secs(2), &counters, 4096);
+        assert_eq!(stats.capture_fps, 15.0);
+        assert_eq!(stats.display_fps, 7.5);
+        assert_eq!(stats.bytes_per_sec, 2048.0);
+        assert_eq!(stats.lines()[2], "dropped     2");
+    }
+
+    #[test]
+    fn test_latency_is_smoothed() {
+        let mut stats = FrameStats::new(Instant::now());
+        let captured = SystemTime::now();
+        let metadata = FrameMetadata {
+            captured_at: Some(captured),
+            ..Default::default()
+        };
+        stats.frame_shown(&metadata, captured + Duration::from_millis(100));
+        assert_eq!(stats.latency, Some(Duration::from_millis(100)));
+        stats.frame_shown(&metadata, captured + Duration::from_millis(200));
+        assert_eq!(stats.latency, Some(Duration::from_millis(120)));
+        assert_eq!(stats.lines()[3], "latency   120 ms");
+    }
+}
@@ -0,0 +1,215 @@
+use anyhow::{bail, Context, Result};
+use std::io::BufRead;
+use std::path::Path;
+
+/// Target that opens toast's generated start page
+pub const START_PAGE: &str = "toast:start";
+
+/// Schemes passed through unchanged even without `://`
+const OPAQUE_SCHEMES: [&str; 4] = ["about:", "data:", "file:", "javascript:"];
+
+/// Expand command-line targets into URLs
+///
+/// `-` reads targets from `stdin`, one per line (blank lines and `#` comments are
+/// skipped), so toast can sit at the end of a pipeline.
+pub fn collect(args: &[String], stdin: impl BufRead) -> Result<Vec<String>> {
+    let mut targets = Vec::new();
+    let mut stdin = Some(stdin);
+
+    for arg in args {
+        if arg == "-" {
+            let Some(input) = stdin.take() else {
+                bail!("'-' (read from stdin) can only be given once");
+            };
+            for line in input.lines() {
+                let line = line.context("Failed to read URLs from stdin")?;
+                let line = line.trim();
+                if !line.is_empty() && !line.starts_with('#') {
+                    targets.push(resolve(line)?);
+                }
+            }
+        } else {
+            targets.push(resolve(arg)?);
+        }
+    }
+
+    Ok(targets)
+}
+
+/// Turn one target into a URL: URLs pass through, local paths become `file://` URLs,
+/// and anything else is treated as a host name over HTTPS
+pub fn resolve(input: &str) -> Result<String> {
+    let input = input.trim();
+    if input.is_empty() {
+        bail!("Empty URL");
+    }
+    if input == START_PAGE {
+        return Ok(start_page());
+    }
+
+    if input.contains("://") || OPAQUE_SCHEMES.iter().any(|s| input.starts_with(s)) {
+        return Ok(input.to_string());
+    }
+
+    let path = Path::new(input);
+    if path.exists() {
+        let absolute = path
+            .canonicalize()
+            .with_context(|| format!("Failed to resolve path {}", input))?;
+        return Ok(format!("file://{}", absolute.display()));
+    }
+    if looks_like_path(input) {
+        bail!("No such file: {}", input);
+    }
+
+    Ok(format!("https://{}", input))
+}
+
+/// Like [`resolve`], but input that doesn't look like an address, like `rust lifetimes`
+/// or `toast`, is searched for with `search`: a URL with
+/// [`{query}`](crate::config::SEARCH_QUERY) where the search terms go
+pub fn resolve_or_search(input: &str, search: Option<&str>) -> Result<String> {
+    match search {
+        Some(search) if looks_like_search(input) => {
+            Ok(search.replace(crate::config::SEARCH_QUERY, &encode_query(input.trim())))
+        }
+        _ => resolve(input),
+    }
+}
+
+/// Words rather than an address: has spaces, or is a single word without a dot or port
+/// that isn't `localhost`, a URL or a local file
+fn looks_like_search(input: &str) -> bool {
+    let input = input.trim();
+    if input.is_empty()
+        || input == START_PAGE
+        || input.contains("://")
+        || OPAQUE_SCHEMES.iter().any(|s| input.starts_with(s))
+        || looks_like_path(input)
+        || Path::new(input).exists()
+    {
+        return false;
+    }
+    input.contains(char::is_whitespace)
+        || !(input.contains('.') || input.contains(':') || input == "localhost")
+}
+
+/// Percent-encode search terms for a query string, spaces as `+`
+fn encode_query(terms: &str) -> String {
+    let mut encoded = String::with_capacity(terms.len());
+    for byte in terms.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                encoded.push(byte as char)
+            }
+            b' ' => encoded.push('+'),
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+/// The generated start page, as a `data:` URL
+pub fn start_page() -> String {
+    let html = concat!(
+        "<!doctype html><html><head><meta charset=utf-8><title>toast</title><style>",
+        "body{font:28px sans-serif;background:#1e1e2e;color:#eee;margin:10% 15%}",
+        "h1{font-size:96px;margin:0 0 40px}kbd{background:#444;padding:2px 10px;border-radius:6px}",
+        "</style></head><body><h1>toast</h1><p>",
+        "<kbd>Arrows</kbd> move the cursor &middot; <kbd>Enter</kbd> clicks<br>",
+        "<kbd>W</kbd>/<kbd>S</kbd> scroll &middot; <kbd>+</kbd>/<kbd>-</kbd> zoom &middot; ",
+        "<kbd>H</kbd> home &middot; <kbd>Ctrl+C</kbd> quits</p>",
+        "<p>Set <code>homepage</code> in config.toml to open your own page here.</p>",
+        "</body></html>"
+    );
+    // Only characters with a meaning in URLs need escaping in a data: URL
+    let encoded = html.replace('%', "%25").replace('#', "%23");
+    format!("data:text/html;charset=utf-8,{}", encoded)
+}
+
+/// Explicit relative or absolute paths, which should never be sent out as host names
+fn looks_like_path(input: &str) -> bool {
+    input.starts_with('/')
+        || input.starts_with("./")
+        || input.starts_with("../")
+        || input.starts_with("~/")
+        || input.starts_with('\\')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_urls_and_hosts() {
+        assert_eq!(
+            resolve("https://example.com").unwrap(),
+            "https://example.com"
+        );
+        assert_eq!(
+            resolve("example.com/a?b").unwrap(),
+            "https://example.com/a?b"
+        );
+        assert_eq!(resolve("about:blank").unwrap(), "about:blank");
+    }
+
+    #[test]
+    fn test_search_for_words() {
+        let search = Some("https://duckduckgo.com/?q={query}");
+        assert_eq!(
+            resolve_or_search("rust lifetimes & borrows", search).unwrap(),
+            "https://duckduckgo.com/?q=rust+lifetimes+%26+borrows"
+        );
+        assert_eq!(
+            resolve_or_search("toast", search).unwrap(),
+            "https://duckduckgo.com/?q=toast"
+        );
+        // Addresses still open as before
+        assert_eq!(
+            resolve_or_search("example.com", search).unwrap(),
+            "https://example.com"
+        );
+        assert_eq!(
+            resolve_or_search("localhost", search).unwrap(),
+            "https://localhost"
+        );
+        assert_eq!(
+            resolve_or_search("about:blank", search).unwrap(),
+            "about:blank"
+        );
+        // Without a search engine, words are host names
+        assert_eq!(resolve_or_search("toast", None).unwrap(), "https://toast");
+    }
+
+    #[test]
+    fn test_start_page_is_data_url() {
+        let url = resolve(START_PAGE).unwrap();
+        assert!(url.starts_with("data:text/html"));
+        assert!(!url.contains('#'));
+    }
+
+    #[test]
+    fn test_resolve_local_paths() {
+        let manifest = env!("CARGO_MANIFEST_DIR");
+        let url = resolve(&format!("{}/Cargo.toml", manifest)).unwrap();
+        assert!(url.starts_with("file:///") && url.ends_with("/Cargo.toml"));
+
+        assert!(resolve("./definitely/not/here.html").is_err());
+    }
+
+    #[test]
+    fn test_collect_reads_stdin() {
+        let stdin = "example.com\n\n# comment\nhttps://example.org\n".as_bytes();
+        let args = vec!["first.example".to_string(), "-".to_string()];
+
+        let targets = collect(&args, stdin).unwrap();
+        assert_eq!(
+            targets,
+            vec![
+                "https://first.example",
+                "https://example.com",
+                "https://example.org"
+            ]
+        );
+    }
+}
@@ -0,0 +1,46 @@
+use toast_core::FrameMetadata;
+
+/// Terminal title unless the command line or config file sets another format
+pub const DEFAULT_TITLE_FORMAT: &str = "{title} - toast";
+
+/// Fill in a terminal title format: `{title}` is the page title (or the URL while the
+/// page has none), `{url}` the page URL and `{host}` its host name
+pub fn format_title(format: &str, metadata: &FrameMetadata) -> String {
+    let url = metadata.url.as_deref().unwrap_or_default();
+    let title = match metadata.title.as_deref() {
+        Some(title) if !title.trim().is_empty() => title.trim(),
+        _ => url,
+    };
+    format
+        .replace("{title}", title)
+        .replace("{url}", url)
+        .replace("{host}", &toast_browser::host(url))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_title() {
+        let metadata = FrameMetadata {
+            url: Some("https://www.example.com/docs".into()),
+            title: Some("Docs".into()),
+            ..Default::default()
+        };
+        assert_eq!(
+            format_title(DEFAULT_TITLE_FORMAT, &metadata),
+            "Docs - toast"
+        );
+        assert_eq!(
+            format_title("{host}: {url}", &metadata),
+            "example.com: https://www.example.com/docs"
+        );
+
+        let untitled = FrameMetadata {
+            url: Some("https://example.com/".into()),
+            ..Default::default()
+        };
+        assert_eq!(format_title("{title}", &untitled), "https://example.com/");
+    }
+}